@@ -33,8 +33,6 @@ const AQUAMARINE_WASM_FILE_NAME: &str = "aquamarine";
 const CALL_SERVICE_NAME: &str = "call_service";
 const CURRENT_PEER_ID_ENV_NAME: &str = "CURRENT_PEER_ID";
 
-unsafe impl Send for AquamarineVM {}
-
 pub struct AquamarineVM {
     faas: FluenceFaaS,
     particle_data_store: PathBuf,
@@ -117,6 +115,13 @@ impl AquamarineVM {
                 host_imports,
                 wasi: None,
                 logging_mask,
+                deterministic: None,
+                import_policy: <_>::default(),
+                hash: None,
+                instance_pool: None,
+                instance_mode: <_>::default(),
+                lazy_instantiation: false,
+                idle_timeout: None,
             }
         };
 
@@ -138,6 +143,8 @@ impl AquamarineVM {
                 aquamarine_module_config,
             )],
             default_modules_config: None,
+            trusted_keys: None,
+            compilation_cache_dir: None,
         }
     }
 
@@ -0,0 +1,51 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::error::Error;
+
+#[derive(Debug)]
+pub enum ModuleSignatureError {
+    /// Module has neither embedded nor detached signature.
+    Unsigned,
+
+    /// Module isn't a well-formed Wasm binary.
+    MalformedModule(String),
+
+    /// A key or a signature can't be decoded.
+    MalformedKey(String),
+
+    /// Module signature doesn't match any of trusted keys, so the module is tampered
+    /// or signed by an unknown key.
+    UntrustedSignature,
+}
+
+impl Error for ModuleSignatureError {}
+
+impl std::fmt::Display for ModuleSignatureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            ModuleSignatureError::Unsigned => write!(f, "module isn't signed"),
+            ModuleSignatureError::MalformedModule(err_msg) => {
+                write!(f, "module is malformed: {}", err_msg)
+            }
+            ModuleSignatureError::MalformedKey(err_msg) => write!(f, "{}", err_msg),
+            ModuleSignatureError::UntrustedSignature => write!(
+                f,
+                "module signature doesn't match any of trusted keys, the module is tampered or signed by an unknown key"
+            ),
+        }
+    }
+}
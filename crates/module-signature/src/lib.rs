@@ -0,0 +1,45 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+#![warn(rust_2018_idioms)]
+#![deny(
+    dead_code,
+    nonstandard_style,
+    unused_imports,
+    unused_mut,
+    unused_variables,
+    unused_unsafe,
+    unreachable_patterns
+)]
+
+mod errors;
+mod sections;
+mod signature;
+
+pub use errors::ModuleSignatureError;
+pub use signature::sign_module;
+pub use signature::embed_signature;
+pub use signature::verify_module;
+pub use signature::parse_public_key;
+pub use signature::parse_secret_key;
+pub use signature::parse_signature;
+pub use signature::detached_signature_path;
+pub use signature::SIGNATURE_SECTION_NAME;
+
+pub use ed25519_dalek::PublicKey;
+pub use ed25519_dalek::SecretKey;
+pub use ed25519_dalek::Signature;
+
+pub(crate) type Result<T> = std::result::Result<T, ModuleSignatureError>;
@@ -0,0 +1,133 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::ModuleSignatureError;
+use crate::Result;
+
+use std::ops::Range;
+
+const WASM_HEADER_SIZE: usize = 8;
+const WASM_MAGIC: &[u8] = b"\0asm";
+const CUSTOM_SECTION_ID: u8 = 0;
+
+/// Custom section found in a raw Wasm binary.
+pub(crate) struct RawCustomSection<'w> {
+    /// Range of the whole section including its id and size.
+    pub(crate) range: Range<usize>,
+
+    /// Section content after the name.
+    pub(crate) data: &'w [u8],
+}
+
+/// Finds custom sections with provided name working on raw bytes, so they could be cut
+/// out without re-encoding the rest of the module.
+pub(crate) fn find_custom_sections<'w>(
+    wasm: &'w [u8],
+    name: &str,
+) -> Result<Vec<RawCustomSection<'w>>> {
+    if wasm.len() < WASM_HEADER_SIZE || &wasm[0..4] != WASM_MAGIC {
+        return Err(malformed("wrong magic number"));
+    }
+
+    let mut sections = Vec::new();
+    let mut offset = WASM_HEADER_SIZE;
+    while offset < wasm.len() {
+        let section_start = offset;
+        let section_id = wasm[offset];
+        let (size, size_len) = read_leb_u32(&wasm[offset + 1..])?;
+
+        let payload_start = offset + 1 + size_len;
+        let payload_end = payload_start + size as usize;
+        if payload_end > wasm.len() {
+            return Err(malformed("section exceeds the module size"));
+        }
+
+        if section_id == CUSTOM_SECTION_ID {
+            let payload = &wasm[payload_start..payload_end];
+            let (name_len, name_len_size) = read_leb_u32(payload)?;
+            let name_end = name_len_size + name_len as usize;
+            if name_end > payload.len() {
+                return Err(malformed("custom section name exceeds the section size"));
+            }
+
+            if &payload[name_len_size..name_end] == name.as_bytes() {
+                sections.push(RawCustomSection {
+                    range: section_start..payload_end,
+                    data: &payload[name_end..],
+                });
+            }
+        }
+
+        offset = payload_end;
+    }
+
+    Ok(sections)
+}
+
+/// Returns the module without provided sections.
+pub(crate) fn cut_sections(wasm: &[u8], sections: &[RawCustomSection<'_>]) -> Vec<u8> {
+    let mut result = Vec::with_capacity(wasm.len());
+    let mut offset = 0;
+    for section in sections {
+        result.extend_from_slice(&wasm[offset..section.range.start]);
+        offset = section.range.end;
+    }
+    result.extend_from_slice(&wasm[offset..]);
+
+    result
+}
+
+/// Encodes a custom section with provided name and content.
+pub(crate) fn encode_custom_section(name: &str, data: &[u8]) -> Vec<u8> {
+    let mut payload = Vec::new();
+    write_leb_u32(&mut payload, name.len() as u32);
+    payload.extend_from_slice(name.as_bytes());
+    payload.extend_from_slice(data);
+
+    let mut section = vec![CUSTOM_SECTION_ID];
+    write_leb_u32(&mut section, payload.len() as u32);
+    section.extend(payload);
+
+    section
+}
+
+fn read_leb_u32(bytes: &[u8]) -> Result<(u32, usize)> {
+    let mut result = 0u32;
+    for (id, byte) in bytes.iter().take(5).enumerate() {
+        result |= ((byte & 0x7F) as u32) << (7 * id);
+        if byte & 0x80 == 0 {
+            return Ok((result, id + 1));
+        }
+    }
+
+    Err(malformed("invalid LEB128 number"))
+}
+
+fn write_leb_u32(bytes: &mut Vec<u8>, mut value: u32) {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            return;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+fn malformed(message: &str) -> ModuleSignatureError {
+    ModuleSignatureError::MalformedModule(message.to_string())
+}
@@ -0,0 +1,145 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::ModuleSignatureError;
+use crate::Result;
+use crate::sections::cut_sections;
+use crate::sections::encode_custom_section;
+use crate::sections::find_custom_sections;
+
+use ed25519_dalek::ExpandedSecretKey;
+use ed25519_dalek::PublicKey;
+use ed25519_dalek::SecretKey;
+use ed25519_dalek::Signature;
+use ed25519_dalek::Verifier;
+
+use std::convert::TryFrom;
+use std::path::Path;
+use std::path::PathBuf;
+
+pub const SIGNATURE_SECTION_NAME: &str = "fce-signature";
+
+/// Signs the module content without signature sections, so the result could be either
+/// embedded by `embed_signature` or stored in a detached file.
+pub fn sign_module(wasm: &[u8], secret_key: &SecretKey) -> Result<Signature> {
+    let unsigned_module = strip_signatures(wasm)?;
+    let public_key = PublicKey::from(secret_key);
+    let signature = ExpandedSecretKey::from(secret_key).sign(&unsigned_module, &public_key);
+
+    Ok(signature)
+}
+
+/// Embeds the signature as a custom section replacing previously embedded ones.
+pub fn embed_signature(wasm: &[u8], signature: &Signature) -> Result<Vec<u8>> {
+    let mut signed_module = strip_signatures(wasm)?;
+    let section = encode_custom_section(SIGNATURE_SECTION_NAME, &signature.to_bytes());
+    signed_module.extend(section);
+
+    Ok(signed_module)
+}
+
+/// Checks that the module is signed by one of trusted keys, a detached signature takes
+/// precedence over an embedded one.
+pub fn verify_module(
+    wasm: &[u8],
+    detached_signature: Option<&Signature>,
+    trusted_keys: &[PublicKey],
+) -> Result<()> {
+    let sections = find_custom_sections(wasm, SIGNATURE_SECTION_NAME)?;
+
+    let signature = match (detached_signature, sections.last()) {
+        (Some(signature), _) => *signature,
+        (None, Some(section)) => Signature::try_from(section.data)
+            .map_err(|e| ModuleSignatureError::MalformedKey(format!("invalid signature: {}", e)))?,
+        (None, None) => return Err(ModuleSignatureError::Unsigned),
+    };
+
+    let unsigned_module = cut_sections(wasm, &sections);
+    let is_trusted = trusted_keys
+        .iter()
+        .any(|key| key.verify(&unsigned_module, &signature).is_ok());
+
+    if is_trusted {
+        Ok(())
+    } else {
+        Err(ModuleSignatureError::UntrustedSignature)
+    }
+}
+
+/// Path of a detached signature for the module, f.e. `greeting.wasm.sig`.
+pub fn detached_signature_path(module_path: &Path) -> PathBuf {
+    let mut file_name = module_path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".sig");
+    module_path.with_file_name(file_name)
+}
+
+/// Parses a hex-encoded Ed25519 public key.
+pub fn parse_public_key(key: &str) -> Result<PublicKey> {
+    let bytes = decode_hex(key, "public key")?;
+    PublicKey::from_bytes(&bytes)
+        .map_err(|e| ModuleSignatureError::MalformedKey(format!("invalid public key: {}", e)))
+}
+
+/// Parses a hex-encoded Ed25519 secret key.
+pub fn parse_secret_key(key: &str) -> Result<SecretKey> {
+    let bytes = decode_hex(key, "secret key")?;
+    SecretKey::from_bytes(&bytes)
+        .map_err(|e| ModuleSignatureError::MalformedKey(format!("invalid secret key: {}", e)))
+}
+
+/// Parses a hex-encoded signature, the format of detached signature files.
+pub fn parse_signature(signature: &str) -> Result<Signature> {
+    let bytes = decode_hex(signature, "signature")?;
+    Signature::try_from(bytes.as_slice())
+        .map_err(|e| ModuleSignatureError::MalformedKey(format!("invalid signature: {}", e)))
+}
+
+fn strip_signatures(wasm: &[u8]) -> Result<Vec<u8>> {
+    let sections = find_custom_sections(wasm, SIGNATURE_SECTION_NAME)?;
+    Ok(cut_sections(wasm, &sections))
+}
+
+fn decode_hex(value: &str, what: &str) -> Result<Vec<u8>> {
+    hex::decode(value.trim()).map_err(|e| {
+        ModuleSignatureError::MalformedKey(format!("{} isn't a valid hex: {}", what, e))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EMPTY_MODULE: &[u8] = b"\0asm\x01\0\0\0";
+
+    #[test]
+    fn sign_and_verify() {
+        let secret_key = SecretKey::from_bytes(&[7; 32]).unwrap();
+        let public_key = PublicKey::from(&secret_key);
+        let other_key = PublicKey::from(&SecretKey::from_bytes(&[8; 32]).unwrap());
+
+        let signature = sign_module(EMPTY_MODULE, &secret_key).unwrap();
+        let signed_module = embed_signature(EMPTY_MODULE, &signature).unwrap();
+
+        assert!(verify_module(&signed_module, None, &[public_key]).is_ok());
+        assert!(verify_module(EMPTY_MODULE, Some(&signature), &[public_key]).is_ok());
+        assert!(verify_module(&signed_module, None, &[other_key]).is_err());
+        assert!(verify_module(EMPTY_MODULE, None, &[public_key]).is_err());
+
+        let mut tampered_module = signed_module;
+        tampered_module[4] = 2;
+        assert!(verify_module(&tampered_module, None, &[public_key]).is_err());
+    }
+}
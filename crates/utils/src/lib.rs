@@ -24,10 +24,10 @@
     unreachable_patterns
 )]
 
-use std::rc::Rc;
+use std::sync::Arc;
 
 #[derive(Debug, Clone, PartialEq, Eq, Default, Hash)]
-pub struct SharedString(pub Rc<String>);
+pub struct SharedString(pub Arc<String>);
 
 impl std::borrow::Borrow<str> for SharedString {
     fn borrow(&self) -> &str {
@@ -18,6 +18,7 @@ use std::borrow::Cow;
 use walrus::{CustomSection, IdsToIndices};
 
 pub const WIT_SECTION_NAME: &str = "interface-types";
+pub const METADATA_SECTION_NAME: &str = "fce-metadata";
 
 #[derive(Debug, Clone)]
 pub(super) struct WITCustom(pub Vec<u8>);
@@ -31,3 +32,16 @@ impl CustomSection for WITCustom {
         Cow::Borrowed(&self.0)
     }
 }
+
+#[derive(Debug, Clone)]
+pub(super) struct MetadataCustom(pub Vec<u8>);
+
+impl CustomSection for MetadataCustom {
+    fn name(&self) -> &str {
+        METADATA_SECTION_NAME
+    }
+
+    fn data(&self, _ids_to_indices: &IdsToIndices) -> Cow<'_, [u8]> {
+        Cow::Borrowed(&self.0)
+    }
+}
@@ -43,6 +43,12 @@ pub enum WITParserError {
 
     // Wasm emittig file error.
     WasmEmitError(anyhow::Error),
+
+    /// Multiple metadata sections.
+    MultipleMetadataSections,
+
+    /// An error occurred while parsing or serializing metadata section.
+    CorruptedMetadataSection(serde_json::Error),
 }
 
 impl Error for WITParserError {}
@@ -70,6 +76,13 @@ impl std::fmt::Display for WITParserError {
                 write!(f, "Wasm AST converting to bytes failed with: {}", err)
             }
             WITParserError::WasmEmitError(err) => write!(f, "Failed to emit Wasm file: {}", err),
+            WITParserError::MultipleMetadataSections => write!(
+                f,
+                "Loaded module contains multiple metadata sections that is unsupported now"
+            ),
+            WITParserError::CorruptedMetadataSection(err) => {
+                write!(f, "metadata section is corrupted: {}", err)
+            }
         }
     }
 }
@@ -85,3 +98,9 @@ impl From<StdIOError> for WITParserError {
         WITParserError::AstToBytesError(err)
     }
 }
+
+impl From<serde_json::Error> for WITParserError {
+    fn from(err: serde_json::Error) -> Self {
+        WITParserError::CorruptedMetadataSection(err)
+    }
+}
@@ -30,6 +30,19 @@ pub fn extract_text_wit(wasm_file_path: PathBuf) -> Result<String, WITParserErro
     Ok((&wit).to_string())
 }
 
+/// Extracts names of functions exported through WIT by provided Wasm binary.
+pub fn extract_export_names(wasm_file_path: PathBuf) -> Result<Vec<String>, WITParserError> {
+    let wit_section_bytes = extract_wit_section_bytes(wasm_file_path)?;
+    let wit = extract_wit_with_fn(&wit_section_bytes)?;
+    let export_names = wit
+        .exports
+        .iter()
+        .map(|export| export.name.to_string())
+        .collect();
+
+    Ok(export_names)
+}
+
 /// Extracts WIT section of provided Wasm binary and converts it to a FCEWITInterfaces.
 pub fn extract_wit(wasmer_module: &WasmerModule) -> Result<Interfaces<'_>, WITParserError> {
     let wit_sections = wasmer_module
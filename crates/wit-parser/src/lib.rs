@@ -29,6 +29,7 @@ mod deleter;
 mod embedder;
 mod errors;
 mod extractor;
+mod metadata;
 
 pub use errors::WITParserError;
 
@@ -37,4 +38,9 @@ pub use deleter::delete_wit_section_from_file;
 pub use embedder::embed_wit;
 pub use embedder::embed_text_wit;
 pub use extractor::extract_wit;
+pub use extractor::extract_export_names;
 pub use extractor::extract_text_wit;
+pub use metadata::ModuleMetadata;
+pub use metadata::embed_metadata;
+pub use metadata::extract_metadata;
+pub use metadata::extract_metadata_from_file;
@@ -0,0 +1,216 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use super::custom::MetadataCustom;
+use super::custom::METADATA_SECTION_NAME;
+use super::errors::WITParserError;
+
+use serde::Deserialize;
+use serde::Serialize;
+use walrus::{IdsToIndices, ModuleConfig};
+use wasmer_core::Module as WasmerModule;
+
+use std::fmt;
+use std::path::PathBuf;
+
+/// Build information of a module embedded by `fce build` in a separate custom section.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModuleMetadata {
+    /// Name of the crate the module was built from.
+    pub name: String,
+
+    /// Version of the crate.
+    pub version: String,
+
+    pub authors: Vec<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repository: Option<String>,
+
+    /// Version of the Fluence Rust SDK the module was built with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sdk_version: Option<String>,
+
+    /// Hex-encoded hash of the crate sources.
+    pub source_hash: String,
+}
+
+impl fmt::Display for ModuleMetadata {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "name: {}", self.name)?;
+        writeln!(f, "version: {}", self.version)?;
+        writeln!(f, "authors: {}", self.authors.join(", "))?;
+        if let Some(repository) = &self.repository {
+            writeln!(f, "repository: {}", repository)?;
+        }
+        if let Some(sdk_version) = &self.sdk_version {
+            writeln!(f, "sdk version: {}", sdk_version)?;
+        }
+        write!(f, "source hash: {}", self.source_hash)
+    }
+}
+
+/// Embed provided metadata to a Wasm file by path, a previously embedded metadata is replaced.
+pub fn embed_metadata(wasm_path: PathBuf, metadata: &ModuleMetadata) -> Result<(), WITParserError> {
+    let mut module = ModuleConfig::new()
+        .parse_file(&wasm_path)
+        .map_err(WITParserError::CorruptedWasmFile)?;
+
+    let metadata_section_ids = module
+        .customs
+        .iter()
+        .filter(|(_, section)| section.name() == METADATA_SECTION_NAME)
+        .map(|(id, _)| id)
+        .collect::<Vec<_>>();
+
+    for id in metadata_section_ids {
+        module.customs.delete(id);
+    }
+
+    let bytes = serde_json::to_vec(metadata)?;
+    module.customs.add(MetadataCustom(bytes));
+
+    module
+        .emit_wasm_file(&wasm_path)
+        .map_err(WITParserError::WasmEmitError)?;
+
+    Ok(())
+}
+
+/// Extracts metadata of provided Wasm module, returns None if it hasn't been embedded.
+pub fn extract_metadata(
+    wasmer_module: &WasmerModule,
+) -> Result<Option<ModuleMetadata>, WITParserError> {
+    let sections = match wasmer_module.custom_sections(METADATA_SECTION_NAME) {
+        Some(sections) => sections,
+        None => return Ok(None),
+    };
+
+    parse_metadata_sections(sections)
+}
+
+/// Extracts metadata of provided Wasm file, returns None if it hasn't been embedded.
+pub fn extract_metadata_from_file(
+    wasm_file_path: PathBuf,
+) -> Result<Option<ModuleMetadata>, WITParserError> {
+    let module = ModuleConfig::new()
+        .parse_file(wasm_file_path)
+        .map_err(WITParserError::CorruptedWasmFile)?;
+
+    let default_ids = IdsToIndices::default();
+    let sections = module
+        .customs
+        .iter()
+        .filter(|(_, section)| section.name() == METADATA_SECTION_NAME)
+        .map(|(_, section)| section.data(&default_ids).into_owned())
+        .collect::<Vec<_>>();
+
+    parse_metadata_sections(&sections)
+}
+
+fn parse_metadata_sections(sections: &[Vec<u8>]) -> Result<Option<ModuleMetadata>, WITParserError> {
+    match sections {
+        [] => Ok(None),
+        [section] => Ok(Some(serde_json::from_slice(section)?)),
+        _ => Err(WITParserError::MultipleMetadataSections),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::embed_metadata;
+    use super::extract_metadata_from_file;
+    use super::ModuleMetadata;
+    use crate::custom::MetadataCustom;
+    use crate::WITParserError;
+
+    use walrus::Module;
+
+    use std::path::PathBuf;
+
+    /// Writes an empty Wasm module with the provided custom sections to a temporary file.
+    fn empty_wasm_file(name: &str, customs: Vec<MetadataCustom>) -> PathBuf {
+        let mut module = Module::default();
+        for custom in customs {
+            module.customs.add(custom);
+        }
+
+        let path =
+            std::env::temp_dir().join(format!("fce_metadata_{}_{}.wasm", name, std::process::id()));
+        module
+            .emit_wasm_file(&path)
+            .expect("module should be written");
+        path
+    }
+
+    fn metadata(version: &str) -> ModuleMetadata {
+        ModuleMetadata {
+            name: String::from("greeting"),
+            version: String::from(version),
+            authors: vec![String::from("Fluence Labs")],
+            repository: None,
+            sdk_version: Some(String::from("0.2.10")),
+            source_hash: String::from("0123abcd"),
+        }
+    }
+
+    #[test]
+    fn embedded_metadata_is_extracted() {
+        let path = empty_wasm_file("round_trip", vec![]);
+        assert_eq!(extract_metadata_from_file(path.clone()).unwrap(), None);
+
+        embed_metadata(path.clone(), &metadata("0.1.0")).unwrap();
+        assert_eq!(
+            extract_metadata_from_file(path.clone()).unwrap(),
+            Some(metadata("0.1.0"))
+        );
+
+        // embedding again replaces the previous section instead of adding one more
+        embed_metadata(path.clone(), &metadata("0.2.0")).unwrap();
+        let extracted = extract_metadata_from_file(path.clone());
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(extracted.unwrap(), Some(metadata("0.2.0")));
+    }
+
+    #[test]
+    fn corrupted_metadata_is_rejected() {
+        let path = empty_wasm_file("corrupted", vec![MetadataCustom(b"{\"name\": ".to_vec())]);
+        let extracted = extract_metadata_from_file(path.clone());
+        std::fs::remove_file(path).unwrap();
+
+        assert!(matches!(
+            extracted,
+            Err(WITParserError::CorruptedMetadataSection(_))
+        ));
+    }
+
+    #[test]
+    fn multiple_metadata_sections_are_rejected() {
+        let section = serde_json::to_vec(&metadata("0.1.0")).unwrap();
+        let path = empty_wasm_file(
+            "multiple",
+            vec![MetadataCustom(section.clone()), MetadataCustom(section)],
+        );
+        let extracted = extract_metadata_from_file(path.clone());
+        std::fs::remove_file(path).unwrap();
+
+        assert!(matches!(
+            extracted,
+            Err(WITParserError::MultipleMetadataSections)
+        ));
+    }
+}
@@ -21,12 +21,15 @@ use crate::HostImportError;
 use wasmer_wasi::WasiVersion;
 use wasmer_runtime::ImportObject;
 use wasmer_core::vm::Ctx;
+use serde::Serialize;
 
 use std::path::PathBuf;
+use std::collections::BTreeSet;
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::time::Duration;
 
-pub type HostExportedFunc = Box<dyn Fn(&mut Ctx, Vec<IValue>) -> Option<IValue> + 'static>;
+pub type HostExportedFunc = Box<dyn Fn(&mut Ctx, Vec<IValue>) -> Option<IValue> + Send + 'static>;
 
 pub struct HostImportDescriptor {
     /// This closure will be invoked for corresponding import.
@@ -40,7 +43,102 @@ pub struct HostImportDescriptor {
 
     /// If Some, this closure is called with error when errors is encountered while lifting.
     /// If None, panic will occur.
-    pub error_handler: Option<Box<dyn Fn(&HostImportError) -> Option<IValue> + 'static>>,
+    pub error_handler: Option<Box<dyn Fn(&HostImportError) -> Option<IValue> + Send + 'static>>,
+}
+
+/// Restrictions applied to a directory mapped into a module.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct MappedDirPermissions {
+    /// If true, a module isn't allowed to create or modify files in the directory.
+    pub readonly: bool,
+
+    /// Maximum total size of files in the directory, writes exceeding it fail with ENOSPC.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_bytes: Option<u64>,
+}
+
+/// Settings of the deterministic execution mode, in which WASI clocks, randomness and
+/// polling are virtualized and NaNs produced by float instructions are canonicalized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct DeterministicConfig {
+    /// Seed of the RNG used by random_get.
+    pub rng_seed: u64,
+
+    /// Initial time of the logical clock in nanoseconds.
+    pub clock_start_ns: u64,
+
+    /// Time in nanoseconds the logical clock advances by on each clock_time_get.
+    pub clock_step_ns: u64,
+}
+
+impl Default for DeterministicConfig {
+    fn default() -> Self {
+        Self {
+            rng_seed: 0,
+            clock_start_ns: 0,
+            clock_step_ns: 1_000,
+        }
+    }
+}
+
+/// Defines how long an instance of a module lives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InstanceMode {
+    /// One instance serves all calls, so the module state is kept between them.
+    Shared,
+
+    /// Each top-level call is served by a pristine instance that is discarded after the call,
+    /// so nothing is carried between calls. Such a module can't be imported by other modules.
+    PerCall,
+}
+
+impl Default for InstanceMode {
+    fn default() -> Self {
+        InstanceMode::Shared
+    }
+}
+
+/// Filesystem visible to a module through WASI.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WASIFileSystem {
+    /// Preopened files and mapped dirs are directories of the host.
+    Host,
+
+    /// Filesystem lives entirely in the host memory and could be seeded
+    /// from a host directory or a tar archive.
+    Memory { seed: Option<PathBuf> },
+}
+
+impl Default for WASIFileSystem {
+    fn default() -> Self {
+        WASIFileSystem::Host
+    }
+}
+
+/// Restricts imports of a module, it's checked against raw imports of the module at load.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum ImportPolicy {
+    /// All imports are allowed.
+    Any,
+
+    /// Only WASI functions that don't access the filesystem, sockets and other processes,
+    /// host imports are forbidden except the logger and call parameters.
+    Pure,
+
+    /// Only listed WASI functions and functions from listed non-WASI namespaces
+    /// (f.e. `host` or names of other modules) are allowed.
+    Allowlist {
+        wasi_functions: BTreeSet<String>,
+        host_namespaces: BTreeSet<String>,
+    },
+}
+
+impl Default for ImportPolicy {
+    fn default() -> Self {
+        ImportPolicy::Any
+    }
 }
 
 pub struct FCEModuleConfig {
@@ -57,6 +155,9 @@ pub struct FCEModuleConfig {
     /// Desired WASI version.
     pub wasi_version: WasiVersion,
 
+    /// Arguments (argv) available for loaded modules, the first one is a program name.
+    pub wasi_args: Vec<Vec<u8>>,
+
     /// Environment variables for loaded modules.
     pub wasi_envs: HashMap<Vec<u8>, Vec<u8>>,
 
@@ -65,6 +166,33 @@ pub struct FCEModuleConfig {
 
     /// Mapping between paths.
     pub wasi_mapped_dirs: HashMap<String, PathBuf>,
+
+    /// Permissions of mapped directories by their aliases,
+    /// directories without permissions are writable and unlimited.
+    pub wasi_mapped_dirs_permissions: HashMap<String, MappedDirPermissions>,
+
+    /// Filesystem backend, in-memory one turns preopened files and mapped dirs
+    /// into empty in-memory directories.
+    pub wasi_fs: WASIFileSystem,
+
+    /// If Some, the module is executed in the deterministic mode.
+    pub deterministic: Option<DeterministicConfig>,
+
+    /// Imports the module is allowed to use.
+    pub import_policy: ImportPolicy,
+
+    /// Whether the module instance is shared by all calls or created anew for each of them.
+    pub instance_mode: InstanceMode,
+
+    /// If true, the module is compiled while loading, but instantiated on its first call.
+    pub lazy_instantiation: bool,
+
+    /// If Some, the module instance is evicted after being idle for this time, its memory
+    /// is kept as a snapshot and restored when the module is instantiated again. The new
+    /// instance runs `_start` once more before its memory is restored, and mutable globals
+    /// aren't a part of the snapshot, so they get their initial values. Modules with the
+    /// in-memory filesystem or in the deterministic mode are never evicted.
+    pub idle_timeout: Option<Duration>,
 }
 
 impl Default for FCEModuleConfig {
@@ -76,9 +204,17 @@ impl Default for FCEModuleConfig {
             raw_imports: ImportObject::new(),
             host_imports: HashMap::new(),
             wasi_version: WasiVersion::Latest,
+            wasi_args: vec![],
             wasi_envs: HashMap::new(),
             wasi_preopened_files: HashSet::new(),
             wasi_mapped_dirs: HashMap::new(),
+            wasi_mapped_dirs_permissions: HashMap::new(),
+            wasi_fs: WASIFileSystem::Host,
+            deterministic: None,
+            import_policy: ImportPolicy::Any,
+            instance_mode: InstanceMode::Shared,
+            lazy_instantiation: false,
+            idle_timeout: None,
         }
     }
 }
@@ -98,6 +234,12 @@ impl FCEModuleConfig {
         self
     }
 
+    #[allow(dead_code)]
+    pub fn with_wasi_args(mut self, args: Vec<Vec<u8>>) -> Self {
+        self.wasi_args = args;
+        self
+    }
+
     #[allow(dead_code)]
     pub fn with_wasi_envs(mut self, envs: HashMap<Vec<u8>, Vec<u8>>) -> Self {
         self.wasi_envs = envs;
@@ -115,4 +257,49 @@ impl FCEModuleConfig {
         self.wasi_mapped_dirs = mapped_dirs;
         self
     }
+
+    #[allow(dead_code)]
+    pub fn with_wasi_mapped_dirs_permissions(
+        mut self,
+        permissions: HashMap<String, MappedDirPermissions>,
+    ) -> Self {
+        self.wasi_mapped_dirs_permissions = permissions;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_wasi_fs(mut self, fs: WASIFileSystem) -> Self {
+        self.wasi_fs = fs;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_deterministic(mut self, deterministic: DeterministicConfig) -> Self {
+        self.deterministic = Some(deterministic);
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_import_policy(mut self, import_policy: ImportPolicy) -> Self {
+        self.import_policy = import_policy;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_instance_mode(mut self, instance_mode: InstanceMode) -> Self {
+        self.instance_mode = instance_mode;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_lazy_instantiation(mut self, lazy_instantiation: bool) -> Self {
+        self.lazy_instantiation = lazy_instantiation;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = Some(idle_timeout);
+        self
+    }
 }
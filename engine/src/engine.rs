@@ -17,31 +17,75 @@
 use super::*;
 use crate::module::FCEModule;
 use crate::module::RecordTypes;
+use crate::wasi::MappedDirUsage;
 
+use serde::Serialize;
+
+use std::borrow::Cow;
 use std::collections::hash_map::Entry;
 use std::collections::HashMap;
-use std::rc::Rc;
+use std::collections::HashSet;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Instant;
 
 /// Represent FCE module interface.
 #[derive(PartialEq, Eq, Debug, Clone)]
 pub struct FCEModuleInterface<'a> {
     pub record_types: &'a RecordTypes,
     pub function_signatures: Vec<FCEFunctionSignature>,
+
+    /// Build information embedded by `fce build`, None for modules built without it.
+    pub metadata: Option<&'a ModuleMetadata>,
+}
+
+/// Runtime statistics of a FCE module.
+#[derive(PartialEq, Eq, Debug, Clone, Default, Serialize)]
+pub struct FCEModuleStats {
+    /// Usage of mapped directories with permissions by their aliases.
+    pub mapped_dirs_usage: HashMap<String, MappedDirUsage>,
+
+    /// False if the module hasn't been instantiated yet or its instance is evicted.
+    pub instantiated: bool,
 }
 
 /// The base struct of the Fluence Compute Engine.
+///
+/// FCE is neither Send nor Sync: Wasmer instances hold raw pointers to their contexts, and
+/// the interface-types interpreter requires Rc-based record types. It should be created, called
+/// and dropped by one thread, other threads could send requests to it (see `AsyncExecutor` and
+/// `InstancePool` of fluence-faas).
 pub struct FCE {
     // set of modules registered inside FCE
     modules: HashMap<String, FCEModule>,
+
+    /// Directory where compiled modules are cached by hashes of their bytes.
+    compilation_cache_dir: Option<PathBuf>,
 }
 
 impl FCE {
     pub fn new() -> Self {
         Self {
             modules: HashMap::new(),
+            compilation_cache_dir: None,
+        }
+    }
+
+    /// Creates FCE that reuses compiled modules from the directory, so identical modules
+    /// loaded by several FCE instances or after a restart are compiled only once.
+    pub fn with_compilation_cache<P: Into<PathBuf>>(cache_dir: P) -> Self {
+        Self {
+            modules: HashMap::new(),
+            compilation_cache_dir: Some(cache_dir.into()),
         }
     }
 
+    /// Returns the directory where compiled modules are cached, if any.
+    pub fn compilation_cache_dir(&self) -> Option<&Path> {
+        self.compilation_cache_dir.as_deref()
+    }
+
     /// Invoke a function of a module inside FCE by given function name with given arguments.
     pub fn call<MN: AsRef<str>, FN: AsRef<str>>(
         &mut self,
@@ -49,15 +93,119 @@ impl FCE {
         func_name: FN,
         arguments: &[IValue],
     ) -> Result<Vec<IValue>> {
-        self.modules.get_mut(module_name.as_ref()).map_or_else(
-            || {
-                Err(FCEError::NoSuchModule(format!(
-                    "trying to call module with name {} that is not loaded",
-                    module_name.as_ref()
-                )))
-            },
-            |module| module.call(func_name.as_ref(), arguments),
-        )
+        self.call_module(module_name.as_ref(), |module| {
+            module.call(func_name.as_ref(), arguments)
+        })
+    }
+
+    /// Invoke a function of a module inside FCE with the supplied buffer as the module stdin.
+    pub fn call_with_stdin<MN: AsRef<str>, FN: AsRef<str>>(
+        &mut self,
+        module_name: MN,
+        func_name: FN,
+        arguments: &[IValue],
+        stdin: Vec<u8>,
+    ) -> Result<Vec<IValue>> {
+        self.call_module(module_name.as_ref(), |module| {
+            module.call_with_stdin(func_name.as_ref(), arguments, stdin)
+        })
+    }
+
+    /// Calls the module instantiating it if needed. An instance of a module in the per-call
+    /// mode is dropped after the call, and a new one is created right away unless the module
+    /// is instantiated lazily. Idle modules are evicted after each call.
+    fn call_module<F>(&mut self, module_name: &str, call: F) -> Result<Vec<IValue>>
+    where
+        F: FnOnce(&mut FCEModule) -> Result<Vec<IValue>>,
+    {
+        if !self.modules.contains_key(module_name) {
+            return Err(FCEError::NoSuchModule(format!(
+                "trying to call module with name {} that is not loaded",
+                module_name
+            )));
+        }
+
+        let module = self.instantiated_module_mut(module_name)?;
+        let result = call(&mut *module);
+
+        if module.instance_mode() == InstanceMode::PerCall {
+            module.evict();
+            if !module.is_lazy() {
+                if let Err(e) = self.instantiate_module(module_name) {
+                    log::warn!(
+                        "module {} can't be instantiated after a call, it'll be retried on the next one: {}",
+                        module_name,
+                        e
+                    );
+                }
+            }
+        }
+
+        self.evict_idle_modules();
+
+        result
+    }
+
+    /// Instantiates a module if it isn't instantiated, modules it imports from are
+    /// instantiated first.
+    fn instantiate_module(&mut self, module_name: &str) -> Result<()> {
+        let module = self
+            .modules
+            .get(module_name)
+            .ok_or_else(|| FCEError::NoSuchModule(module_name.to_string()))?;
+        if module.is_instantiated() {
+            return Ok(());
+        }
+
+        let imported_modules = module
+            .imported_modules()
+            .iter()
+            .cloned()
+            .collect::<Vec<_>>();
+        for imported_module in imported_modules {
+            self.instantiate_module(&imported_module)?;
+        }
+
+        // the module is taken out to be instantiated against others, it can't import itself
+        let mut module = self
+            .modules
+            .remove(module_name)
+            .ok_or_else(|| FCEError::NoSuchModule(module_name.to_string()))?;
+        let result = module.instantiate(&self.modules);
+        self.modules.insert(module_name.to_string(), module);
+
+        result
+    }
+
+    fn instantiated_module_mut(&mut self, module_name: &str) -> Result<&mut FCEModule> {
+        self.instantiate_module(module_name)?;
+        self.module_mut(module_name)
+    }
+
+    /// Evicts instances of modules that have been idle longer than their idle timeouts,
+    /// returns names of evicted modules. Memory of an evicted instance is kept as a snapshot
+    /// and restored when the module is called again. Modules imported by instantiated
+    /// modules aren't evicted. It's called after each call, but should be also called
+    /// periodically if FCE could stay without calls for a long time.
+    pub fn evict_idle_modules(&mut self) -> Vec<String> {
+        let now = Instant::now();
+        let used_by_others = self
+            .modules
+            .values()
+            .filter(|module| module.is_instantiated())
+            .flat_map(|module| module.imported_modules().iter().cloned())
+            .collect::<HashSet<_>>();
+
+        self.modules
+            .iter_mut()
+            .filter(|(module_name, module)| {
+                module.is_idle(now) && !used_by_others.contains(module_name.as_str())
+            })
+            .map(|(module_name, module)| {
+                module.evict();
+                module_name.clone()
+            })
+            .collect()
     }
 
     /// Load a new module inside FCE.
@@ -77,16 +225,39 @@ impl FCE {
         config: FCEModuleConfig,
     ) -> Result<()> {
         let _prepared_wasm_bytes = crate::misc::prepare_module(wasm_bytes, config.mem_pages_count)?;
+        crate::misc::check_import_policy(wasm_bytes, &config.import_policy)?;
 
-        let module = FCEModule::new(&wasm_bytes, config, &self.modules)?;
+        let wasm_bytes = match config.deterministic {
+            Some(_) => Cow::Owned(crate::misc::prepare_deterministic_module(wasm_bytes)?),
+            None => Cow::Borrowed(wasm_bytes),
+        };
 
-        match self.modules.entry(name) {
+        let module = FCEModule::new(
+            name.clone(),
+            &wasm_bytes,
+            config,
+            &self.modules,
+            self.compilation_cache_dir.as_deref(),
+        )?;
+        let is_lazy = module.is_lazy();
+
+        match self.modules.entry(name.clone()) {
             Entry::Vacant(entry) => {
                 entry.insert(module);
-                Ok(())
             }
-            Entry::Occupied(entry) => Err(FCEError::NonUniqueModuleName(entry.key().clone())),
+            Entry::Occupied(entry) => {
+                return Err(FCEError::NonUniqueModuleName(entry.key().clone()))
+            }
         }
+
+        if is_lazy {
+            return Ok(());
+        }
+
+        self.instantiate_module(&name).map_err(|e| {
+            self.modules.remove(&name);
+            e
+        })
     }
 
     /// Unload previously loaded module.
@@ -103,13 +274,59 @@ impl FCE {
             })
     }
 
+    /// Return WASI state of module with given name, a lazy module isn't instantiated by this
+    /// and its state is available only after the first call.
     pub fn module_wasi_state<S: AsRef<str>>(
         &mut self,
         module_name: S,
-    ) -> Option<&wasmer_wasi::state::WasiState> {
+    ) -> Result<&wasmer_wasi::state::WasiState> {
+        self.module_mut(module_name.as_ref())?.get_wasi_state()
+    }
+
+    /// Return paths and sizes of all files of the in-memory filesystem of module with given name.
+    pub fn module_memory_fs_files<S: AsRef<str>>(
+        &mut self,
+        module_name: S,
+    ) -> Result<Vec<(PathBuf, u64)>> {
+        self.instantiated_module_mut(module_name.as_ref())?
+            .memory_fs_files()
+    }
+
+    /// Return content of a file of the in-memory filesystem of module with given name.
+    pub fn read_module_memory_fs_file<S: AsRef<str>, P: AsRef<Path>>(
+        &mut self,
+        module_name: S,
+        path: P,
+    ) -> Result<Vec<u8>> {
+        self.instantiated_module_mut(module_name.as_ref())?
+            .read_memory_fs_file(path.as_ref())
+    }
+
+    /// Return a copy of the linear memory of module with given name,
+    /// the module is instantiated if it isn't.
+    pub fn module_memory_snapshot<S: AsRef<str>>(&mut self, module_name: S) -> Result<Vec<u8>> {
+        self.instantiated_module_mut(module_name.as_ref())?
+            .memory_snapshot()
+    }
+
+    /// Replace the linear memory of module with given name with the snapshot.
+    pub fn restore_module_memory_snapshot<S: AsRef<str>>(
+        &mut self,
+        module_name: S,
+        snapshot: &[u8],
+    ) -> Result<()> {
+        self.instantiated_module_mut(module_name.as_ref())?
+            .restore_memory_snapshot(snapshot)
+    }
+
+    /// Return runtime statistics of module with given name.
+    pub fn module_stats<S: AsRef<str>>(&self, module_name: S) -> Option<FCEModuleStats> {
         self.modules
-            .get_mut(module_name.as_ref())
-            .map(|module| module.get_wasi_state())
+            .get(module_name.as_ref())
+            .map(|module| FCEModuleStats {
+                mapped_dirs_usage: module.mapped_dirs_usage(),
+                instantiated: module.is_instantiated(),
+            })
     }
 
     /// Return function signatures of all loaded info FCE modules with their names.
@@ -141,12 +358,18 @@ impl FCE {
         &self,
         module_name: S,
         record_id: u64,
-    ) -> Option<&Rc<IRecordType>> {
+    ) -> Option<&Arc<IRecordType>> {
         self.modules
             .get(module_name.as_ref())
             .and_then(|module| module.export_record_type_by_id(record_id))
     }
 
+    fn module_mut(&mut self, module_name: &str) -> Result<&mut FCEModule> {
+        self.modules
+            .get_mut(module_name)
+            .ok_or_else(|| FCEError::NoSuchModule(module_name.to_string()))
+    }
+
     fn get_module_interface(module: &FCEModule) -> FCEModuleInterface<'_> {
         let record_types = module.export_record_types();
 
@@ -155,6 +378,7 @@ impl FCE {
         FCEModuleInterface {
             record_types,
             function_signatures,
+            metadata: module.metadata(),
         }
     }
 }
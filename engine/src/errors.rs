@@ -59,6 +59,24 @@ pub enum FCEError {
 
     /// Incorrect WIT section.
     IncorrectWIT(String),
+
+    /// An error occurred while manipulating with the WASI state of a module.
+    WASIError(String),
+
+    /// Module can't be executed in the deterministic mode.
+    NondeterministicModule(String),
+
+    /// Module imports functions forbidden by its import policy, contains `namespace.name` of them.
+    ForbiddenImports(Vec<String>),
+
+    /// Memory snapshot can't be restored into a module.
+    MemorySnapshotError(String),
+
+    /// Module instantiated per call can't be imported by other modules, contains its name.
+    PerCallModuleImport(String),
+
+    /// Operation requires an instance of a module that is evicted or not instantiated yet.
+    ModuleNotInstantiated(String),
 }
 
 impl Error for FCEError {}
@@ -85,6 +103,28 @@ impl std::fmt::Display for FCEError {
             FCEError::HostImportError(host_import_error) => write!(f, "{}", host_import_error),
             FCEError::WITParseError(err) => write!(f, "{}", err),
             FCEError::IncorrectWIT(err_msg) => write!(f, "{}", err_msg),
+            FCEError::WASIError(err_msg) => write!(f, "{}", err_msg),
+            FCEError::NondeterministicModule(err_msg) => write!(
+                f,
+                "module can't be executed in the deterministic mode: {}",
+                err_msg
+            ),
+            FCEError::ForbiddenImports(imports) => write!(
+                f,
+                "module imports functions forbidden by its import policy: {}",
+                imports.join(", ")
+            ),
+            FCEError::MemorySnapshotError(err_msg) => {
+                write!(f, "memory snapshot can't be restored: {}", err_msg)
+            }
+            FCEError::PerCallModuleImport(module_name) => write!(
+                f,
+                r#"module "{}" is instantiated per call and can't be imported by other modules"#,
+                module_name
+            ),
+            FCEError::ModuleNotInstantiated(module_name) => {
+                write!(f, r#"module "{}" isn't instantiated"#, module_name)
+            }
         }
     }
 }
@@ -19,6 +19,7 @@ use super::ivalues_lifting::wvalues_to_ivalues;
 use super::ivalues_lowering::ivalue_to_wvalues;
 use super::utils::itypes_args_to_wtypes;
 use super::utils::itypes_output_to_wtypes;
+use crate::IType;
 use crate::RecordTypes;
 
 use crate::init_wasm_func_once;
@@ -32,39 +33,36 @@ use wasmer_core::types::Value as WValue;
 use wasmer_core::types::FuncSig;
 
 use std::cell::RefCell;
-use std::rc::Rc;
+use std::sync::Arc;
 
+/// Creates a host import for one instance, the descriptor is shared, so imports for
+/// another instance of the same module could be created from it.
 pub(crate) fn create_host_import_func(
-    descriptor: HostImportDescriptor,
-    record_types: Rc<RecordTypes>,
+    descriptor: Arc<HostImportDescriptor>,
+    record_types: Arc<RecordTypes>,
 ) -> DynamicFunc<'static> {
     let allocate_func: AllocateFunc = Box::new(RefCell::new(None));
     let set_result_ptr_func: SetResultPtrFunc = Box::new(RefCell::new(None));
     let set_result_size_func: SetResultSizeFunc = Box::new(RefCell::new(None));
 
-    let HostImportDescriptor {
-        host_exported_func,
-        argument_types,
-        output_type,
-        error_handler,
-    } = descriptor;
-
-    let output_type_to_types = |output_type| match output_type {
-        Some(ty) => vec![ty],
+    let output_type_to_types = |output_type: &Option<IType>| match output_type {
+        Some(ty) => vec![ty.clone()],
         None => vec![],
     };
 
-    let raw_args = itypes_args_to_wtypes(&argument_types);
-    let raw_output = itypes_output_to_wtypes(&output_type_to_types(output_type));
+    let raw_args = itypes_args_to_wtypes(&descriptor.argument_types);
+    let raw_output = itypes_output_to_wtypes(&output_type_to_types(&descriptor.output_type));
 
     let func = move |ctx: &mut Ctx, inputs: &[WValue]| -> Vec<WValue> {
         init_wasm_func_once!(allocate_func, ctx, i32, i32, ALLOCATE_FUNC_NAME, 2);
 
-        let result = match wvalues_to_ivalues(ctx, inputs, &argument_types, &record_types) {
-            Ok(ivalues) => host_exported_func(ctx, ivalues),
+        let argument_types = &descriptor.argument_types;
+        let result = match wvalues_to_ivalues(ctx, inputs, argument_types, &record_types) {
+            Ok(ivalues) => (descriptor.host_exported_func)(ctx, ivalues),
             Err(e) => {
                 log::error!("error occurred while lifting values in host import: {}", e);
-                error_handler
+                descriptor
+                    .error_handler
                     .as_ref()
                     .map_or_else(|| default_error_handler(&e), |h| h(&e))
             }
@@ -101,10 +99,7 @@ pub(crate) fn create_host_import_func(
         }
     };
 
-    DynamicFunc::new(
-        std::sync::Arc::new(FuncSig::new(raw_args, raw_output)),
-        func,
-    )
+    DynamicFunc::new(Arc::new(FuncSig::new(raw_args, raw_output)), func)
 }
 
 fn default_error_handler(err: &HostImportError) -> Option<crate::IValue> {
@@ -33,12 +33,19 @@ mod errors;
 mod host_imports;
 mod misc;
 mod module;
+mod wasi;
 
+pub use config::DeterministicConfig;
 pub use config::FCEModuleConfig;
 pub use config::HostExportedFunc;
 pub use config::HostImportDescriptor;
+pub use config::ImportPolicy;
+pub use config::InstanceMode;
+pub use config::MappedDirPermissions;
+pub use config::WASIFileSystem;
 pub use engine::FCE;
 pub use engine::FCEModuleInterface;
+pub use engine::FCEModuleStats;
 pub use errors::FCEError;
 pub use host_imports::HostImportError;
 pub use module::IValue;
@@ -49,6 +56,8 @@ pub use module::RecordTypes;
 pub use module::FCEFunctionSignature;
 pub use module::from_interface_values;
 pub use module::to_interface_value;
+pub use wasi::MappedDirUsage;
+pub use fce_wit_parser::ModuleMetadata;
 
 pub use wasmer_wit::types::RecordFieldType as IRecordFieldType;
 pub mod vec1 {
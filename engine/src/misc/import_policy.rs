@@ -0,0 +1,97 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::FCEError;
+use crate::ImportPolicy;
+use crate::Result;
+
+use parity_wasm::elements;
+use parity_wasm::elements::External;
+
+const WASI_NAMESPACES: [&str; 2] = ["wasi_unstable", "wasi_snapshot_preview1"];
+
+/// Host namespace used for host imports, the logger and call parameters.
+const HOST_NAMESPACE: &str = "host";
+
+/// WASI functions that don't access the filesystem, sockets and other processes,
+/// fd_* functions are harmless here since a pure module can't open any file.
+const PURE_WASI_FUNCTIONS: [&str; 19] = [
+    "args_get",
+    "args_sizes_get",
+    "environ_get",
+    "environ_sizes_get",
+    "clock_res_get",
+    "clock_time_get",
+    "random_get",
+    "sched_yield",
+    "poll_oneoff",
+    "proc_exit",
+    "fd_write",
+    "fd_read",
+    "fd_close",
+    "fd_seek",
+    "fd_tell",
+    "fd_sync",
+    "fd_fdstat_get",
+    "fd_prestat_get",
+    "fd_prestat_dir_name",
+];
+
+/// Host imports that are allowed for a pure module.
+const PURE_HOST_FUNCTIONS: [&str; 2] = ["log_utf8_string", "get_call_parameters"];
+
+/// Checks that all function imports of the module are allowed by the policy,
+/// returns all forbidden imports in the `namespace.name` form otherwise.
+pub(crate) fn check_import_policy(module_code: &[u8], policy: &ImportPolicy) -> Result<()> {
+    if let ImportPolicy::Any = policy {
+        return Ok(());
+    }
+
+    let module: elements::Module = elements::deserialize_buffer(module_code)?;
+    let imports = match module.import_section() {
+        Some(section) => section.entries(),
+        None => return Ok(()),
+    };
+
+    let forbidden_imports = imports
+        .iter()
+        .filter(|entry| matches!(entry.external(), External::Function(_)))
+        .filter(|entry| !is_allowed(entry.module(), entry.field(), policy))
+        .map(|entry| format!("{}.{}", entry.module(), entry.field()))
+        .collect::<Vec<_>>();
+
+    if forbidden_imports.is_empty() {
+        Ok(())
+    } else {
+        Err(FCEError::ForbiddenImports(forbidden_imports))
+    }
+}
+
+fn is_allowed(namespace: &str, name: &str, policy: &ImportPolicy) -> bool {
+    let is_wasi = WASI_NAMESPACES.contains(&namespace);
+
+    match policy {
+        ImportPolicy::Any => true,
+        ImportPolicy::Pure if is_wasi => PURE_WASI_FUNCTIONS.contains(&name),
+        ImportPolicy::Pure if namespace == HOST_NAMESPACE => PURE_HOST_FUNCTIONS.contains(&name),
+        // imports from other modules are checked by policies of these modules
+        ImportPolicy::Pure => true,
+        ImportPolicy::Allowlist { wasi_functions, .. } if is_wasi => wasi_functions.contains(name),
+        ImportPolicy::Allowlist {
+            host_namespaces, ..
+        } => host_namespaces.contains(namespace),
+    }
+}
@@ -14,6 +14,9 @@
  * limitations under the License.
  */
 
+mod import_policy;
 mod prepare;
 
+pub(crate) use import_policy::check_import_policy;
 pub(crate) use prepare::prepare_module;
+pub(crate) use prepare::prepare_deterministic_module;
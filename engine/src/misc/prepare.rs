@@ -22,9 +22,15 @@ use crate::Result;
 
 use parity_wasm::{
     builder, elements,
-    elements::{MemorySection, MemoryType},
+    elements::{FuncBody, Instruction, Local, MemorySection, MemoryType, Type, ValueType},
 };
 
+/// Bit pattern of the canonical f32 NaN.
+const CANONICAL_F32_NAN: u32 = 0x7FC0_0000;
+
+/// Bit pattern of the canonical f64 NaN.
+const CANONICAL_F64_NAN: u64 = 0x7FF8_0000_0000_0000;
+
 struct ModuleBootstrapper {
     module: elements::Module,
 }
@@ -66,6 +72,21 @@ impl<'a> ModuleBootstrapper {
         }
     }
 
+    /// Float instructions could produce NaNs with arbitrary payload depending on a platform,
+    /// so results of each such instruction are replaced with the canonical NaN if they are NaN.
+    fn canonicalize_nans(self) -> Self {
+        let Self { mut module } = self;
+
+        let params_counts = functions_params_counts(&module);
+        if let Some(code_section) = module.code_section_mut() {
+            for (body, params_count) in code_section.bodies_mut().iter_mut().zip(params_counts) {
+                canonicalize_body_nans(body, params_count);
+            }
+        }
+
+        Self { module }
+    }
+
     fn into_wasm(self) -> Result<Vec<u8>> {
         elements::serialize(self.module).map_err(Into::into)
     }
@@ -78,3 +99,173 @@ pub(crate) fn prepare_module(module: &[u8], mem_pages_count: u32) -> Result<Vec<
         .set_mem_pages_count(mem_pages_count)
         .into_wasm()
 }
+
+/// Prepares a Wasm module for the deterministic execution:
+///   - canonicalize NaNs produced by float instructions
+pub(crate) fn prepare_deterministic_module(module: &[u8]) -> Result<Vec<u8>> {
+    ModuleBootstrapper::init(module)?
+        .canonicalize_nans()
+        .into_wasm()
+}
+
+/// Returns count of parameters of each function defined in the module.
+fn functions_params_counts(module: &elements::Module) -> Vec<u32> {
+    let types = module
+        .type_section()
+        .map(|section| section.types())
+        .unwrap_or_default();
+
+    module
+        .function_section()
+        .map(|section| section.entries())
+        .unwrap_or_default()
+        .iter()
+        .map(|func| match types.get(func.type_ref() as usize) {
+            Some(Type::Function(func_type)) => func_type.params().len() as u32,
+            None => 0,
+        })
+        .collect()
+}
+
+fn canonicalize_body_nans(body: &mut FuncBody, params_count: u32) {
+    let has_nan_producers = body
+        .code()
+        .elements()
+        .iter()
+        .any(|instruction| nan_result_type(instruction).is_some());
+    if !has_nan_producers {
+        return;
+    }
+
+    // temporary locals to check results of float instructions
+    let locals_count: u32 = body.locals().iter().map(Local::count).sum();
+    let f32_local = params_count + locals_count;
+    let f64_local = f32_local + 1;
+    body.locals_mut().push(Local::new(1, ValueType::F32));
+    body.locals_mut().push(Local::new(1, ValueType::F64));
+
+    let instructions = std::mem::take(body.code_mut().elements_mut());
+    let mut canonicalized = Vec::with_capacity(instructions.len());
+    for instruction in instructions {
+        let result_type = nan_result_type(&instruction);
+        canonicalized.push(instruction);
+
+        // result = result == result ? result : NaN
+        match result_type {
+            Some(ValueType::F32) => canonicalized.extend(vec![
+                Instruction::TeeLocal(f32_local),
+                Instruction::F32Const(CANONICAL_F32_NAN),
+                Instruction::GetLocal(f32_local),
+                Instruction::GetLocal(f32_local),
+                Instruction::F32Eq,
+                Instruction::Select,
+            ]),
+            Some(_) => canonicalized.extend(vec![
+                Instruction::TeeLocal(f64_local),
+                Instruction::F64Const(CANONICAL_F64_NAN),
+                Instruction::GetLocal(f64_local),
+                Instruction::GetLocal(f64_local),
+                Instruction::F64Eq,
+                Instruction::Select,
+            ]),
+            None => {}
+        }
+    }
+
+    *body.code_mut().elements_mut() = canonicalized;
+}
+
+/// Returns type of a float instruction result that could be NaN with arbitrary payload.
+fn nan_result_type(instruction: &Instruction) -> Option<ValueType> {
+    use Instruction::*;
+
+    match instruction {
+        F32Add | F32Sub | F32Mul | F32Div | F32Sqrt | F32Min | F32Max | F32Ceil | F32Floor
+        | F32Trunc | F32Nearest | F32DemoteF64 => Some(ValueType::F32),
+        F64Add | F64Sub | F64Mul | F64Div | F64Sqrt | F64Min | F64Max | F64Ceil | F64Floor
+        | F64Trunc | F64Nearest | F64PromoteF32 => Some(ValueType::F64),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::prepare_deterministic_module;
+    use super::CANONICAL_F32_NAN;
+    use super::CANONICAL_F64_NAN;
+
+    use parity_wasm::builder;
+    use parity_wasm::elements::{Instruction, Instructions, ValueType};
+    use wasmer_runtime::imports;
+    use wasmer_runtime::Func;
+
+    /// NaNs with a payload which float instructions propagate on most platforms.
+    const PAYLOAD_F32_NAN: u32 = 0x7FC0_0001;
+    const PAYLOAD_F64_NAN: u64 = 0x7FF8_0000_0000_0001;
+
+    /// Returns a module exporting f32_add(a, b) = a + b and f64_div(a, b) = a / b.
+    fn float_module() -> Vec<u8> {
+        let module = builder::module()
+            .function()
+            .signature()
+            .with_param(ValueType::F32)
+            .with_param(ValueType::F32)
+            .with_return_type(Some(ValueType::F32))
+            .build()
+            .body()
+            .with_instructions(Instructions::new(vec![
+                Instruction::GetLocal(0),
+                Instruction::GetLocal(1),
+                Instruction::F32Add,
+                Instruction::End,
+            ]))
+            .build()
+            .build()
+            .function()
+            .signature()
+            .with_param(ValueType::F64)
+            .with_param(ValueType::F64)
+            .with_return_type(Some(ValueType::F64))
+            .build()
+            .body()
+            .with_instructions(Instructions::new(vec![
+                Instruction::GetLocal(0),
+                Instruction::GetLocal(1),
+                Instruction::F64Div,
+                Instruction::End,
+            ]))
+            .build()
+            .build()
+            .export()
+            .field("f32_add")
+            .internal()
+            .func(0)
+            .build()
+            .export()
+            .field("f64_div")
+            .internal()
+            .func(1)
+            .build()
+            .build();
+
+        parity_wasm::serialize(module).expect("module should be serialized")
+    }
+
+    #[test]
+    fn float_results_nans_are_canonicalized() {
+        let wasm = prepare_deterministic_module(&float_module()).expect("module is valid");
+        let instance = wasmer_runtime::instantiate(&wasm, &imports! {}).expect("module is valid");
+
+        let f32_add: Func<'_, (f32, f32), f32> = instance.exports.get("f32_add").unwrap();
+        assert_eq!(f32_add.call(1.5, 2.0).unwrap(), 3.5);
+        let nan_sum = f32_add.call(f32::from_bits(PAYLOAD_F32_NAN), 1.0).unwrap();
+        assert_eq!(nan_sum.to_bits(), CANONICAL_F32_NAN);
+
+        let f64_div: Func<'_, (f64, f64), f64> = instance.exports.get("f64_div").unwrap();
+        assert_eq!(f64_div.call(1.0, 4.0).unwrap(), 0.25);
+        let nan_quotient = f64_div.call(f64::from_bits(PAYLOAD_F64_NAN), 1.0).unwrap();
+        assert_eq!(nan_quotient.to_bits(), CANONICAL_F64_NAN);
+        // 0/0 is the negative NaN on x86
+        assert_eq!(f64_div.call(0.0, 0.0).unwrap().to_bits(), CANONICAL_F64_NAN);
+    }
+}
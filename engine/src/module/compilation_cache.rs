@@ -0,0 +1,235 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::Result;
+
+use wasmer_core::cache::Artifact;
+use wasmer_core::Module as WasmerModule;
+use wasmer_runtime::compile;
+
+use std::path::Path;
+
+/// Extension of files with compiled modules in the cache dir.
+const ARTIFACT_EXTENSION: &str = "artifact";
+
+/// Loads a compiled module from the cache dir or compiles and stores it there,
+/// cache failures aren't fatal since the module could always be compiled.
+///
+/// Compiled modules are native code loaded without any validation, so each one is stored
+/// under the hash of the module bytes together with a digest of this hash and the artifact,
+/// which is checked before loading. It protects from corrupted artifacts and artifacts of
+/// other modules, but not from someone who could write to the cache dir, that's why the cache
+/// is used only if the dir isn't writable by group and others.
+pub(super) fn compile_with_cache(wasm_bytes: &[u8], cache_dir: &Path) -> Result<WasmerModule> {
+    if let Err(message) = prepare_cache_dir(cache_dir) {
+        log::warn!("compilation cache {:?} isn't used: {}", cache_dir, message);
+        return Ok(compile(wasm_bytes)?);
+    }
+
+    let module_hash = blake3::hash(wasm_bytes);
+    let artifact_path = cache_dir.join(format!("{}.{}", module_hash.to_hex(), ARTIFACT_EXTENSION));
+    match load_artifact(&artifact_path, &module_hash) {
+        Ok(Some(module)) => return Ok(module),
+        Ok(None) => {}
+        Err(message) => log::warn!(
+            "compiled module {:?} is ignored: {}",
+            artifact_path,
+            message
+        ),
+    }
+
+    let module = compile(wasm_bytes)?;
+    if let Err(message) = store_artifact(&artifact_path, &module_hash, &module) {
+        log::warn!(
+            "compiled module can't be stored to {:?}: {}",
+            artifact_path,
+            message
+        );
+    }
+
+    Ok(module)
+}
+
+/// Creates the cache dir accessible only by its owner or checks that the existing one
+/// couldn't be modified by other users.
+fn prepare_cache_dir(cache_dir: &Path) -> std::result::Result<(), String> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        use std::os::unix::fs::PermissionsExt;
+
+        std::fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(cache_dir)
+            .map_err(|e| e.to_string())?;
+
+        let mode = std::fs::metadata(cache_dir)
+            .map_err(|e| e.to_string())?
+            .permissions()
+            .mode();
+        if mode & 0o022 != 0 {
+            return Err(format!(
+                "it's writable by group or others (mode {:o})",
+                mode & 0o777
+            ));
+        }
+    }
+
+    #[cfg(not(unix))]
+    std::fs::create_dir_all(cache_dir).map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Returns None if the module hasn't been stored yet.
+fn load_artifact(
+    artifact_path: &Path,
+    module_hash: &blake3::Hash,
+) -> std::result::Result<Option<WasmerModule>, String> {
+    let content = match std::fs::read(artifact_path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.to_string()),
+    };
+
+    if content.len() < blake3::OUT_LEN {
+        return Err(String::from("it's truncated"));
+    }
+    let (digest, artifact_bytes) = content.split_at(blake3::OUT_LEN);
+    if digest != &artifact_digest(module_hash, artifact_bytes).as_bytes()[..] {
+        return Err(String::from("its digest doesn't match the module"));
+    }
+
+    let artifact = Artifact::deserialize(artifact_bytes).map_err(|e| format!("{:?}", e))?;
+    let compiler = wasmer_runtime::default_compiler();
+    // it's safe because the artifact has been produced by store_artifact for this module
+    let module = unsafe { wasmer_core::load_cache_with(artifact, &compiler) }
+        .map_err(|e| format!("{:?}", e))?;
+
+    Ok(Some(module))
+}
+
+fn store_artifact(
+    artifact_path: &Path,
+    module_hash: &blake3::Hash,
+    module: &WasmerModule,
+) -> std::result::Result<(), String> {
+    let artifact_bytes = module
+        .cache()
+        .and_then(|artifact| artifact.serialize())
+        .map_err(|e| format!("{:?}", e))?;
+
+    let mut content = artifact_digest(module_hash, &artifact_bytes)
+        .as_bytes()
+        .to_vec();
+    content.extend(artifact_bytes);
+
+    // write to a temporary file first, so a partially written artifact is never loaded
+    let tmp_path = artifact_path.with_extension(format!("{}.tmp", std::process::id()));
+    std::fs::write(&tmp_path, content)
+        .and_then(|_| std::fs::rename(&tmp_path, artifact_path))
+        .map_err(|e| e.to_string())
+}
+
+fn artifact_digest(module_hash: &blake3::Hash, artifact_bytes: &[u8]) -> blake3::Hash {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(module_hash.as_bytes());
+    hasher.update(artifact_bytes);
+    hasher.finalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::compile_with_cache;
+    use super::ARTIFACT_EXTENSION;
+
+    use parity_wasm::builder;
+
+    use std::path::Path;
+    use std::path::PathBuf;
+
+    fn empty_module() -> Vec<u8> {
+        parity_wasm::serialize(builder::module().build()).expect("module should be serialized")
+    }
+
+    fn cache_dir(name: &str) -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("fce_cache_{}_{}", name, std::process::id()))
+            .join("artifacts")
+    }
+
+    fn artifact_path(cache_dir: &Path, wasm_bytes: &[u8]) -> PathBuf {
+        let module_hash = blake3::hash(wasm_bytes);
+        cache_dir.join(format!("{}.{}", module_hash.to_hex(), ARTIFACT_EXTENSION))
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn compiled_module_is_cached() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let cache_dir = cache_dir("cached");
+        let wasm_bytes = empty_module();
+
+        compile_with_cache(&wasm_bytes, &cache_dir).expect("module should be compiled");
+        let artifact = std::fs::read(artifact_path(&cache_dir, &wasm_bytes));
+        let mode = std::fs::metadata(&cache_dir).map(|m| m.permissions().mode());
+        let cached = compile_with_cache(&wasm_bytes, &cache_dir);
+        std::fs::remove_dir_all(cache_dir.parent().unwrap()).unwrap();
+
+        assert!(!artifact.expect("artifact should be stored").is_empty());
+        assert_eq!(mode.unwrap() & 0o777, 0o700);
+        assert!(cached.is_ok());
+    }
+
+    #[test]
+    fn tampered_artifact_is_replaced() {
+        let cache_dir = cache_dir("tampered");
+        let wasm_bytes = empty_module();
+        let artifact_path = artifact_path(&cache_dir, &wasm_bytes);
+
+        compile_with_cache(&wasm_bytes, &cache_dir).expect("module should be compiled");
+        let mut tampered_artifact = std::fs::read(&artifact_path).unwrap();
+        *tampered_artifact.last_mut().unwrap() ^= 0xFF;
+        std::fs::write(&artifact_path, &tampered_artifact).unwrap();
+
+        let recompiled = compile_with_cache(&wasm_bytes, &cache_dir);
+        let stored_artifact = std::fs::read(&artifact_path);
+        std::fs::remove_dir_all(cache_dir.parent().unwrap()).unwrap();
+
+        assert!(recompiled.is_ok());
+        assert_ne!(stored_artifact.unwrap(), tampered_artifact);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn shared_cache_dir_is_not_used() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let cache_dir = cache_dir("shared");
+        let wasm_bytes = empty_module();
+        std::fs::create_dir_all(&cache_dir).unwrap();
+        std::fs::set_permissions(&cache_dir, std::fs::Permissions::from_mode(0o777)).unwrap();
+
+        let compiled = compile_with_cache(&wasm_bytes, &cache_dir);
+        let is_stored = artifact_path(&cache_dir, &wasm_bytes).exists();
+        std::fs::remove_dir_all(cache_dir.parent().unwrap()).unwrap();
+
+        assert!(compiled.is_ok());
+        assert!(!is_stored);
+    }
+}
@@ -17,23 +17,44 @@
 use super::wit_prelude::*;
 use super::{IType, IRecordType, IFunctionArg, IValue, WValue};
 use super::RecordTypes;
+use super::compilation_cache::compile_with_cache;
 use crate::Result;
 use crate::FCEModuleConfig;
+use crate::HostImportDescriptor;
+use crate::InstanceMode;
+use crate::wasi::StdinBuffer;
+use crate::wasi::DirRestrictions;
+use crate::wasi::MappedDirUsage;
+use crate::wasi::WASIConfig;
+use crate::wasi::memory_fs_files;
+use crate::wasi::read_memory_fs_file;
+use crate::WASIFileSystem;
 
 use fce_wit_interfaces::FCEWITInterfaces;
 use fce_wit_parser::extract_wit;
+use fce_wit_parser::extract_metadata;
+use fce_wit_parser::ModuleMetadata;
 use fce_utils::SharedString;
 use wasmer_core::Instance as WasmerInstance;
+use wasmer_core::Module as WasmerModule;
 use wasmer_core::import::Namespace;
+use wasmer_core::memory::Memory;
 use wasmer_runtime::compile;
 use wasmer_runtime::ImportObject;
 use wasmer_wit::interpreter::Interpreter;
+use wasmer_wasi::state::WasiFile;
 
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::convert::TryInto;
 use std::mem::MaybeUninit;
+use std::path::Path;
+use std::path::PathBuf;
 use std::sync::Arc;
-use std::rc::Rc;
+use std::sync::Mutex;
+use std::sync::PoisonError;
+use std::time::Duration;
+use std::time::Instant;
 
 type WITInterpreter =
     Interpreter<WITInstance, WITExport, WITFunction, WITMemory, WITMemoryView<'static>>;
@@ -41,16 +62,14 @@ type WITInterpreter =
 #[derive(Clone)]
 pub(super) struct WITModuleFunc {
     interpreter: Arc<WITInterpreter>,
-    pub(super) arguments: Rc<Vec<IFunctionArg>>,
-    pub(super) output_types: Rc<Vec<IType>>,
 }
 
 /// Represent a function type inside FCE module.
 #[derive(PartialEq, Eq, Debug, Clone, Hash)]
 pub struct FCEFunctionSignature {
-    pub name: Rc<String>,
-    pub arguments: Rc<Vec<IFunctionArg>>,
-    pub outputs: Rc<Vec<IType>>,
+    pub name: Arc<String>,
+    pub arguments: Arc<Vec<IFunctionArg>>,
+    pub outputs: Arc<Vec<IType>>,
 }
 
 #[derive(Clone)]
@@ -74,24 +93,66 @@ impl Callable {
     }
 }
 
-type ExportFunctions = HashMap<SharedString, Rc<Callable>>;
+type ExportFunctions = HashMap<SharedString, Arc<Callable>>;
 
-pub(crate) struct FCEModule {
+/// Compiled module together with everything needed to instantiate it once more.
+#[derive(Clone)]
+struct ModuleSource {
+    name: String,
+    wasmer_module: WasmerModule,
+    raw_imports: ImportObject,
+    host_imports: HashMap<String, Arc<HostImportDescriptor>>,
+    wasi_config: WASIConfig,
+    instance_mode: InstanceMode,
+    lazy_instantiation: bool,
+    idle_timeout: Option<Duration>,
+}
+
+impl ModuleSource {
+    fn new(name: String, wasmer_module: WasmerModule, config: FCEModuleConfig) -> Self {
+        let host_imports = config
+            .host_imports
+            .into_iter()
+            .map(|(import_name, descriptor)| (import_name, Arc::new(descriptor)))
+            .collect();
+
+        let wasi_config = WASIConfig {
+            version: config.wasi_version,
+            args: config.wasi_args,
+            envs: config.wasi_envs,
+            preopened_files: config.wasi_preopened_files,
+            mapped_dirs: config.wasi_mapped_dirs,
+            mapped_dirs_permissions: config.wasi_mapped_dirs_permissions,
+            fs: config.wasi_fs,
+            deterministic: config.deterministic,
+        };
+
+        Self {
+            name,
+            wasmer_module,
+            raw_imports: config.raw_imports,
+            host_imports,
+            wasi_config,
+            instance_mode: config.instance_mode,
+            lazy_instantiation: config.lazy_instantiation,
+            idle_timeout: config.idle_timeout,
+        }
+    }
+}
+
+/// Instance of a module with its imports, everything here is dropped on eviction.
+struct ModuleInstance {
     // wasmer_instance is needed because WITInstance contains dynamic functions
     // that internally keep pointer to it.
-    #[allow(unused)]
     wasmer_instance: Box<WasmerInstance>,
 
     // import_object is needed because ImportObject::extend doesn't really deep copy
     // imports, so we need to store imports of this module to prevent their removing.
+    // It must be dropped before export_funcs: its closures keep WITInstance as MaybeUninit,
+    // so WITInstance is dropped only if the last reference to it is from a Callable.
     #[allow(unused)]
     wit_import_object: ImportObject,
 
-    // host_import_object is needed because ImportObject::extend doesn't really deep copy
-    // imports, so we need to store imports of this module to prevent their removing.
-    #[allow(unused)]
-    host_import_object: ImportObject,
-
     // host_closures_import_object is needed because ImportObject::extend doesn't really deep copy
     // imports, so we need to store imports of this module to prevent their removing.
     #[allow(unused)]
@@ -100,26 +161,252 @@ pub(crate) struct FCEModule {
     // TODO: replace with dyn Trait
     export_funcs: ExportFunctions,
 
+    /// Permissions of mapped directories shared with the WASI fd_write replacement.
+    dir_restrictions: Arc<Mutex<DirRestrictions>>,
+}
+
+impl ModuleInstance {
+    /// Returns the linear memory of the instance, None if the module neither defines
+    /// nor imports a memory.
+    fn memory(&self) -> Option<&Memory> {
+        let ctx = self.wasmer_instance.context();
+        // the module info is kept alive by the instance
+        let module_info = unsafe { &(*ctx.module).info };
+        if module_info.memories.is_empty() && module_info.imported_memories.is_empty() {
+            return None;
+        }
+
+        Some(ctx.memory(0))
+    }
+
+    /// Returns a copy of the linear memory, it's empty for a module without a memory.
+    fn memory_snapshot(&self) -> Vec<u8> {
+        let memory = match self.memory() {
+            Some(memory) => memory,
+            None => return Vec::new(),
+        };
+        let view = memory.view::<u8>();
+        // Cell<u8> has the same memory layout as u8
+        let memory_bytes =
+            unsafe { std::slice::from_raw_parts(view.as_ptr() as *const u8, view.len()) };
+
+        memory_bytes.to_vec()
+    }
+
+    /// Grows memory to at least `memory_size` bytes and replaces its content with the snapshot
+    /// followed by zeros.
+    fn restore_memory(&self, snapshot: &[u8], memory_size: usize) -> Result<()> {
+        use wasmer_core::units::Pages;
+        use wasmer_core::units::WASM_PAGE_SIZE;
+
+        let memory = match self.memory() {
+            Some(memory) => memory,
+            // an empty snapshot of a module without a memory
+            None if snapshot.is_empty() && memory_size == 0 => return Ok(()),
+            None => {
+                return Err(FCEError::MemorySnapshotError(String::from(
+                    "module doesn't have a memory",
+                )))
+            }
+        };
+        let current_size = memory.size().bytes().0;
+        let required_size = std::cmp::max(snapshot.len(), memory_size);
+        if required_size > current_size {
+            let delta = (required_size - current_size + WASM_PAGE_SIZE - 1) / WASM_PAGE_SIZE;
+            memory
+                .grow(Pages(delta as u32))
+                .map_err(|e| FCEError::MemorySnapshotError(format!("{:?}", e)))?;
+        }
+
+        let view = memory.view::<u8>();
+        // Cell<u8> has the same memory layout as u8 and allows mutation through a shared
+        // reference, the memory isn't accessed by anything else while it's restored
+        let memory_bytes =
+            unsafe { std::slice::from_raw_parts_mut(view.as_ptr() as *mut u8, view.len()) };
+        let (restored_bytes, rest_bytes) = memory_bytes.split_at_mut(snapshot.len());
+        restored_bytes.copy_from_slice(snapshot);
+        for byte in rest_bytes {
+            *byte = 0;
+        }
+
+        Ok(())
+    }
+
+    fn wasi_state(&mut self) -> &mut wasmer_wasi::state::WasiState {
+        unsafe { wasmer_wasi::state::get_wasi_state(self.wasmer_instance.context_mut()) }
+    }
+}
+
+/// Memory of an evicted instance, trailing zeros are cut off to save space.
+struct EvictedMemory {
+    memory_size: usize,
+    content: Vec<u8>,
+}
+
+impl EvictedMemory {
+    fn new(mut content: Vec<u8>) -> Self {
+        let memory_size = content.len();
+        let content_size = content
+            .iter()
+            .rposition(|byte| *byte != 0)
+            .map_or(0, |position| position + 1);
+        content.truncate(content_size);
+        content.shrink_to_fit();
+
+        Self {
+            memory_size,
+            content,
+        }
+    }
+}
+
+pub(crate) struct FCEModule {
+    // source is needed to create instances of the module, also it keeps raw imports
+    // because ImportObject::extend doesn't really deep copy imports.
+    source: ModuleSource,
+
+    /// None if the module hasn't been instantiated yet or its instance is evicted.
+    instance: Option<ModuleInstance>,
+
+    /// Memory of the evicted instance restored when the module is instantiated again.
+    evicted_memory: Option<EvictedMemory>,
+
+    /// Signatures of exported functions, they are extracted from WIT
+    /// to be available without an instance.
+    export_signatures: Vec<FCEFunctionSignature>,
+
     // TODO: save refs instead copying of a record types HashMap.
     /// Record types used in exported functions as arguments or return values.
     export_record_types: RecordTypes,
+
+    /// Names of modules this module imports functions from.
+    imported_modules: HashSet<String>,
+
+    /// True if the module uses the in-memory WASI filesystem.
+    is_memory_fs: bool,
+
+    /// Build information embedded into the module, if any.
+    metadata: Option<ModuleMetadata>,
+
+    /// Time of the last call or instantiation used to find idle modules.
+    last_used: Instant,
 }
 
 impl FCEModule {
+    /// Compiles a module and extracts its interface, the module isn't instantiated here.
+    /// Modules it imports from must be already loaded.
     pub(crate) fn new(
+        name: String,
         wasm_bytes: &[u8],
         config: FCEModuleConfig,
         modules: &HashMap<String, FCEModule>,
+        compilation_cache_dir: Option<&Path>,
     ) -> Result<Self> {
-        let wasmer_module = compile(&wasm_bytes)?;
+        let wasmer_module = match compilation_cache_dir {
+            Some(cache_dir) => compile_with_cache(wasm_bytes, cache_dir)?,
+            None => compile(&wasm_bytes)?,
+        };
+        let wit = extract_wit(&wasmer_module)?;
+        let metadata = Self::extract_module_metadata(&name, &wasmer_module);
+        let fce_wit = FCEWITInterfaces::new(wit);
+
+        let export_signatures = Self::extract_export_signatures(&fce_wit)?;
+        let export_record_types = Self::extract_export_record_types(&export_signatures, &fce_wit)?;
+        let imported_modules = Self::extract_imported_modules(&fce_wit, modules)?;
+        let is_memory_fs = config.wasi_fs != WASIFileSystem::Host;
+
+        Ok(Self {
+            source: ModuleSource::new(name, wasmer_module.clone(), config),
+            instance: None,
+            evicted_memory: None,
+            export_signatures,
+            export_record_types,
+            imported_modules,
+            is_memory_fs,
+            metadata,
+            last_used: Instant::now(),
+        })
+    }
+
+    /// Metadata is informational only, so a module with a corrupted metadata section is
+    /// still loaded as if the section was absent.
+    fn extract_module_metadata(name: &str, wasmer_module: &WasmerModule) -> Option<ModuleMetadata> {
+        extract_metadata(wasmer_module).unwrap_or_else(|e| {
+            log::warn!("metadata of module {} is ignored: {}", name, e);
+            None
+        })
+    }
+
+    /// Creates an instance of the module and calls its _start, memory of the evicted instance
+    /// is restored after that. Modules this module imports from must be instantiated, they are
+    /// looked up in `modules`.
+    pub(crate) fn instantiate(&mut self, modules: &HashMap<String, FCEModule>) -> Result<()> {
+        if self.instance.is_some() {
+            return Ok(());
+        }
+
+        let instance = Self::create_instance(&self.source, modules)?;
+        if let Some(evicted_memory) = &self.evicted_memory {
+            instance.restore_memory(&evicted_memory.content, evicted_memory.memory_size)?;
+        }
+
+        self.instance = Some(instance);
+        self.evicted_memory = None;
+        self.last_used = Instant::now();
+
+        Ok(())
+    }
+
+    /// Drops the module instance, memory of a module in the shared mode is kept
+    /// to be restored on the next instantiation.
+    pub(crate) fn evict(&mut self) {
+        if let Some(instance) = self.instance.take() {
+            if self.source.instance_mode == InstanceMode::Shared {
+                self.evicted_memory = Some(EvictedMemory::new(instance.memory_snapshot()));
+            }
+        }
+    }
+
+    /// Returns true if the instance of the module has been idle longer than its idle timeout.
+    /// Modules with the in-memory filesystem are never idle, since its files would be lost,
+    /// and neither are deterministic ones, since their logical clock and RNG would be reset.
+    pub(crate) fn is_idle(&self, now: Instant) -> bool {
+        match self.source.idle_timeout {
+            Some(idle_timeout) => {
+                self.instance.is_some()
+                    && !self.is_memory_fs
+                    && self.source.wasi_config.deterministic.is_none()
+                    && now.saturating_duration_since(self.last_used) >= idle_timeout
+            }
+            None => false,
+        }
+    }
+
+    pub(crate) fn is_instantiated(&self) -> bool {
+        self.instance.is_some()
+    }
+
+    pub(crate) fn is_lazy(&self) -> bool {
+        self.source.lazy_instantiation
+    }
+
+    pub(crate) fn imported_modules(&self) -> &HashSet<String> {
+        &self.imported_modules
+    }
+
+    fn create_instance(
+        source: &ModuleSource,
+        modules: &HashMap<String, FCEModule>,
+    ) -> Result<ModuleInstance> {
+        // WIT borrows custom sections of the module, the handle is cheap to clone
+        let wasmer_module = source.wasmer_module.clone();
         let wit = extract_wit(&wasmer_module)?;
         let fce_wit = FCEWITInterfaces::new(wit);
 
         let mut wit_instance = Arc::new_uninit();
         let wit_import_object = Self::adjust_wit_imports(&fce_wit, wit_instance.clone())?;
-        let raw_imports = config.raw_imports.clone();
-        let (wasi_import_object, host_closures_import_object) =
-            Self::create_import_objects(config, &fce_wit, wit_import_object.clone());
+        let (wasi_import_object, host_closures_import_object, dir_restrictions) =
+            Self::create_import_objects(source, &fce_wit, wit_import_object.clone())?;
 
         let wasmer_instance = wasmer_module.instantiate(&wasi_import_object)?;
         let wit_instance = unsafe {
@@ -131,7 +418,6 @@ impl FCEModule {
         };
 
         let export_funcs = Self::instantiate_wit_exports(&wit_instance, &fce_wit)?;
-        let export_record_types = Self::extract_export_record_types(&export_funcs, &wit_instance)?;
 
         // call _start to populate the WASI state of the module
         #[rustfmt::skip]
@@ -139,53 +425,136 @@ impl FCEModule {
             start_func.call()?;
         }
 
-        Ok(Self {
+        Ok(ModuleInstance {
             wasmer_instance: Box::new(wasmer_instance),
             wit_import_object,
-            host_import_object: raw_imports,
             host_closures_import_object,
             export_funcs,
-            export_record_types,
+            dir_restrictions,
         })
     }
 
     pub(crate) fn call(&mut self, function_name: &str, args: &[IValue]) -> Result<Vec<IValue>> {
-        self.export_funcs.get_mut(function_name).map_or_else(
-            || {
-                Err(FCEError::NoSuchFunction(format!(
-                    "{} hasn't been found while calling",
-                    function_name
-                )))
-            },
-            |func| Rc::make_mut(func).call(args),
-        )
+        self.last_used = Instant::now();
+        self.instance_mut()?
+            .export_funcs
+            .get_mut(function_name)
+            .map_or_else(
+                || {
+                    Err(FCEError::NoSuchFunction(format!(
+                        "{} hasn't been found while calling",
+                        function_name
+                    )))
+                },
+                |func| Arc::make_mut(func).call(args),
+            )
+    }
+
+    /// Calls a function with the supplied buffer as WASI stdin of the module,
+    /// the previous stdin is restored after the call.
+    pub(crate) fn call_with_stdin(
+        &mut self,
+        function_name: &str,
+        args: &[IValue],
+        stdin: Vec<u8>,
+    ) -> Result<Vec<IValue>> {
+        let previous_stdin = self.swap_stdin(Box::new(StdinBuffer::new(stdin)))?;
+        let result = self.call(function_name, args);
+
+        if let Some(previous_stdin) = previous_stdin {
+            self.swap_stdin(previous_stdin)?;
+        }
+
+        result
     }
 
     pub(crate) fn get_exports_signatures(&self) -> impl Iterator<Item = FCEFunctionSignature> + '_ {
-        self.export_funcs
-            .iter()
-            .map(|(func_name, func)| FCEFunctionSignature {
-                name: func_name.0.clone(),
-                arguments: func.wit_module_func.arguments.clone(),
-                outputs: func.wit_module_func.output_types.clone(),
-            })
+        self.export_signatures.iter().cloned()
     }
 
     pub(crate) fn export_record_types(&self) -> &RecordTypes {
         &self.export_record_types
     }
 
-    pub(crate) fn export_record_type_by_id(&self, record_type: u64) -> Option<&Rc<IRecordType>> {
+    pub(crate) fn instance_mode(&self) -> InstanceMode {
+        self.source.instance_mode
+    }
+
+    pub(crate) fn metadata(&self) -> Option<&ModuleMetadata> {
+        self.metadata.as_ref()
+    }
+
+    pub(crate) fn export_record_type_by_id(&self, record_type: u64) -> Option<&Arc<IRecordType>> {
         self.export_record_types.get(&record_type)
     }
 
-    pub(crate) fn get_wasi_state(&mut self) -> &wasmer_wasi::state::WasiState {
-        unsafe { wasmer_wasi::state::get_wasi_state(self.wasmer_instance.context_mut()) }
+    /// Returns a copy of the module linear memory.
+    pub(crate) fn memory_snapshot(&self) -> Result<Vec<u8>> {
+        Ok(self.instance()?.memory_snapshot())
+    }
+
+    /// Replaces the module linear memory with the snapshot, memory is grown if it's smaller
+    /// than the snapshot. Globals aren't a part of a snapshot, so it should be restored
+    /// between calls when the shadow stack pointer has its initial value.
+    pub(crate) fn restore_memory_snapshot(&mut self, snapshot: &[u8]) -> Result<()> {
+        self.instance()?.restore_memory(snapshot, snapshot.len())
+    }
+
+    /// Returns usage of mapped directories, it's empty if the module isn't instantiated.
+    pub(crate) fn mapped_dirs_usage(&self) -> HashMap<String, MappedDirUsage> {
+        self.instance
+            .as_ref()
+            .map(|instance| {
+                instance
+                    .dir_restrictions
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .usage()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns paths and sizes of all files of the in-memory filesystem.
+    pub(crate) fn memory_fs_files(&mut self) -> Result<Vec<(PathBuf, u64)>> {
+        self.check_memory_fs()?;
+        Ok(memory_fs_files(&self.get_wasi_state()?.fs))
+    }
+
+    /// Returns content of a file of the in-memory filesystem.
+    pub(crate) fn read_memory_fs_file(&mut self, path: &Path) -> Result<Vec<u8>> {
+        self.check_memory_fs()?;
+
+        let wasi_state = self.instance_mut()?.wasi_state();
+        read_memory_fs_file(&mut wasi_state.fs, path).map_err(FCEError::WASIError)
+    }
+
+    fn check_memory_fs(&self) -> Result<()> {
+        if self.is_memory_fs {
+            Ok(())
+        } else {
+            Err(FCEError::WASIError(String::from(
+                "module doesn't use the in-memory filesystem",
+            )))
+        }
+    }
+
+    pub(crate) fn get_wasi_state(&mut self) -> Result<&wasmer_wasi::state::WasiState> {
+        Ok(self.instance_mut()?.wasi_state())
+    }
+
+    fn swap_stdin(&mut self, stdin: Box<dyn WasiFile>) -> Result<Option<Box<dyn WasiFile>>> {
+        use wasmer_wasi::types::__WASI_STDIN_FILENO;
+
+        self.instance_mut()?
+            .wasi_state()
+            .fs
+            .swap_file(__WASI_STDIN_FILENO, stdin)
+            .map_err(|e| FCEError::WASIError(format!("stdin can't be replaced: {:?}", e)))
     }
 
     // TODO: change the cloning Callable behaviour after changes of Wasmer API
-    pub(super) fn get_callable(&self, function_name: &str) -> Result<Rc<Callable>> {
-        match self.export_funcs.get(function_name) {
+    pub(super) fn get_callable(&self, function_name: &str) -> Result<Arc<Callable>> {
+        match self.instance()?.export_funcs.get(function_name) {
             Some(func) => Ok(func.clone()),
             None => Err(FCEError::NoSuchFunction(format!(
                 "{} hasn't been found while calling",
@@ -194,52 +563,54 @@ impl FCEModule {
         }
     }
 
+    fn instance(&self) -> Result<&ModuleInstance> {
+        let name = &self.source.name;
+        self.instance
+            .as_ref()
+            .ok_or_else(|| FCEError::ModuleNotInstantiated(name.clone()))
+    }
+
+    fn instance_mut(&mut self) -> Result<&mut ModuleInstance> {
+        let name = &self.source.name;
+        self.instance
+            .as_mut()
+            .ok_or_else(|| FCEError::ModuleNotInstantiated(name.clone()))
+    }
+
     fn create_import_objects(
-        config: FCEModuleConfig,
+        source: &ModuleSource,
         fce_wit: &FCEWITInterfaces<'_>,
         wit_import_object: ImportObject,
-    ) -> (ImportObject, ImportObject) {
+    ) -> Result<(ImportObject, ImportObject, Arc<Mutex<DirRestrictions>>)> {
         use crate::host_imports::create_host_import_func;
+        use crate::wasi::create_wasi_import_object;
 
-        let wasi_envs = config
-            .wasi_envs
-            .into_iter()
-            .map(|(mut left, right)| {
-                left.push(61); // 61 is ASCII code of '='
-                left.extend(right);
-                left
-            })
-            .collect::<Vec<_>>();
-        let wasi_preopened_files = config.wasi_preopened_files.into_iter().collect::<Vec<_>>();
-        let wasi_mapped_dirs = config.wasi_mapped_dirs.into_iter().collect::<Vec<_>>();
-
-        let mut wasi_import_object = wasmer_wasi::generate_import_object_for_version(
-            config.wasi_version,
-            vec![],
-            wasi_envs,
-            wasi_preopened_files,
-            wasi_mapped_dirs,
-        );
+        let (mut wasi_import_object, dir_restrictions) =
+            create_wasi_import_object(source.wasi_config.clone())?;
 
         let mut host_closures_namespace = Namespace::new();
         let record_types = fce_wit
             .record_types()
-            .map(|(id, r)| (id, r.clone()))
+            .map(|(id, r)| (id, Arc::new(IRecordType::clone(r))))
             .collect::<HashMap<_, _>>();
-        let record_types = Rc::new(record_types);
+        let record_types = Arc::new(record_types);
 
-        for (import_name, descriptor) in config.host_imports {
-            let host_import = create_host_import_func(descriptor, record_types.clone());
-            host_closures_namespace.insert(import_name, host_import);
+        for (import_name, descriptor) in source.host_imports.iter() {
+            let host_import = create_host_import_func(descriptor.clone(), record_types.clone());
+            host_closures_namespace.insert(import_name.clone(), host_import);
         }
         let mut host_closures_import_object = ImportObject::new();
         host_closures_import_object.register("host", host_closures_namespace);
 
         wasi_import_object.extend(wit_import_object);
-        wasi_import_object.extend(config.raw_imports);
+        wasi_import_object.extend(source.raw_imports.clone());
         wasi_import_object.extend(host_closures_import_object.clone());
 
-        (wasi_import_object, host_closures_import_object)
+        Ok((
+            wasi_import_object,
+            host_closures_import_object,
+            dir_restrictions,
+        ))
     }
 
     fn instantiate_wit_exports(
@@ -269,20 +640,16 @@ impl FCEModule {
                 let wit_type = wit.type_by_idx_r(adapter_function_type)?;
 
                 match wit_type {
-                    WITAstType::Function {
-                        arguments,
-                        output_types,
-                    } => {
+                    WITAstType::Function { .. } => {
                         let interpreter: WITInterpreter =
                             adapter_instructions.clone().try_into()?;
                         let wit_module_func = WITModuleFunc {
                             interpreter: Arc::new(interpreter),
-                            arguments: arguments.clone(),
-                            output_types: output_types.clone(),
                         };
 
-                        let shared_string = SharedString(Rc::new(export_function_name.to_string()));
-                        let callable = Rc::new(Callable {
+                        let shared_string =
+                            SharedString(Arc::new(export_function_name.to_string()));
+                        let callable = Arc::new(Callable {
                             wit_instance: wit_instance.clone(),
                             wit_module_func,
                         });
@@ -435,35 +802,91 @@ impl FCEModule {
         Ok(import_object)
     }
 
+    fn extract_export_signatures(wit: &FCEWITInterfaces<'_>) -> Result<Vec<FCEFunctionSignature>> {
+        use fce_wit_interfaces::WITAstType;
+
+        wit.implementations()
+            .filter_map(|(adapter_function_type, core_function_type)| {
+                match wit.exports_by_type(*core_function_type) {
+                    Some(export_function_name) => {
+                        Some((adapter_function_type, export_function_name))
+                    }
+                    // pass functions that aren't export
+                    None => None,
+                }
+            })
+            .map(|(adapter_function_type, export_function_names)| {
+                export_function_names
+                    .iter()
+                    .map(move |export_function_name| (*adapter_function_type, export_function_name))
+            })
+            .flatten()
+            .map(|(adapter_function_type, export_function_name)| {
+                match wit.type_by_idx_r(adapter_function_type)? {
+                    WITAstType::Function {
+                        arguments,
+                        output_types,
+                    } => Ok(FCEFunctionSignature {
+                        name: Arc::new(export_function_name.to_string()),
+                        arguments: Arc::new(arguments.to_vec()),
+                        outputs: Arc::new(output_types.to_vec()),
+                    }),
+                    _ => Err(FCEError::IncorrectWIT(format!(
+                        "type with idx = {} isn't a function type",
+                        adapter_function_type
+                    ))),
+                }
+            })
+            .collect::<Result<Vec<_>>>()
+    }
+
+    /// Returns names of modules whose functions are imported through WIT adapters,
+    /// they must be loaded and can't be instantiated per call.
+    fn extract_imported_modules(
+        wit: &FCEWITInterfaces<'_>,
+        modules: &HashMap<String, FCEModule>,
+    ) -> Result<HashSet<String>> {
+        wit.imports()
+            .filter(|import|
+                // filter out imports that have implementations
+                matches!(wit.adapter_types_by_core_type(import.function_type), Some(_)))
+            .map(|import| match modules.get(import.namespace) {
+                Some(module) if module.instance_mode() == InstanceMode::PerCall => {
+                    Err(FCEError::PerCallModuleImport(import.namespace.to_string()))
+                }
+                Some(_) => Ok(import.namespace.to_string()),
+                None => Err(FCEError::NoSuchModule(format!(
+                    "trying to get imports from module with name {} that is not loaded",
+                    import.namespace
+                ))),
+            })
+            .collect()
+    }
+
     fn extract_export_record_types(
-        export_funcs: &ExportFunctions,
-        wit_instance: &Arc<WITInstance>,
+        export_signatures: &[FCEFunctionSignature],
+        wit: &FCEWITInterfaces<'_>,
     ) -> Result<RecordTypes> {
         fn handle_itype(
             itype: &IType,
-            wit_instance: &Arc<WITInstance>,
+            record_types: &RecordTypes,
             export_record_types: &mut RecordTypes,
         ) -> Result<()> {
-            use wasmer_wit::interpreter::wasm::structures::Instance;
-
             fn handle_record_type(
                 record_type_id: u64,
-                wit_instance: &Arc<WITInstance>,
+                record_types: &RecordTypes,
                 export_record_types: &mut RecordTypes,
             ) -> Result<()> {
-                let record_type =
-                    wit_instance
-                        .wit_record_by_id(record_type_id)
-                        .ok_or_else(|| {
-                            FCEError::WasmerResolveError(format!(
-                                "record type with type id {} not found",
-                                record_type_id
-                            ))
-                        })?;
+                let record_type = record_types.get(&record_type_id).ok_or_else(|| {
+                    FCEError::WasmerResolveError(format!(
+                        "record type with type id {} not found",
+                        record_type_id
+                    ))
+                })?;
                 export_record_types.insert(record_type_id, record_type.clone());
 
                 for field in record_type.fields.iter() {
-                    handle_itype(&field.ty, wit_instance, export_record_types)?;
+                    handle_itype(&field.ty, record_types, export_record_types)?;
                 }
 
                 Ok(())
@@ -471,10 +894,10 @@ impl FCEModule {
 
             match itype {
                 IType::Record(record_type_id) => {
-                    handle_record_type(*record_type_id, wit_instance, export_record_types)?
+                    handle_record_type(*record_type_id, record_types, export_record_types)?
                 }
                 IType::Array(array_ty) => {
-                    handle_itype(array_ty, wit_instance, export_record_types)?
+                    handle_itype(array_ty, record_types, export_record_types)?
                 }
                 _ => {}
             }
@@ -482,19 +905,22 @@ impl FCEModule {
             Ok(())
         }
 
+        let record_types = wit
+            .record_types()
+            .map(|(id, r)| (id, Arc::new(IRecordType::clone(r))))
+            .collect::<RecordTypes>();
         let mut export_record_types = HashMap::new();
 
-        let itypes = export_funcs.iter().flat_map(|(_, ref mut callable)| {
-            callable
-                .wit_module_func
+        let itypes = export_signatures.iter().flat_map(|signature| {
+            signature
                 .arguments
                 .iter()
                 .map(|arg| &arg.ty)
-                .chain(callable.wit_module_func.output_types.iter())
+                .chain(signature.outputs.iter())
         });
 
         for itype in itypes {
-            handle_itype(itype, wit_instance, &mut export_record_types)?;
+            handle_itype(itype, &record_types, &mut export_record_types)?;
         }
 
         Ok(export_record_types)
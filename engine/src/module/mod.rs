@@ -20,6 +20,7 @@ mod wit_function;
 mod wit_instance;
 mod type_converters;
 mod fce_module;
+mod compilation_cache;
 
 pub use wit_instance::RecordTypes;
 
@@ -22,17 +22,16 @@ use crate::Result;
 use wasmer_wit::interpreter::wasm;
 use wasmer_core::instance::DynFunc;
 
-// use std::sync::Arc;
-use std::rc::Rc;
+use std::sync::Arc;
 
 #[derive(Clone)]
 enum WITFunctionInner {
     Export {
-        func: Rc<DynFunc<'static>>,
+        func: Arc<DynFunc<'static>>,
     },
     Import {
         // TODO: use dyn Callable here
-        callable: Rc<Callable>,
+        callable: Arc<Callable>,
     },
 }
 
@@ -40,8 +39,8 @@ enum WITFunctionInner {
 #[derive(Clone)]
 pub(super) struct WITFunction {
     name: String,
-    arguments: Rc<Vec<IFunctionArg>>,
-    outputs: Rc<Vec<IType>>,
+    arguments: Arc<Vec<IFunctionArg>>,
+    outputs: Arc<Vec<IType>>,
     inner: WITFunctionInner,
 }
 
@@ -67,11 +66,11 @@ impl WITFunction {
             .collect::<Vec<_>>();
 
         let inner = WITFunctionInner::Export {
-            func: Rc::new(dyn_func),
+            func: Arc::new(dyn_func),
         };
 
-        let arguments = Rc::new(arguments);
-        let outputs = Rc::new(outputs);
+        let arguments = Arc::new(arguments);
+        let outputs = Arc::new(outputs);
 
         Ok(Self {
             name,
@@ -85,8 +84,8 @@ impl WITFunction {
     pub(super) fn from_import(
         wit_module: &FCEModule,
         function_name: &str,
-        arguments: Rc<Vec<IFunctionArg>>,
-        outputs: Rc<Vec<IType>>,
+        arguments: Arc<Vec<IFunctionArg>>,
+        outputs: Arc<Vec<IType>>,
     ) -> Result<Self> {
         let callable = wit_module.get_callable(function_name)?;
 
@@ -133,7 +132,7 @@ impl wasm::structures::LocalImport for WITFunction {
                 .call(&arguments.iter().map(ival_to_wval).collect::<Vec<WValue>>())
                 .map(|result| result.iter().map(wval_to_ival).collect())
                 .map_err(|_| ()),
-            WITFunctionInner::Import { callable, .. } => Rc::make_mut(&mut callable.clone())
+            WITFunctionInner::Import { callable, .. } => Arc::make_mut(&mut callable.clone())
                 .call(arguments)
                 .map_err(|_| ()),
         }
@@ -27,8 +27,13 @@ use wasmer_core::Instance as WasmerInstance;
 
 use std::collections::HashMap;
 use std::rc::Rc;
+use std::sync::Arc;
 
-pub type RecordTypes = HashMap<u64, Rc<IRecordType>>;
+pub type RecordTypes = HashMap<u64, Arc<IRecordType>>;
+
+/// Record types in the form required by the interface-types interpreter,
+/// they never leave WITInstance.
+type WITRecordTypes = HashMap<u64, Rc<IRecordType>>;
 
 /// Contains all import and export functions that could be called from WIT context by call-core.
 #[derive(Clone)]
@@ -40,7 +45,7 @@ pub(super) struct WITInstance {
     memories: Vec<WITMemory>,
 
     /// All record types that instance contains.
-    record_types_by_id: RecordTypes,
+    record_types_by_id: WITRecordTypes,
 }
 
 impl WITInstance {
@@ -110,7 +115,10 @@ impl WITInstance {
                             Type::Function {
                                 arguments,
                                 output_types,
-                            } => (arguments.clone(), output_types.clone()),
+                            } => (
+                                Arc::new(arguments.to_vec()),
+                                Arc::new(output_types.to_vec()),
+                            ),
                             ty => {
                                 return Err(FCEError::IncorrectWIT(format!(
                                     "WIT should has Type::Function, but {:?} met",
@@ -153,7 +161,7 @@ impl WITInstance {
         memories
     }
 
-    fn extract_record_types(wit: &FCEWITInterfaces<'_>) -> RecordTypes {
+    fn extract_record_types(wit: &FCEWITInterfaces<'_>) -> WITRecordTypes {
         let (record_types_by_id, _) = wit.types().fold(
             (HashMap::new(), 0u64),
             |(mut record_types_by_id, id), ty| {
@@ -0,0 +1,261 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Deterministic replacements of WASI functions depending on the outside world:
+//! clocks return time of a logical clock, randomness comes from a seeded RNG and
+//! polling completes immediately advancing the logical clock to the nearest deadline.
+
+use super::utils::*;
+use crate::DeterministicConfig;
+
+use wasmer_core::import::Namespace;
+use wasmer_core::vm::Ctx;
+use wasmer_core::typed_func::DynamicFunc;
+use wasmer_core::types::Value as WValue;
+use wasmer_core::types::FuncSig;
+use wasmer_core::types::Type;
+use wasmer_wasi::types::*;
+use rand_chacha::rand_core::RngCore;
+use rand_chacha::rand_core::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::PoisonError;
+
+const SUBSCRIPTION_SIZE: usize = 48;
+const EVENT_SIZE: usize = 32;
+
+/// Logical clock and RNG shared by replaced WASI functions of a module.
+pub(crate) struct DeterministicState {
+    rng: ChaCha20Rng,
+    time_ns: u64,
+    clock_step_ns: u64,
+}
+
+impl DeterministicState {
+    pub(crate) fn new(config: &DeterministicConfig) -> Self {
+        Self {
+            rng: ChaCha20Rng::seed_from_u64(config.rng_seed),
+            time_ns: config.clock_start_ns,
+            clock_step_ns: config.clock_step_ns,
+        }
+    }
+
+    /// Returns the current logical time and advances the clock by one step.
+    fn tick(&mut self) -> u64 {
+        let time_ns = self.time_ns;
+        self.time_ns = self.time_ns.saturating_add(self.clock_step_ns);
+        time_ns
+    }
+
+    /// Fills bytes with the next output of the seeded RNG.
+    fn fill_random(&mut self, bytes: &mut [u8]) {
+        self.rng.fill_bytes(bytes);
+    }
+}
+
+/// Returns deterministic replacements of clock_time_get, random_get and poll_oneoff.
+pub(crate) fn deterministic_overrides(state: Arc<Mutex<DeterministicState>>) -> Namespace {
+    use Type::*;
+
+    let mut namespace = Namespace::new();
+
+    let clock_state = state.clone();
+    namespace.insert(
+        "clock_time_get",
+        create_func(vec![I32, I64, I32], move |ctx, inputs| {
+            clock_time_get(ctx, inputs, &clock_state)
+        }),
+    );
+
+    let random_state = state.clone();
+    namespace.insert(
+        "random_get",
+        create_func(vec![I32, I32], move |ctx, inputs| {
+            random_get(ctx, inputs, &random_state)
+        }),
+    );
+
+    namespace.insert(
+        "poll_oneoff",
+        create_func(vec![I32, I32, I32, I32], move |ctx, inputs| {
+            poll_oneoff(ctx, inputs, &state)
+        }),
+    );
+
+    namespace
+}
+
+fn create_func<F>(argument_types: Vec<Type>, func: F) -> DynamicFunc<'static>
+where
+    F: Fn(&mut Ctx, &[WValue]) -> WASIResult<()> + 'static,
+{
+    DynamicFunc::new(
+        Arc::new(FuncSig::new(argument_types, vec![Type::I32])),
+        move |ctx: &mut Ctx, inputs: &[WValue]| -> Vec<WValue> {
+            to_errno_wvalue(func(ctx, inputs))
+        },
+    )
+}
+
+fn clock_time_get(
+    ctx: &mut Ctx,
+    inputs: &[WValue],
+    state: &Mutex<DeterministicState>,
+) -> WASIResult<()> {
+    let time_ptr = match inputs {
+        [WValue::I32(_clock_id), WValue::I64(_precision), WValue::I32(time_ptr)] => {
+            *time_ptr as usize
+        }
+        _ => return Err(__WASI_EINVAL),
+    };
+
+    let time_ns = state.lock().unwrap_or_else(PoisonError::into_inner).tick();
+    write_bytes(ctx, time_ptr, &time_ns.to_le_bytes())
+}
+
+fn random_get(
+    ctx: &mut Ctx,
+    inputs: &[WValue],
+    state: &Mutex<DeterministicState>,
+) -> WASIResult<()> {
+    let (buf, buf_len) = match inputs {
+        [WValue::I32(buf), WValue::I32(buf_len)] => (*buf as usize, *buf_len as usize),
+        _ => return Err(__WASI_EINVAL),
+    };
+
+    let mut random_bytes = vec![0u8; buf_len];
+    state
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .fill_random(&mut random_bytes);
+
+    write_bytes(ctx, buf, &random_bytes)
+}
+
+/// All subscriptions to file descriptors are reported as ready, clock subscriptions
+/// advance the logical clock to the nearest deadline which subscriptions are then reported.
+fn poll_oneoff(
+    ctx: &mut Ctx,
+    inputs: &[WValue],
+    state: &Mutex<DeterministicState>,
+) -> WASIResult<()> {
+    let (subscriptions, events, subscriptions_count, events_count_ptr) = match inputs {
+        [WValue::I32(subscriptions), WValue::I32(events), WValue::I32(subscriptions_count), WValue::I32(events_count_ptr)] => {
+            (
+                *subscriptions as usize,
+                *events as usize,
+                *subscriptions_count as usize,
+                *events_count_ptr as usize,
+            )
+        }
+        _ => return Err(__WASI_EINVAL),
+    };
+
+    let subscriptions = read_bytes(ctx, subscriptions, subscriptions_count * SUBSCRIPTION_SIZE)?;
+    let mut state = state.lock().unwrap_or_else(PoisonError::into_inner);
+
+    let mut fd_subscriptions = Vec::new();
+    let mut clock_subscriptions = Vec::new();
+    for subscription in subscriptions.chunks(SUBSCRIPTION_SIZE) {
+        let userdata = read_u64_le(&subscription[0..8]);
+        match subscription[8] {
+            __WASI_EVENTTYPE_CLOCK => {
+                let timeout = read_u64_le(&subscription[24..32]);
+                let flags = u16::from_le_bytes([subscription[40], subscription[41]]);
+                let deadline = if flags & __WASI_SUBSCRIPTION_CLOCK_ABSTIME != 0 {
+                    timeout
+                } else {
+                    state.time_ns.saturating_add(timeout)
+                };
+                clock_subscriptions.push((userdata, deadline));
+            }
+            event_type => fd_subscriptions.push((userdata, event_type)),
+        }
+    }
+
+    let mut ready_events = fd_subscriptions;
+    // clocks are waited only if there is nothing else to report
+    if ready_events.is_empty() {
+        if let Some(nearest_deadline) = clock_subscriptions.iter().map(|(_, d)| *d).min() {
+            state.time_ns = std::cmp::max(state.time_ns, nearest_deadline);
+            ready_events.extend(
+                clock_subscriptions
+                    .iter()
+                    .filter(|(_, deadline)| *deadline <= state.time_ns)
+                    .map(|(userdata, _)| (*userdata, __WASI_EVENTTYPE_CLOCK)),
+            );
+        }
+    }
+
+    let mut serialized_events = vec![0u8; ready_events.len() * EVENT_SIZE];
+    for ((userdata, event_type), event) in ready_events
+        .iter()
+        .zip(serialized_events.chunks_mut(EVENT_SIZE))
+    {
+        event[0..8].copy_from_slice(&userdata.to_le_bytes());
+        // error is __WASI_ESUCCESS and fd_readwrite fields are zeroed
+        event[10] = *event_type;
+    }
+
+    write_bytes(ctx, events, &serialized_events)?;
+    write_u32(ctx, events_count_ptr, ready_events.len() as u32)
+}
+
+fn read_u64_le(bytes: &[u8]) -> u64 {
+    let mut le_bytes = [0u8; 8];
+    le_bytes.copy_from_slice(&bytes[..8]);
+    u64::from_le_bytes(le_bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DeterministicState;
+    use crate::DeterministicConfig;
+
+    /// Returns results of a fixed sequence of random_get and clock_time_get calls.
+    fn run_calls(config: &DeterministicConfig) -> (Vec<u8>, Vec<u64>) {
+        let mut state = DeterministicState::new(config);
+
+        let mut random_bytes = vec![0u8; 16];
+        state.fill_random(&mut random_bytes[..8]);
+        let first_time = state.tick();
+        state.fill_random(&mut random_bytes[8..]);
+        let times = vec![first_time, state.tick(), state.tick()];
+
+        (random_bytes, times)
+    }
+
+    #[test]
+    fn same_seed_gives_same_results() {
+        let config = DeterministicConfig {
+            rng_seed: 42,
+            clock_start_ns: 1_000,
+            clock_step_ns: 10,
+        };
+
+        let (random_bytes, times) = run_calls(&config);
+        assert_eq!(run_calls(&config), (random_bytes.clone(), times.clone()));
+        assert_eq!(times, vec![1_000, 1_010, 1_020]);
+
+        let other_seed = DeterministicConfig {
+            rng_seed: 43,
+            ..config
+        };
+        assert_ne!(run_calls(&other_seed).0, random_bytes);
+    }
+}
@@ -0,0 +1,220 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::MappedDirPermissions;
+
+use wasmer_wasi::types::__wasi_errno_t;
+use wasmer_wasi::types::__WASI_ENOSPC;
+use wasmer_wasi::types::__WASI_EROFS;
+use serde::Serialize;
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Usage of a restricted mapped directory.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct MappedDirUsage {
+    /// Total size of files in the directory.
+    pub used_bytes: u64,
+
+    /// Maximum total size of files in the directory, if it's limited.
+    pub max_bytes: Option<u64>,
+
+    /// Count of writes rejected because the directory is read-only or its quota is exceeded.
+    pub rejected_writes: u64,
+}
+
+struct RestrictedDir {
+    alias: String,
+    host_path: PathBuf,
+    permissions: MappedDirPermissions,
+    usage: MappedDirUsage,
+}
+
+/// Tracks writes to mapped directories with permissions and enforces them.
+#[derive(Default)]
+pub(crate) struct DirRestrictions {
+    dirs: Vec<RestrictedDir>,
+}
+
+impl DirRestrictions {
+    pub(crate) fn new(
+        mapped_dirs: &HashMap<String, PathBuf>,
+        permissions: &HashMap<String, MappedDirPermissions>,
+    ) -> Self {
+        let dirs = permissions
+            .iter()
+            .filter_map(|(alias, permissions)| {
+                let host_path = mapped_dirs.get(alias)?;
+                let usage = MappedDirUsage {
+                    used_bytes: dir_size(host_path),
+                    max_bytes: permissions.max_bytes,
+                    rejected_writes: 0,
+                };
+
+                Some(RestrictedDir {
+                    alias: alias.clone(),
+                    host_path: host_path.clone(),
+                    permissions: *permissions,
+                    usage,
+                })
+            })
+            .collect();
+
+        Self { dirs }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.dirs.is_empty()
+    }
+
+    /// Checks that a file with the supplied host path could grow by growth bytes
+    /// and accounts this growth in the usage of all directories containing the file.
+    /// Directories are rescanned only if the growth doesn't fit, so files removed or
+    /// truncated by other means than `release` free space no later than it's needed.
+    pub(crate) fn check_write(
+        &mut self,
+        file_path: &Path,
+        growth: u64,
+    ) -> std::result::Result<(), __wasi_errno_t> {
+        let mut dirs = self
+            .dirs
+            .iter_mut()
+            .filter(|dir| file_path.starts_with(&dir.host_path))
+            .collect::<Vec<_>>();
+
+        if let Some(dir) = dirs.iter_mut().find(|dir| dir.permissions.readonly) {
+            dir.usage.rejected_writes += 1;
+            return Err(__WASI_EROFS);
+        }
+
+        for dir in dirs.iter_mut() {
+            if !dir.fits(growth) {
+                // files could be removed since the last check
+                dir.usage.used_bytes = dir_size(&dir.host_path);
+            }
+
+            if !dir.fits(growth) {
+                dir.usage.rejected_writes += 1;
+                return Err(__WASI_ENOSPC);
+            }
+        }
+
+        for dir in dirs {
+            dir.usage.used_bytes += growth;
+        }
+
+        Ok(())
+    }
+
+    /// Checks that a file or a directory of the supplied size could be moved between
+    /// host paths, its size is accounted only in directories containing just one of them.
+    pub(crate) fn check_rename(
+        &mut self,
+        from: &Path,
+        to: &Path,
+        size: u64,
+    ) -> std::result::Result<(), __wasi_errno_t> {
+        if let Some(dir) = self
+            .dirs
+            .iter_mut()
+            .find(|dir| dir.permissions.readonly && (dir.contains(from) || dir.contains(to)))
+        {
+            dir.usage.rejected_writes += 1;
+            return Err(__WASI_EROFS);
+        }
+
+        let mut target_dirs = self
+            .dirs
+            .iter_mut()
+            .filter(|dir| dir.contains(to) && !dir.contains(from))
+            .collect::<Vec<_>>();
+        for dir in target_dirs.iter_mut() {
+            if !dir.fits(size) {
+                dir.usage.used_bytes = dir_size(&dir.host_path);
+            }
+
+            if !dir.fits(size) {
+                dir.usage.rejected_writes += 1;
+                return Err(__WASI_ENOSPC);
+            }
+        }
+
+        for dir in target_dirs {
+            dir.usage.used_bytes += size;
+        }
+        self.dirs
+            .iter_mut()
+            .filter(|dir| dir.contains(from) && !dir.contains(to))
+            .for_each(|dir| dir.usage.used_bytes = dir.usage.used_bytes.saturating_sub(size));
+
+        Ok(())
+    }
+
+    /// Frees space of all directories containing a file with the supplied host path.
+    pub(crate) fn release(&mut self, file_path: &Path, size: u64) {
+        self.dirs
+            .iter_mut()
+            .filter(|dir| file_path.starts_with(&dir.host_path))
+            .for_each(|dir| dir.usage.used_bytes = dir.usage.used_bytes.saturating_sub(size));
+    }
+
+    /// Returns usage of all restricted directories, their sizes are rescanned to take
+    /// removed and truncated files into account.
+    pub(crate) fn usage(&mut self) -> HashMap<String, MappedDirUsage> {
+        self.dirs
+            .iter_mut()
+            .map(|dir| {
+                dir.usage.used_bytes = dir_size(&dir.host_path);
+                (dir.alias.clone(), dir.usage)
+            })
+            .collect()
+    }
+}
+
+impl RestrictedDir {
+    fn contains(&self, path: &Path) -> bool {
+        path.starts_with(&self.host_path)
+    }
+
+    fn fits(&self, growth: u64) -> bool {
+        match self.permissions.max_bytes {
+            Some(max_bytes) => self.usage.used_bytes + growth <= max_bytes,
+            None => true,
+        }
+    }
+}
+
+/// Returns total size of all files inside the directory, unreadable entries are skipped.
+pub(super) fn dir_size(path: &Path) -> u64 {
+    let entries = match std::fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(_) => return 0,
+    };
+
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            if metadata.is_dir() {
+                Some(dir_size(&entry.path()))
+            } else {
+                Some(metadata.len())
+            }
+        })
+        .sum()
+}
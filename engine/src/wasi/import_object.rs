@@ -0,0 +1,252 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use super::DirRestrictions;
+use super::DeterministicState;
+use super::deterministic_overrides;
+use super::memory_fs_overrides;
+use super::restricted_paths_overrides;
+use super::restricted_writes_overrides;
+use super::seed_memory_fs;
+use super::setup_memory_fs;
+use crate::DeterministicConfig;
+use crate::FCEError;
+use crate::MappedDirPermissions;
+use crate::Result;
+use crate::WASIFileSystem;
+
+use wasmer_core::import::LikeNamespace;
+use wasmer_core::import::Namespace;
+use wasmer_runtime::ImportObject;
+use wasmer_wasi::state::WasiState;
+use wasmer_wasi::state::WasiStateBuilder;
+use wasmer_wasi::state::WasiStateCreationError;
+use wasmer_wasi::WasiVersion;
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+/// WASI related parts of a module config.
+#[derive(Clone)]
+pub(crate) struct WASIConfig {
+    pub(crate) version: WasiVersion,
+    pub(crate) args: Vec<Vec<u8>>,
+    pub(crate) envs: HashMap<Vec<u8>, Vec<u8>>,
+    pub(crate) preopened_files: HashSet<PathBuf>,
+    pub(crate) mapped_dirs: HashMap<String, PathBuf>,
+    pub(crate) mapped_dirs_permissions: HashMap<String, MappedDirPermissions>,
+    pub(crate) fs: WASIFileSystem,
+    pub(crate) deterministic: Option<DeterministicConfig>,
+}
+
+/// Creates WASI import object for the supplied filesystem. For the host filesystem
+/// read-only directories are preopened without write rights and functions writing to files,
+/// changing their sizes or creating, removing and renaming them by paths are replaced
+/// to enforce restrictions of mapped directories.
+/// For the in-memory filesystem functions resolving paths are replaced with ones that
+/// never touch the host filesystem.
+/// In the deterministic mode clocks, randomness and polling are replaced as well.
+pub(crate) fn create_wasi_import_object(
+    config: WASIConfig,
+) -> Result<(ImportObject, Arc<Mutex<DirRestrictions>>)> {
+    let mut overrides = Namespace::new();
+
+    let (wasi_state, restrictions) = match &config.fs {
+        WASIFileSystem::Host => {
+            let wasi_state = create_host_fs_state(&config)?;
+            let restrictions =
+                DirRestrictions::new(&config.mapped_dirs, &config.mapped_dirs_permissions);
+            (wasi_state, restrictions)
+        }
+        WASIFileSystem::Memory { seed } => {
+            let has_restrictions = config
+                .mapped_dirs_permissions
+                .values()
+                .any(|permissions| permissions.readonly || permissions.max_bytes.is_some());
+            if has_restrictions {
+                return Err(FCEError::WASIError(String::from(
+                    "read-only and size-limited mapped dirs aren't supported by the in-memory filesystem",
+                )));
+            }
+
+            let wasi_state = create_memory_fs_state(&config, seed.as_deref())?;
+            for (name, export) in memory_fs_overrides(config.version).get_exports() {
+                overrides.insert(name, export);
+            }
+            (wasi_state, DirRestrictions::default())
+        }
+    };
+
+    let mut import_object =
+        wasmer_wasi::generate_import_object_from_state(wasi_state, config.version);
+
+    let has_restrictions = !restrictions.is_empty();
+    let restrictions = Arc::new(Mutex::new(restrictions));
+    if has_restrictions {
+        for (name, export) in restricted_writes_overrides(restrictions.clone()).get_exports() {
+            overrides.insert(name, export);
+        }
+        for (name, export) in restricted_paths_overrides(restrictions.clone()).get_exports() {
+            overrides.insert(name, export);
+        }
+    }
+
+    if let Some(deterministic) = &config.deterministic {
+        if config.version == WasiVersion::Snapshot0 {
+            return Err(FCEError::NondeterministicModule(String::from(
+                "WASI snapshot0 isn't supported, poll_oneoff can't be replaced",
+            )));
+        }
+
+        let state = Arc::new(Mutex::new(DeterministicState::new(deterministic)));
+        for (name, export) in deterministic_overrides(state).get_exports() {
+            overrides.insert(name, export);
+        }
+    }
+    override_wasi_functions(&mut import_object, config.version, overrides);
+
+    Ok((import_object, restrictions))
+}
+
+fn create_host_fs_state(config: &WASIConfig) -> Result<WasiState> {
+    let readonly_dirs = config
+        .mapped_dirs
+        .iter()
+        .filter(|(alias, _)| is_readonly(&config.mapped_dirs_permissions, alias))
+        .map(|(_, path)| canonical_path(path))
+        .collect::<Vec<_>>();
+    // a read-only directory mustn't be reachable by a writable preopen, so preopens
+    // containing read-only directories or contained in them are skipped
+    let preopened_files = config
+        .preopened_files
+        .iter()
+        .filter(|path| {
+            let path = canonical_path(path);
+            !readonly_dirs
+                .iter()
+                .any(|dir| dir.starts_with(&path) || path.starts_with(dir))
+        })
+        .collect::<Vec<_>>();
+
+    let mut state_builder = create_state_builder(config);
+    state_builder
+        .preopen_dirs(preopened_files)
+        .map_err(to_wasi_error)?;
+
+    for (alias, path) in config.mapped_dirs.iter() {
+        let writable = !is_readonly(&config.mapped_dirs_permissions, alias);
+        state_builder
+            .preopen(|dir| {
+                dir.directory(path)
+                    .alias(alias)
+                    .read(true)
+                    .write(writable)
+                    .create(writable)
+            })
+            .map_err(to_wasi_error)?;
+    }
+
+    state_builder.build().map_err(to_wasi_error)
+}
+
+/// Preopened files and mapped dirs become empty in-memory directories,
+/// mapped dirs are located in the root by their aliases.
+fn create_memory_fs_state(config: &WASIConfig, seed: Option<&Path>) -> Result<WasiState> {
+    let memory_fs_error = |e| FCEError::WASIError(format!("in-memory filesystem: {}", e));
+
+    let mut wasi_state = create_state_builder(config)
+        .build()
+        .map_err(to_wasi_error)?;
+
+    let preopened_dirs = config
+        .preopened_files
+        .iter()
+        .map(|path| (path.to_string_lossy().to_string(), path.clone()))
+        .chain(
+            config
+                .mapped_dirs
+                .keys()
+                .map(|alias| (alias.clone(), PathBuf::from(alias))),
+        )
+        .collect::<Vec<_>>();
+
+    if let Some(seed) = seed {
+        seed_memory_fs(&mut wasi_state.fs, seed).map_err(memory_fs_error)?;
+    }
+    setup_memory_fs(&mut wasi_state.fs, &preopened_dirs).map_err(memory_fs_error)?;
+
+    Ok(wasi_state)
+}
+
+fn create_state_builder(config: &WASIConfig) -> WasiStateBuilder {
+    let program_name = config
+        .args
+        .first()
+        .map(|name| String::from_utf8_lossy(name).to_string())
+        .unwrap_or_default();
+
+    let mut state_builder = WasiState::new(&program_name);
+    state_builder
+        .args(config.args.iter().skip(1))
+        .envs(config.envs.iter());
+
+    state_builder
+}
+
+fn to_wasi_error(error: WasiStateCreationError) -> FCEError {
+    FCEError::WASIError(format!("WASI state can't be created: {:?}", error))
+}
+
+/// Replaces functions of the WASI namespace with the supplied ones, other functions are kept.
+fn override_wasi_functions(
+    import_object: &mut ImportObject,
+    version: WasiVersion,
+    overrides: Namespace,
+) {
+    let namespace_name = match version {
+        WasiVersion::Snapshot0 => "wasi_unstable",
+        WasiVersion::Snapshot1 | WasiVersion::Latest => "wasi_snapshot_preview1",
+    };
+
+    let mut namespace = Namespace::new();
+    let wasi_exports = import_object
+        .maybe_with_namespace(namespace_name, |namespace| Some(namespace.get_exports()))
+        .unwrap_or_default();
+    for (name, export) in wasi_exports {
+        namespace.insert(name, export);
+    }
+    for (name, export) in overrides.get_exports() {
+        namespace.insert(name, export);
+    }
+
+    import_object.register(namespace_name, namespace);
+}
+
+/// Returns an absolute path without symlinks if it exists or the path itself otherwise.
+fn canonical_path(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn is_readonly(permissions: &HashMap<String, MappedDirPermissions>, alias: &str) -> bool {
+    permissions
+        .get(alias)
+        .map(|permissions| permissions.readonly)
+        .unwrap_or(false)
+}
@@ -0,0 +1,127 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use wasmer_wasi::state::WasiFile;
+use wasmer_wasi::state::WasiFsError;
+use wasmer_wasi::types::__wasi_filesize_t;
+use wasmer_wasi::types::__wasi_timestamp_t;
+use serde::Deserialize;
+use serde::Serialize;
+
+use std::io;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::io::Write;
+
+/// File of the in-memory WASI filesystem.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct MemoryFile {
+    data: Vec<u8>,
+    position: u64,
+}
+
+impl MemoryFile {
+    pub(crate) fn new(data: Vec<u8>) -> Self {
+        Self { data, position: 0 }
+    }
+}
+
+impl Read for MemoryFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let position = std::cmp::min(self.position, self.data.len() as u64) as usize;
+        let remainder = &self.data[position..];
+        let read_count = std::cmp::min(remainder.len(), buf.len());
+
+        buf[..read_count].copy_from_slice(&remainder[..read_count]);
+        self.position += read_count as u64;
+
+        Ok(read_count)
+    }
+}
+
+impl Write for MemoryFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let position = self.position as usize;
+        let end = position + buf.len();
+        if self.data.len() < end {
+            self.data.resize(end, 0);
+        }
+
+        self.data[position..end].copy_from_slice(buf);
+        self.position = end as u64;
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for MemoryFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => offset as i64,
+            SeekFrom::End(offset) => self.data.len() as i64 + offset,
+            SeekFrom::Current(offset) => self.position as i64 + offset,
+        };
+
+        if position < 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "seek to a negative position",
+            ));
+        }
+
+        self.position = position as u64;
+        Ok(self.position)
+    }
+}
+
+#[typetag::serde]
+impl WasiFile for MemoryFile {
+    fn last_accessed(&self) -> __wasi_timestamp_t {
+        0
+    }
+
+    fn last_modified(&self) -> __wasi_timestamp_t {
+        0
+    }
+
+    fn created_time(&self) -> __wasi_timestamp_t {
+        0
+    }
+
+    fn size(&self) -> u64 {
+        self.data.len() as u64
+    }
+
+    fn set_len(&mut self, new_size: __wasi_filesize_t) -> Result<(), WasiFsError> {
+        self.data.resize(new_size as usize, 0);
+        Ok(())
+    }
+
+    fn unlink(&mut self) -> Result<(), WasiFsError> {
+        self.data.clear();
+        self.position = 0;
+        Ok(())
+    }
+
+    fn bytes_available(&self) -> Result<usize, WasiFsError> {
+        Ok(self.data.len().saturating_sub(self.position as usize))
+    }
+}
@@ -0,0 +1,302 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use super::MemoryFile;
+use super::utils::WASIResult;
+
+use wasmer_wasi::state::Fd;
+use wasmer_wasi::state::Inode;
+use wasmer_wasi::state::Kind;
+use wasmer_wasi::state::WasiFs;
+use wasmer_wasi::types::*;
+
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+
+pub(super) const ALL_RIGHTS: __wasi_rights_t = 0x1FFF_FFFF;
+
+/// Makes the virtual root of the filesystem and the supplied directories preopened,
+/// directories are created in memory and preopened under the supplied names.
+pub(crate) fn setup_memory_fs(
+    fs: &mut WasiFs,
+    preopened_dirs: &[(String, PathBuf)],
+) -> Result<(), String> {
+    let root = root_inode(fs).ok_or_else(|| String::from("virtual root isn't found"))?;
+    preopen_inode(fs, root)?;
+
+    for (name, path) in preopened_dirs {
+        let inode = create_dir_all(fs, root, &normalize_path(path))?;
+        fs.inodes[inode].name = name.clone();
+        preopen_inode(fs, inode)?;
+    }
+
+    Ok(())
+}
+
+/// Copies content of a host directory or a tar archive to the root of the in-memory filesystem.
+pub(crate) fn seed_memory_fs(fs: &mut WasiFs, seed: &Path) -> Result<(), String> {
+    let root = root_inode(fs).ok_or_else(|| String::from("virtual root isn't found"))?;
+
+    if seed.is_dir() {
+        seed_from_dir(fs, root, seed, seed)
+    } else {
+        seed_from_tar(fs, root, seed)
+    }
+}
+
+fn seed_from_dir(fs: &mut WasiFs, root: Inode, seed: &Path, dir: &Path) -> Result<(), String> {
+    let to_error = |e: std::io::Error| format!("{:?}: {}", dir, e);
+
+    for entry in std::fs::read_dir(dir).map_err(to_error)? {
+        let entry = entry.map_err(to_error)?;
+        let host_path = entry.path();
+        // strip_prefix can't fail here, because all paths are inside the seed directory
+        let path = normalize_path(host_path.strip_prefix(seed).unwrap_or(&host_path));
+
+        if entry.file_type().map_err(to_error)?.is_dir() {
+            create_dir_all(fs, root, &path)?;
+            seed_from_dir(fs, root, seed, &host_path)?;
+        } else {
+            let data = std::fs::read(&host_path).map_err(to_error)?;
+            create_file_at_path(fs, root, &path, data)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn seed_from_tar(fs: &mut WasiFs, root: Inode, seed: &Path) -> Result<(), String> {
+    let to_error = |e: std::io::Error| format!("{:?}: {}", seed, e);
+
+    let archive = std::fs::File::open(seed).map_err(to_error)?;
+    let mut archive = tar::Archive::new(archive);
+
+    for entry in archive.entries().map_err(to_error)? {
+        let mut entry = entry.map_err(to_error)?;
+        let path = normalize_path(&entry.path().map_err(to_error)?);
+
+        match entry.header().entry_type() {
+            tar::EntryType::Directory => {
+                create_dir_all(fs, root, &path)?;
+            }
+            tar::EntryType::Regular => {
+                let mut data = Vec::new();
+                entry.read_to_end(&mut data).map_err(to_error)?;
+                create_file_at_path(fs, root, &path, data)?;
+            }
+            // links and special files can't be represented in memory
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns paths and sizes of all files of the in-memory filesystem.
+pub(crate) fn memory_fs_files(fs: &WasiFs) -> Vec<(PathBuf, u64)> {
+    fn collect(fs: &WasiFs, inode: Inode, path: PathBuf, files: &mut Vec<(PathBuf, u64)>) {
+        match &fs.inodes[inode].kind {
+            Kind::Dir { entries, .. } | Kind::Root { entries } => {
+                for (name, inode) in entries {
+                    collect(fs, *inode, path.join(name), files);
+                }
+            }
+            Kind::File {
+                handle: Some(handle),
+                ..
+            } => files.push((path, handle.size())),
+            _ => {}
+        }
+    }
+
+    let mut files = Vec::new();
+    if let Some(root) = root_inode(fs) {
+        collect(fs, root, PathBuf::from("/"), &mut files);
+    }
+    files.sort();
+
+    files
+}
+
+/// Returns content of a file of the in-memory filesystem.
+pub(crate) fn read_memory_fs_file(fs: &mut WasiFs, path: &Path) -> Result<Vec<u8>, String> {
+    let root = root_inode(fs).ok_or_else(|| String::from("virtual root isn't found"))?;
+    let inode = resolve_path(fs, root, &normalize_path(path))
+        .map_err(|_| format!("{:?} isn't found", path))?;
+
+    match &mut fs.inodes[inode].kind {
+        Kind::File {
+            handle: Some(handle),
+            ..
+        } => {
+            let to_error = |e: std::io::Error| format!("{:?}: {}", path, e);
+
+            let position = handle.seek(SeekFrom::Current(0)).map_err(to_error)?;
+            handle.seek(SeekFrom::Start(0)).map_err(to_error)?;
+            let mut data = Vec::new();
+            let result = handle.read_to_end(&mut data).map_err(to_error);
+            handle.seek(SeekFrom::Start(position)).map_err(to_error)?;
+
+            result.map(|_| data)
+        }
+        _ => Err(format!("{:?} isn't a file", path)),
+    }
+}
+
+/// Converts a path to components relative to the root, f.e. "./a/../b" to ["b"].
+pub(super) fn normalize_path(path: &Path) -> Vec<String> {
+    let mut components = Vec::new();
+
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => components.push(name.to_string_lossy().to_string()),
+            Component::ParentDir => {
+                components.pop();
+            }
+            Component::RootDir | Component::CurDir | Component::Prefix(_) => {}
+        }
+    }
+
+    components
+}
+
+pub(super) fn root_inode(fs: &WasiFs) -> Option<Inode> {
+    fs.inodes
+        .iter()
+        .find(|(_, inode)| matches!(inode.kind, Kind::Root { .. }))
+        .map(|(inode, _)| inode)
+}
+
+pub(super) fn dir_entry(fs: &WasiFs, dir: Inode, name: &str) -> Option<Inode> {
+    match &fs.inodes[dir].kind {
+        Kind::Dir { entries, .. } | Kind::Root { entries } => entries.get(name).copied(),
+        _ => None,
+    }
+}
+
+pub(super) fn is_dir(fs: &WasiFs, inode: Inode) -> bool {
+    matches!(fs.inodes[inode].kind, Kind::Dir { .. } | Kind::Root { .. })
+}
+
+/// Resolves components relative to the supplied directory.
+pub(super) fn resolve_path(fs: &WasiFs, dir: Inode, components: &[String]) -> WASIResult<Inode> {
+    let mut inode = dir;
+    for name in components {
+        if !is_dir(fs, inode) {
+            return Err(__WASI_ENOTDIR);
+        }
+        inode = dir_entry(fs, inode, name).ok_or(__WASI_ENOENT)?;
+    }
+
+    Ok(inode)
+}
+
+/// Creates all missing directories from the supplied components, returns the last one.
+pub(super) fn create_dir_all(
+    fs: &mut WasiFs,
+    dir: Inode,
+    components: &[String],
+) -> Result<Inode, String> {
+    let mut inode = dir;
+    for name in components {
+        inode = match dir_entry(fs, inode, name) {
+            Some(entry) if is_dir(fs, entry) => entry,
+            Some(_) => return Err(format!("{} already exists and isn't a directory", name)),
+            None => with_temp_fd(fs, inode, |fs, fd| {
+                fs.open_dir_all(fd, name.clone(), ALL_RIGHTS, ALL_RIGHTS, 0)
+                    .map_err(|e| format!("directory {} can't be created: {:?}", name, e))
+            })?,
+        };
+    }
+
+    Ok(inode)
+}
+
+/// Creates a new file with the supplied content in the directory.
+pub(super) fn create_file(
+    fs: &mut WasiFs,
+    dir: Inode,
+    name: &str,
+    data: Vec<u8>,
+) -> Result<Inode, String> {
+    with_temp_fd(fs, dir, |fs, fd| {
+        fs.open_file_at(
+            fd,
+            Box::new(MemoryFile::new(data)),
+            Fd::READ | Fd::WRITE,
+            name.to_string(),
+            ALL_RIGHTS,
+            ALL_RIGHTS,
+            0,
+        )
+        .map_err(|e| format!("file {} can't be created: {:?}", name, e))
+    })
+}
+
+fn create_file_at_path(
+    fs: &mut WasiFs,
+    root: Inode,
+    path: &[String],
+    data: Vec<u8>,
+) -> Result<(), String> {
+    let (name, dir_path) = match path.split_last() {
+        Some(split) => split,
+        None => return Ok(()),
+    };
+
+    let dir = create_dir_all(fs, root, dir_path)?;
+    create_file(fs, dir, name, data).map(|_| ())
+}
+
+/// Opens a temporary descriptor of the directory for WasiFs functions working with descriptors,
+/// returns the inode of the descriptor returned by the supplied function.
+fn with_temp_fd<F>(fs: &mut WasiFs, dir: Inode, f: F) -> Result<Inode, String>
+where
+    F: FnOnce(&mut WasiFs, __wasi_fd_t) -> Result<__wasi_fd_t, String>,
+{
+    let dir_fd = fs
+        .create_fd(ALL_RIGHTS, ALL_RIGHTS, 0, Fd::READ, dir)
+        .map_err(|e| format!("descriptor can't be created: {}", e))?;
+
+    let result = f(fs, dir_fd);
+    fs.fd_map.remove(&dir_fd);
+
+    let fd = result?;
+    let inode = fs.fd_map.remove(&fd).map(|fd| fd.inode);
+    inode.ok_or_else(|| String::from("descriptor isn't found"))
+}
+
+fn preopen_inode(fs: &mut WasiFs, inode: Inode) -> Result<(), String> {
+    let already_preopened = fs
+        .preopen_fds
+        .iter()
+        .any(|fd| fs.fd_map.get(fd).map(|fd| fd.inode) == Some(inode));
+    if already_preopened {
+        return Ok(());
+    }
+
+    let fd = fs
+        .create_fd(ALL_RIGHTS, ALL_RIGHTS, 0, Fd::READ | Fd::WRITE, inode)
+        .map_err(|e| format!("descriptor can't be created: {}", e))?;
+    fs.inodes[inode].is_preopened = true;
+    fs.preopen_fds.push(fd);
+
+    Ok(())
+}
@@ -0,0 +1,731 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Replacements of WASI functions that resolve paths in the host filesystem
+//! with ones working only with the in-memory filesystem.
+
+use super::memory_fs::*;
+use super::utils::*;
+
+use wasmer_core::import::Namespace;
+use wasmer_core::vm::Ctx;
+use wasmer_core::typed_func::DynamicFunc;
+use wasmer_core::types::Value as WValue;
+use wasmer_core::types::FuncSig;
+use wasmer_core::types::Type;
+use wasmer_wasi::state::get_wasi_state;
+use wasmer_wasi::state::Fd;
+use wasmer_wasi::state::Inode;
+use wasmer_wasi::state::Kind;
+use wasmer_wasi::state::WasiFs;
+use wasmer_wasi::types::*;
+use wasmer_wasi::WasiVersion;
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+const DIRENT_PADDING_SIZE: usize = 3;
+
+/// Returns replacements of all WASI functions resolving paths, the in-memory filesystem
+/// doesn't support links, so functions creating and reading them fail with ENOTCAPABLE.
+pub(crate) fn memory_fs_overrides(version: WasiVersion) -> Namespace {
+    use Type::*;
+
+    let mut namespace = Namespace::new();
+    namespace.insert(
+        "path_open",
+        create_func(vec![I32, I32, I32, I32, I32, I64, I64, I32, I32], path_open),
+    );
+    namespace.insert(
+        "path_create_directory",
+        create_func(vec![I32, I32, I32], path_create_directory),
+    );
+    namespace.insert(
+        "fd_readdir",
+        create_func(vec![I32, I32, I32, I64, I32], fd_readdir),
+    );
+    namespace.insert(
+        "path_filestat_get",
+        create_func(vec![I32; 5], move |ctx, inputs| {
+            path_filestat_get(ctx, inputs, version)
+        }),
+    );
+    namespace.insert(
+        "path_filestat_set_times",
+        create_func(
+            vec![I32, I32, I32, I32, I64, I64, I32],
+            path_filestat_set_times,
+        ),
+    );
+    namespace.insert(
+        "path_unlink_file",
+        create_func(vec![I32; 3], path_unlink_file),
+    );
+    namespace.insert(
+        "path_remove_directory",
+        create_func(vec![I32; 3], path_remove_directory),
+    );
+    namespace.insert("path_rename", create_func(vec![I32; 6], path_rename));
+    namespace.insert("path_link", create_func(vec![I32; 7], not_capable));
+    namespace.insert("path_readlink", create_func(vec![I32; 6], not_capable));
+    namespace.insert("path_symlink", create_func(vec![I32; 5], not_capable));
+
+    namespace
+}
+
+fn create_func<F>(argument_types: Vec<Type>, func: F) -> DynamicFunc<'static>
+where
+    F: Fn(&mut Ctx, &[WValue]) -> WASIResult<()> + 'static,
+{
+    DynamicFunc::new(
+        Arc::new(FuncSig::new(argument_types, vec![Type::I32])),
+        move |ctx: &mut Ctx, inputs: &[WValue]| -> Vec<WValue> {
+            to_errno_wvalue(func(ctx, inputs))
+        },
+    )
+}
+
+fn path_open(ctx: &mut Ctx, inputs: &[WValue]) -> WASIResult<()> {
+    let (dir_fd, path, path_len, o_flags, rights, rights_inheriting, fs_flags, fd_out) =
+        match inputs {
+            [WValue::I32(dir_fd), WValue::I32(_dirflags), WValue::I32(path), WValue::I32(path_len), WValue::I32(o_flags), WValue::I64(rights), WValue::I64(rights_inheriting), WValue::I32(fs_flags), WValue::I32(fd_out)] => {
+                (
+                    *dir_fd as __wasi_fd_t,
+                    *path as usize,
+                    *path_len as usize,
+                    *o_flags as __wasi_oflags_t,
+                    *rights as __wasi_rights_t,
+                    *rights_inheriting as __wasi_rights_t,
+                    *fs_flags as __wasi_fdflags_t,
+                    *fd_out as usize,
+                )
+            }
+            _ => return Err(__WASI_EINVAL),
+        };
+
+    let path = read_path(ctx, path, path_len)?;
+    let state = unsafe { get_wasi_state(ctx) };
+    let fd = open(
+        &mut state.fs,
+        dir_fd,
+        &path,
+        o_flags,
+        rights,
+        rights_inheriting,
+        fs_flags,
+    )?;
+
+    write_u32(ctx, fd_out, fd)
+}
+
+fn path_create_directory(ctx: &mut Ctx, inputs: &[WValue]) -> WASIResult<()> {
+    let (dir_fd, path) = read_dir_fd_and_path(ctx, inputs)?;
+    let state = unsafe { get_wasi_state(ctx) };
+    create_directory(&mut state.fs, dir_fd, &path)
+}
+
+fn fd_readdir(ctx: &mut Ctx, inputs: &[WValue]) -> WASIResult<()> {
+    let (fd, buf, buf_len, cookie, buf_used) = match inputs {
+        [WValue::I32(fd), WValue::I32(buf), WValue::I32(buf_len), WValue::I64(cookie), WValue::I32(buf_used)] => {
+            (
+                *fd as __wasi_fd_t,
+                *buf as usize,
+                *buf_len as usize,
+                *cookie as u64,
+                *buf_used as usize,
+            )
+        }
+        _ => return Err(__WASI_EINVAL),
+    };
+
+    let state = unsafe { get_wasi_state(ctx) };
+    let fs = &state.fs;
+
+    let dir = dir_inode(fs, fd, __WASI_RIGHT_FD_READDIR)?;
+    let mut entries = match &fs.inodes[dir].kind {
+        Kind::Dir { entries, .. } | Kind::Root { entries } => entries
+            .iter()
+            .map(|(name, inode)| (name.clone(), *inode))
+            .collect::<Vec<_>>(),
+        _ => return Err(__WASI_ENOTDIR),
+    };
+    entries.sort();
+
+    // dirents are serialized one by one, the last one could be truncated as WASI allows
+    let mut dirents = Vec::new();
+    for (id, (name, inode)) in entries.iter().enumerate().skip(cookie as usize) {
+        if dirents.len() >= buf_len {
+            break;
+        }
+
+        let file_type = if is_dir(fs, *inode) {
+            __WASI_FILETYPE_DIRECTORY
+        } else {
+            __WASI_FILETYPE_REGULAR_FILE
+        };
+
+        dirents.extend_from_slice(&(id as u64 + 1).to_le_bytes());
+        dirents.extend_from_slice(&fs.inodes[*inode].stat.st_ino.to_le_bytes());
+        dirents.extend_from_slice(&(name.len() as u32).to_le_bytes());
+        dirents.push(file_type);
+        // padding of __wasi_dirent_t
+        dirents.resize(dirents.len() + DIRENT_PADDING_SIZE, 0);
+        dirents.extend_from_slice(name.as_bytes());
+    }
+    dirents.truncate(buf_len);
+
+    write_bytes(ctx, buf, &dirents)?;
+    write_u32(ctx, buf_used, dirents.len() as u32)
+}
+
+fn path_filestat_get(ctx: &mut Ctx, inputs: &[WValue], version: WasiVersion) -> WASIResult<()> {
+    let (dir_fd, path, path_len, buf) = match inputs {
+        [WValue::I32(dir_fd), WValue::I32(_flags), WValue::I32(path), WValue::I32(path_len), WValue::I32(buf)] => {
+            (
+                *dir_fd as __wasi_fd_t,
+                *path as usize,
+                *path_len as usize,
+                *buf as usize,
+            )
+        }
+        _ => return Err(__WASI_EINVAL),
+    };
+
+    let path = read_path(ctx, path, path_len)?;
+    let state = unsafe { get_wasi_state(ctx) };
+    let stat = filestat(&mut state.fs, dir_fd, &path)?;
+
+    write_bytes(ctx, buf, &serialize_filestat(&stat, version))
+}
+
+fn path_filestat_set_times(ctx: &mut Ctx, inputs: &[WValue]) -> WASIResult<()> {
+    let (dir_fd, path, path_len, atim, mtim, fst_flags) = match inputs {
+        [WValue::I32(dir_fd), WValue::I32(_flags), WValue::I32(path), WValue::I32(path_len), WValue::I64(atim), WValue::I64(mtim), WValue::I32(fst_flags)] => {
+            (
+                *dir_fd as __wasi_fd_t,
+                *path as usize,
+                *path_len as usize,
+                *atim as __wasi_timestamp_t,
+                *mtim as __wasi_timestamp_t,
+                *fst_flags as __wasi_fstflags_t,
+            )
+        }
+        _ => return Err(__WASI_EINVAL),
+    };
+
+    let path = read_path(ctx, path, path_len)?;
+    let state = unsafe { get_wasi_state(ctx) };
+    set_times(&mut state.fs, dir_fd, &path, atim, mtim, fst_flags)
+}
+
+fn path_unlink_file(ctx: &mut Ctx, inputs: &[WValue]) -> WASIResult<()> {
+    let (dir_fd, path) = read_dir_fd_and_path(ctx, inputs)?;
+    let state = unsafe { get_wasi_state(ctx) };
+    unlink_file(&mut state.fs, dir_fd, &path)
+}
+
+fn path_remove_directory(ctx: &mut Ctx, inputs: &[WValue]) -> WASIResult<()> {
+    let (dir_fd, path) = read_dir_fd_and_path(ctx, inputs)?;
+    let state = unsafe { get_wasi_state(ctx) };
+    remove_directory(&mut state.fs, dir_fd, &path)
+}
+
+fn path_rename(ctx: &mut Ctx, inputs: &[WValue]) -> WASIResult<()> {
+    let (old_dir_fd, old_path, old_path_len, new_dir_fd, new_path, new_path_len) = match inputs {
+        [WValue::I32(old_dir_fd), WValue::I32(old_path), WValue::I32(old_path_len), WValue::I32(new_dir_fd), WValue::I32(new_path), WValue::I32(new_path_len)] => {
+            (
+                *old_dir_fd as __wasi_fd_t,
+                *old_path as usize,
+                *old_path_len as usize,
+                *new_dir_fd as __wasi_fd_t,
+                *new_path as usize,
+                *new_path_len as usize,
+            )
+        }
+        _ => return Err(__WASI_EINVAL),
+    };
+
+    let old_path = read_path(ctx, old_path, old_path_len)?;
+    let new_path = read_path(ctx, new_path, new_path_len)?;
+    let state = unsafe { get_wasi_state(ctx) };
+    rename(&mut state.fs, old_dir_fd, &old_path, new_dir_fd, &new_path)
+}
+
+fn not_capable(_ctx: &mut Ctx, _inputs: &[WValue]) -> WASIResult<()> {
+    Err(__WASI_ENOTCAPABLE)
+}
+
+fn open(
+    fs: &mut WasiFs,
+    dir_fd: __wasi_fd_t,
+    path: &str,
+    o_flags: __wasi_oflags_t,
+    rights: __wasi_rights_t,
+    rights_inheriting: __wasi_rights_t,
+    fs_flags: __wasi_fdflags_t,
+) -> WASIResult<__wasi_fd_t> {
+    let dir = dir_inode(fs, dir_fd, __WASI_RIGHT_PATH_OPEN)?;
+    let components = normalize_path(Path::new(path));
+    let inode = match components.split_last() {
+        None => dir,
+        Some((name, dir_path)) => {
+            let dir = resolve_path(fs, dir, dir_path)?;
+            match dir_entry(fs, dir, name) {
+                Some(_) if o_flags & __WASI_O_CREAT != 0 && o_flags & __WASI_O_EXCL != 0 => {
+                    return Err(__WASI_EEXIST)
+                }
+                Some(inode) => inode,
+                None if o_flags & __WASI_O_CREAT != 0 => {
+                    create_file(fs, dir, name, vec![]).map_err(|_| __WASI_EIO)?
+                }
+                None => return Err(__WASI_ENOENT),
+            }
+        }
+    };
+
+    if o_flags & __WASI_O_DIRECTORY != 0 && !is_dir(fs, inode) {
+        return Err(__WASI_ENOTDIR);
+    }
+
+    let mut size = 0;
+    if let Kind::File {
+        handle: Some(handle),
+        ..
+    } = &mut fs.inodes[inode].kind
+    {
+        if o_flags & __WASI_O_TRUNC != 0 {
+            handle.set_len(0).map_err(|_| __WASI_EIO)?;
+        }
+        size = handle.size();
+    }
+
+    let mut open_flags = 0;
+    if rights & __WASI_RIGHT_FD_READ != 0 {
+        open_flags |= Fd::READ;
+    }
+    if rights & __WASI_RIGHT_FD_WRITE != 0 {
+        open_flags |= Fd::WRITE;
+    }
+
+    let fd = fs.create_fd(rights, rights_inheriting, fs_flags, open_flags, inode)?;
+    if fs_flags & __WASI_FDFLAG_APPEND != 0 {
+        if let Some(fd) = fs.fd_map.get_mut(&fd) {
+            fd.offset = size;
+        }
+    }
+
+    Ok(fd)
+}
+
+fn create_directory(fs: &mut WasiFs, dir_fd: __wasi_fd_t, path: &str) -> WASIResult<()> {
+    let (dir, name) = resolve_parent(fs, dir_fd, path, __WASI_RIGHT_PATH_CREATE_DIRECTORY)?;
+    if dir_entry(fs, dir, &name).is_some() {
+        return Err(__WASI_EEXIST);
+    }
+
+    create_dir_all(fs, dir, &[name])
+        .map(|_| ())
+        .map_err(|_| __WASI_EIO)
+}
+
+fn filestat(fs: &mut WasiFs, dir_fd: __wasi_fd_t, path: &str) -> WASIResult<__wasi_filestat_t> {
+    let inode = resolve_inode(fs, dir_fd, path, __WASI_RIGHT_PATH_FILESTAT_GET)?;
+
+    let inode = &fs.inodes[inode];
+    let mut stat = inode.stat;
+    if let Kind::File {
+        handle: Some(handle),
+        ..
+    } = &inode.kind
+    {
+        stat.st_size = handle.size();
+    }
+
+    Ok(stat)
+}
+
+fn set_times(
+    fs: &mut WasiFs,
+    dir_fd: __wasi_fd_t,
+    path: &str,
+    atim: __wasi_timestamp_t,
+    mtim: __wasi_timestamp_t,
+    fst_flags: __wasi_fstflags_t,
+) -> WASIResult<()> {
+    let inode = resolve_inode(fs, dir_fd, path, __WASI_RIGHT_PATH_FILESTAT_SET_TIMES)?;
+    let now = || {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_nanos() as __wasi_timestamp_t)
+            .unwrap_or_default()
+    };
+
+    let stat = &mut fs.inodes[inode].stat;
+    if fst_flags & __WASI_FILESTAT_SET_ATIM != 0 {
+        stat.st_atim = atim;
+    } else if fst_flags & __WASI_FILESTAT_SET_ATIM_NOW != 0 {
+        stat.st_atim = now();
+    }
+
+    if fst_flags & __WASI_FILESTAT_SET_MTIM != 0 {
+        stat.st_mtim = mtim;
+    } else if fst_flags & __WASI_FILESTAT_SET_MTIM_NOW != 0 {
+        stat.st_mtim = now();
+    }
+
+    Ok(())
+}
+
+fn unlink_file(fs: &mut WasiFs, dir_fd: __wasi_fd_t, path: &str) -> WASIResult<()> {
+    let (dir, name) = resolve_parent(fs, dir_fd, path, __WASI_RIGHT_PATH_UNLINK_FILE)?;
+    let inode = dir_entry(fs, dir, &name).ok_or(__WASI_ENOENT)?;
+    if is_dir(fs, inode) {
+        return Err(__WASI_EISDIR);
+    }
+
+    // the inode is kept while it's opened by descriptors
+    dir_entries_mut(fs, dir)?.remove(&name);
+    Ok(())
+}
+
+fn remove_directory(fs: &mut WasiFs, dir_fd: __wasi_fd_t, path: &str) -> WASIResult<()> {
+    let (dir, name) = resolve_parent(fs, dir_fd, path, __WASI_RIGHT_PATH_REMOVE_DIRECTORY)?;
+    let inode = dir_entry(fs, dir, &name).ok_or(__WASI_ENOENT)?;
+    if !is_dir(fs, inode) {
+        return Err(__WASI_ENOTDIR);
+    }
+    if !dir_entries_mut(fs, inode)?.is_empty() {
+        return Err(__WASI_ENOTEMPTY);
+    }
+
+    dir_entries_mut(fs, dir)?.remove(&name);
+    Ok(())
+}
+
+fn rename(
+    fs: &mut WasiFs,
+    old_dir_fd: __wasi_fd_t,
+    old_path: &str,
+    new_dir_fd: __wasi_fd_t,
+    new_path: &str,
+) -> WASIResult<()> {
+    let (old_dir, old_name) =
+        resolve_parent(fs, old_dir_fd, old_path, __WASI_RIGHT_PATH_RENAME_SOURCE)?;
+    let (new_dir, new_name) =
+        resolve_parent(fs, new_dir_fd, new_path, __WASI_RIGHT_PATH_RENAME_TARGET)?;
+
+    let inode = dir_entry(fs, old_dir, &old_name).ok_or(__WASI_ENOENT)?;
+    let is_moved_dir = is_dir(fs, inode);
+    // a directory can't be moved inside itself
+    if is_moved_dir && is_ancestor(fs, inode, new_dir) {
+        return Err(__WASI_EINVAL);
+    }
+
+    if let Some(target) = dir_entry(fs, new_dir, &new_name) {
+        if target == inode {
+            return Ok(());
+        }
+
+        match (is_moved_dir, is_dir(fs, target)) {
+            (true, false) => return Err(__WASI_ENOTDIR),
+            (false, true) => return Err(__WASI_EISDIR),
+            (true, true) if !dir_entries_mut(fs, target)?.is_empty() => {
+                return Err(__WASI_ENOTEMPTY)
+            }
+            _ => {}
+        }
+    }
+
+    dir_entries_mut(fs, old_dir)?.remove(&old_name);
+    dir_entries_mut(fs, new_dir)?.insert(new_name.clone(), inode);
+
+    let inode = &mut fs.inodes[inode];
+    inode.name = new_name;
+    if let Kind::Dir { parent, .. } = &mut inode.kind {
+        *parent = Some(new_dir);
+    }
+
+    Ok(())
+}
+
+/// Returns an inode of the directory opened by the supplied descriptor with the required right.
+fn dir_inode(fs: &WasiFs, fd: __wasi_fd_t, right: __wasi_rights_t) -> WASIResult<Inode> {
+    let fd = fs.fd_map.get(&fd).ok_or(__WASI_EBADF)?;
+    if fd.rights & right == 0 {
+        return Err(__WASI_EACCES);
+    }
+
+    if !is_dir(fs, fd.inode) {
+        return Err(__WASI_ENOTDIR);
+    }
+
+    Ok(fd.inode)
+}
+
+/// Resolves a path relative to the directory opened by the supplied descriptor.
+fn resolve_inode(
+    fs: &WasiFs,
+    dir_fd: __wasi_fd_t,
+    path: &str,
+    right: __wasi_rights_t,
+) -> WASIResult<Inode> {
+    let dir = dir_inode(fs, dir_fd, right)?;
+    resolve_path(fs, dir, &normalize_path(Path::new(path)))
+}
+
+/// Resolves a path relative to the directory opened by the supplied descriptor
+/// to the directory containing its last component and the name of this component.
+fn resolve_parent(
+    fs: &WasiFs,
+    dir_fd: __wasi_fd_t,
+    path: &str,
+    right: __wasi_rights_t,
+) -> WASIResult<(Inode, String)> {
+    let dir = dir_inode(fs, dir_fd, right)?;
+    let mut components = normalize_path(Path::new(path));
+    // the path refers to the directory itself
+    let name = components.pop().ok_or(__WASI_EINVAL)?;
+    let dir = resolve_path(fs, dir, &components)?;
+    if !is_dir(fs, dir) {
+        return Err(__WASI_ENOTDIR);
+    }
+
+    Ok((dir, name))
+}
+
+fn dir_entries_mut(fs: &mut WasiFs, dir: Inode) -> WASIResult<&mut HashMap<String, Inode>> {
+    match &mut fs.inodes[dir].kind {
+        Kind::Dir { entries, .. } | Kind::Root { entries } => Ok(entries),
+        _ => Err(__WASI_ENOTDIR),
+    }
+}
+
+/// Checks whether the supplied directory is the inode itself or one of its parents.
+fn is_ancestor(fs: &WasiFs, dir: Inode, inode: Inode) -> bool {
+    let mut current = Some(inode);
+    while let Some(inode) = current {
+        if inode == dir {
+            return true;
+        }
+
+        current = match &fs.inodes[inode].kind {
+            Kind::Dir { parent, .. } => *parent,
+            _ => None,
+        };
+    }
+
+    false
+}
+
+/// Serializes __wasi_filestat_t, its layout differs between WASI versions by the link count size.
+fn serialize_filestat(stat: &__wasi_filestat_t, version: WasiVersion) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(&stat.st_dev.to_le_bytes());
+    bytes.extend_from_slice(&stat.st_ino.to_le_bytes());
+    bytes.push(stat.st_filetype);
+
+    match version {
+        WasiVersion::Snapshot0 => {
+            bytes.resize(20, 0);
+            bytes.extend_from_slice(&(stat.st_nlink as u32).to_le_bytes());
+        }
+        _ => {
+            bytes.resize(24, 0);
+            bytes.extend_from_slice(&stat.st_nlink.to_le_bytes());
+        }
+    }
+
+    bytes.extend_from_slice(&stat.st_size.to_le_bytes());
+    bytes.extend_from_slice(&stat.st_atim.to_le_bytes());
+    bytes.extend_from_slice(&stat.st_mtim.to_le_bytes());
+    bytes.extend_from_slice(&stat.st_ctim.to_le_bytes());
+
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::create_directory;
+    use super::filestat;
+    use super::open;
+    use super::remove_directory;
+    use super::rename;
+    use super::serialize_filestat;
+    use super::set_times;
+    use super::unlink_file;
+    use crate::wasi::memory_fs::ALL_RIGHTS;
+    use crate::wasi::memory_fs_files;
+    use crate::wasi::setup_memory_fs;
+
+    use wasmer_wasi::state::Kind;
+    use wasmer_wasi::state::WasiFs;
+    use wasmer_wasi::state::WasiState;
+    use wasmer_wasi::types::*;
+    use wasmer_wasi::WasiVersion;
+
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    /// Creates an in-memory filesystem with the `data` dir, returns it with the dir descriptor.
+    fn create_memory_fs() -> (WasiState, __wasi_fd_t) {
+        let mut state = WasiState::new("test").build().unwrap();
+        let preopened_dirs = vec![(String::from("data"), PathBuf::from("data"))];
+        setup_memory_fs(&mut state.fs, &preopened_dirs).unwrap();
+        let data_fd = *state.fs.preopen_fds.last().unwrap();
+
+        (state, data_fd)
+    }
+
+    fn create_data_file(fs: &mut WasiFs, data_fd: __wasi_fd_t, path: &str, content: &[u8]) {
+        let fd = open(fs, data_fd, path, __WASI_O_CREAT, ALL_RIGHTS, ALL_RIGHTS, 0).unwrap();
+        let inode = fs.fd_map[&fd].inode;
+        if let Kind::File {
+            handle: Some(handle),
+            ..
+        } = &mut fs.inodes[inode].kind
+        {
+            handle.write_all(content).unwrap();
+        }
+    }
+
+    fn files(fs: &WasiFs) -> Vec<PathBuf> {
+        memory_fs_files(fs)
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect()
+    }
+
+    #[test]
+    fn files_are_renamed_and_removed() {
+        let (mut state, data_fd) = create_memory_fs();
+        let fs = &mut state.fs;
+
+        assert_eq!(create_directory(fs, data_fd, "dir"), Ok(()));
+        create_data_file(fs, data_fd, "dir/file", b"content");
+
+        let stat = filestat(fs, data_fd, "dir/file").unwrap();
+        assert_eq!(stat.st_filetype, __WASI_FILETYPE_REGULAR_FILE);
+        assert_eq!(stat.st_size, 7);
+
+        assert_eq!(rename(fs, data_fd, "dir/file", data_fd, "moved"), Ok(()));
+        assert_eq!(
+            filestat(fs, data_fd, "dir/file").map(|_| ()),
+            Err(__WASI_ENOENT)
+        );
+        assert_eq!(files(fs), vec![PathBuf::from("/data/moved")]);
+
+        assert_eq!(remove_directory(fs, data_fd, "moved"), Err(__WASI_ENOTDIR));
+        assert_eq!(unlink_file(fs, data_fd, "dir"), Err(__WASI_EISDIR));
+
+        assert_eq!(remove_directory(fs, data_fd, "dir"), Ok(()));
+        assert_eq!(unlink_file(fs, data_fd, "moved"), Ok(()));
+        assert_eq!(
+            filestat(fs, data_fd, "moved").map(|_| ()),
+            Err(__WASI_ENOENT)
+        );
+        assert!(files(fs).is_empty());
+    }
+
+    #[test]
+    fn dirs_are_checked_on_rename_and_remove() {
+        let (mut state, data_fd) = create_memory_fs();
+        let fs = &mut state.fs;
+
+        assert_eq!(create_directory(fs, data_fd, "dir"), Ok(()));
+        assert_eq!(create_directory(fs, data_fd, "dir/nested"), Ok(()));
+        create_data_file(fs, data_fd, "file", b"");
+
+        assert_eq!(remove_directory(fs, data_fd, "dir"), Err(__WASI_ENOTEMPTY));
+        assert_eq!(
+            rename(fs, data_fd, "dir", data_fd, "dir/nested/dir"),
+            Err(__WASI_EINVAL)
+        );
+        assert_eq!(
+            rename(fs, data_fd, "file", data_fd, "dir"),
+            Err(__WASI_EISDIR)
+        );
+        assert_eq!(
+            rename(fs, data_fd, "dir", data_fd, "file"),
+            Err(__WASI_ENOTDIR)
+        );
+
+        assert_eq!(rename(fs, data_fd, "dir/nested", data_fd, "nested"), Ok(()));
+        assert_eq!(remove_directory(fs, data_fd, "dir"), Ok(()));
+        create_data_file(fs, data_fd, "nested/file", b"1");
+        assert_eq!(
+            files(fs),
+            vec![
+                PathBuf::from("/data/file"),
+                PathBuf::from("/data/nested/file")
+            ]
+        );
+    }
+
+    #[test]
+    fn host_paths_are_unreachable() {
+        let (mut state, data_fd) = create_memory_fs();
+        let fs = &mut state.fs;
+        create_data_file(fs, data_fd, "file", b"content");
+
+        // paths are resolved only inside the in-memory filesystem and can't leave the dir
+        assert_eq!(
+            filestat(fs, data_fd, "/etc/passwd").map(|_| ()),
+            Err(__WASI_ENOENT)
+        );
+        assert_eq!(
+            unlink_file(fs, data_fd, "../../etc/passwd"),
+            Err(__WASI_ENOENT)
+        );
+        assert_eq!(
+            rename(fs, data_fd, "file", data_fd, "../../tmp/file"),
+            Err(__WASI_ENOENT)
+        );
+        assert_eq!(rename(fs, data_fd, "file", data_fd, "../../moved"), Ok(()));
+        assert_eq!(files(fs), vec![PathBuf::from("/data/moved")]);
+    }
+
+    #[test]
+    fn times_are_set() {
+        let (mut state, data_fd) = create_memory_fs();
+        let fs = &mut state.fs;
+        create_data_file(fs, data_fd, "file", b"");
+
+        let flags = __WASI_FILESTAT_SET_ATIM | __WASI_FILESTAT_SET_MTIM;
+        assert_eq!(set_times(fs, data_fd, "file", 1, 2, flags), Ok(()));
+
+        let stat = filestat(fs, data_fd, "file").unwrap();
+        assert_eq!((stat.st_atim, stat.st_mtim), (1, 2));
+    }
+
+    #[test]
+    fn filestat_layout() {
+        let (mut state, data_fd) = create_memory_fs();
+        let fs = &mut state.fs;
+        create_data_file(fs, data_fd, "file", b"content");
+        let stat = filestat(fs, data_fd, "file").unwrap();
+
+        let snapshot0 = serialize_filestat(&stat, WasiVersion::Snapshot0);
+        assert_eq!(snapshot0.len(), 56);
+        assert_eq!(snapshot0[24..32], 7u64.to_le_bytes());
+
+        let snapshot1 = serialize_filestat(&stat, WasiVersion::Snapshot1);
+        assert_eq!(snapshot1.len(), 64);
+        assert_eq!(snapshot1[32..40], 7u64.to_le_bytes());
+    }
+}
@@ -0,0 +1,42 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+mod deterministic;
+mod dir_restrictions;
+mod import_object;
+mod memory_file;
+mod memory_fs;
+mod memory_fs_syscalls;
+mod restricted_paths;
+mod restricted_writes;
+mod stdin_buffer;
+mod utils;
+
+pub use dir_restrictions::MappedDirUsage;
+pub(crate) use deterministic::deterministic_overrides;
+pub(crate) use deterministic::DeterministicState;
+pub(crate) use dir_restrictions::DirRestrictions;
+pub(crate) use import_object::create_wasi_import_object;
+pub(crate) use import_object::WASIConfig;
+pub(crate) use memory_file::MemoryFile;
+pub(crate) use memory_fs::memory_fs_files;
+pub(crate) use memory_fs::read_memory_fs_file;
+pub(crate) use memory_fs::seed_memory_fs;
+pub(crate) use memory_fs::setup_memory_fs;
+pub(crate) use memory_fs_syscalls::memory_fs_overrides;
+pub(crate) use restricted_paths::restricted_paths_overrides;
+pub(crate) use restricted_writes::restricted_writes_overrides;
+pub(crate) use stdin_buffer::StdinBuffer;
@@ -0,0 +1,659 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Replacements of WASI functions creating, truncating, removing and renaming files
+//! and directories by paths that enforce permissions of restricted mapped directories.
+//! Original functions can't be called after their replacement, so these ones work with
+//! the host filesystem directly and keep the WASI inode cache consistent with it.
+
+use super::DirRestrictions;
+use super::dir_restrictions::dir_size;
+use super::memory_fs::resolve_path;
+use super::restricted_writes::create_func;
+use super::utils::*;
+
+use wasmer_core::import::Namespace;
+use wasmer_core::vm::Ctx;
+use wasmer_core::types::Value as WValue;
+use wasmer_core::types::Type;
+use wasmer_wasi::state::get_wasi_state;
+use wasmer_wasi::state::Fd;
+use wasmer_wasi::state::HostFile;
+use wasmer_wasi::state::Inode;
+use wasmer_wasi::state::Kind;
+use wasmer_wasi::state::WasiFs;
+use wasmer_wasi::types::*;
+
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::sync::PoisonError;
+
+/// Returns replacements of WASI functions changing the host filesystem by paths,
+/// a target path is checked against restrictions of directories containing it.
+pub(crate) fn restricted_paths_overrides(restrictions: Arc<Mutex<DirRestrictions>>) -> Namespace {
+    use Type::*;
+
+    let mut namespace = Namespace::new();
+    namespace.insert(
+        "path_open",
+        create_func(
+            vec![I32, I32, I32, I32, I32, I64, I64, I32, I32],
+            path_open,
+            restrictions.clone(),
+        ),
+    );
+    namespace.insert(
+        "path_create_directory",
+        create_func(vec![I32; 3], path_create_directory, restrictions.clone()),
+    );
+    namespace.insert(
+        "path_remove_directory",
+        create_func(vec![I32; 3], path_remove_directory, restrictions.clone()),
+    );
+    namespace.insert(
+        "path_unlink_file",
+        create_func(vec![I32; 3], path_unlink_file, restrictions.clone()),
+    );
+    namespace.insert(
+        "path_rename",
+        create_func(vec![I32; 6], path_rename, restrictions.clone()),
+    );
+    namespace.insert(
+        "path_symlink",
+        create_func(vec![I32; 5], path_symlink, restrictions),
+    );
+
+    namespace
+}
+
+/// A path resolved relative to a directory descriptor.
+struct ResolvedPath {
+    /// The preopened directory containing the path.
+    base: Inode,
+    /// Components of the path relative to the preopened directory.
+    components: Vec<String>,
+    host_path: PathBuf,
+}
+
+fn path_open(
+    ctx: &mut Ctx,
+    inputs: &[WValue],
+    restrictions: &Mutex<DirRestrictions>,
+) -> WASIResult<()> {
+    let (dir_fd, path, path_len, o_flags, rights, rights_inheriting, fs_flags, fd_out) =
+        match inputs {
+            [WValue::I32(dir_fd), WValue::I32(_dirflags), WValue::I32(path), WValue::I32(path_len), WValue::I32(o_flags), WValue::I64(rights), WValue::I64(rights_inheriting), WValue::I32(fs_flags), WValue::I32(fd_out)] => {
+                (
+                    *dir_fd as __wasi_fd_t,
+                    *path as usize,
+                    *path_len as usize,
+                    *o_flags as __wasi_oflags_t,
+                    *rights as __wasi_rights_t,
+                    *rights_inheriting as __wasi_rights_t,
+                    *fs_flags as __wasi_fdflags_t,
+                    *fd_out as usize,
+                )
+            }
+            _ => return Err(__WASI_EINVAL),
+        };
+
+    let path = read_path(ctx, path, path_len)?;
+    let state = unsafe { get_wasi_state(ctx) };
+    let fd = open(
+        &mut state.fs,
+        dir_fd,
+        &path,
+        o_flags,
+        rights,
+        rights_inheriting,
+        fs_flags,
+        restrictions,
+    )?;
+
+    write_u32(ctx, fd_out, fd)
+}
+
+fn path_create_directory(
+    ctx: &mut Ctx,
+    inputs: &[WValue],
+    restrictions: &Mutex<DirRestrictions>,
+) -> WASIResult<()> {
+    let (dir_fd, path) = read_dir_fd_and_path(ctx, inputs)?;
+    let state = unsafe { get_wasi_state(ctx) };
+    let resolved = resolve(&state.fs, dir_fd, &path, __WASI_RIGHT_PATH_CREATE_DIRECTORY)?;
+
+    lock(restrictions).check_write(&resolved.host_path, 0)?;
+    std::fs::create_dir(&resolved.host_path).map_err(to_errno)
+}
+
+fn path_remove_directory(
+    ctx: &mut Ctx,
+    inputs: &[WValue],
+    restrictions: &Mutex<DirRestrictions>,
+) -> WASIResult<()> {
+    let (dir_fd, path) = read_dir_fd_and_path(ctx, inputs)?;
+    let state = unsafe { get_wasi_state(ctx) };
+    let resolved = resolve(&state.fs, dir_fd, &path, __WASI_RIGHT_PATH_REMOVE_DIRECTORY)?;
+    // the preopened directory itself can't be removed
+    if resolved.components.is_empty() {
+        return Err(__WASI_EACCES);
+    }
+
+    let metadata = std::fs::symlink_metadata(&resolved.host_path).map_err(to_errno)?;
+    if !metadata.is_dir() {
+        return Err(__WASI_ENOTDIR);
+    }
+    let is_empty = std::fs::read_dir(&resolved.host_path)
+        .map_err(to_errno)?
+        .next()
+        .is_none();
+    if !is_empty {
+        return Err(__WASI_ENOTEMPTY);
+    }
+
+    lock(restrictions).check_write(&resolved.host_path, 0)?;
+    std::fs::remove_dir(&resolved.host_path).map_err(to_errno)?;
+    forget_entry(&mut state.fs, &resolved);
+
+    Ok(())
+}
+
+fn path_unlink_file(
+    ctx: &mut Ctx,
+    inputs: &[WValue],
+    restrictions: &Mutex<DirRestrictions>,
+) -> WASIResult<()> {
+    let (dir_fd, path) = read_dir_fd_and_path(ctx, inputs)?;
+    let state = unsafe { get_wasi_state(ctx) };
+    let resolved = resolve(&state.fs, dir_fd, &path, __WASI_RIGHT_PATH_UNLINK_FILE)?;
+
+    let metadata = std::fs::symlink_metadata(&resolved.host_path).map_err(to_errno)?;
+    if metadata.is_dir() {
+        return Err(__WASI_EISDIR);
+    }
+
+    let mut restrictions = lock(restrictions);
+    restrictions.check_write(&resolved.host_path, 0)?;
+    std::fs::remove_file(&resolved.host_path).map_err(to_errno)?;
+    restrictions.release(&resolved.host_path, metadata.len());
+    forget_entry(&mut state.fs, &resolved);
+
+    Ok(())
+}
+
+fn path_rename(
+    ctx: &mut Ctx,
+    inputs: &[WValue],
+    restrictions: &Mutex<DirRestrictions>,
+) -> WASIResult<()> {
+    let (old_dir_fd, old_path, old_path_len, new_dir_fd, new_path, new_path_len) = match inputs {
+        [WValue::I32(old_dir_fd), WValue::I32(old_path), WValue::I32(old_path_len), WValue::I32(new_dir_fd), WValue::I32(new_path), WValue::I32(new_path_len)] => {
+            (
+                *old_dir_fd as __wasi_fd_t,
+                *old_path as usize,
+                *old_path_len as usize,
+                *new_dir_fd as __wasi_fd_t,
+                *new_path as usize,
+                *new_path_len as usize,
+            )
+        }
+        _ => return Err(__WASI_EINVAL),
+    };
+
+    let old_path = read_path(ctx, old_path, old_path_len)?;
+    let new_path = read_path(ctx, new_path, new_path_len)?;
+    let state = unsafe { get_wasi_state(ctx) };
+    let from = resolve(
+        &state.fs,
+        old_dir_fd,
+        &old_path,
+        __WASI_RIGHT_PATH_RENAME_SOURCE,
+    )?;
+    let to = resolve(
+        &state.fs,
+        new_dir_fd,
+        &new_path,
+        __WASI_RIGHT_PATH_RENAME_TARGET,
+    )?;
+    if from.components.is_empty() || to.components.is_empty() {
+        return Err(__WASI_EACCES);
+    }
+
+    let metadata = std::fs::symlink_metadata(&from.host_path).map_err(to_errno)?;
+    let size = if metadata.is_dir() {
+        dir_size(&from.host_path)
+    } else {
+        metadata.len()
+    };
+
+    // if renaming fails after the check, the usage is corrected by the next rescan
+    lock(restrictions).check_rename(&from.host_path, &to.host_path, size)?;
+    std::fs::rename(&from.host_path, &to.host_path).map_err(to_errno)?;
+    forget_entry(&mut state.fs, &from);
+    forget_entry(&mut state.fs, &to);
+
+    Ok(())
+}
+
+fn path_symlink(
+    ctx: &mut Ctx,
+    inputs: &[WValue],
+    restrictions: &Mutex<DirRestrictions>,
+) -> WASIResult<()> {
+    let (old_path, old_path_len, dir_fd, new_path, new_path_len) = match inputs {
+        [WValue::I32(old_path), WValue::I32(old_path_len), WValue::I32(dir_fd), WValue::I32(new_path), WValue::I32(new_path_len)] => {
+            (
+                *old_path as usize,
+                *old_path_len as usize,
+                *dir_fd as __wasi_fd_t,
+                *new_path as usize,
+                *new_path_len as usize,
+            )
+        }
+        _ => return Err(__WASI_EINVAL),
+    };
+
+    let target = read_path(ctx, old_path, old_path_len)?;
+    let new_path = read_path(ctx, new_path, new_path_len)?;
+    let state = unsafe { get_wasi_state(ctx) };
+    let resolved = resolve(&state.fs, dir_fd, &new_path, __WASI_RIGHT_PATH_SYMLINK)?;
+    // links pointing outside of the sandbox are never followed, so they aren't created
+    if Path::new(&target).has_root() {
+        return Err(__WASI_ENOTCAPABLE);
+    }
+
+    lock(restrictions).check_write(&resolved.host_path, 0)?;
+    create_symlink(&target, &resolved.host_path)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn open(
+    fs: &mut WasiFs,
+    dir_fd: __wasi_fd_t,
+    path: &str,
+    o_flags: __wasi_oflags_t,
+    rights: __wasi_rights_t,
+    rights_inheriting: __wasi_rights_t,
+    fs_flags: __wasi_fdflags_t,
+    restrictions: &Mutex<DirRestrictions>,
+) -> WASIResult<__wasi_fd_t> {
+    let creates = o_flags & __WASI_O_CREAT != 0;
+    let truncates = o_flags & __WASI_O_TRUNC != 0;
+
+    let mut required_rights = __WASI_RIGHT_PATH_OPEN;
+    if creates {
+        required_rights |= __WASI_RIGHT_PATH_CREATE_FILE;
+    }
+    if truncates {
+        required_rights |= __WASI_RIGHT_PATH_FILESTAT_SET_SIZE;
+    }
+    let resolved = resolve(fs, dir_fd, path, required_rights)?;
+    let host_path = resolved.host_path.clone();
+
+    let metadata = std::fs::metadata(&host_path).ok();
+    if let Some(metadata) = &metadata {
+        if creates && o_flags & __WASI_O_EXCL != 0 {
+            return Err(__WASI_EEXIST);
+        }
+        // the file could be a link to a file outside of the sandbox
+        check_sandboxed(fs, resolved.base, &host_path)?;
+        if metadata.is_dir() && (creates || truncates) {
+            return Err(__WASI_EISDIR);
+        }
+    }
+
+    let is_dir = metadata.as_ref().map(|metadata| metadata.is_dir());
+    let inode = match is_dir {
+        Some(true) => {
+            // directories could be resolved by other WASI functions, so cached ones are reused
+            let parent = resolved
+                .components
+                .split_last()
+                .and_then(|(_, dir_path)| resolve_path(fs, resolved.base, dir_path).ok());
+            match resolve_path(fs, resolved.base, &resolved.components) {
+                Ok(inode) => inode,
+                Err(_) => fs.create_inode(
+                    Kind::Dir {
+                        parent,
+                        path: host_path,
+                        entries: HashMap::new(),
+                    },
+                    false,
+                    entry_name(&resolved),
+                )?,
+            }
+        }
+        _ if o_flags & __WASI_O_DIRECTORY != 0 => {
+            return Err(is_dir.map_or(__WASI_ENOENT, |_| __WASI_ENOTDIR))
+        }
+        None if !creates => return Err(__WASI_ENOENT),
+        _ => {
+            let size = metadata.map(|metadata| metadata.len());
+            let mut restrictions = lock(restrictions);
+            if creates || truncates {
+                restrictions.check_write(&host_path, 0)?;
+            }
+
+            let read = rights & __WASI_RIGHT_FD_READ != 0;
+            let write = creates
+                || truncates
+                || rights
+                    & (__WASI_RIGHT_FD_WRITE
+                        | __WASI_RIGHT_FD_ALLOCATE
+                        | __WASI_RIGHT_FD_FILESTAT_SET_SIZE)
+                    != 0;
+            let append = fs_flags & __WASI_FDFLAG_APPEND != 0;
+            let file = std::fs::OpenOptions::new()
+                .read(read || !write)
+                .write(write)
+                .create(creates)
+                .truncate(truncates)
+                .open(&host_path)
+                .map_err(to_errno)?;
+            if truncates {
+                restrictions.release(&host_path, size.unwrap_or_default());
+            }
+            // a new file could be cached by other WASI functions before as missing
+            forget_entry(fs, &resolved);
+
+            fs.create_inode(
+                Kind::File {
+                    handle: Some(Box::new(HostFile::new(
+                        file,
+                        host_path.clone(),
+                        read,
+                        write,
+                        append,
+                    ))),
+                    path: host_path,
+                    fd: None,
+                },
+                false,
+                entry_name(&resolved),
+            )?
+        }
+    };
+
+    let dir_rights_inheriting = fs
+        .fd_map
+        .get(&dir_fd)
+        .map(|fd| fd.rights_inheriting)
+        .ok_or(__WASI_EBADF)?;
+    let mut open_flags = 0;
+    if rights & __WASI_RIGHT_FD_READ != 0 {
+        open_flags |= Fd::READ;
+    }
+    if rights & __WASI_RIGHT_FD_WRITE != 0 {
+        open_flags |= Fd::WRITE;
+    }
+
+    let fd = fs.create_fd(
+        rights & dir_rights_inheriting,
+        rights_inheriting & dir_rights_inheriting,
+        fs_flags,
+        open_flags,
+        inode,
+    )?;
+    if fs_flags & __WASI_FDFLAG_APPEND != 0 {
+        let size = match &fs.inodes[inode].kind {
+            Kind::File {
+                handle: Some(handle),
+                ..
+            } => handle.size(),
+            _ => 0,
+        };
+        if let Some(fd) = fs.fd_map.get_mut(&fd) {
+            fd.offset = size;
+        }
+    }
+
+    Ok(fd)
+}
+
+/// Resolves a path relative to the directory opened by the supplied descriptor with
+/// the required rights, the path can't leave the preopened directory containing it.
+fn resolve(
+    fs: &WasiFs,
+    dir_fd: __wasi_fd_t,
+    path: &str,
+    rights: __wasi_rights_t,
+) -> WASIResult<ResolvedPath> {
+    let fd = fs.fd_map.get(&dir_fd).ok_or(__WASI_EBADF)?;
+    if fd.rights & rights != rights {
+        return Err(__WASI_EACCES);
+    }
+
+    let base = preopened_ancestor(fs, fd.inode)?;
+    let base_path = host_dir_path(fs, base)?;
+    let dir_path = host_dir_path(fs, fd.inode)?;
+    let dir_relative_path = dir_path
+        .strip_prefix(&base_path)
+        .map_err(|_| __WASI_ENOTCAPABLE)?;
+
+    let components = sandboxed_components(dir_relative_path, path)?;
+    let host_path = components
+        .iter()
+        .fold(base_path, |host_path, name| host_path.join(name));
+    if let (Some(parent), false) = (host_path.parent(), components.is_empty()) {
+        // intermediate directories could be links to directories outside of the sandbox
+        check_sandboxed(fs, base, parent)?;
+    }
+
+    Ok(ResolvedPath {
+        base,
+        components,
+        host_path,
+    })
+}
+
+/// Converts a path relative to a directory inside a preopened one to components
+/// relative to the preopened directory, fails if the path leaves it.
+fn sandboxed_components(dir_relative_path: &Path, path: &str) -> WASIResult<Vec<String>> {
+    let mut components = dir_relative_path
+        .iter()
+        .map(|name| name.to_string_lossy().to_string())
+        .collect::<Vec<_>>();
+
+    for component in Path::new(path).components() {
+        match component {
+            Component::Normal(name) => components.push(name.to_string_lossy().to_string()),
+            Component::ParentDir => {
+                components.pop().ok_or(__WASI_ENOTCAPABLE)?;
+            }
+            Component::CurDir => {}
+            Component::RootDir | Component::Prefix(_) => return Err(__WASI_ENOTCAPABLE),
+        }
+    }
+
+    Ok(components)
+}
+
+/// Checks that an existing host path stays inside the preopened directory after
+/// resolving all links.
+fn check_sandboxed(fs: &WasiFs, base: Inode, host_path: &Path) -> WASIResult<()> {
+    let base_path = host_dir_path(fs, base)?.canonicalize().map_err(to_errno)?;
+    let host_path = host_path.canonicalize().map_err(to_errno)?;
+
+    if host_path.starts_with(base_path) {
+        Ok(())
+    } else {
+        Err(__WASI_ENOTCAPABLE)
+    }
+}
+
+fn preopened_ancestor(fs: &WasiFs, dir: Inode) -> WASIResult<Inode> {
+    let mut inode = dir;
+    loop {
+        if fs.inodes[inode].is_preopened {
+            return Ok(inode);
+        }
+
+        inode = match &fs.inodes[inode].kind {
+            Kind::Dir {
+                parent: Some(parent),
+                ..
+            } => *parent,
+            _ => return Err(__WASI_ENOTCAPABLE),
+        };
+    }
+}
+
+fn host_dir_path(fs: &WasiFs, dir: Inode) -> WASIResult<PathBuf> {
+    match &fs.inodes[dir].kind {
+        Kind::Dir { path, .. } => Ok(path.clone()),
+        // the virtual root doesn't exist in the host filesystem
+        Kind::Root { .. } => Err(__WASI_ENOTCAPABLE),
+        _ => Err(__WASI_ENOTDIR),
+    }
+}
+
+/// Removes a cached entry of a changed path, so it's looked up in the host filesystem again.
+fn forget_entry(fs: &mut WasiFs, resolved: &ResolvedPath) {
+    let (name, dir_path) = match resolved.components.split_last() {
+        Some(split) => split,
+        None => return,
+    };
+
+    if let Ok(dir) = resolve_path(fs, resolved.base, dir_path) {
+        if let Kind::Dir { entries, .. } = &mut fs.inodes[dir].kind {
+            entries.remove(name);
+        }
+    }
+}
+
+fn entry_name(resolved: &ResolvedPath) -> String {
+    resolved
+        .components
+        .last()
+        .cloned()
+        .unwrap_or_else(|| String::from("."))
+}
+
+fn lock(restrictions: &Mutex<DirRestrictions>) -> MutexGuard<'_, DirRestrictions> {
+    restrictions.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(unix)]
+fn create_symlink(target: &str, host_path: &Path) -> WASIResult<()> {
+    std::os::unix::fs::symlink(target, host_path).map_err(to_errno)
+}
+
+#[cfg(not(unix))]
+fn create_symlink(_target: &str, _host_path: &Path) -> WASIResult<()> {
+    Err(__WASI_ENOTSUP)
+}
+
+fn to_errno(error: std::io::Error) -> __wasi_errno_t {
+    match error.kind() {
+        ErrorKind::NotFound => __WASI_ENOENT,
+        ErrorKind::PermissionDenied => __WASI_EACCES,
+        ErrorKind::AlreadyExists => __WASI_EEXIST,
+        ErrorKind::InvalidInput => __WASI_EINVAL,
+        _ => __WASI_EIO,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::sandboxed_components;
+    use crate::wasi::DirRestrictions;
+    use crate::MappedDirPermissions;
+
+    use wasmer_wasi::types::__WASI_ENOSPC;
+    use wasmer_wasi::types::__WASI_ENOTCAPABLE;
+    use wasmer_wasi::types::__WASI_EROFS;
+
+    use std::collections::HashMap;
+    use std::path::Path;
+
+    #[test]
+    fn paths_are_sandboxed() {
+        let components = sandboxed_components(Path::new("a/b"), "../c/./d");
+        assert_eq!(
+            components,
+            Ok(vec![
+                String::from("a"),
+                String::from("c"),
+                String::from("d")
+            ])
+        );
+
+        let components = sandboxed_components(Path::new("a"), "../..");
+        assert_eq!(components, Err(__WASI_ENOTCAPABLE));
+
+        let components = sandboxed_components(Path::new(""), "/etc/passwd");
+        assert_eq!(components, Err(__WASI_ENOTCAPABLE));
+    }
+
+    #[test]
+    fn renames_are_checked_against_restrictions() {
+        let dir = std::env::temp_dir().join(format!(
+            "fce_renames_are_checked_against_restrictions_{}",
+            std::process::id()
+        ));
+        let small_dir = dir.join("small");
+        let readonly_dir = dir.join("readonly");
+        let other_dir = dir.join("other");
+        for dir in &[&small_dir, &readonly_dir, &other_dir] {
+            std::fs::create_dir_all(dir).unwrap();
+        }
+
+        let mut mapped_dirs = HashMap::new();
+        mapped_dirs.insert(String::from("small"), small_dir.clone());
+        mapped_dirs.insert(String::from("readonly"), readonly_dir.clone());
+        let mut permissions = HashMap::new();
+        permissions.insert(
+            String::from("small"),
+            MappedDirPermissions {
+                readonly: false,
+                max_bytes: Some(10),
+            },
+        );
+        permissions.insert(
+            String::from("readonly"),
+            MappedDirPermissions {
+                readonly: true,
+                max_bytes: None,
+            },
+        );
+        let mut restrictions = DirRestrictions::new(&mapped_dirs, &permissions);
+
+        let into_readonly =
+            restrictions.check_rename(&other_dir.join("a"), &readonly_dir.join("a"), 1);
+        let out_of_readonly =
+            restrictions.check_rename(&readonly_dir.join("a"), &other_dir.join("a"), 1);
+        let too_big = restrictions.check_rename(&other_dir.join("a"), &small_dir.join("a"), 11);
+        let fits = restrictions.check_rename(&other_dir.join("a"), &small_dir.join("a"), 10);
+        // moving inside a directory doesn't change its usage
+        let inside = restrictions.check_rename(&small_dir.join("a"), &small_dir.join("b"), 10);
+        let usage = restrictions.usage();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(into_readonly, Err(__WASI_EROFS));
+        assert_eq!(out_of_readonly, Err(__WASI_EROFS));
+        assert_eq!(too_big, Err(__WASI_ENOSPC));
+        assert_eq!(fits, Ok(()));
+        assert_eq!(inside, Ok(()));
+        assert_eq!(usage["small"].rejected_writes, 1);
+        assert_eq!(usage["readonly"].rejected_writes, 2);
+    }
+}
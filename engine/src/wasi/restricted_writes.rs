@@ -0,0 +1,487 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Replacements of WASI functions writing to files or changing their sizes
+//! that enforce permissions of restricted mapped directories.
+
+use super::DirRestrictions;
+use super::utils::*;
+
+use wasmer_core::import::Namespace;
+use wasmer_core::vm::Ctx;
+use wasmer_core::typed_func::DynamicFunc;
+use wasmer_core::types::Value as WValue;
+use wasmer_core::types::FuncSig;
+use wasmer_core::types::Type;
+use wasmer_wasi::state::get_wasi_state;
+use wasmer_wasi::state::Kind;
+use wasmer_wasi::state::WasiFile;
+use wasmer_wasi::types::*;
+
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::PoisonError;
+
+pub(super) type RestrictedFunc = fn(&mut Ctx, &[WValue], &Mutex<DirRestrictions>) -> WASIResult<()>;
+
+/// Returns replacements of WASI functions that could make files bigger or modify them.
+/// Functions creating, truncating and removing files by paths are replaced separately
+/// (see `restricted_paths_overrides`).
+pub(crate) fn restricted_writes_overrides(restrictions: Arc<Mutex<DirRestrictions>>) -> Namespace {
+    use Type::*;
+
+    let mut namespace = Namespace::new();
+    namespace.insert(
+        "fd_write",
+        create_func(vec![I32, I32, I32, I32], fd_write, restrictions.clone()),
+    );
+    namespace.insert(
+        "fd_pwrite",
+        create_func(
+            vec![I32, I32, I32, I64, I32],
+            fd_pwrite,
+            restrictions.clone(),
+        ),
+    );
+    namespace.insert(
+        "fd_allocate",
+        create_func(vec![I32, I64, I64], fd_allocate, restrictions.clone()),
+    );
+    namespace.insert(
+        "fd_filestat_set_size",
+        create_func(vec![I32, I64], fd_filestat_set_size, restrictions),
+    );
+
+    namespace
+}
+
+pub(super) fn create_func(
+    argument_types: Vec<Type>,
+    func: RestrictedFunc,
+    restrictions: Arc<Mutex<DirRestrictions>>,
+) -> DynamicFunc<'static> {
+    DynamicFunc::new(
+        Arc::new(FuncSig::new(argument_types, vec![Type::I32])),
+        move |ctx: &mut Ctx, inputs: &[WValue]| -> Vec<WValue> {
+            to_errno_wvalue(func(ctx, inputs, &restrictions))
+        },
+    )
+}
+
+fn fd_write(
+    ctx: &mut Ctx,
+    inputs: &[WValue],
+    restrictions: &Mutex<DirRestrictions>,
+) -> WASIResult<()> {
+    let (fd, iovs, iovs_len, nwritten) = match inputs {
+        [WValue::I32(fd), WValue::I32(iovs), WValue::I32(iovs_len), WValue::I32(nwritten)] => (
+            *fd as __wasi_fd_t,
+            *iovs as usize,
+            *iovs_len as usize,
+            *nwritten as usize,
+        ),
+        _ => return Err(__WASI_EINVAL),
+    };
+
+    let bytes = read_iovs(ctx, iovs, iovs_len)?;
+
+    let state = unsafe { get_wasi_state(ctx) };
+    let fd_entry = state.fs.fd_map.get_mut(&fd).ok_or(__WASI_EBADF)?;
+    check_rights(fd_entry.rights, __WASI_RIGHT_FD_WRITE)?;
+
+    let inode = &mut state.fs.inodes[fd_entry.inode];
+    match &mut inode.kind {
+        Kind::File {
+            handle: Some(handle),
+            ..
+        } if fd <= __WASI_STDERR_FILENO => {
+            handle.write_all(&bytes).map_err(|_| __WASI_EIO)?;
+        }
+        Kind::File {
+            handle: Some(handle),
+            path,
+            ..
+        } => {
+            let offset = if fd_entry.flags & __WASI_FDFLAG_APPEND != 0 {
+                handle.size()
+            } else {
+                fd_entry.offset
+            };
+            write_file(handle.as_mut(), path, offset, &bytes, restrictions)?;
+
+            fd_entry.offset = offset + bytes.len() as u64;
+            inode.stat.st_size = handle.size();
+        }
+        Kind::Buffer { buffer } => {
+            write_buffer(buffer, fd_entry.offset as usize, &bytes);
+            fd_entry.offset += bytes.len() as u64;
+        }
+        Kind::Dir { .. } | Kind::Root { .. } => return Err(__WASI_EISDIR),
+        _ => return Err(__WASI_EINVAL),
+    }
+
+    write_u32(ctx, nwritten, bytes.len() as u32)
+}
+
+/// Unlike fd_write, writes at the supplied offset and doesn't change the descriptor offset.
+fn fd_pwrite(
+    ctx: &mut Ctx,
+    inputs: &[WValue],
+    restrictions: &Mutex<DirRestrictions>,
+) -> WASIResult<()> {
+    let (fd, iovs, iovs_len, offset, nwritten) = match inputs {
+        [WValue::I32(fd), WValue::I32(iovs), WValue::I32(iovs_len), WValue::I64(offset), WValue::I32(nwritten)] => {
+            (
+                *fd as __wasi_fd_t,
+                *iovs as usize,
+                *iovs_len as usize,
+                *offset as u64,
+                *nwritten as usize,
+            )
+        }
+        _ => return Err(__WASI_EINVAL),
+    };
+
+    let bytes = read_iovs(ctx, iovs, iovs_len)?;
+
+    let state = unsafe { get_wasi_state(ctx) };
+    let fd_entry = state.fs.fd_map.get(&fd).ok_or(__WASI_EBADF)?;
+    check_rights(
+        fd_entry.rights,
+        __WASI_RIGHT_FD_WRITE | __WASI_RIGHT_FD_SEEK,
+    )?;
+
+    let inode = &mut state.fs.inodes[fd_entry.inode];
+    match &mut inode.kind {
+        Kind::File { .. } if fd <= __WASI_STDERR_FILENO => return Err(__WASI_ESPIPE),
+        Kind::File {
+            handle: Some(handle),
+            path,
+            ..
+        } => {
+            write_file(handle.as_mut(), path, offset, &bytes, restrictions)?;
+            inode.stat.st_size = handle.size();
+        }
+        Kind::Buffer { buffer } => write_buffer(buffer, offset as usize, &bytes),
+        Kind::Dir { .. } | Kind::Root { .. } => return Err(__WASI_EISDIR),
+        _ => return Err(__WASI_EINVAL),
+    }
+
+    write_u32(ctx, nwritten, bytes.len() as u32)
+}
+
+fn fd_allocate(
+    ctx: &mut Ctx,
+    inputs: &[WValue],
+    restrictions: &Mutex<DirRestrictions>,
+) -> WASIResult<()> {
+    let (fd, offset, len) = match inputs {
+        [WValue::I32(fd), WValue::I64(offset), WValue::I64(len)] => {
+            (*fd as __wasi_fd_t, *offset as u64, *len as u64)
+        }
+        _ => return Err(__WASI_EINVAL),
+    };
+    let new_size = offset.checked_add(len).ok_or(__WASI_EINVAL)?;
+
+    let state = unsafe { get_wasi_state(ctx) };
+    let fd_entry = state.fs.fd_map.get(&fd).ok_or(__WASI_EBADF)?;
+    check_rights(fd_entry.rights, __WASI_RIGHT_FD_ALLOCATE)?;
+
+    let inode = &mut state.fs.inodes[fd_entry.inode];
+    match &mut inode.kind {
+        Kind::File {
+            handle: Some(handle),
+            path,
+            ..
+        } => {
+            // allocation never shrinks a file
+            if new_size > handle.size() {
+                resize_file(handle.as_mut(), path, new_size, restrictions)?;
+                inode.stat.st_size = new_size;
+            }
+        }
+        Kind::Buffer { buffer } => {
+            if new_size as usize > buffer.len() {
+                buffer.resize(new_size as usize, 0);
+            }
+        }
+        Kind::Dir { .. } | Kind::Root { .. } => return Err(__WASI_EISDIR),
+        _ => return Err(__WASI_EBADF),
+    }
+
+    Ok(())
+}
+
+fn fd_filestat_set_size(
+    ctx: &mut Ctx,
+    inputs: &[WValue],
+    restrictions: &Mutex<DirRestrictions>,
+) -> WASIResult<()> {
+    let (fd, new_size) = match inputs {
+        [WValue::I32(fd), WValue::I64(new_size)] => (*fd as __wasi_fd_t, *new_size as u64),
+        _ => return Err(__WASI_EINVAL),
+    };
+
+    let state = unsafe { get_wasi_state(ctx) };
+    let fd_entry = state.fs.fd_map.get(&fd).ok_or(__WASI_EBADF)?;
+    check_rights(fd_entry.rights, __WASI_RIGHT_FD_FILESTAT_SET_SIZE)?;
+
+    let inode = &mut state.fs.inodes[fd_entry.inode];
+    match &mut inode.kind {
+        Kind::File {
+            handle: Some(handle),
+            path,
+            ..
+        } => {
+            resize_file(handle.as_mut(), path, new_size, restrictions)?;
+            inode.stat.st_size = new_size;
+        }
+        Kind::Buffer { buffer } => buffer.resize(new_size as usize, 0),
+        Kind::Dir { .. } | Kind::Root { .. } => return Err(__WASI_EISDIR),
+        _ => return Err(__WASI_EBADF),
+    }
+
+    Ok(())
+}
+
+/// Writes bytes at the offset of a file with the supplied host path,
+/// the file growth is checked against restrictions of directories containing it.
+fn write_file(
+    handle: &mut dyn WasiFile,
+    path: &Path,
+    offset: u64,
+    bytes: &[u8],
+    restrictions: &Mutex<DirRestrictions>,
+) -> WASIResult<()> {
+    let growth = (offset + bytes.len() as u64).saturating_sub(handle.size());
+    restrictions
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .check_write(path, growth)?;
+
+    handle
+        .seek(SeekFrom::Start(offset))
+        .map_err(|_| __WASI_EIO)?;
+    handle.write_all(bytes).map_err(|_| __WASI_EIO)
+}
+
+/// Changes size of a file with the supplied host path, the file growth is checked
+/// against restrictions of directories containing it, shrinking frees their space.
+fn resize_file(
+    handle: &mut dyn WasiFile,
+    path: &Path,
+    new_size: u64,
+    restrictions: &Mutex<DirRestrictions>,
+) -> WASIResult<()> {
+    let size = handle.size();
+    let mut restrictions = restrictions.lock().unwrap_or_else(PoisonError::into_inner);
+    restrictions.check_write(path, new_size.saturating_sub(size))?;
+
+    handle.set_len(new_size).map_err(|_| __WASI_EIO)?;
+    restrictions.release(path, size.saturating_sub(new_size));
+
+    Ok(())
+}
+
+fn write_buffer(buffer: &mut Vec<u8>, offset: usize, bytes: &[u8]) {
+    if buffer.len() < offset + bytes.len() {
+        buffer.resize(offset + bytes.len(), 0);
+    }
+    buffer[offset..offset + bytes.len()].copy_from_slice(bytes);
+}
+
+fn check_rights(rights: __wasi_rights_t, required: __wasi_rights_t) -> WASIResult<()> {
+    if rights & required != required {
+        return Err(__WASI_EACCES);
+    }
+
+    Ok(())
+}
+
+/// Collects bytes from an array of __wasi_ciovec_t located in the module memory.
+fn read_iovs(ctx: &Ctx, iovs: usize, iovs_len: usize) -> WASIResult<Vec<u8>> {
+    const CIOVEC_SIZE: usize = 8;
+
+    let mut result = Vec::new();
+    for iov_id in 0..iovs_len {
+        let iov_offset = iovs + iov_id * CIOVEC_SIZE;
+        let buf = read_u32(ctx, iov_offset)? as usize;
+        let buf_len = read_u32(ctx, iov_offset + 4)? as usize;
+
+        result.extend(read_bytes(ctx, buf, buf_len)?);
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::resize_file;
+    use super::write_file;
+    use crate::wasi::DirRestrictions;
+    use crate::MappedDirPermissions;
+
+    use wasmer_wasi::state::HostFile;
+    use wasmer_wasi::types::__WASI_ENOSPC;
+    use wasmer_wasi::types::__WASI_EROFS;
+
+    use std::collections::HashMap;
+    use std::path::Path;
+    use std::path::PathBuf;
+    use std::sync::Mutex;
+
+    fn create_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("fce_{}_{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn create_restrictions(
+        dir: &Path,
+        permissions: MappedDirPermissions,
+    ) -> Mutex<DirRestrictions> {
+        let mut mapped_dirs = HashMap::new();
+        mapped_dirs.insert(String::from("data"), dir.to_path_buf());
+        let mut dirs_permissions = HashMap::new();
+        dirs_permissions.insert(String::from("data"), permissions);
+
+        Mutex::new(DirRestrictions::new(&mapped_dirs, &dirs_permissions))
+    }
+
+    fn open_file(path: &Path) -> HostFile {
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .open(path)
+            .unwrap();
+
+        HostFile::new(file, path.to_path_buf(), true, true, false)
+    }
+
+    fn used_bytes(restrictions: &Mutex<DirRestrictions>) -> u64 {
+        restrictions.lock().unwrap().usage()["data"].used_bytes
+    }
+
+    #[test]
+    fn quota_limits_writes_and_truncation() {
+        let dir = create_dir("quota_limits_writes_and_truncation");
+        let restrictions = create_restrictions(
+            &dir,
+            MappedDirPermissions {
+                readonly: false,
+                max_bytes: Some(10),
+            },
+        );
+        let path = dir.join("file");
+        let mut file = open_file(&path);
+
+        // write
+        assert_eq!(
+            write_file(&mut file, &path, 0, b"01234567", &restrictions),
+            Ok(())
+        );
+        // pwrite partially overwriting the file
+        assert_eq!(
+            write_file(&mut file, &path, 6, b"6789", &restrictions),
+            Ok(())
+        );
+        assert_eq!(used_bytes(&restrictions), 10);
+
+        assert_eq!(
+            write_file(&mut file, &path, 10, b"a", &restrictions),
+            Err(__WASI_ENOSPC)
+        );
+        // truncating upward and allocating
+        assert_eq!(
+            resize_file(&mut file, &path, 11, &restrictions),
+            Err(__WASI_ENOSPC)
+        );
+
+        // truncating downward frees space
+        assert_eq!(resize_file(&mut file, &path, 4, &restrictions), Ok(()));
+        assert_eq!(used_bytes(&restrictions), 4);
+        assert_eq!(
+            write_file(&mut file, &path, 4, b"456789", &restrictions),
+            Ok(())
+        );
+
+        let usage = restrictions.lock().unwrap().usage()["data"];
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(usage.used_bytes, 10);
+        assert_eq!(usage.rejected_writes, 2);
+    }
+
+    #[test]
+    fn removed_files_free_quota() {
+        let dir = create_dir("removed_files_free_quota");
+        let restrictions = create_restrictions(
+            &dir,
+            MappedDirPermissions {
+                readonly: false,
+                max_bytes: Some(10),
+            },
+        );
+        let path = dir.join("file");
+        let mut file = open_file(&path);
+        assert_eq!(
+            write_file(&mut file, &path, 0, b"0123456789", &restrictions),
+            Ok(())
+        );
+
+        std::fs::remove_file(&path).unwrap();
+        let path = dir.join("another_file");
+        let mut file = open_file(&path);
+        let result = write_file(&mut file, &path, 0, b"0123456789", &restrictions);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn readonly_dir_rejects_writes_and_truncation() {
+        let dir = create_dir("readonly_dir_rejects_writes_and_truncation");
+        let path = dir.join("file");
+        std::fs::write(&path, b"content").unwrap();
+
+        let restrictions = create_restrictions(
+            &dir,
+            MappedDirPermissions {
+                readonly: true,
+                max_bytes: None,
+            },
+        );
+        let mut file = open_file(&path);
+
+        let write_result = write_file(&mut file, &path, 7, b"!", &restrictions);
+        let pwrite_result = write_file(&mut file, &path, 0, b"C", &restrictions);
+        let truncate_result = resize_file(&mut file, &path, 1, &restrictions);
+        let content = std::fs::read(&path).unwrap();
+        let usage = restrictions.lock().unwrap().usage()["data"];
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(write_result, Err(__WASI_EROFS));
+        assert_eq!(pwrite_result, Err(__WASI_EROFS));
+        assert_eq!(truncate_result, Err(__WASI_EROFS));
+        assert_eq!(content, b"content");
+        assert_eq!(usage.rejected_writes, 3);
+    }
+}
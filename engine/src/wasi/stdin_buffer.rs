@@ -0,0 +1,106 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use wasmer_wasi::state::WasiFile;
+use wasmer_wasi::state::WasiFsError;
+use wasmer_wasi::types::__wasi_filesize_t;
+use wasmer_wasi::types::__wasi_timestamp_t;
+use serde::Deserialize;
+use serde::Serialize;
+
+use std::io;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::io::Write;
+
+/// Read-only in-memory file used as a module stdin during one call.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct StdinBuffer {
+    data: Vec<u8>,
+    position: usize,
+}
+
+impl StdinBuffer {
+    pub(crate) fn new(data: Vec<u8>) -> Self {
+        Self { data, position: 0 }
+    }
+}
+
+impl Read for StdinBuffer {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remainder = &self.data[self.position..];
+        let read_count = std::cmp::min(remainder.len(), buf.len());
+
+        buf[..read_count].copy_from_slice(&remainder[..read_count]);
+        self.position += read_count;
+
+        Ok(read_count)
+    }
+}
+
+impl Write for StdinBuffer {
+    fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+        Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "stdin can't be written",
+        ))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for StdinBuffer {
+    fn seek(&mut self, _pos: SeekFrom) -> io::Result<u64> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "stdin can't be seeked",
+        ))
+    }
+}
+
+#[typetag::serde]
+impl WasiFile for StdinBuffer {
+    fn last_accessed(&self) -> __wasi_timestamp_t {
+        0
+    }
+
+    fn last_modified(&self) -> __wasi_timestamp_t {
+        0
+    }
+
+    fn created_time(&self) -> __wasi_timestamp_t {
+        0
+    }
+
+    fn size(&self) -> u64 {
+        self.data.len() as u64
+    }
+
+    fn set_len(&mut self, _new_size: __wasi_filesize_t) -> Result<(), WasiFsError> {
+        Err(WasiFsError::PermissionDenied)
+    }
+
+    fn unlink(&mut self) -> Result<(), WasiFsError> {
+        Ok(())
+    }
+
+    fn bytes_available(&self) -> Result<usize, WasiFsError> {
+        Ok(self.data.len() - self.position)
+    }
+}
@@ -0,0 +1,94 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use wasmer_core::vm::Ctx;
+use wasmer_core::types::Value as WValue;
+use wasmer_wasi::types::__wasi_errno_t;
+use wasmer_wasi::types::__wasi_fd_t;
+use wasmer_wasi::types::__WASI_EFAULT;
+use wasmer_wasi::types::__WASI_EINVAL;
+
+use std::cell::Cell;
+
+pub(super) type WASIResult<T> = std::result::Result<T, __wasi_errno_t>;
+
+/// Reads bytes from the module memory.
+pub(super) fn read_bytes(ctx: &Ctx, offset: usize, size: usize) -> WASIResult<Vec<u8>> {
+    let memory_view = ctx.memory(0).view::<u8>();
+    let cells = memory_view
+        .get(offset..offset + size)
+        .ok_or(__WASI_EFAULT)?;
+
+    Ok(cells.iter().map(Cell::get).collect())
+}
+
+/// Writes bytes to the module memory.
+pub(super) fn write_bytes(ctx: &Ctx, offset: usize, bytes: &[u8]) -> WASIResult<()> {
+    let memory_view = ctx.memory(0).view::<u8>();
+    let cells = memory_view
+        .get(offset..offset + bytes.len())
+        .ok_or(__WASI_EFAULT)?;
+
+    for (cell, byte) in cells.iter().zip(bytes) {
+        cell.set(*byte);
+    }
+
+    Ok(())
+}
+
+pub(super) fn read_u32(ctx: &Ctx, offset: usize) -> WASIResult<u32> {
+    let bytes = read_bytes(ctx, offset, 4)?;
+    let mut le_bytes = [0u8; 4];
+    le_bytes.copy_from_slice(&bytes);
+
+    Ok(u32::from_le_bytes(le_bytes))
+}
+
+pub(super) fn write_u32(ctx: &Ctx, offset: usize, value: u32) -> WASIResult<()> {
+    write_bytes(ctx, offset, &value.to_le_bytes())
+}
+
+/// Reads a directory descriptor and a path from arguments of WASI functions like path_unlink_file.
+pub(super) fn read_dir_fd_and_path(
+    ctx: &Ctx,
+    inputs: &[WValue],
+) -> WASIResult<(__wasi_fd_t, String)> {
+    match inputs {
+        [WValue::I32(dir_fd), WValue::I32(path), WValue::I32(path_len)] => {
+            let path = read_path(ctx, *path as usize, *path_len as usize)?;
+            Ok((*dir_fd as __wasi_fd_t, path))
+        }
+        _ => Err(__WASI_EINVAL),
+    }
+}
+
+/// Reads a UTF-8 path from the module memory.
+pub(super) fn read_path(ctx: &Ctx, path: usize, path_len: usize) -> WASIResult<String> {
+    let path = read_bytes(ctx, path, path_len)?;
+    String::from_utf8(path).map_err(|_| __WASI_EINVAL)
+}
+
+/// Converts errno returned by a WASI function to the returned value.
+pub(super) fn to_errno_wvalue(result: WASIResult<()>) -> Vec<wasmer_core::types::Value> {
+    use wasmer_wasi::types::__WASI_ESUCCESS;
+
+    let errno = match result {
+        Ok(()) => __WASI_ESUCCESS,
+        Err(errno) => errno,
+    };
+
+    vec![wasmer_core::types::Value::I32(errno as _)]
+}
@@ -15,7 +15,9 @@
  */
 
 use fce::FCE;
+use fce::FCEModuleConfig;
 use fce::IValue;
+use wasmer_wasi::state::WasiFile;
 
 use once_cell::sync::Lazy;
 
@@ -109,3 +111,40 @@ pub fn non_exist_module_func() {
         fce::FCEError::NoSuchModule(non_exist_name)
     ));
 }
+
+#[test]
+pub fn greeting_with_args_and_stdin() {
+    let mut fce = FCE::new();
+    let config =
+        FCEModuleConfig::default().with_wasi_args(vec![b"greeting".to_vec(), b"-v".to_vec()]);
+    fce.load_module("greeting", &*GREETING_WASM_BYTES, config)
+        .unwrap_or_else(|e| panic!("can't load a module into FCE: {:?}", e));
+
+    let wasi_state = fce
+        .module_wasi_state("greeting")
+        .expect("greeting should be instantiated");
+    // the first arg is the program name
+    assert_eq!(wasi_state.args, vec![b"greeting".to_vec(), b"-v".to_vec()]);
+
+    let result = fce
+        .call_with_stdin(
+            "greeting",
+            "greeting",
+            &[IValue::String(String::from("Fluence"))],
+            b"stdin content".to_vec(),
+        )
+        .unwrap_or_else(|e| panic!("can't invoke greeting with stdin: {:?}", e));
+    assert_eq!(result, vec![IValue::String(String::from("Hi, Fluence"))]);
+
+    // the supplied stdin is replaced back by the process one after the call
+    let wasi_state = fce
+        .module_wasi_state("greeting")
+        .expect("greeting should be instantiated");
+    let stdin = wasi_state
+        .fs
+        .stdin()
+        .unwrap_or_else(|e| panic!("can't get stdin: {:?}", e))
+        .as_ref()
+        .expect("stdin should be set");
+    assert_eq!(stdin.size(), 0);
+}
@@ -0,0 +1,130 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use fce::FCE;
+use fce::FCEError;
+use fce::FCEModuleConfig;
+use fce::ImportPolicy;
+
+use std::collections::BTreeSet;
+
+fn load_with_policy(path: &str, policy: ImportPolicy) -> Result<(), FCEError> {
+    let wasm_bytes =
+        std::fs::read(path).unwrap_or_else(|e| panic!("{} should presence: {}", path, e));
+    // host imports of these modules aren't provided, so they aren't instantiated
+    let config = FCEModuleConfig::default()
+        .with_import_policy(policy)
+        .with_lazy_instantiation(true);
+
+    let mut fce = FCE::new();
+    fce.load_module("module", &wasm_bytes, config)
+}
+
+fn forbidden_imports(result: Result<(), FCEError>) -> Vec<String> {
+    match result {
+        Err(FCEError::ForbiddenImports(imports)) => imports,
+        result => panic!("expected forbidden imports, got {:?}", result),
+    }
+}
+
+fn to_set(names: &[&str]) -> BTreeSet<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+#[test]
+pub fn pure_policy_rejects_filesystem_access() {
+    let result = load_with_policy(
+        "../examples/site-storage/artifacts/local_storage.wasm",
+        ImportPolicy::Pure,
+    );
+
+    // the logger stays allowed
+    assert_eq!(
+        forbidden_imports(result),
+        vec![
+            "wasi_snapshot_preview1.fd_filestat_get",
+            "wasi_snapshot_preview1.path_open",
+        ]
+    );
+}
+
+#[test]
+pub fn pure_policy_rejects_host_imports() {
+    let result = load_with_policy(
+        "../examples/site-storage/artifacts/curl.wasm",
+        ImportPolicy::Pure,
+    );
+
+    assert_eq!(forbidden_imports(result), vec!["host.curl"]);
+}
+
+#[test]
+pub fn pure_policy_allows_call_parameters() {
+    let result = load_with_policy(
+        "../examples/call_parameters/artifacts/call_parameters.wasm",
+        ImportPolicy::Pure,
+    );
+
+    assert!(result.is_ok(), "{:?}", result);
+}
+
+#[test]
+pub fn allowlist_policy_rejects_unlisted_imports() {
+    let wasi_functions = to_set(&[
+        "proc_exit",
+        "fd_write",
+        "fd_close",
+        "fd_filestat_get",
+        "fd_read",
+        "fd_prestat_get",
+        "fd_prestat_dir_name",
+        "environ_sizes_get",
+        "environ_get",
+    ]);
+
+    let result = load_with_policy(
+        "../examples/site-storage/artifacts/local_storage.wasm",
+        ImportPolicy::Allowlist {
+            wasi_functions: wasi_functions.clone(),
+            host_namespaces: to_set(&["host"]),
+        },
+    );
+    assert_eq!(
+        forbidden_imports(result),
+        vec!["wasi_snapshot_preview1.path_open"]
+    );
+
+    let result = load_with_policy(
+        "../examples/site-storage/artifacts/curl.wasm",
+        ImportPolicy::Allowlist {
+            wasi_functions: wasi_functions.clone(),
+            host_namespaces: BTreeSet::new(),
+        },
+    );
+    assert_eq!(
+        forbidden_imports(result),
+        vec!["host.curl", "host.log_utf8_string"]
+    );
+
+    let result = load_with_policy(
+        "../examples/site-storage/artifacts/curl.wasm",
+        ImportPolicy::Allowlist {
+            wasi_functions,
+            host_namespaces: to_set(&["host"]),
+        },
+    );
+    assert!(result.is_ok(), "{:?}", result);
+}
@@ -0,0 +1,165 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::Result;
+use crate::AppServiceError;
+use crate::CallParameters;
+
+use serde_derive::Serialize;
+use serde_derive::Deserialize;
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+
+/// The rule name applied to facade functions without their own rules.
+const ANY_FUNCTION_RULE_NAME: &str = "*";
+
+/// Callers allowed to invoke a function, a caller is allowed if its user name
+/// or application id from call parameters is listed here.
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccessRule {
+    #[serde(default)]
+    pub users: BTreeSet<String>,
+    #[serde(default)]
+    pub applications: BTreeSet<String>,
+}
+
+impl AccessRule {
+    pub fn is_allowed(&self, call_parameters: &CallParameters) -> bool {
+        self.users.contains(&call_parameters.user_name)
+            || self.applications.contains(&call_parameters.application_id)
+    }
+}
+
+/// Access rules of facade functions by their names, the "*" rule is applied to functions
+/// without their own rules, functions not covered by any rule could be called by anyone.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccessControlList {
+    rules: BTreeMap<String, AccessRule>,
+}
+
+impl AccessControlList {
+    pub fn new(rules: BTreeMap<String, AccessRule>) -> Self {
+        Self { rules }
+    }
+
+    /// Checks that the caller is allowed to invoke the function.
+    pub fn check(&self, function_name: &str, call_parameters: &CallParameters) -> Result<()> {
+        let rule = self
+            .rules
+            .get(function_name)
+            .or_else(|| self.rules.get(ANY_FUNCTION_RULE_NAME));
+
+        match rule {
+            Some(rule) if !rule.is_allowed(call_parameters) => Err(AppServiceError::AccessDenied {
+                function_name: function_name.to_string(),
+                user_name: call_parameters.user_name.clone(),
+                application_id: call_parameters.application_id.clone(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns names of rules that don't refer to any of the functions except the "*" rule,
+    /// such rules are most likely misspelled and would never be applied.
+    pub fn unknown_functions<'a>(&'a self, function_names: &[&str]) -> Vec<&'a str> {
+        self.rules
+            .keys()
+            .map(String::as_str)
+            .filter(|name| *name != ANY_FUNCTION_RULE_NAME && !function_names.contains(name))
+            .collect()
+    }
+
+    pub fn rules(&self) -> &BTreeMap<String, AccessRule> {
+        &self.rules
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AccessControlList;
+    use super::AccessRule;
+    use crate::AppServiceError;
+    use crate::CallParameters;
+
+    use std::collections::BTreeMap;
+
+    fn rule(users: &[&str], applications: &[&str]) -> AccessRule {
+        AccessRule {
+            users: users.iter().map(|user| user.to_string()).collect(),
+            applications: applications.iter().map(|app| app.to_string()).collect(),
+        }
+    }
+
+    fn caller(user_name: &str, application_id: &str) -> CallParameters {
+        CallParameters {
+            user_name: user_name.to_string(),
+            application_id: application_id.to_string(),
+            ..<_>::default()
+        }
+    }
+
+    fn acl() -> AccessControlList {
+        let mut rules = BTreeMap::new();
+        rules.insert(String::from("get_secret"), rule(&["owner"], &[]));
+        rules.insert(String::from("*"), rule(&["admin"], &["dashboard"]));
+
+        AccessControlList::new(rules)
+    }
+
+    fn is_denied(acl: &AccessControlList, function_name: &str, caller: &CallParameters) -> bool {
+        matches!(
+            acl.check(function_name, caller),
+            Err(AppServiceError::AccessDenied { .. })
+        )
+    }
+
+    #[test]
+    fn function_rule_overrides_any_function_rule() {
+        let acl = acl();
+
+        assert!(acl.check("get_secret", &caller("owner", "")).is_ok());
+        assert!(is_denied(&acl, "get_secret", &caller("admin", "")));
+        assert!(is_denied(&acl, "get_secret", &caller("", "dashboard")));
+    }
+
+    #[test]
+    fn any_function_rule_is_fallback() {
+        let acl = acl();
+
+        assert!(acl.check("greeting", &caller("admin", "")).is_ok());
+        assert!(acl.check("greeting", &caller("", "dashboard")).is_ok());
+        assert!(is_denied(&acl, "greeting", &caller("owner", "other_app")));
+    }
+
+    #[test]
+    fn empty_acl_allows_everything() {
+        let acl = AccessControlList::default();
+
+        assert!(acl.check("get_secret", &caller("", "")).is_ok());
+    }
+
+    #[test]
+    fn unknown_functions_are_reported() {
+        let acl = acl();
+
+        assert_eq!(
+            acl.unknown_functions(&["get_secret", "greeting"]),
+            Vec::<&str>::new()
+        );
+        assert_eq!(acl.unknown_functions(&["greeting"]), vec!["get_secret"]);
+    }
+}
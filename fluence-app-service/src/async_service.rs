@@ -0,0 +1,95 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::AppService;
+use crate::CallParameters;
+use crate::Result;
+
+use fluence_faas::AsyncExecutor;
+use serde_json::Value as JValue;
+
+use std::future::Future;
+
+/// AppService called from async code, calls are executed one by one by a dedicated thread
+/// of the service and wait for it in a bounded queue.
+///
+/// Dropping AsyncAppService doesn't wait for the service: its thread executes queued calls
+/// and then drops the service, running the shutdown hook of its facade at that time.
+/// Use `shutdown` if the hook has to be finished at a certain point, f.e. before removing
+/// the service directory or exiting the process.
+pub struct AsyncAppService {
+    executor: AsyncExecutor<AppService>,
+}
+
+impl AsyncAppService {
+    /// Creates the service by `create` on its executor thread named after `service_id`,
+    /// at most `queue_capacity` calls could wait for it.
+    pub fn new<C, S>(create: C, service_id: S, queue_capacity: usize) -> Result<Self>
+    where
+        C: FnOnce() -> Result<AppService> + Send + 'static,
+        S: AsRef<str>,
+    {
+        let name = format!("app-service-{}", service_id.as_ref());
+        let executor = AsyncExecutor::new(create, name, queue_capacity)?;
+
+        Ok(Self { executor })
+    }
+
+    /// Queues a call of a facade function, fails immediately if the queue is full.
+    /// The call is cancelled if the returned future is dropped before the call is started,
+    /// so it could be used with timeouts of async runtimes.
+    pub fn call<S: Into<String>>(
+        &self,
+        func_name: S,
+        arguments: JValue,
+        call_parameters: CallParameters,
+    ) -> Result<impl Future<Output = Result<JValue>>> {
+        let func_name = func_name.into();
+        let response = self
+            .executor
+            .execute(move |service| service.call(func_name, arguments, call_parameters))?;
+
+        Ok(async move { response.await? })
+    }
+
+    /// Queues a call of a facade function with the supplied buffer as its stdin,
+    /// see `call` for details.
+    pub fn call_with_stdin<S: Into<String>>(
+        &self,
+        func_name: S,
+        arguments: JValue,
+        call_parameters: CallParameters,
+        stdin: Vec<u8>,
+    ) -> Result<impl Future<Output = Result<JValue>>> {
+        let func_name = func_name.into();
+        let response = self.executor.execute(move |service| {
+            service.call_with_stdin(func_name, arguments, call_parameters, stdin)
+        })?;
+
+        Ok(async move { response.await? })
+    }
+
+    /// Returns the executor to run arbitrary requests to the service.
+    pub fn executor(&self) -> &AsyncExecutor<AppService> {
+        &self.executor
+    }
+
+    /// Waits until all queued calls are executed and the service is dropped,
+    /// so its shutdown hook is finished when this returns.
+    pub fn shutdown(self) -> Result<()> {
+        Ok(self.executor.shutdown()?)
+    }
+}
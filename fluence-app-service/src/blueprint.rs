@@ -0,0 +1,52 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::Result;
+
+use fluence_faas::TomlFaaSModuleConfig;
+use serde_derive::Serialize;
+use serde_derive::Deserialize;
+
+use std::path::PathBuf;
+
+/// Describes a service as an ordered list of modules from a module store,
+/// the last module is the facade one.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct Blueprint {
+    pub name: String,
+
+    #[serde(rename = "module")]
+    pub modules: Vec<BlueprintModule>,
+}
+
+/// A module of a blueprint referred by its hash in a module store.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct BlueprintModule {
+    pub name: String,
+    pub hash: String,
+    #[serde(flatten)]
+    pub config: TomlFaaSModuleConfig,
+}
+
+impl Blueprint {
+    /// Load blueprint from filesystem, environment variables substitution
+    /// and includes are supported (see `fluence_faas::load_toml_value` for details).
+    pub fn load<P: Into<PathBuf>>(path: P) -> Result<Self> {
+        let path: PathBuf = path.into();
+        let blueprint = fluence_faas::load_toml_config(&path)?;
+        Ok(blueprint)
+    }
+}
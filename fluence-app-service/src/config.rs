@@ -14,7 +14,11 @@
  * limitations under the License.
  */
 
+use crate::AccessControlList;
+use crate::TomlAppServiceConfig;
+
 use std::path::PathBuf;
+use std::time::Duration;
 use fluence_faas::FaaSConfig;
 
 /// Describes behaviour of the Fluence AppService.
@@ -23,4 +27,67 @@ pub struct AppServiceConfig {
     /// Used for preparing filesystem on the service initialization stage.
     pub service_base_dir: PathBuf,
     pub faas_config: FaaSConfig,
+
+    /// Name of the module callable through the service, the last module is used if None.
+    pub facade: Option<String>,
+
+    /// Version of the service passed to on_upgrade of the next version.
+    pub version: Option<String>,
+
+    /// Facade functions called at specific points of the service lifecycle.
+    pub lifecycle_hooks: LifecycleHooks,
+
+    /// Callers allowed to invoke facade functions.
+    pub acl: AccessControlList,
+
+    /// Quota and cleanup settings of the service local and tmp dirs.
+    pub disk_config: DiskConfig,
+
+    /// The config this one was parsed from, it's needed to export the service state.
+    pub source_config: Option<TomlAppServiceConfig>,
+}
+
+/// Limits of the service local and tmp dirs.
+#[derive(Default, Debug, Clone)]
+pub struct DiskConfig {
+    /// Maximum total size of files in the local and tmp dirs in bytes, writes making
+    /// any of them larger fail, a call leaving them together above it is only reported.
+    pub quota: Option<u64>,
+
+    /// Defines when the tmp dir content is deleted.
+    pub tmp_cleanup: TmpCleanup,
+}
+
+/// Defines when the tmp dir content of a service is deleted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TmpCleanup {
+    /// The tmp dir is cleaned up only when the service is removed.
+    Never,
+
+    /// The tmp dir is cleaned up after each call of the service.
+    AfterCall,
+
+    /// The tmp dir is cleaned up after a call if the period passed since the last cleanup.
+    Periodic(Duration),
+}
+
+impl Default for TmpCleanup {
+    fn default() -> Self {
+        TmpCleanup::Never
+    }
+}
+
+/// Names of facade functions called by the service, if a name isn't set,
+/// the well-known one (init, on_upgrade, shutdown) is used when the facade exports it.
+#[derive(Default, Debug, Clone)]
+pub struct LifecycleHooks {
+    /// Called without arguments after the service is instantiated,
+    /// its failure aborts the service creation.
+    pub init: Option<String>,
+
+    /// Called with the previous service version as a string after the service is upgraded.
+    pub on_upgrade: Option<String>,
+
+    /// Called without arguments before the service is dropped.
+    pub shutdown: Option<String>,
 }
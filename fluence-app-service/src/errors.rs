@@ -36,6 +36,37 @@ pub enum AppServiceError {
 
     /// Errors related to malformed config.
     ConfigParseError(String),
+
+    /// A module is absent in a module store or its content doesn't match the hash.
+    ModuleStoreError(String),
+
+    /// A service with such id is already hosted by a manager.
+    ServiceAlreadyExists(String),
+
+    /// A service with such id isn't hosted by a manager.
+    NoSuchService(String),
+
+    /// A manager already hosts the maximum allowed count of services.
+    TooManyServices(usize),
+
+    /// Service id can't be used as a name of the service dir.
+    InvalidServiceId(String),
+
+    /// A lifecycle function (init, on_upgrade or shutdown) of the facade failed.
+    LifecycleFunctionFailed {
+        function_name: String,
+        err: FaaSError,
+    },
+
+    /// The caller isn't allowed to invoke the function by the service ACL.
+    AccessDenied {
+        function_name: String,
+        user_name: String,
+        application_id: String,
+    },
+
+    /// A service state archive is malformed or doesn't match the service.
+    InvalidState(String),
 }
 
 impl Error for AppServiceError {}
@@ -50,6 +81,36 @@ impl std::fmt::Display for AppServiceError {
                 write!(f, "Failed to create dir {:?}: {:?}", path, err)
             }
             AppServiceError::ConfigParseError(err_msg) => write!(f, "{}", err_msg),
+            AppServiceError::ModuleStoreError(err_msg) => write!(f, "{}", err_msg),
+            AppServiceError::ServiceAlreadyExists(service_id) => {
+                write!(f, "service with id {} already exists", service_id)
+            }
+            AppServiceError::NoSuchService(service_id) => {
+                write!(f, "service with id {} not found", service_id)
+            }
+            AppServiceError::TooManyServices(max_services) => write!(
+                f,
+                "service can't be created: limit of {} services is reached",
+                max_services
+            ),
+            AppServiceError::InvalidServiceId(service_id) => {
+                write!(f, "invalid service id {:?}", service_id)
+            }
+            AppServiceError::LifecycleFunctionFailed { function_name, err } => {
+                write!(f, "lifecycle function {} failed: {}", function_name, err)
+            }
+            AppServiceError::AccessDenied {
+                function_name,
+                user_name,
+                application_id,
+            } => write!(
+                f,
+                "user {:?} of application {:?} isn't allowed to call {}",
+                user_name, application_id, function_name
+            ),
+            AppServiceError::InvalidState(err_msg) => {
+                write!(f, "invalid service state: {}", err_msg)
+            }
         }
     }
 }
@@ -24,36 +24,82 @@
     unreachable_patterns
 )]
 
+mod acl;
+mod async_service;
+mod blueprint;
 mod config;
+mod disk_usage;
 mod errors;
+mod module_store;
 mod service;
+mod service_manager;
+mod service_state;
 mod raw_toml_config;
 
 pub(crate) type Result<T> = std::result::Result<T, AppServiceError>;
 
 pub use errors::AppServiceError;
 pub use service::AppService;
+pub use async_service::AsyncAppService;
+pub use service_manager::AppServiceManager;
+pub use service_state::ServiceStateInfo;
+pub use blueprint::Blueprint;
+pub use blueprint::BlueprintModule;
+pub use module_store::ModuleStore;
 
+pub use acl::AccessControlList;
+pub use acl::AccessRule;
 pub use config::AppServiceConfig;
+pub use config::LifecycleHooks;
+pub use config::DiskConfig;
+pub use config::TmpCleanup;
+pub use disk_usage::ServiceDiskUsage;
 pub use raw_toml_config::TomlAppServiceConfig;
 
 pub use fluence_faas::FaaSInterface as ServiceInterface;
 pub use fluence_faas::FaaSModuleInterface as ServiceModuleInterface;
 pub use fluence_faas::FaaSFunctionSignature as ServiceFunctionSignature;
+pub use fluence_faas::FaaSModuleStats as ServiceModuleStats;
 
 pub use fluence_faas::FaaSConfig;
 pub use fluence_faas::FaaSModuleConfig;
 pub use fluence_faas::FaaSWASIConfig;
+pub use fluence_faas::ConfigDiagnostic;
+pub use fluence_faas::FaaSEffectiveConfig;
+pub use fluence_faas::FaaSEffectiveModuleConfig;
+pub use fluence_faas::FaaSEffectiveWASIConfig;
+pub use fluence_faas::HostImportSignature;
+pub use fluence_faas::MappedDirPermissions;
+pub use fluence_faas::MappedDirUsage;
+pub use fluence_faas::WASIFileSystem;
+pub use fluence_faas::DeterministicConfig;
+pub use fluence_faas::ImportPolicy;
+pub use fluence_faas::InstanceMode;
+pub use fluence_faas::InstancePoolConfig;
+pub use fluence_faas::ModuleMetadata;
+pub use fluence_faas::PublicKey;
 pub use fluence_faas::TomlFaaSConfig;
 pub use fluence_faas::TomlFaaSModuleConfig;
 pub use fluence_faas::TomlFaaSNamedModuleConfig;
 pub use fluence_faas::TomlWASIConfig;
+pub use fluence_faas::TomlDeterministicConfig;
+pub use fluence_faas::TomlImportPolicy;
+pub use fluence_faas::TomlInstancePoolConfig;
 pub use fluence_faas::from_toml_faas_config;
 pub use fluence_faas::from_toml_module_config;
 pub use fluence_faas::from_toml_named_module_config;
 pub use fluence_faas::from_toml_wasi_config;
+pub use fluence_faas::module_hash;
+pub use fluence_faas::parse_module_hash;
+pub use fluence_faas::BundleManifest;
+pub use fluence_faas::ServiceBundle;
+pub use fluence_faas::from_toml_deterministic_config;
+pub use fluence_faas::from_toml_import_policy;
+pub use fluence_faas::from_toml_instance_pool_config;
 
 pub use fluence_faas::FaaSError;
+pub use fluence_faas::AsyncExecutor;
+pub use fluence_faas::ExecutionFuture;
 
 pub use fluence_faas::IValue;
 pub use fluence_faas::IRecordType;
@@ -0,0 +1,205 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::Result;
+use crate::AppServiceError;
+
+use fluence_faas::module_hash;
+use fluence_faas::parse_module_hash;
+
+use std::path::PathBuf;
+
+const MODULES_DIR_NAME: &str = "modules";
+const ARTIFACTS_DIR_NAME: &str = "artifacts";
+const SERVICES_DIR_NAME: &str = "services";
+
+/// Local content-addressed store of Wasm modules, it has the following layout:
+///  - root/modules/<blake3 digest>.wasm - module bytes, each module is stored only once
+///  - root/artifacts - compiled modules shared by all services created from the store
+///  - root/services - base dir of services created from blueprints
+///
+/// Modules are checked against their hashes on each read, but compiled modules are native
+/// code which is only checked for integrity, so the store must be writable only by the user
+/// running services. The artifacts dir is made owner-only, otherwise FCE doesn't use it.
+#[derive(Debug, Clone)]
+pub struct ModuleStore {
+    root: PathBuf,
+}
+
+impl ModuleStore {
+    /// Opens the store in the given dir creating its directory structure if needed.
+    pub fn new<P: Into<PathBuf>>(root: P) -> Result<Self> {
+        let root = root.into();
+        for dir_name in &[MODULES_DIR_NAME, ARTIFACTS_DIR_NAME, SERVICES_DIR_NAME] {
+            let dir = root.join(dir_name);
+            std::fs::create_dir_all(&dir)
+                .map_err(|err| AppServiceError::CreateDir { err, path: dir })?;
+        }
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let permissions = std::fs::Permissions::from_mode(0o700);
+            std::fs::set_permissions(root.join(ARTIFACTS_DIR_NAME), permissions)?;
+        }
+
+        Ok(Self { root })
+    }
+
+    /// Adds the module to the store and returns its hash,
+    /// adding an already stored module doesn't touch the disk.
+    pub fn add_module(&self, module_bytes: &[u8]) -> Result<String> {
+        let hash = module_hash(module_bytes);
+        let module_path = self.module_path(&hash)?;
+        if module_path.exists() {
+            return Ok(hash);
+        }
+
+        // write to a temporary file first, so a partially written module is never visible
+        let tmp_path = module_path.with_extension("tmp");
+        std::fs::write(&tmp_path, module_bytes)?;
+        std::fs::rename(&tmp_path, &module_path)?;
+
+        Ok(hash)
+    }
+
+    /// Returns bytes of the module with the given hash, the content is checked against the hash.
+    pub fn get_module(&self, hash: &str) -> Result<Vec<u8>> {
+        let hash = parse_module_hash(hash).map_err(AppServiceError::ModuleStoreError)?;
+        let module_path = self.module_path(&hash)?;
+        let module_bytes = std::fs::read(&module_path).map_err(|e| {
+            AppServiceError::ModuleStoreError(format!(
+                "module {} can't be read from {:?}: {}",
+                hash, module_path, e
+            ))
+        })?;
+
+        let actual_hash = module_hash(&module_bytes);
+        if actual_hash != hash {
+            return Err(AppServiceError::ModuleStoreError(format!(
+                "module {:?} is corrupted, its hash is {}",
+                module_path, actual_hash
+            )));
+        }
+
+        Ok(module_bytes)
+    }
+
+    /// Returns true if the module with the given hash is in the store.
+    pub fn contains(&self, hash: &str) -> bool {
+        self.module_path(hash)
+            .map(|path| path.exists())
+            .unwrap_or(false)
+    }
+
+    /// Returns the dir with compiled modules.
+    pub fn artifacts_dir(&self) -> PathBuf {
+        self.root.join(ARTIFACTS_DIR_NAME)
+    }
+
+    /// Returns the base dir of services created from blueprints.
+    pub fn services_dir(&self) -> PathBuf {
+        self.root.join(SERVICES_DIR_NAME)
+    }
+
+    fn module_path(&self, hash: &str) -> Result<PathBuf> {
+        let hash = parse_module_hash(hash).map_err(AppServiceError::ModuleStoreError)?;
+        // parse_module_hash guarantees that the hash consists of a prefix and a hex digest
+        let digest = hash.rsplit(':').next().unwrap_or(&hash);
+
+        Ok(self.modules_dir().join(format!("{}.wasm", digest)))
+    }
+
+    fn modules_dir(&self) -> PathBuf {
+        self.root.join(MODULES_DIR_NAME)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ModuleStore;
+    use crate::AppServiceError;
+
+    use fluence_faas::module_hash;
+
+    use std::path::PathBuf;
+
+    fn store_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("fce_store_{}_{}", name, std::process::id()))
+    }
+
+    #[test]
+    fn added_module_is_returned() {
+        let store_dir = store_dir("add");
+        let store = ModuleStore::new(&store_dir).unwrap();
+
+        let hash = store.add_module(b"module").unwrap();
+        let added_again = store.add_module(b"module");
+        let module_bytes = store.get_module(&hash.to_uppercase().replace("BLAKE3", "blake3"));
+        let contains_other = store.contains(&module_hash(b"other module"));
+        let contains_added = store.contains(&hash);
+        std::fs::remove_dir_all(store_dir).unwrap();
+
+        assert_eq!(hash, module_hash(b"module"));
+        assert_eq!(added_again.unwrap(), hash);
+        assert_eq!(module_bytes.unwrap(), b"module".to_vec());
+        assert!(contains_added);
+        assert!(!contains_other);
+    }
+
+    #[test]
+    fn corrupted_and_missing_modules_are_rejected() {
+        let store_dir = store_dir("corrupted");
+        let store = ModuleStore::new(&store_dir).unwrap();
+
+        let hash = store.add_module(b"module").unwrap();
+        let digest = hash.strip_prefix("blake3:").unwrap();
+        std::fs::write(
+            store_dir.join("modules").join(format!("{}.wasm", digest)),
+            b"corrupted module",
+        )
+        .unwrap();
+
+        let corrupted = store.get_module(&hash);
+        let missing = store.get_module(&module_hash(b"other module"));
+        let malformed = store.get_module("blake3:../../etc/passwd");
+        std::fs::remove_dir_all(store_dir).unwrap();
+
+        assert!(matches!(
+            corrupted,
+            Err(AppServiceError::ModuleStoreError(_))
+        ));
+        assert!(matches!(missing, Err(AppServiceError::ModuleStoreError(_))));
+        assert!(matches!(
+            malformed,
+            Err(AppServiceError::ModuleStoreError(_))
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn artifacts_dir_is_owner_only() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let store_dir = store_dir("artifacts");
+        let store = ModuleStore::new(&store_dir).unwrap();
+        let mode = std::fs::metadata(store.artifacts_dir()).map(|m| m.permissions().mode());
+        std::fs::remove_dir_all(store_dir).unwrap();
+
+        assert_eq!(mode.unwrap() & 0o777, 0o700);
+    }
+}
@@ -17,31 +17,143 @@
 use crate::Result;
 use crate::AppServiceError;
 use crate::config::AppServiceConfig;
+use crate::config::DiskConfig;
+use crate::config::LifecycleHooks;
+use crate::config::TmpCleanup;
+use crate::AccessControlList;
+use crate::AccessRule;
 
+use fluence_faas::ConfigDiagnostic;
 use fluence_faas::TomlFaaSConfig;
 use fluence_faas::from_toml_faas_config;
+use fce_wit_parser::extract_export_names;
 use serde_derive::Serialize;
 use serde_derive::Deserialize;
 
+use std::collections::BTreeMap;
 use std::convert::TryInto;
+use std::path::Path;
 use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(Deserialize, Serialize, Debug, Clone, Default)]
 pub struct TomlAppServiceConfig {
     pub service_base_dir: Option<String>,
+    pub facade: Option<String>,
+    pub version: Option<String>,
+    pub init_function: Option<String>,
+    pub on_upgrade_function: Option<String>,
+    pub shutdown_function: Option<String>,
+    /// An integer in bytes or a string like "100MB", like max_bytes of mapped dirs.
+    pub disk_quota: Option<toml::Value>,
+    pub tmp_cleanup: Option<String>,
+    pub tmp_cleanup_interval_secs: Option<u64>,
 
     #[serde(flatten)]
     pub toml_faas_config: TomlFaaSConfig,
+
+    // tables are placed after the flattened config since TOML values can't follow tables
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub acl: Option<BTreeMap<String, AccessRule>>,
 }
 
 impl TomlAppServiceConfig {
-    /// Load config from filesystem.
+    /// Load config from filesystem, environment variables substitution
+    /// and includes are supported (see `fluence_faas::load_toml_value` for details).
     pub fn load<P: Into<PathBuf>>(path: P) -> Result<Self> {
-        let path = path.into();
-        let file_content = std::fs::read(&path)?;
-        toml::from_slice(&file_content).map_err(|e| {
-            AppServiceError::ConfigParseError(format!("Error parsing config {:?}: {:?}", path, e))
-        })
+        let path: PathBuf = path.into();
+        let config = fluence_faas::load_toml_config(&path)?;
+        Ok(config)
+    }
+
+    /// Check the config against the filesystem and return all found problems at once.
+    pub fn validate(&self) -> Vec<ConfigDiagnostic> {
+        let mut diagnostics = self.toml_faas_config.validate();
+
+        if let Some(service_base_dir) = &self.service_base_dir {
+            if !std::path::Path::new(service_base_dir).is_dir() {
+                diagnostics.push(ConfigDiagnostic::new(
+                    "service_base_dir",
+                    format!("{:?} isn't an existing directory", service_base_dir),
+                ));
+            }
+        }
+
+        if let Err(e) = parse_disk_quota(self.disk_quota.as_ref()) {
+            diagnostics.push(ConfigDiagnostic::new("disk_quota", e));
+        }
+
+        if let Err(e) =
+            parse_tmp_cleanup(self.tmp_cleanup.as_deref(), self.tmp_cleanup_interval_secs)
+        {
+            diagnostics.push(ConfigDiagnostic::new("tmp_cleanup", e));
+        }
+
+        if let Some(facade) = &self.facade {
+            let is_known_module = self
+                .toml_faas_config
+                .module
+                .iter()
+                .any(|module| &module.name == facade);
+            if !is_known_module {
+                diagnostics.push(ConfigDiagnostic::new(
+                    "facade",
+                    format!("module {} isn't specified in the config", facade),
+                ));
+            }
+        }
+
+        self.validate_acl(&mut diagnostics);
+
+        diagnostics
+    }
+
+    /// ACL rules must refer to functions exported by the facade module, it's checked
+    /// only if the facade module file could be found.
+    fn validate_acl(&self, diagnostics: &mut Vec<ConfigDiagnostic>) {
+        let acl = match &self.acl {
+            Some(rules) => AccessControlList::new(rules.clone()),
+            None => return,
+        };
+        let facade = self.facade.as_ref().or_else(|| {
+            self.toml_faas_config
+                .module
+                .last()
+                .map(|module| &module.name)
+        });
+        let (modules_dir, facade) = match (&self.toml_faas_config.modules_dir, facade) {
+            (Some(modules_dir), Some(facade)) => (modules_dir, facade),
+            _ => return,
+        };
+        let facade_path = Path::new(modules_dir).join(format!("{}.wasm", facade));
+        if !facade_path.is_file() {
+            return;
+        }
+
+        let facade_functions = match extract_export_names(facade_path.clone()) {
+            Ok(facade_functions) => facade_functions,
+            Err(e) => {
+                diagnostics.push(ConfigDiagnostic::new(
+                    "acl",
+                    format!("exports of {:?} can't be extracted: {}", facade_path, e),
+                ));
+                return;
+            }
+        };
+        let facade_functions = facade_functions
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+
+        for function_name in acl.unknown_functions(&facade_functions) {
+            diagnostics.push(ConfigDiagnostic::new(
+                format!("acl.{}", function_name),
+                format!(
+                    "function {} isn't exported by the facade module {}",
+                    function_name, facade
+                ),
+            ));
+        }
     }
 }
 
@@ -49,16 +161,70 @@ impl TryInto<AppServiceConfig> for TomlAppServiceConfig {
     type Error = AppServiceError;
 
     fn try_into(self) -> Result<AppServiceConfig> {
+        let source_config = self.clone();
         let faas_config = from_toml_faas_config(self.toml_faas_config)?;
         let service_base_dir = match self.service_base_dir {
             Some(service_base_dir) => PathBuf::from(service_base_dir),
             // use tmp dir for service base dir if it isn't defined
-            None => std::env::temp_dir(),
+            None => {
+                log::warn!("service_base_dir isn't set, services will be created in the temp dir");
+                std::env::temp_dir()
+            }
         };
+        let tmp_cleanup =
+            parse_tmp_cleanup(self.tmp_cleanup.as_deref(), self.tmp_cleanup_interval_secs)
+                .map_err(AppServiceError::ConfigParseError)?;
+        let disk_quota = parse_disk_quota(self.disk_quota.as_ref())
+            .map_err(AppServiceError::ConfigParseError)?;
 
         Ok(AppServiceConfig {
             service_base_dir,
             faas_config,
+            facade: self.facade,
+            version: self.version,
+            lifecycle_hooks: LifecycleHooks {
+                init: self.init_function,
+                on_upgrade: self.on_upgrade_function,
+                shutdown: self.shutdown_function,
+            },
+            acl: AccessControlList::new(self.acl.unwrap_or_default()),
+            disk_config: DiskConfig {
+                quota: disk_quota,
+                tmp_cleanup,
+            },
+            source_config: Some(source_config),
         })
     }
 }
+
+pub(crate) fn parse_disk_quota(
+    disk_quota: Option<&toml::Value>,
+) -> std::result::Result<Option<u64>, String> {
+    disk_quota
+        .map(fluence_faas::parse_bytes_value)
+        .transpose()
+        .map_err(|e| format!("disk_quota: {}", e))
+}
+
+/// Parses tmp_cleanup ("never" or "after_call") and tmp_cleanup_interval_secs,
+/// only one of them could be set.
+pub(crate) fn parse_tmp_cleanup(
+    tmp_cleanup: Option<&str>,
+    interval_secs: Option<u64>,
+) -> std::result::Result<TmpCleanup, String> {
+    match (tmp_cleanup, interval_secs) {
+        (Some(_), Some(_)) => Err(String::from(
+            "tmp_cleanup and tmp_cleanup_interval_secs can't be set simultaneously",
+        )),
+        (None, Some(0)) => Err(String::from(
+            "tmp_cleanup_interval_secs should be greater than zero",
+        )),
+        (None, Some(interval_secs)) => Ok(TmpCleanup::Periodic(Duration::from_secs(interval_secs))),
+        (Some("never"), None) | (None, None) => Ok(TmpCleanup::Never),
+        (Some("after_call"), None) => Ok(TmpCleanup::AfterCall),
+        (Some(tmp_cleanup), None) => Err(format!(
+            r#"unknown tmp_cleanup "{}", expected "never" or "after_call""#,
+            tmp_cleanup
+        )),
+    }
+}
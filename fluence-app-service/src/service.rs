@@ -15,25 +15,77 @@
  */
 
 use crate::Result;
+use crate::blueprint::Blueprint;
 use crate::config::AppServiceConfig;
+use crate::config::DiskConfig;
+use crate::config::TmpCleanup;
+use crate::AccessControlList;
+use crate::disk_usage::clear_dir;
+use crate::disk_usage::dir_size;
+use crate::disk_usage::ServiceDiskUsage;
+use crate::service_state::ServiceState;
+use crate::service_state::ServiceStateInfo;
+use crate::service_state::STATE_FORMAT_VERSION;
+use crate::module_store::ModuleStore;
+use crate::raw_toml_config::TomlAppServiceConfig;
+use crate::IValue;
+use crate::IType;
 use super::AppServiceError;
 
+use fluence_faas::FaaSConfig;
+use fluence_faas::FaaSModuleConfig;
+use fluence_faas::MappedDirPermissions;
+use fluence_faas::WASIFileSystem;
 use fluence_faas::FluenceFaaS;
+use fluence_faas::ServiceBundle;
+use fluence_faas::module_hash;
 use serde_json::Value as JValue;
 
 use std::convert::TryInto;
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::path::Path;
 use std::path::PathBuf;
 use std::io::ErrorKind;
+use std::time::Instant;
 
 const SERVICE_ID_ENV_NAME: &str = "service_id";
 const SERVICE_LOCAL_DIR_NAME: &str = "local";
 const SERVICE_TMP_DIR_NAME: &str = "tmp";
 
+const INIT_FUNCTION_NAME: &str = "init";
+const ON_UPGRADE_FUNCTION_NAME: &str = "on_upgrade";
+const SHUTDOWN_FUNCTION_NAME: &str = "shutdown";
+
 pub struct AppService {
     faas: FluenceFaaS,
     facade_module_name: String,
+
+    /// Id and environment variables of the service, they're reused on upgrades.
+    service_id: String,
+    envs: HashMap<Vec<u8>, Vec<u8>>,
+
+    /// Version of the service, it's passed to on_upgrade of the next version.
+    version: Option<String>,
+
+    /// Facade function called before the service is dropped.
+    shutdown_function: Option<String>,
+
+    /// Callers allowed to invoke facade functions.
+    acl: AccessControlList,
+
+    /// The service_base_dir/service_id dir with local and tmp dirs of the service.
+    service_dir: PathBuf,
+
+    /// Quota and cleanup settings of the service dirs.
+    disk_config: DiskConfig,
+
+    /// Time of the last tmp dir cleanup used by the periodic cleanup.
+    last_tmp_cleanup: Instant,
+
+    /// The config the service was created from, it's written to exported states.
+    source_config: Option<TomlAppServiceConfig>,
 }
 
 impl AppService {
@@ -45,30 +97,129 @@ impl AppService {
         AppServiceError: From<C::Error>,
     {
         let mut config: AppServiceConfig = config.try_into()?;
-        let facade_module_name = config
-            .faas_config
-            .modules_config
-            .last()
-            .ok_or_else(|| {
-                AppServiceError::ConfigParseError(String::from(
-                    "config should contain at least one module",
-                ))
-            })?
-            .0
-            .clone();
+        let facade_module_name = Self::facade_module_name(&config)?;
 
         let service_id = service_id.into();
-        Self::set_env_and_dirs(&mut config, service_id, envs)?;
+        Self::set_env_and_dirs(&mut config, service_id.clone(), envs.clone())?;
 
-        let faas = FluenceFaaS::with_raw_config(config.faas_config)?;
+        let faas = FluenceFaaS::with_raw_config(std::mem::take(&mut config.faas_config))?;
 
-        Ok(Self {
+        Self::start(faas, facade_module_name, service_id, envs, config)
+    }
+
+    /// Create Service from a bundle, the local dir of the service is seeded with the bundle
+    /// data, files that already exist there are kept intact.
+    pub fn with_bundle<P, S>(
+        bundle_path: P,
+        service_id: S,
+        envs: HashMap<Vec<u8>, Vec<u8>>,
+    ) -> Result<Self>
+    where
+        P: AsRef<Path>,
+        S: Into<String>,
+    {
+        let bundle = ServiceBundle::load(bundle_path)?;
+        let config: TomlAppServiceConfig = toml::from_str(&bundle.config)
+            .map_err(|e| AppServiceError::ConfigParseError(format!("bundle config: {}", e)))?;
+        let mut config: AppServiceConfig = config.try_into()?;
+        let facade_module_name = Self::facade_module_name(&config)?;
+
+        let service_id = service_id.into();
+        let local_dir = config
+            .service_base_dir
+            .join(&service_id)
+            .join(SERVICE_LOCAL_DIR_NAME);
+        Self::set_env_and_dirs(&mut config, service_id.clone(), envs.clone())?;
+        Self::seed_local_dir(&local_dir, bundle.data)?;
+
+        let faas =
+            FluenceFaaS::with_modules(bundle.modules, std::mem::take(&mut config.faas_config))?;
+
+        Self::start(faas, facade_module_name, service_id, envs, config)
+    }
+
+    /// Create Service from modules of the store referred by the blueprint, the service dir
+    /// is created inside the store and compiled modules are shared with other its services.
+    pub fn from_blueprint<S: Into<String>>(
+        store: &ModuleStore,
+        blueprint: &Blueprint,
+        service_id: S,
+    ) -> Result<Self> {
+        let mut modules = HashMap::with_capacity(blueprint.modules.len());
+        let mut modules_config = Vec::with_capacity(blueprint.modules.len());
+        for module in blueprint.modules.iter() {
+            let module_bytes = store.get_module(&module.hash)?;
+            let mut module_config = fluence_faas::from_toml_module_config(module.config.clone())?;
+            module_config.hash = Some(module_hash(&module_bytes));
+
+            modules.insert(module.name.clone(), module_bytes);
+            modules_config.push((module.name.clone(), module_config));
+        }
+
+        let faas_config = FaaSConfig {
+            modules_config,
+            compilation_cache_dir: Some(store.artifacts_dir()),
+            ..<_>::default()
+        };
+        let mut config = AppServiceConfig {
+            service_base_dir: store.services_dir(),
+            faas_config,
+            ..<_>::default()
+        };
+        let facade_module_name = Self::facade_module_name(&config).map_err(|_| {
+            AppServiceError::ConfigParseError(format!(
+                "blueprint {} should contain at least one module",
+                blueprint.name
+            ))
+        })?;
+
+        let service_id = service_id.into();
+        Self::set_env_and_dirs(&mut config, service_id.clone(), HashMap::new())?;
+        let faas = FluenceFaaS::with_modules(modules, std::mem::take(&mut config.faas_config))?;
+
+        Self::start(faas, facade_module_name, service_id, HashMap::new(), config)
+    }
+
+    /// Replace modules of the service with the ones from the new config keeping its id,
+    /// environment variables and dirs. The new service is created and initialized first,
+    /// then on_upgrade of its facade is called with the previous service version (an empty
+    /// string if it isn't set). Only after that the old service is shut down and replaced,
+    /// if any of these steps fails, the old service is kept running and the new one is
+    /// dropped without calling its shutdown function.
+    pub fn upgrade<C>(&mut self, config: C) -> Result<()>
+    where
+        C: TryInto<AppServiceConfig>,
+        AppServiceError: From<C::Error>,
+    {
+        let mut config: AppServiceConfig = config.try_into()?;
+        let facade_module_name = Self::facade_module_name(&config)?;
+        Self::set_env_and_dirs(&mut config, self.service_id.clone(), self.envs.clone())?;
+
+        let faas = FluenceFaaS::with_raw_config(std::mem::take(&mut config.faas_config))?;
+        let on_upgrade = config.lifecycle_hooks.on_upgrade.clone();
+        let mut service = Self::start(
             faas,
             facade_module_name,
-        })
+            self.service_id.clone(),
+            self.envs.clone(),
+            config,
+        )?;
+
+        let old_version = self.version.clone().unwrap_or_default();
+        if let Err(e) = service.on_upgrade(on_upgrade.as_deref(), old_version) {
+            // the new service doesn't replace the old one, so it mustn't be shut down
+            service.shutdown_function = None;
+            return Err(e);
+        }
+
+        self.shutdown();
+        *self = service;
+
+        Ok(())
     }
 
-    /// Call a specified function of loaded module by its name.
+    /// Call a specified function of loaded module by its name,
+    /// the caller from call parameters must be allowed to call it by the service ACL.
     // TODO: replace serde_json::Value with Vec<u8>?
     pub fn call<S: AsRef<str>>(
         &mut self,
@@ -76,14 +227,200 @@ impl AppService {
         arguments: JValue,
         call_parameters: crate::CallParameters,
     ) -> Result<JValue> {
-        self.faas
-            .call_with_json(
-                &self.facade_module_name,
-                func_name,
-                arguments,
-                call_parameters,
+        self.acl.check(func_name.as_ref(), &call_parameters)?;
+
+        let result = self.faas.call_with_json(
+            &self.facade_module_name,
+            func_name,
+            arguments,
+            call_parameters,
+        );
+
+        self.finish_call(result)
+    }
+
+    /// Call a specified function of loaded module by its name,
+    /// the supplied buffer is used as the facade module stdin during this call.
+    pub fn call_with_stdin<S: AsRef<str>>(
+        &mut self,
+        func_name: S,
+        arguments: JValue,
+        call_parameters: crate::CallParameters,
+        stdin: Vec<u8>,
+    ) -> Result<JValue> {
+        self.acl.check(func_name.as_ref(), &call_parameters)?;
+
+        let result = self.faas.call_with_json_and_stdin(
+            &self.facade_module_name,
+            func_name,
+            arguments,
+            call_parameters,
+            stdin,
+        );
+
+        self.finish_call(result)
+    }
+
+    /// Shut down the service and delete its tmp dir content, the local dir
+    /// with the service state is deleted too if purge_data is set.
+    pub fn remove(mut self, purge_data: bool) -> Result<()> {
+        let service_dir = std::mem::take(&mut self.service_dir);
+        // the service has to be shut down before its files are deleted
+        drop(self);
+
+        if !purge_data {
+            return clear_dir(&service_dir.join(SERVICE_TMP_DIR_NAME)).map_err(Into::into);
+        }
+
+        match std::fs::remove_dir_all(&service_dir) {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Write the service state to an archive: the config with module hashes pinned,
+    /// files of the local dir and memory snapshots of all modules. Only services created
+    /// from a TOML config could be exported, the state should be exported between calls.
+    /// Services with modules using the in-memory filesystem aren't exported, since their
+    /// files couldn't be restored, neither are services with pooled modules.
+    pub fn export_state<P: AsRef<Path>>(&mut self, archive_path: P) -> Result<()> {
+        let mut config = self.source_config.clone().ok_or_else(|| {
+            AppServiceError::InvalidState(format!(
+                "service {} isn't created from a TOML config, so it can't be exported",
+                self.service_id
+            ))
+        })?;
+
+        let memory_fs_modules = self
+            .faas
+            .effective_config()
+            .module
+            .iter()
+            .filter(|module| module.wasi.fs == "memory")
+            .map(|module| module.name.as_str())
+            .collect::<Vec<_>>();
+        if !memory_fs_modules.is_empty() {
+            return Err(AppServiceError::InvalidState(format!(
+                "service {} can't be exported, files of modules {} are in the in-memory filesystem",
+                self.service_id,
+                memory_fs_modules.join(", ")
+            )));
+        }
+
+        // calls of pooled modules are served by instances of their pools, the module itself
+        // isn't instantiated by FaaS, so its memory doesn't reflect any calls
+        let pooled_modules = self
+            .faas
+            .effective_config()
+            .module
+            .iter()
+            .filter(|module| module.instance_pool.is_some())
+            .map(|module| module.name.as_str())
+            .collect::<Vec<_>>();
+        if !pooled_modules.is_empty() {
+            return Err(AppServiceError::InvalidState(format!(
+                "service {} can't be exported, modules {} are served by instance pools",
+                self.service_id,
+                pooled_modules.join(", ")
+            )));
+        }
+
+        let module_hashes = self
+            .faas
+            .effective_config()
+            .module
+            .iter()
+            .map(|module| (module.name.clone(), module.hash.clone()))
+            .collect::<BTreeMap<_, _>>();
+        for module in config.toml_faas_config.module.iter_mut() {
+            module.hash = module_hashes.get(&module.name).cloned();
+        }
+
+        let memory_snapshots = module_hashes
+            .keys()
+            .map(|module_name| {
+                let snapshot = self.faas.module_memory_snapshot(module_name)?;
+                Ok((module_name.clone(), snapshot))
+            })
+            .collect::<Result<HashMap<_, _>>>()?;
+
+        let local_dir = self.service_dir.join(SERVICE_LOCAL_DIR_NAME);
+        let info = ServiceStateInfo {
+            format_version: STATE_FORMAT_VERSION,
+            service_id: self.service_id.clone(),
+            module_hashes,
+            envs: self.envs.clone().into_iter().collect(),
+        };
+        let state = ServiceState {
+            info,
+            config,
+            local_files: ServiceState::collect_local_files(&local_dir)?,
+            memory_snapshots,
+        };
+
+        state.write(archive_path.as_ref())
+    }
+
+    /// Recreate a service from an archive written by `export_state`. The archive and the
+    /// modules from the config modules_dir are checked against recorded hashes, the local dir
+    /// of the new service is restored before it's created and memory snapshots right after.
+    /// The init function isn't called, since the snapshots contain already initialized state.
+    pub fn import_state<P: AsRef<Path>, S: Into<String>>(
+        archive_path: P,
+        service_id: S,
+    ) -> Result<Self> {
+        let ServiceState {
+            info,
+            config,
+            local_files,
+            memory_snapshots,
+        } = ServiceState::read(archive_path.as_ref())?;
+        let config: AppServiceConfig = config.try_into()?;
+
+        let service_id = service_id.into();
+        let service_dir = config.service_base_dir.join(&service_id);
+        if service_dir.exists() {
+            return Err(AppServiceError::InvalidState(format!(
+                "dir {:?} of the imported service already exists",
+                service_dir
+            )));
+        }
+
+        let import = || -> Result<Self> {
+            let mut config = config;
+            let facade_module_name = Self::facade_module_name(&config)?;
+            let envs: HashMap<_, _> = info.envs.into_iter().collect();
+            Self::set_env_and_dirs(&mut config, service_id.clone(), envs.clone())?;
+            Self::seed_local_dir(&service_dir.join(SERVICE_LOCAL_DIR_NAME), local_files)?;
+
+            let faas = FluenceFaaS::with_raw_config(std::mem::take(&mut config.faas_config))?;
+            Self::restore(
+                faas,
+                facade_module_name,
+                service_id,
+                envs,
+                config,
+                memory_snapshots,
             )
-            .map_err(Into::into)
+        };
+
+        import().map_err(|e| {
+            let _ = std::fs::remove_dir_all(&service_dir);
+            e
+        })
+    }
+
+    /// Return id of the service.
+    pub fn service_id(&self) -> &str {
+        &self.service_id
+    }
+
+    /// Return sizes of files in the local and tmp dirs of the service.
+    pub fn disk_usage(&self) -> Result<ServiceDiskUsage> {
+        let local = dir_size(&self.service_dir.join(SERVICE_LOCAL_DIR_NAME))?;
+        let tmp = dir_size(&self.service_dir.join(SERVICE_TMP_DIR_NAME))?;
+
+        Ok(ServiceDiskUsage { local, tmp })
     }
 
     /// Return all export functions (name and signatures) of loaded modules.
@@ -91,11 +428,389 @@ impl AppService {
         self.faas.get_interface()
     }
 
+    /// Return export functions (name and signatures) of the facade module only,
+    /// since only they could be called through the service.
+    pub fn get_facade_interface(&self) -> fluence_faas::FaaSInterface<'_> {
+        let modules = self
+            .faas
+            .get_module_interface(&self.facade_module_name)
+            .map(|interface| (self.facade_module_name.as_str(), interface))
+            .into_iter()
+            .collect();
+
+        fluence_faas::FaaSInterface { modules }
+    }
+
+    /// Return fully resolved config of loaded modules including environment variables
+    /// and directories added by the service.
+    pub fn effective_config(&self) -> &fluence_faas::FaaSEffectiveConfig {
+        self.faas.effective_config()
+    }
+
+    /// Return paths and sizes of all files of the in-memory filesystem of a module.
+    pub fn module_memory_fs_files<S: AsRef<str>>(
+        &mut self,
+        module_name: S,
+    ) -> Result<Vec<(PathBuf, u64)>> {
+        self.faas
+            .module_memory_fs_files(module_name)
+            .map_err(Into::into)
+    }
+
+    /// Return content of a file of the in-memory filesystem of a module.
+    pub fn read_module_memory_fs_file<S: AsRef<str>, P: AsRef<Path>>(
+        &mut self,
+        module_name: S,
+        path: P,
+    ) -> Result<Vec<u8>> {
+        self.faas
+            .read_module_memory_fs_file(module_name, path)
+            .map_err(Into::into)
+    }
+
+    /// Return runtime statistics of a module of the service.
+    pub fn module_stats<S: AsRef<str>>(
+        &self,
+        module_name: S,
+    ) -> Result<fluence_faas::FaaSModuleStats> {
+        self.faas.module_stats(module_name).map_err(Into::into)
+    }
+
+    /// Evict instances of modules of the service idle longer than their idle timeouts,
+    /// return their names.
+    pub fn evict_idle_modules(&mut self) -> Vec<String> {
+        self.faas.evict_idle_modules()
+    }
+
+    /// Delete content of the tmp dir of the service.
+    pub fn cleanup_tmp(&mut self) -> Result<()> {
+        clear_dir(&self.service_dir.join(SERVICE_TMP_DIR_NAME))?;
+        self.last_tmp_cleanup = Instant::now();
+
+        Ok(())
+    }
+
+    /// Delete content of the tmp dir if it's time by the tmp cleanup settings, returns whether
+    /// it's been deleted. Periodic cleanup is also done after calls, so this function should be
+    /// called by a timer to clean up services that aren't called.
+    pub fn cleanup_tmp_if_due(&mut self) -> Result<bool> {
+        let is_due = match self.disk_config.tmp_cleanup {
+            TmpCleanup::Never => false,
+            TmpCleanup::AfterCall => true,
+            TmpCleanup::Periodic(period) => self.last_tmp_cleanup.elapsed() >= period,
+        };
+        if is_due {
+            self.cleanup_tmp()?;
+        }
+
+        Ok(is_due)
+    }
+
+    /// Clean up the tmp dir if it's time after a facade call. The disk quota is enforced
+    /// by limiting writes of host-fs modules while the call is running, since each of the local
+    /// and tmp dirs is limited by the whole quota, their total size could still exceed it,
+    /// such a call isn't failed because it's already done, but it's reported.
+    fn finish_call(
+        &mut self,
+        result: std::result::Result<JValue, fluence_faas::FaaSError>,
+    ) -> Result<JValue> {
+        // a failed cleanup shouldn't hide the call result, it's retried after the next call
+        if let Err(e) = self.cleanup_tmp_if_due() {
+            log::warn!(
+                "service {}: tmp dir can't be cleaned up: {}",
+                self.service_id,
+                e
+            );
+        }
+
+        let result = result?;
+        if let Some(quota) = self.disk_config.quota {
+            match self.disk_usage() {
+                Ok(usage) if usage.total() > quota => log::warn!(
+                    "service {} uses {} bytes of disk exceeding its quota of {} bytes",
+                    self.service_id,
+                    usage.total(),
+                    quota
+                ),
+                Ok(_) => {}
+                Err(e) => log::warn!(
+                    "service {}: disk usage can't be checked: {}",
+                    self.service_id,
+                    e
+                ),
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Finish service creation by calling the init function of the facade.
+    fn start(
+        faas: FluenceFaaS,
+        facade_module_name: String,
+        service_id: String,
+        envs: HashMap<Vec<u8>, Vec<u8>>,
+        config: AppServiceConfig,
+    ) -> Result<Self> {
+        let lifecycle_hooks = config.lifecycle_hooks.clone();
+        let mut service = Self::build(faas, facade_module_name, service_id, envs, config)?;
+
+        let init_function = service.resolve_lifecycle_function(
+            lifecycle_hooks.init.as_deref(),
+            INIT_FUNCTION_NAME,
+            &[],
+        )?;
+        let shutdown_function = service.resolve_lifecycle_function(
+            lifecycle_hooks.shutdown.as_deref(),
+            SHUTDOWN_FUNCTION_NAME,
+            &[],
+        )?;
+
+        if let Some(init_function) = init_function {
+            service.call_lifecycle_function(&init_function, &[])?;
+        }
+        // shutdown is called only for successfully initialized services
+        service.shutdown_function = shutdown_function;
+
+        Ok(service)
+    }
+
+    /// Finish creation of an imported service by restoring memory snapshots of its modules
+    /// instead of calling the init function.
+    fn restore(
+        faas: FluenceFaaS,
+        facade_module_name: String,
+        service_id: String,
+        envs: HashMap<Vec<u8>, Vec<u8>>,
+        config: AppServiceConfig,
+        memory_snapshots: HashMap<String, Vec<u8>>,
+    ) -> Result<Self> {
+        let shutdown_hook = config.lifecycle_hooks.shutdown.clone();
+        let mut service = Self::build(faas, facade_module_name, service_id, envs, config)?;
+
+        let shutdown_function = service.resolve_lifecycle_function(
+            shutdown_hook.as_deref(),
+            SHUTDOWN_FUNCTION_NAME,
+            &[],
+        )?;
+
+        for (module_name, snapshot) in memory_snapshots {
+            service
+                .faas
+                .restore_module_memory_snapshot(module_name, &snapshot)?;
+        }
+        service.shutdown_function = shutdown_function;
+
+        Ok(service)
+    }
+
+    /// Create a service without any lifecycle function called and check its ACL.
+    fn build(
+        faas: FluenceFaaS,
+        facade_module_name: String,
+        service_id: String,
+        envs: HashMap<Vec<u8>, Vec<u8>>,
+        config: AppServiceConfig,
+    ) -> Result<Self> {
+        let service = Self {
+            faas,
+            facade_module_name,
+            service_dir: config.service_base_dir.join(&service_id),
+            service_id,
+            envs,
+            version: config.version,
+            shutdown_function: None,
+            acl: config.acl,
+            disk_config: config.disk_config,
+            last_tmp_cleanup: Instant::now(),
+            source_config: config.source_config,
+        };
+
+        service.check_acl()?;
+
+        Ok(service)
+    }
+
+    /// A function set in the config must be exported by the facade with the expected arguments,
+    /// while a function with the well-known name is called only if it's exported with them,
+    /// so an unrelated facade function that happens to have this name is never called.
+    fn resolve_lifecycle_function(
+        &self,
+        configured_name: Option<&str>,
+        well_known_name: &str,
+        expected_arguments: &[IType],
+    ) -> Result<Option<String>> {
+        // None if the function isn't exported, otherwise whether its arguments are expected
+        let has_expected_arguments = |function_name: &str| {
+            self.faas
+                .get_module_interface(&self.facade_module_name)?
+                .function_signatures
+                .iter()
+                .find(|signature| signature.name.as_str() == function_name)
+                .map(|signature| {
+                    signature
+                        .arguments
+                        .iter()
+                        .map(|argument| &argument.ty)
+                        .eq(expected_arguments.iter())
+                })
+        };
+
+        match configured_name {
+            Some(function_name) => match has_expected_arguments(function_name) {
+                Some(true) => Ok(Some(function_name.to_string())),
+                Some(false) => Err(AppServiceError::ConfigParseError(format!(
+                    "lifecycle function {} of the facade module {} should have arguments {:?}",
+                    function_name, self.facade_module_name, expected_arguments
+                ))),
+                None => Err(AppServiceError::ConfigParseError(format!(
+                    "lifecycle function {} isn't exported by the facade module {}",
+                    function_name, self.facade_module_name
+                ))),
+            },
+            None => match has_expected_arguments(well_known_name) {
+                Some(true) => Ok(Some(well_known_name.to_string())),
+                Some(false) => {
+                    log::warn!(
+                        "{} of the facade module {} isn't called as a lifecycle function, \
+                         since it doesn't have arguments {:?}",
+                        well_known_name,
+                        self.facade_module_name,
+                        expected_arguments
+                    );
+                    Ok(None)
+                }
+                None => Ok(None),
+            },
+        }
+    }
+
+    /// Rules of the ACL must refer to functions exported by the facade.
+    fn check_acl(&self) -> Result<()> {
+        let facade_functions = self
+            .faas
+            .get_module_interface(&self.facade_module_name)
+            .map(|interface| {
+                interface
+                    .function_signatures
+                    .iter()
+                    .map(|signature| signature.name.to_string())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        let facade_functions = facade_functions
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+
+        let unknown_functions = self.acl.unknown_functions(&facade_functions);
+        if unknown_functions.is_empty() {
+            return Ok(());
+        }
+
+        Err(AppServiceError::ConfigParseError(format!(
+            "ACL has rules for functions not exported by the facade module {}: {}",
+            self.facade_module_name,
+            unknown_functions.join(", ")
+        )))
+    }
+
+    /// Calls on_upgrade of the facade if it's exported or set in the config.
+    fn on_upgrade(&mut self, configured_name: Option<&str>, old_version: String) -> Result<()> {
+        let on_upgrade = self.resolve_lifecycle_function(
+            configured_name,
+            ON_UPGRADE_FUNCTION_NAME,
+            &[IType::String],
+        )?;
+
+        match on_upgrade {
+            Some(on_upgrade) => {
+                self.call_lifecycle_function(&on_upgrade, &[IValue::String(old_version)])
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Calls the shutdown function if the service hasn't been shut down yet.
+    fn shutdown(&mut self) {
+        if let Some(shutdown_function) = self.shutdown_function.take() {
+            if let Err(e) = self.call_lifecycle_function(&shutdown_function, &[]) {
+                log::warn!(
+                    "service {} hasn't been shut down properly: {}",
+                    self.service_id,
+                    e
+                );
+            }
+        }
+    }
+
+    fn call_lifecycle_function(&mut self, function_name: &str, args: &[IValue]) -> Result<()> {
+        self.faas
+            .call_with_ivalues(
+                &self.facade_module_name,
+                function_name,
+                args,
+                <_>::default(),
+            )
+            .map_err(|err| AppServiceError::LifecycleFunctionFailed {
+                function_name: function_name.to_string(),
+                err,
+            })?;
+
+        Ok(())
+    }
+
+    /// The facade module is set explicitly in the config, otherwise the last module is used.
+    fn facade_module_name(config: &AppServiceConfig) -> Result<String> {
+        let modules_config = &config.faas_config.modules_config;
+        if let Some(facade) = &config.facade {
+            if !modules_config.iter().any(|(name, _)| name == facade) {
+                return Err(AppServiceError::ConfigParseError(format!(
+                    "facade module {} isn't specified in the config",
+                    facade
+                )));
+            }
+
+            return Ok(facade.clone());
+        }
+
+        modules_config
+            .last()
+            .map(|(name, _)| name.clone())
+            .ok_or_else(|| {
+                AppServiceError::ConfigParseError(String::from(
+                    "config should contain at least one module",
+                ))
+            })
+    }
+
+    /// Writes seed files to the service local dir skipping already existing ones,
+    /// so the state of a restarted service isn't overwritten.
+    fn seed_local_dir(
+        local_dir: &Path,
+        data: impl IntoIterator<Item = (PathBuf, Vec<u8>)>,
+    ) -> Result<()> {
+        for (path, content) in data {
+            let path = local_dir.join(path);
+            if path.exists() {
+                continue;
+            }
+
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(path, content)?;
+        }
+
+        Ok(())
+    }
+
     /// Prepare service before starting by:
     ///  1. creating a directory structure in the following form:
     ///     - service_base_dir/service_id/SERVICE_LOCAL_DIR_NAME
     ///     - service_base_dir/service_id/SERVICE_TMP_DIR_NAME
     ///  2. adding service_id to environment variables
+    ///  3. limiting sizes of these dirs by the disk quota for modules with the host filesystem
     fn set_env_and_dirs(
         config: &mut AppServiceConfig,
         service_id: String,
@@ -139,15 +854,46 @@ impl AppService {
             service_id.into_bytes(),
         );
 
+        let quota = config.disk_config.quota;
         for (_, module_config) in &mut config.faas_config.modules_config {
             module_config.extend_wasi_envs(envs.clone());
             module_config.extend_wasi_files(preopened_files.clone(), mapped_dirs.clone());
+            if let Some(quota) = quota {
+                limit_service_dirs(module_config, quota);
+            }
         }
 
         Ok(())
     }
 }
 
+/// Limit sizes of the local and tmp dirs of the module by the quota, so that writes
+/// exceeding it fail with ENOSPC. Files of the in-memory filesystem don't use the disk
+/// and mapped dir restrictions aren't supported by it, so such modules are left as is.
+fn limit_service_dirs(module_config: &mut FaaSModuleConfig, quota: u64) {
+    let wasi = match &mut module_config.wasi {
+        Some(wasi) if wasi.fs == WASIFileSystem::Host => wasi,
+        _ => return,
+    };
+
+    for alias in &[SERVICE_LOCAL_DIR_NAME, SERVICE_TMP_DIR_NAME] {
+        let permissions = wasi
+            .mapped_dirs_permissions
+            .entry(alias.to_string())
+            .or_insert_with(MappedDirPermissions::default);
+        let max_bytes = permissions
+            .max_bytes
+            .map_or(quota, |max_bytes| max_bytes.min(quota));
+        permissions.max_bytes = Some(max_bytes);
+    }
+}
+
+impl Drop for AppService {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
 // This API is intended for testing purposes (mostly in FCE REPL)
 #[cfg(feature = "raw-module-api")]
 impl AppService {
@@ -163,13 +909,23 @@ impl AppService {
     {
         let mut config: AppServiceConfig = config.try_into()?;
         let service_id = service_id.into();
-        Self::set_env_and_dirs(&mut config, service_id, envs)?;
+        let service_dir = config.service_base_dir.join(&service_id);
+        Self::set_env_and_dirs(&mut config, service_id.clone(), envs.clone())?;
 
         let faas = FluenceFaaS::with_raw_config(config.faas_config)?;
 
         Ok(Self {
             faas,
             facade_module_name: String::new(),
+            service_id,
+            envs,
+            version: config.version,
+            shutdown_function: None,
+            acl: config.acl,
+            service_dir,
+            disk_config: config.disk_config,
+            last_tmp_cleanup: Instant::now(),
+            source_config: config.source_config,
         })
     }
 
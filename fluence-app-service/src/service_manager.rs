@@ -0,0 +1,257 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::Result;
+use crate::AppService;
+use crate::AppServiceError;
+use crate::config::AppServiceConfig;
+use crate::ServiceDiskUsage;
+use crate::ServiceInterface;
+
+use serde_json::Value as JValue;
+
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Hosts many services in one process, each service lives in
+/// the service_base_dir/service_id directory owned by the manager.
+pub struct AppServiceManager {
+    /// The base dir of all services of this manager.
+    service_base_dir: PathBuf,
+
+    /// If Some, creation of services above this count fails.
+    max_services: Option<usize>,
+
+    /// Hosted services by their ids.
+    services: HashMap<String, AppService>,
+}
+
+impl AppServiceManager {
+    /// Creates a manager of services located in the given dir creating it if needed.
+    pub fn new<P: Into<PathBuf>>(service_base_dir: P) -> Result<Self> {
+        let service_base_dir = service_base_dir.into();
+        std::fs::create_dir_all(&service_base_dir).map_err(|err| AppServiceError::CreateDir {
+            err,
+            path: service_base_dir.clone(),
+        })?;
+
+        Ok(Self {
+            service_base_dir,
+            max_services: None,
+            services: HashMap::new(),
+        })
+    }
+
+    /// Limits the count of simultaneously hosted services.
+    pub fn with_max_services(mut self, max_services: usize) -> Self {
+        self.max_services = Some(max_services);
+        self
+    }
+
+    /// Creates a new service with the given id, service_base_dir of the config
+    /// is replaced with the manager one.
+    pub fn create_service<C, S>(
+        &mut self,
+        service_id: S,
+        config: C,
+        envs: HashMap<Vec<u8>, Vec<u8>>,
+    ) -> Result<&mut AppService>
+    where
+        C: TryInto<AppServiceConfig>,
+        S: Into<String>,
+        AppServiceError: From<C::Error>,
+    {
+        let service_id = service_id.into();
+        Self::check_service_id(&service_id)?;
+        if self.services.contains_key(&service_id) {
+            return Err(AppServiceError::ServiceAlreadyExists(service_id));
+        }
+        if let Some(max_services) = self.max_services {
+            if self.services.len() >= max_services {
+                return Err(AppServiceError::TooManyServices(max_services));
+            }
+        }
+
+        let mut config: AppServiceConfig = config.try_into()?;
+        config.service_base_dir = self.service_base_dir.clone();
+
+        // a dir left by a previous run keeps the service state, so only a new one is cleaned up
+        let service_dir = self.service_dir(&service_id);
+        let is_new_dir = !service_dir.exists();
+        let service = match AppService::new(config, service_id.clone(), envs) {
+            Ok(service) => service,
+            Err(e) => {
+                if is_new_dir {
+                    let _ = std::fs::remove_dir_all(&service_dir);
+                }
+                return Err(e);
+            }
+        };
+
+        Ok(self.services.entry(service_id).or_insert(service))
+    }
+
+    /// Upgrades modules of a service with the given id keeping its dir,
+    /// service_base_dir of the config is replaced with the manager one.
+    pub fn upgrade_service<C, S>(&mut self, service_id: S, config: C) -> Result<()>
+    where
+        C: TryInto<AppServiceConfig>,
+        S: AsRef<str>,
+        AppServiceError: From<C::Error>,
+    {
+        let service_id = service_id.as_ref();
+        let service = self
+            .services
+            .get_mut(service_id)
+            .ok_or_else(|| AppServiceError::NoSuchService(service_id.to_string()))?;
+
+        let mut config: AppServiceConfig = config.try_into()?;
+        config.service_base_dir = self.service_base_dir.clone();
+
+        service.upgrade(config)
+    }
+
+    /// Returns a service by its id.
+    pub fn get<S: AsRef<str>>(&self, service_id: S) -> Option<&AppService> {
+        self.services.get(service_id.as_ref())
+    }
+
+    /// Returns a service by its id.
+    pub fn get_mut<S: AsRef<str>>(&mut self, service_id: S) -> Option<&mut AppService> {
+        self.services.get_mut(service_id.as_ref())
+    }
+
+    /// Returns ids of all hosted services in the sorted order.
+    pub fn service_ids(&self) -> Vec<&str> {
+        let mut service_ids = self.services.keys().map(String::as_str).collect::<Vec<_>>();
+        service_ids.sort_unstable();
+        service_ids
+    }
+
+    /// Returns the count of hosted services.
+    pub fn len(&self) -> usize {
+        self.services.len()
+    }
+
+    /// Returns true if there are no hosted services.
+    pub fn is_empty(&self) -> bool {
+        self.services.is_empty()
+    }
+
+    /// Calls a function of the facade module of a service with the given id.
+    pub fn call<S: AsRef<str>, FN: AsRef<str>>(
+        &mut self,
+        service_id: S,
+        func_name: FN,
+        arguments: JValue,
+        call_parameters: crate::CallParameters,
+    ) -> Result<JValue> {
+        let service_id = service_id.as_ref();
+        self.services
+            .get_mut(service_id)
+            .ok_or_else(|| AppServiceError::NoSuchService(service_id.to_string()))?
+            .call(func_name, arguments, call_parameters)
+    }
+
+    /// Removes a service, its directory with all the data is deleted if purge_data is set.
+    pub fn remove<S: AsRef<str>>(&mut self, service_id: S, purge_data: bool) -> Result<()> {
+        let service_id = service_id.as_ref();
+        self.services
+            .remove(service_id)
+            .ok_or_else(|| AppServiceError::NoSuchService(service_id.to_string()))?
+            .remove(purge_data)
+    }
+
+    /// Returns disk usage of all hosted services by their ids.
+    pub fn disk_usage(&self) -> Result<HashMap<&str, ServiceDiskUsage>> {
+        self.services
+            .iter()
+            .map(|(service_id, service)| Ok((service_id.as_str(), service.disk_usage()?)))
+            .collect()
+    }
+
+    /// Deletes content of tmp dirs of all hosted services for which it's time by their tmp
+    /// cleanup settings, it should be called by a timer, since otherwise periodic cleanup
+    /// is done only after calls.
+    pub fn cleanup_tmp_dirs(&mut self) -> Result<()> {
+        for service in self.services.values_mut() {
+            service.cleanup_tmp_if_due()?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns facade interfaces of all hosted services by their ids.
+    pub fn get_interfaces(&self) -> HashMap<&str, ServiceInterface<'_>> {
+        self.services
+            .iter()
+            .map(|(service_id, service)| (service_id.as_str(), service.get_facade_interface()))
+            .collect()
+    }
+
+    /// Returns the base dir of all services of this manager.
+    pub fn service_base_dir(&self) -> &Path {
+        &self.service_base_dir
+    }
+
+    fn service_dir(&self, service_id: &str) -> PathBuf {
+        self.service_base_dir.join(service_id)
+    }
+
+    /// Service id is used as a dir name, so it must be a single path component.
+    fn check_service_id(service_id: &str) -> Result<()> {
+        let is_valid = !service_id.is_empty()
+            && service_id != "."
+            && service_id != ".."
+            && !service_id.contains(|c| c == '/' || c == '\\' || c == '\0');
+
+        if is_valid {
+            Ok(())
+        } else {
+            Err(AppServiceError::InvalidServiceId(service_id.to_string()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AppServiceManager;
+    use crate::AppServiceError;
+
+    #[test]
+    fn service_id_is_single_path_component() {
+        for service_id in &["service_1", "1b5c-77fe", "..service", "service.."] {
+            assert!(AppServiceManager::check_service_id(service_id).is_ok());
+        }
+
+        for service_id in &[
+            "",
+            ".",
+            "..",
+            "../service",
+            "dir/service",
+            "dir\\service",
+            "ser\0vice",
+        ] {
+            assert!(matches!(
+                AppServiceManager::check_service_id(service_id),
+                Err(AppServiceError::InvalidServiceId(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,179 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::Result;
+use crate::AppServiceError;
+use crate::TomlAppServiceConfig;
+
+use fluence_faas::collect_dir_files;
+use fluence_faas::read_hashed_archive;
+use fluence_faas::write_hashed_archive;
+use serde_derive::Serialize;
+use serde_derive::Deserialize;
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+pub(crate) const STATE_FORMAT_VERSION: u32 = 1;
+const STATE_FILE_NAME: &str = "state.json";
+const CONFIG_FILE_NAME: &str = "Config.toml";
+const LOCAL_DIR_NAME: &str = "local";
+const MEMORY_DIR_NAME: &str = "memory";
+
+/// Describes an exported service, it's stored in state.json of a state archive.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ServiceStateInfo {
+    pub format_version: u32,
+
+    /// Id of the exported service.
+    pub service_id: String,
+
+    /// Hashes of the service modules by their names, the config has them pinned.
+    pub module_hashes: BTreeMap<String, String>,
+
+    /// Environment variables passed to the service on its creation.
+    pub envs: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+/// Content of a state archive: a tar archive with the manifest, state.json, Config.toml,
+/// files of the service local dir in `local/` and memory snapshots of modules in `memory/`.
+pub(crate) struct ServiceState {
+    pub(crate) info: ServiceStateInfo,
+    pub(crate) config: TomlAppServiceConfig,
+    /// Files of the local dir with paths relative to it.
+    pub(crate) local_files: BTreeMap<PathBuf, Vec<u8>>,
+    pub(crate) memory_snapshots: HashMap<String, Vec<u8>>,
+}
+
+impl ServiceState {
+    /// Returns files of the local dir with paths relative to it.
+    pub(crate) fn collect_local_files(local_dir: &Path) -> Result<BTreeMap<PathBuf, Vec<u8>>> {
+        let mut local_files = BTreeMap::new();
+        if local_dir.is_dir() {
+            collect_dir_files(local_dir, Path::new(""), &mut local_files)?;
+        }
+
+        Ok(local_files)
+    }
+
+    pub(crate) fn write(self, archive_path: &Path) -> Result<()> {
+        let info = serde_json::to_vec_pretty(&self.info).map_err(|e| {
+            invalid_state(format!("{} can't be serialized: {}", STATE_FILE_NAME, e))
+        })?;
+        // conversion to toml::Value places values before tables regardless of the struct order
+        let config = toml::Value::try_from(&self.config)
+            .and_then(|config| toml::to_string_pretty(&config))
+            .map_err(|e| {
+                invalid_state(format!("{} can't be serialized: {}", CONFIG_FILE_NAME, e))
+            })?;
+
+        let mut files = BTreeMap::new();
+        files.insert(PathBuf::from(STATE_FILE_NAME), info);
+        files.insert(PathBuf::from(CONFIG_FILE_NAME), config.into_bytes());
+        for (module_name, snapshot) in self.memory_snapshots {
+            files.insert(Path::new(MEMORY_DIR_NAME).join(module_name), snapshot);
+        }
+        for (path, content) in self.local_files {
+            files.insert(Path::new(LOCAL_DIR_NAME).join(path), content);
+        }
+
+        write_hashed_archive(archive_path, STATE_FORMAT_VERSION, files).map_err(Into::into)
+    }
+
+    /// Reads a state archive checking hashes of all its files and format versions.
+    pub(crate) fn read(archive_path: &Path) -> Result<Self> {
+        let mut files = read_hashed_archive(archive_path, STATE_FORMAT_VERSION)?;
+
+        let info = files
+            .remove(STATE_FILE_NAME)
+            .ok_or_else(|| invalid_state(format!("{} is missing", STATE_FILE_NAME)))?;
+        let info: ServiceStateInfo = serde_json::from_slice(&info)
+            .map_err(|e| invalid_state(format!("{} is malformed: {}", STATE_FILE_NAME, e)))?;
+        if info.format_version != STATE_FORMAT_VERSION {
+            return Err(invalid_state(format!(
+                "unsupported state format version {}, expected {}",
+                info.format_version, STATE_FORMAT_VERSION
+            )));
+        }
+
+        let config = files
+            .remove(CONFIG_FILE_NAME)
+            .ok_or_else(|| invalid_state(format!("{} is missing", CONFIG_FILE_NAME)))?;
+        let config: TomlAppServiceConfig = toml::from_slice(&config)
+            .map_err(|e| invalid_state(format!("{} is malformed: {}", CONFIG_FILE_NAME, e)))?;
+        check_module_hashes(&info, &config)?;
+
+        let mut local_files = BTreeMap::new();
+        let mut memory_snapshots = HashMap::new();
+        for (path, content) in files {
+            let path = PathBuf::from(path);
+            if let Ok(local_path) = path.strip_prefix(LOCAL_DIR_NAME) {
+                local_files.insert(local_path.to_path_buf(), content);
+            } else if let Ok(snapshot_path) = path.strip_prefix(MEMORY_DIR_NAME) {
+                let module_name = snapshot_path.to_string_lossy().to_string();
+                if !info.module_hashes.contains_key(&module_name) {
+                    return Err(invalid_state(format!(
+                        "memory snapshot of unknown module {}",
+                        module_name
+                    )));
+                }
+                memory_snapshots.insert(module_name, content);
+            } else {
+                return Err(invalid_state(format!("unexpected file {:?}", path)));
+            }
+        }
+
+        Ok(Self {
+            info,
+            config,
+            local_files,
+            memory_snapshots,
+        })
+    }
+}
+
+/// Each module of the config must be pinned to the hash recorded in state.json,
+/// so the service can't be recreated from other modules.
+fn check_module_hashes(info: &ServiceStateInfo, config: &TomlAppServiceConfig) -> Result<()> {
+    let modules = &config.toml_faas_config.module;
+    if modules.len() != info.module_hashes.len() {
+        return Err(invalid_state(format!(
+            "{} has {} modules, but {} has {}",
+            CONFIG_FILE_NAME,
+            modules.len(),
+            STATE_FILE_NAME,
+            info.module_hashes.len()
+        )));
+    }
+
+    for module in modules {
+        let expected_hash = info.module_hashes.get(&module.name);
+        if module.hash.is_none() || module.hash.as_ref() != expected_hash {
+            return Err(invalid_state(format!(
+                "module {} isn't pinned to the hash from {}",
+                module.name, STATE_FILE_NAME
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+fn invalid_state(message: String) -> AppServiceError {
+    AppServiceError::InvalidState(message)
+}
@@ -0,0 +1,151 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use fluence_app_service::AppService;
+use fluence_app_service::AppServiceError;
+use fluence_app_service::CallParameters;
+use fluence_app_service::ConfigDiagnostic;
+use fluence_app_service::TomlAppServiceConfig;
+
+use serde_json::json;
+
+use std::path::Path;
+use std::path::PathBuf;
+
+fn service_base_dir(name: &str) -> PathBuf {
+    let base_dir = std::env::temp_dir().join(format!("fce_acl_{}_{}", name, std::process::id()));
+    std::fs::create_dir_all(&base_dir).unwrap();
+    base_dir
+}
+
+fn greeting_config(base_dir: &Path, acl: &str) -> TomlAppServiceConfig {
+    let config = format!(
+        r#"
+        modules_dir = "../examples/greeting/artifacts/"
+
+        [[module]]
+            name = "greeting"
+            mem_pages_count = 1
+
+        {}
+        "#,
+        acl
+    );
+    let mut config: TomlAppServiceConfig =
+        toml::from_str(&config).expect("config should be well-formed");
+    config.service_base_dir = Some(base_dir.to_string_lossy().to_string());
+
+    config
+}
+
+fn caller(user_name: &str, application_id: &str) -> CallParameters {
+    CallParameters {
+        user_name: user_name.to_string(),
+        application_id: application_id.to_string(),
+        ..<_>::default()
+    }
+}
+
+fn is_denied<T>(result: Result<T, AppServiceError>) -> bool {
+    matches!(result, Err(AppServiceError::AccessDenied { .. }))
+}
+
+#[test]
+pub fn function_rule() {
+    let base_dir = service_base_dir("function_rule");
+    let config = greeting_config(
+        &base_dir,
+        r#"
+        [acl.greeting]
+        users = ["owner"]
+
+        [acl."*"]
+        users = ["admin"]
+        "#,
+    );
+    assert_eq!(config.validate(), vec![]);
+
+    let mut service = AppService::new(config, "service_1", <_>::default())
+        .unwrap_or_else(|e| panic!("service should be created: {:?}", e));
+    let args = json!(["Fluence"]);
+    let allowed = service.call("greeting", args.clone(), caller("owner", ""));
+    let denied = service.call("greeting", args.clone(), caller("admin", ""));
+    let allowed_with_stdin =
+        service.call_with_stdin("greeting", args.clone(), caller("owner", ""), vec![]);
+    let denied_with_stdin = service.call_with_stdin("greeting", args, caller("admin", ""), vec![]);
+    drop(service);
+    std::fs::remove_dir_all(&base_dir).unwrap();
+
+    assert_eq!(allowed.unwrap(), json!("Hi, Fluence"));
+    assert!(is_denied(denied));
+    assert_eq!(allowed_with_stdin.unwrap(), json!("Hi, Fluence"));
+    assert!(is_denied(denied_with_stdin));
+}
+
+#[test]
+pub fn any_function_rule_fallback() {
+    let base_dir = service_base_dir("fallback");
+    let config = greeting_config(
+        &base_dir,
+        r#"
+        [acl."*"]
+        applications = ["dashboard"]
+        "#,
+    );
+
+    let mut service = AppService::new(config, "service_1", <_>::default())
+        .unwrap_or_else(|e| panic!("service should be created: {:?}", e));
+    let args = json!(["Fluence"]);
+    let allowed = service.call("greeting", args.clone(), caller("", "dashboard"));
+    let denied = service.call("greeting", args.clone(), caller("owner", "other_app"));
+    let allowed_with_stdin =
+        service.call_with_stdin("greeting", args.clone(), caller("", "dashboard"), vec![]);
+    let denied_with_stdin = service.call_with_stdin("greeting", args, caller("", ""), vec![]);
+    drop(service);
+    std::fs::remove_dir_all(&base_dir).unwrap();
+
+    assert_eq!(allowed.unwrap(), json!("Hi, Fluence"));
+    assert!(is_denied(denied));
+    assert_eq!(allowed_with_stdin.unwrap(), json!("Hi, Fluence"));
+    assert!(is_denied(denied_with_stdin));
+}
+
+#[test]
+pub fn rules_for_unknown_functions_are_rejected() {
+    let base_dir = service_base_dir("unknown_functions");
+    let config = greeting_config(
+        &base_dir,
+        r#"
+        [acl.greting]
+        users = ["owner"]
+        "#,
+    );
+
+    let diagnostics = config.validate();
+    let service = AppService::new(config, "service_1", <_>::default());
+    let is_config_error = matches!(service, Err(AppServiceError::ConfigParseError(_)));
+    drop(service);
+    std::fs::remove_dir_all(&base_dir).unwrap();
+
+    assert_eq!(
+        diagnostics,
+        vec![ConfigDiagnostic::new(
+            "acl.greting",
+            "function greting isn't exported by the facade module greeting"
+        )]
+    );
+    assert!(is_config_error);
+}
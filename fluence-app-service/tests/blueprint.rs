@@ -0,0 +1,96 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use fluence_app_service::AppService;
+use fluence_app_service::AppServiceError;
+use fluence_app_service::Blueprint;
+use fluence_app_service::BlueprintModule;
+use fluence_app_service::ModuleStore;
+
+use serde_json::json;
+
+use std::path::PathBuf;
+
+fn test_store(name: &str) -> (ModuleStore, PathBuf) {
+    let store_dir =
+        std::env::temp_dir().join(format!("fce_blueprint_{}_{}", name, std::process::id()));
+    let store = ModuleStore::new(&store_dir)
+        .unwrap_or_else(|e| panic!("module store should be created: {:?}", e));
+
+    (store, store_dir)
+}
+
+fn greeting_blueprint(store: &ModuleStore) -> Blueprint {
+    let greeting_bytes = std::fs::read("../examples/greeting/artifacts/greeting.wasm")
+        .expect("../examples/greeting/artifacts should presence");
+    let hash = store
+        .add_module(&greeting_bytes)
+        .unwrap_or_else(|e| panic!("module should be added: {:?}", e));
+
+    Blueprint {
+        name: String::from("greeting"),
+        modules: vec![BlueprintModule {
+            name: String::from("greeting"),
+            hash,
+            config: <_>::default(),
+        }],
+    }
+}
+
+#[test]
+pub fn services_share_compiled_modules() {
+    let (store, store_dir) = test_store("share");
+    let blueprint = greeting_blueprint(&store);
+
+    let mut service_1 = AppService::from_blueprint(&store, &blueprint, "service_1")
+        .unwrap_or_else(|e| panic!("service should be created: {:?}", e));
+    let result = service_1.call("greeting", json!(["Fluence"]), <_>::default());
+    let service_2 = AppService::from_blueprint(&store, &blueprint, "service_2");
+    let artifacts_count = std::fs::read_dir(store.artifacts_dir()).map(|dir| dir.count());
+    let services_dirs = (
+        store.services_dir().join("service_1").is_dir(),
+        store.services_dir().join("service_2").is_dir(),
+    );
+
+    drop(service_1);
+    drop(service_2);
+    std::fs::remove_dir_all(store_dir).unwrap();
+
+    assert_eq!(result.unwrap(), json!("Hi, Fluence"));
+    assert_eq!(artifacts_count.unwrap(), 1);
+    assert_eq!(services_dirs, (true, true));
+}
+
+#[test]
+pub fn invalid_blueprints_are_rejected() {
+    let (store, store_dir) = test_store("invalid");
+
+    let mut missing_module = greeting_blueprint(&store);
+    missing_module.modules[0].hash = fluence_app_service::module_hash(b"other module");
+    let missing_module = AppService::from_blueprint(&store, &missing_module, "service_1");
+
+    let mut empty = greeting_blueprint(&store);
+    empty.modules.clear();
+    let empty = AppService::from_blueprint(&store, &empty, "service_2");
+
+    std::fs::remove_dir_all(store_dir).unwrap();
+
+    assert!(matches!(
+        missing_module,
+        Err(AppServiceError::ModuleStoreError(_))
+    ));
+    assert!(matches!(empty, Err(AppServiceError::ConfigParseError(_))));
+}
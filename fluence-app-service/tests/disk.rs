@@ -0,0 +1,246 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use fluence_app_service::AppService;
+use fluence_app_service::AppServiceError;
+use fluence_app_service::CallParameters;
+use fluence_app_service::ServiceDiskUsage;
+use fluence_app_service::TomlAppServiceConfig;
+
+use serde_json::json;
+
+use std::path::Path;
+use std::path::PathBuf;
+
+fn service_base_dir(name: &str) -> PathBuf {
+    let base_dir = std::env::temp_dir().join(format!("fce_disk_{}_{}", name, std::process::id()));
+    std::fs::create_dir_all(&base_dir).unwrap();
+    base_dir
+}
+
+fn service_config(
+    base_dir: &Path,
+    modules_dir: &str,
+    module_name: &str,
+    settings: &str,
+) -> TomlAppServiceConfig {
+    let config = format!(
+        r#"
+        modules_dir = "{}"
+        {}
+
+        [[module]]
+            name = "{}"
+        "#,
+        modules_dir, settings, module_name
+    );
+    let mut config: TomlAppServiceConfig =
+        toml::from_str(&config).expect("config should be well-formed");
+    config.service_base_dir = Some(base_dir.to_string_lossy().to_string());
+
+    config
+}
+
+fn greeting_config(base_dir: &Path, settings: &str) -> TomlAppServiceConfig {
+    service_config(
+        base_dir,
+        "../examples/greeting/artifacts/",
+        "greeting",
+        settings,
+    )
+}
+
+/// Creates a file of the supplied size in the service dir, like a module would do.
+fn write_file(service_dir: &Path, file_path: &str, size: usize) {
+    let file_path = service_dir.join(file_path);
+    std::fs::create_dir_all(file_path.parent().unwrap()).unwrap();
+    std::fs::write(file_path, vec![0u8; size]).unwrap();
+}
+
+fn greet(service: &mut AppService) -> Result<serde_json::Value, AppServiceError> {
+    service.call("greeting", json!(["Fluence"]), CallParameters::default())
+}
+
+#[test]
+pub fn disk_usage() {
+    let base_dir = service_base_dir("usage");
+    let service_dir = base_dir.join("service_1");
+    let service = AppService::new(greeting_config(&base_dir, ""), "service_1", <_>::default())
+        .unwrap_or_else(|e| panic!("service should be created: {:?}", e));
+
+    let empty_usage = service.disk_usage().unwrap();
+    write_file(&service_dir, "local/state", 100);
+    write_file(&service_dir, "local/nested/state", 20);
+    write_file(&service_dir, "tmp/scratch", 3);
+    let usage = service.disk_usage().unwrap();
+    drop(service);
+    std::fs::remove_dir_all(&base_dir).unwrap();
+
+    assert_eq!(empty_usage, ServiceDiskUsage { local: 0, tmp: 0 });
+    assert_eq!(usage, ServiceDiskUsage { local: 120, tmp: 3 });
+    assert_eq!(usage.total(), 123);
+}
+
+#[test]
+pub fn tmp_cleanup() {
+    let base_dir = service_base_dir("tmp_cleanup");
+    let config = greeting_config(&base_dir, r#"tmp_cleanup = "after_call""#);
+    let mut service = AppService::new(config, "service_1", <_>::default())
+        .unwrap_or_else(|e| panic!("service should be created: {:?}", e));
+    let cleaned_service_dir = base_dir.join("service_1");
+    write_file(&cleaned_service_dir, "local/state", 10);
+    write_file(&cleaned_service_dir, "tmp/scratch", 10);
+    write_file(&cleaned_service_dir, "tmp/nested/scratch", 10);
+    let result = greet(&mut service);
+    let usage_after_cleanup = service.disk_usage().unwrap();
+    let tmp_dir_exists = cleaned_service_dir.join("tmp").is_dir();
+    drop(service);
+
+    let config = greeting_config(&base_dir, "");
+    let mut service = AppService::new(config, "service_2", <_>::default())
+        .unwrap_or_else(|e| panic!("service should be created: {:?}", e));
+    write_file(&base_dir.join("service_2"), "tmp/scratch", 10);
+    greet(&mut service).unwrap();
+    let usage_without_cleanup = service.disk_usage().unwrap();
+    drop(service);
+    std::fs::remove_dir_all(&base_dir).unwrap();
+
+    assert_eq!(result.unwrap(), json!("Hi, Fluence"));
+    assert_eq!(usage_after_cleanup, ServiceDiskUsage { local: 10, tmp: 0 });
+    assert!(tmp_dir_exists);
+    assert_eq!(
+        usage_without_cleanup,
+        ServiceDiskUsage { local: 0, tmp: 10 }
+    );
+}
+
+#[test]
+pub fn total_usage_above_quota_does_not_fail_calls() {
+    let base_dir = service_base_dir("quota_after_call");
+    let config = greeting_config(&base_dir, r#"disk_quota = "100B""#);
+    let mut service = AppService::new(config, "service_1", <_>::default())
+        .unwrap_or_else(|e| panic!("service should be created: {:?}", e));
+    let service_dir = base_dir.join("service_1");
+    write_file(&service_dir, "local/state", 60);
+    write_file(&service_dir, "tmp/scratch", 60);
+    // each dir is within the quota, but together they exceed it
+    let result = greet(&mut service);
+    let usage = service.disk_usage().unwrap();
+    drop(service);
+    std::fs::remove_dir_all(&base_dir).unwrap();
+
+    assert_eq!(result.unwrap(), json!("Hi, Fluence"));
+    assert_eq!(usage.total(), 120);
+}
+
+#[test]
+pub fn invalid_quota_is_rejected() {
+    let base_dir = service_base_dir("invalid_quota");
+    let config = greeting_config(&base_dir, r#"disk_quota = "lots""#);
+    let diagnostics = config.validate();
+    let service = AppService::new(config, "service_1", <_>::default());
+    let is_config_error = matches!(service, Err(AppServiceError::ConfigParseError(_)));
+    drop(service);
+    std::fs::remove_dir_all(&base_dir).unwrap();
+
+    assert_eq!(diagnostics.len(), 1);
+    assert!(is_config_error);
+}
+
+#[test]
+pub fn tmp_cleanup_without_calls() {
+    let base_dir = service_base_dir("tmp_cleanup_without_calls");
+    let config = greeting_config(&base_dir, "tmp_cleanup_interval_secs = 1");
+    let mut service = AppService::new(config, "service_1", <_>::default())
+        .unwrap_or_else(|e| panic!("service should be created: {:?}", e));
+    let service_dir = base_dir.join("service_1");
+
+    write_file(&service_dir, "tmp/scratch", 10);
+    let is_cleaned_early = service.cleanup_tmp_if_due().unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+    let is_cleaned_on_time = service.cleanup_tmp_if_due().unwrap();
+    let usage_after_periodic_cleanup = service.disk_usage().unwrap();
+
+    write_file(&service_dir, "tmp/scratch", 10);
+    let cleanup_result = service.cleanup_tmp();
+    let usage_after_cleanup = service.disk_usage().unwrap();
+    drop(service);
+    std::fs::remove_dir_all(&base_dir).unwrap();
+
+    assert!(!is_cleaned_early);
+    assert!(is_cleaned_on_time);
+    assert_eq!(usage_after_periodic_cleanup.tmp, 0);
+    assert!(cleanup_result.is_ok());
+    assert_eq!(usage_after_cleanup.tmp, 0);
+}
+
+#[test]
+pub fn writes_above_quota_fail() {
+    // lifecycle functions of the module append their names to local/events
+    let base_dir = service_base_dir("quota_writes");
+    let modules_dir = "tests/wasm_tests/lifecycle/artifacts/";
+    write_file(&base_dir.join("service_1"), "local/state", 6);
+    write_file(&base_dir.join("service_2"), "local/state", 6);
+
+    let config = service_config(&base_dir, modules_dir, "lifecycle", "disk_quota = 8");
+    let rejected_service = AppService::new(config, "service_1", <_>::default());
+    let is_init_failed = matches!(
+        rejected_service,
+        Err(AppServiceError::LifecycleFunctionFailed { .. })
+    );
+    drop(rejected_service);
+    let rejected_events =
+        std::fs::read(base_dir.join("service_1/local/events")).unwrap_or_default();
+
+    let config = service_config(&base_dir, modules_dir, "lifecycle", r#"disk_quota = "32B""#);
+    let service = AppService::new(config, "service_2", <_>::default());
+    let is_created = service.is_ok();
+    drop(service);
+    let events = std::fs::read_to_string(base_dir.join("service_2/local/events")).unwrap();
+    std::fs::remove_dir_all(&base_dir).unwrap();
+
+    assert!(is_init_failed);
+    assert!(rejected_events.is_empty());
+    assert!(is_created);
+    assert_eq!(events, "init\nshutdown\n");
+}
+
+#[test]
+pub fn remove() {
+    let base_dir = service_base_dir("remove");
+    let kept_service_dir = base_dir.join("service_1");
+    let service = AppService::new(greeting_config(&base_dir, ""), "service_1", <_>::default())
+        .unwrap_or_else(|e| panic!("service should be created: {:?}", e));
+    write_file(&kept_service_dir, "local/state", 10);
+    write_file(&kept_service_dir, "tmp/scratch", 10);
+    let kept_result = service.remove(false);
+    let local_state_exists = kept_service_dir.join("local/state").is_file();
+    let tmp_scratch_exists = kept_service_dir.join("tmp/scratch").exists();
+
+    let purged_service_dir = base_dir.join("service_2");
+    let service = AppService::new(greeting_config(&base_dir, ""), "service_2", <_>::default())
+        .unwrap_or_else(|e| panic!("service should be created: {:?}", e));
+    write_file(&purged_service_dir, "local/state", 10);
+    let purged_result = service.remove(true);
+    let purged_service_dir_exists = purged_service_dir.exists();
+    std::fs::remove_dir_all(&base_dir).unwrap();
+
+    assert!(kept_result.is_ok());
+    assert!(local_state_exists);
+    assert!(!tmp_scratch_exists);
+    assert!(purged_result.is_ok());
+    assert!(!purged_service_dir_exists);
+}
@@ -0,0 +1,119 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use fluence_app_service::AppService;
+use fluence_app_service::AppServiceError;
+use fluence_app_service::ConfigDiagnostic;
+use fluence_app_service::TomlAppServiceConfig;
+
+use std::path::Path;
+use std::path::PathBuf;
+
+fn service_base_dir(name: &str) -> PathBuf {
+    let base_dir = std::env::temp_dir().join(format!("fce_facade_{}_{}", name, std::process::id()));
+    std::fs::create_dir_all(&base_dir).unwrap();
+    base_dir
+}
+
+fn records_config(base_dir: &Path, facade: Option<&str>) -> TomlAppServiceConfig {
+    let mut config: TomlAppServiceConfig = toml::from_str(
+        r#"
+        modules_dir = "../examples/records/artifacts/"
+
+        [[module]]
+            name = "records_effector"
+            mem_pages_count = 1
+
+        [[module]]
+            name = "records_pure"
+            mem_pages_count = 1
+        "#,
+    )
+    .expect("config should be well-formed");
+    config.service_base_dir = Some(base_dir.to_string_lossy().to_string());
+    config.facade = facade.map(String::from);
+
+    config
+}
+
+fn module_names(interface: fluence_app_service::ServiceInterface<'_>) -> Vec<String> {
+    let mut names = interface
+        .modules
+        .keys()
+        .map(|name| name.to_string())
+        .collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+#[test]
+pub fn last_module_is_default_facade() {
+    let base_dir = service_base_dir("default");
+    let service = AppService::new(records_config(&base_dir, None), "service_1", <_>::default())
+        .unwrap_or_else(|e| panic!("service should be created: {:?}", e));
+
+    let modules = module_names(service.get_interface());
+    let facade_modules = module_names(service.get_facade_interface());
+    drop(service);
+    std::fs::remove_dir_all(&base_dir).unwrap();
+
+    assert_eq!(modules, vec!["records_effector", "records_pure"]);
+    assert_eq!(facade_modules, vec!["records_pure"]);
+}
+
+#[test]
+pub fn explicit_facade() {
+    let base_dir = service_base_dir("explicit");
+    let config = records_config(&base_dir, Some("records_effector"));
+    assert_eq!(config.validate(), vec![]);
+
+    let service = AppService::new(config, "service_1", <_>::default())
+        .unwrap_or_else(|e| panic!("service should be created: {:?}", e));
+    let facade_interface = service.get_facade_interface();
+    let facade_modules = module_names(service.get_facade_interface());
+    let facade_functions = facade_interface.modules["records_effector"]
+        .function_signatures
+        .len();
+    let effector_functions = service.get_interface().modules["records_effector"]
+        .function_signatures
+        .len();
+    drop(service);
+    std::fs::remove_dir_all(&base_dir).unwrap();
+
+    assert_eq!(facade_modules, vec!["records_effector"]);
+    assert_eq!(facade_functions, effector_functions);
+}
+
+#[test]
+pub fn unknown_facade_is_rejected() {
+    let base_dir = service_base_dir("unknown");
+    let config = records_config(&base_dir, Some("non_existent"));
+
+    let diagnostics = config.validate();
+    let service = AppService::new(config, "service_1", <_>::default());
+    let is_config_error = matches!(service, Err(AppServiceError::ConfigParseError(_)));
+    drop(service);
+    std::fs::remove_dir_all(&base_dir).unwrap();
+
+    assert_eq!(
+        diagnostics,
+        vec![ConfigDiagnostic::new(
+            "facade",
+            "module non_existent isn't specified in the config"
+        )]
+    );
+    assert!(is_config_error);
+}
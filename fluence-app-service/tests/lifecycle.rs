@@ -0,0 +1,192 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use fluence_app_service::AppService;
+use fluence_app_service::AppServiceError;
+use fluence_app_service::AsyncAppService;
+use fluence_app_service::TomlAppServiceConfig;
+
+use std::path::Path;
+use std::path::PathBuf;
+
+fn service_base_dir(name: &str) -> PathBuf {
+    let base_dir =
+        std::env::temp_dir().join(format!("fce_lifecycle_{}_{}", name, std::process::id()));
+    std::fs::create_dir_all(&base_dir).unwrap();
+    base_dir
+}
+
+fn lifecycle_config(base_dir: &Path, module_name: &str, settings: &str) -> TomlAppServiceConfig {
+    let config = format!(
+        r#"
+        modules_dir = "tests/wasm_tests/lifecycle/artifacts/"
+        service_base_dir = {:?}
+        {}
+
+        [[module]]
+            name = "{}"
+        "#,
+        base_dir, settings, module_name
+    );
+
+    toml::from_str(&config).expect("config should be well-formed")
+}
+
+/// Returns lifecycle events recorded by the test module in the service local dir.
+fn recorded_events(base_dir: &Path, service_id: &str) -> Vec<String> {
+    let events_path = base_dir.join(service_id).join("local").join("events");
+    std::fs::read_to_string(events_path)
+        .unwrap_or_default()
+        .lines()
+        .map(String::from)
+        .collect()
+}
+
+#[test]
+pub fn shutdown_on_drop() {
+    let base_dir = service_base_dir("drop");
+    let config = lifecycle_config(&base_dir, "lifecycle", "");
+
+    let service = AppService::new(config, "service_1", <_>::default())
+        .unwrap_or_else(|e| panic!("service should be created: {:?}", e));
+    let events_before_drop = recorded_events(&base_dir, "service_1");
+    drop(service);
+    let events = recorded_events(&base_dir, "service_1");
+    std::fs::remove_dir_all(&base_dir).unwrap();
+
+    assert_eq!(events_before_drop, vec!["init"]);
+    assert_eq!(events, vec!["init", "shutdown"]);
+}
+
+#[test]
+pub fn async_service_shutdown_waits_for_hook() {
+    let base_dir = service_base_dir("async_shutdown");
+    let config = lifecycle_config(&base_dir, "lifecycle", "");
+
+    let create = move || AppService::new(config, "service_1", <_>::default());
+    let service = AsyncAppService::new(create, "service_1", 0)
+        .unwrap_or_else(|e| panic!("service should be created: {:?}", e));
+    let shutdown_result = service.shutdown();
+    let events = recorded_events(&base_dir, "service_1");
+    std::fs::remove_dir_all(&base_dir).unwrap();
+
+    assert!(shutdown_result.is_ok());
+    assert_eq!(events, vec!["init", "shutdown"]);
+}
+
+#[test]
+pub fn init_failure() {
+    let base_dir = service_base_dir("init_failure");
+    let local_dir = base_dir.join("service_1").join("local");
+    std::fs::create_dir_all(&local_dir).unwrap();
+    std::fs::write(local_dir.join("fail_init"), b"").unwrap();
+
+    let config = lifecycle_config(&base_dir, "lifecycle", "");
+    let service = AppService::new(config, "service_1", <_>::default());
+    let is_init_failed = matches!(
+        service,
+        Err(AppServiceError::LifecycleFunctionFailed { ref function_name, .. })
+            if function_name == "init"
+    );
+    drop(service);
+    let events = recorded_events(&base_dir, "service_1");
+    std::fs::remove_dir_all(&base_dir).unwrap();
+
+    assert!(is_init_failed);
+    // shutdown isn't called for a service that hasn't been initialized
+    assert!(events.is_empty());
+}
+
+#[test]
+pub fn upgrade_order() {
+    let base_dir = service_base_dir("upgrade");
+
+    let mut service = AppService::new(
+        lifecycle_config(&base_dir, "lifecycle", r#"version = "1""#),
+        "service_1",
+        <_>::default(),
+    )
+    .unwrap_or_else(|e| panic!("service should be created: {:?}", e));
+    let upgraded = service.upgrade(lifecycle_config(&base_dir, "lifecycle", r#"version = "2""#));
+    let events_after_upgrade = recorded_events(&base_dir, "service_1");
+    drop(service);
+    let events = recorded_events(&base_dir, "service_1");
+    std::fs::remove_dir_all(&base_dir).unwrap();
+
+    assert!(upgraded.is_ok());
+    assert_eq!(
+        events_after_upgrade,
+        vec!["init", "init", "on_upgrade 1", "shutdown"]
+    );
+    assert_eq!(
+        events,
+        vec!["init", "init", "on_upgrade 1", "shutdown", "shutdown"]
+    );
+}
+
+#[test]
+pub fn upgrade_failure_keeps_old_service() {
+    let base_dir = service_base_dir("upgrade_failure");
+
+    let mut service = AppService::new(
+        lifecycle_config(&base_dir, "lifecycle", r#"version = "1""#),
+        "service_1",
+        <_>::default(),
+    )
+    .unwrap_or_else(|e| panic!("service should be created: {:?}", e));
+    let local_dir = base_dir.join("service_1").join("local");
+    std::fs::write(local_dir.join("fail_on_upgrade"), b"").unwrap();
+
+    let upgraded = service.upgrade(lifecycle_config(&base_dir, "lifecycle", r#"version = "2""#));
+    let is_on_upgrade_failed = matches!(
+        upgraded,
+        Err(AppServiceError::LifecycleFunctionFailed { ref function_name, .. })
+            if function_name == "on_upgrade"
+    );
+    let events_after_upgrade = recorded_events(&base_dir, "service_1");
+    drop(service);
+    let events = recorded_events(&base_dir, "service_1");
+    std::fs::remove_dir_all(&base_dir).unwrap();
+
+    assert!(is_on_upgrade_failed);
+    // neither the old service nor the failed replacement is shut down by the upgrade
+    assert_eq!(events_after_upgrade, vec!["init", "init"]);
+    assert_eq!(events, vec!["init", "init", "shutdown"]);
+}
+
+#[test]
+pub fn well_known_names_with_other_arguments_are_not_called() {
+    let base_dir = service_base_dir("other_arguments");
+    let config = lifecycle_config(&base_dir, "lifecycle_with_arguments", "");
+
+    let service = AppService::new(config, "service_1", <_>::default())
+        .unwrap_or_else(|e| panic!("service should be created: {:?}", e));
+    drop(service);
+    let events = recorded_events(&base_dir, "service_1");
+
+    let config = lifecycle_config(
+        &base_dir,
+        "lifecycle_with_arguments",
+        r#"init_function = "init""#,
+    );
+    let configured = AppService::new(config, "service_2", <_>::default());
+    let is_config_error = matches!(configured, Err(AppServiceError::ConfigParseError(_)));
+    drop(configured);
+    std::fs::remove_dir_all(&base_dir).unwrap();
+
+    assert!(events.is_empty());
+    assert!(is_config_error);
+}
@@ -0,0 +1,217 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use fluence_app_service::AppServiceError;
+use fluence_app_service::AppServiceManager;
+use fluence_app_service::TomlAppServiceConfig;
+
+use serde_json::json;
+
+use std::path::PathBuf;
+
+fn service_base_dir(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("fce_manager_{}_{}", name, std::process::id()))
+}
+
+fn service_config(module_name: &str) -> TomlAppServiceConfig {
+    let config = format!(
+        r#"
+        modules_dir = "../examples/greeting/artifacts"
+
+        [[module]]
+            name = "{}"
+        "#,
+        module_name
+    );
+
+    toml::from_str(&config).expect("config should be well-formed")
+}
+
+fn greeting_config() -> TomlAppServiceConfig {
+    service_config("greeting")
+}
+
+#[test]
+pub fn max_services_limit() {
+    let base_dir = service_base_dir("max_services");
+    let mut manager = AppServiceManager::new(&base_dir)
+        .unwrap_or_else(|e| panic!("manager should be created: {:?}", e))
+        .with_max_services(1);
+
+    let first = manager
+        .create_service("service_1", greeting_config(), <_>::default())
+        .map(|_| ());
+    let over_limit = manager
+        .create_service("service_2", greeting_config(), <_>::default())
+        .map(|_| ());
+    let is_over_limit_dir_created = base_dir.join("service_2").exists();
+    let removed = manager.remove("service_1", true);
+    let after_remove = manager
+        .create_service("service_2", greeting_config(), <_>::default())
+        .map(|_| ());
+    let result = manager.call("service_2", "greeting", json!(["Fluence"]), <_>::default());
+
+    drop(manager);
+    std::fs::remove_dir_all(&base_dir).unwrap();
+
+    assert!(first.is_ok());
+    assert!(matches!(
+        over_limit,
+        Err(AppServiceError::TooManyServices(1))
+    ));
+    assert!(!is_over_limit_dir_created);
+    assert!(removed.is_ok());
+    assert!(after_remove.is_ok());
+    assert_eq!(result.unwrap(), json!("Hi, Fluence"));
+}
+
+#[test]
+pub fn invalid_and_duplicated_service_ids() {
+    let base_dir = service_base_dir("service_ids");
+    let mut manager = AppServiceManager::new(&base_dir)
+        .unwrap_or_else(|e| panic!("manager should be created: {:?}", e));
+
+    let invalid = manager
+        .create_service("../service_1", greeting_config(), <_>::default())
+        .map(|_| ());
+    let first = manager
+        .create_service("service_1", greeting_config(), <_>::default())
+        .map(|_| ());
+    let duplicated = manager
+        .create_service("service_1", greeting_config(), <_>::default())
+        .map(|_| ());
+    let service_ids = manager
+        .service_ids()
+        .into_iter()
+        .map(String::from)
+        .collect::<Vec<_>>();
+
+    drop(manager);
+    let is_escaped_dir_created = base_dir.join("..").join("service_1").exists();
+    std::fs::remove_dir_all(&base_dir).unwrap();
+
+    assert!(matches!(invalid, Err(AppServiceError::InvalidServiceId(_))));
+    assert!(!is_escaped_dir_created);
+    assert!(first.is_ok());
+    assert!(matches!(
+        duplicated,
+        Err(AppServiceError::ServiceAlreadyExists(_))
+    ));
+    assert_eq!(service_ids, vec![String::from("service_1")]);
+}
+
+#[test]
+pub fn failed_creation_cleans_up_only_new_dirs() {
+    let base_dir = service_base_dir("failed_creation");
+    let mut manager = AppServiceManager::new(&base_dir)
+        .unwrap_or_else(|e| panic!("manager should be created: {:?}", e));
+
+    let new_service = manager
+        .create_service("service_1", service_config("non_existent"), <_>::default())
+        .map(|_| ());
+    let is_new_dir_kept = base_dir.join("service_1").exists();
+
+    // the dir of a service from a previous run keeps its state
+    let old_state_path = base_dir.join("service_2").join("local").join("state");
+    std::fs::create_dir_all(old_state_path.parent().unwrap()).unwrap();
+    std::fs::write(&old_state_path, b"state").unwrap();
+    let old_service = manager
+        .create_service("service_2", service_config("non_existent"), <_>::default())
+        .map(|_| ());
+    let old_state = std::fs::read(&old_state_path);
+
+    let is_empty = manager.is_empty();
+    drop(manager);
+    std::fs::remove_dir_all(&base_dir).unwrap();
+
+    assert!(new_service.is_err());
+    assert!(!is_new_dir_kept);
+    assert!(old_service.is_err());
+    assert_eq!(old_state.unwrap(), b"state".to_vec());
+    assert!(is_empty);
+}
+
+#[test]
+pub fn remove_with_and_without_purge() {
+    let base_dir = service_base_dir("remove");
+    let mut manager = AppServiceManager::new(&base_dir)
+        .unwrap_or_else(|e| panic!("manager should be created: {:?}", e));
+    let service_dir = base_dir.join("service_1");
+
+    manager
+        .create_service("service_1", greeting_config(), <_>::default())
+        .unwrap_or_else(|e| panic!("service should be created: {:?}", e));
+    std::fs::write(service_dir.join("local").join("state"), b"state").unwrap();
+    std::fs::write(service_dir.join("tmp").join("scratch"), b"scratch").unwrap();
+    let kept = manager.remove("service_1", false);
+    let is_state_kept = service_dir.join("local").join("state").exists();
+    let is_tmp_cleared = !service_dir.join("tmp").join("scratch").exists();
+
+    manager
+        .create_service("service_1", greeting_config(), <_>::default())
+        .unwrap_or_else(|e| panic!("service should be created: {:?}", e));
+    let purged = manager.remove("service_1", true);
+    let is_dir_purged = !service_dir.exists();
+    let removed_again = manager.remove("service_1", true);
+
+    drop(manager);
+    std::fs::remove_dir_all(&base_dir).unwrap();
+
+    assert!(kept.is_ok());
+    assert!(is_state_kept);
+    assert!(is_tmp_cleared);
+    assert!(purged.is_ok());
+    assert!(is_dir_purged);
+    assert!(matches!(
+        removed_again,
+        Err(AppServiceError::NoSuchService(_))
+    ));
+}
+
+#[test]
+pub fn interfaces_contain_only_facades() {
+    let base_dir = service_base_dir("interfaces");
+    let mut manager = AppServiceManager::new(&base_dir)
+        .unwrap_or_else(|e| panic!("manager should be created: {:?}", e));
+    let config: TomlAppServiceConfig = toml::from_str(
+        r#"
+        modules_dir = "../examples/records/artifacts/"
+
+        [[module]]
+            name = "records_effector"
+
+        [[module]]
+            name = "records_pure"
+        "#,
+    )
+    .expect("config should be well-formed");
+
+    manager
+        .create_service("service_1", config, <_>::default())
+        .unwrap_or_else(|e| panic!("service should be created: {:?}", e));
+    let interfaces = manager.get_interfaces();
+    let modules = interfaces["service_1"]
+        .modules
+        .keys()
+        .map(|name| name.to_string())
+        .collect::<Vec<_>>();
+
+    drop(interfaces);
+    drop(manager);
+    std::fs::remove_dir_all(&base_dir).unwrap();
+
+    assert_eq!(modules, vec!["records_pure"]);
+}
@@ -0,0 +1,145 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use fluence_app_service::AppService;
+use fluence_app_service::AppServiceError;
+use fluence_app_service::CallParameters;
+use fluence_app_service::TomlAppServiceConfig;
+
+use serde_json::json;
+use serde_json::Value as JValue;
+
+use std::path::Path;
+use std::path::PathBuf;
+
+fn service_base_dir(name: &str) -> PathBuf {
+    let base_dir = std::env::temp_dir().join(format!("fce_state_{}_{}", name, std::process::id()));
+    std::fs::create_dir_all(&base_dir).unwrap();
+    base_dir
+}
+
+fn service_config(base_dir: &Path, modules_dir: &str, module: &str) -> TomlAppServiceConfig {
+    let config = format!(
+        r#"
+        modules_dir = "{}"
+
+        {}
+        "#,
+        modules_dir, module
+    );
+    let mut config: TomlAppServiceConfig =
+        toml::from_str(&config).expect("config should be well-formed");
+    config.service_base_dir = Some(base_dir.to_string_lossy().to_string());
+
+    config
+}
+
+fn call(service: &mut AppService, func_name: &str) -> JValue {
+    service
+        .call(func_name, json!([]), CallParameters::default())
+        .unwrap_or_else(|e| panic!("{} should be called: {:?}", func_name, e))
+}
+
+#[test]
+pub fn export_import_round_trip() {
+    let base_dir = service_base_dir("round_trip");
+    let archive_path = base_dir.join("state.tar");
+    let config = service_config(
+        &base_dir,
+        "tests/wasm_tests/lifecycle/artifacts/",
+        r#"[[module]]
+            name = "stateful""#,
+    );
+
+    let mut service = AppService::new(config, "service_1", <_>::default())
+        .unwrap_or_else(|e| panic!("service should be created: {:?}", e));
+    call(&mut service, "increment");
+    call(&mut service, "increment");
+    let export_result = service.export_state(&archive_path);
+    drop(service);
+
+    let imported_service = AppService::import_state(&archive_path, "service_2");
+    let (counter, events) = match imported_service {
+        Ok(mut service) => (call(&mut service, "counter"), call(&mut service, "events")),
+        Err(e) => panic!("service should be imported: {:?}", e),
+    };
+    let reimported_service = AppService::import_state(&archive_path, "service_2");
+    let is_reimport_rejected = matches!(reimported_service, Err(AppServiceError::InvalidState(_)));
+    drop(reimported_service);
+    std::fs::remove_dir_all(&base_dir).unwrap();
+
+    assert!(export_result.is_ok());
+    // init isn't called again and the counter set by it is restored from the snapshot
+    assert_eq!(counter, json!(102));
+    assert_eq!(events, json!("init\n"));
+    assert!(is_reimport_rejected);
+}
+
+#[test]
+pub fn memory_fs_services_are_not_exported() {
+    let base_dir = service_base_dir("memory_fs");
+    let archive_path = base_dir.join("state.tar");
+    let config = service_config(
+        &base_dir,
+        "../examples/greeting/artifacts/",
+        r#"[[module]]
+            name = "greeting"
+
+            [module.wasi]
+            fs = "memory""#,
+    );
+
+    let mut service = AppService::new(config, "service_1", <_>::default())
+        .unwrap_or_else(|e| panic!("service should be created: {:?}", e));
+    let export_result = service.export_state(&archive_path);
+    drop(service);
+    let archive_exists = archive_path.exists();
+    std::fs::remove_dir_all(&base_dir).unwrap();
+
+    assert!(matches!(
+        export_result,
+        Err(AppServiceError::InvalidState(_))
+    ));
+    assert!(!archive_exists);
+}
+
+#[test]
+pub fn pooled_modules_are_not_exported() {
+    let base_dir = service_base_dir("pooled");
+    let archive_path = base_dir.join("state.tar");
+    let config = service_config(
+        &base_dir,
+        "../examples/greeting/artifacts/",
+        r#"[[module]]
+            name = "greeting"
+
+            [module.instance_pool]
+            size = 2"#,
+    );
+
+    let mut service = AppService::new(config, "service_1", <_>::default())
+        .unwrap_or_else(|e| panic!("service should be created: {:?}", e));
+    let export_result = service.export_state(&archive_path);
+    drop(service);
+    let archive_exists = archive_path.exists();
+    std::fs::remove_dir_all(&base_dir).unwrap();
+
+    assert!(matches!(
+        export_result,
+        Err(AppServiceError::InvalidState(_))
+    ));
+    assert!(!archive_exists);
+}
@@ -0,0 +1,12 @@
+#!/bin/sh
+
+# This script builds all tests
+cd lifecycle
+cargo update
+fce build --release
+rm artifacts/*
+
+cd ..
+cp ../../../target/wasm32-wasi/release/lifecycle.wasm lifecycle/artifacts/
+cp ../../../target/wasm32-wasi/release/lifecycle_with_arguments.wasm lifecycle/artifacts/
+cp ../../../target/wasm32-wasi/release/stateful.wasm lifecycle/artifacts/
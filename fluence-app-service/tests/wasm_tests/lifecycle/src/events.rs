@@ -0,0 +1,36 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::fs::OpenOptions;
+use std::io::Write;
+
+/// Lifecycle events are appended to this file in the service local dir,
+/// so the host could check their order.
+const EVENTS_FILE_PATH: &str = "/local/events";
+
+pub(crate) fn record_event(event: &str) {
+    let mut events_file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(EVENTS_FILE_PATH)
+        .unwrap();
+
+    writeln!(events_file, "{}", event).unwrap();
+}
+
+pub(crate) fn read_events() -> String {
+    std::fs::read_to_string(EVENTS_FILE_PATH).unwrap_or_default()
+}
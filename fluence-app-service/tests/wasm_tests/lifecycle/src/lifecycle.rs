@@ -0,0 +1,58 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+mod events;
+
+use crate::events::read_events;
+use crate::events::record_event;
+
+use fluence::fce;
+
+/// If this file exists in the service local dir, init fails.
+const FAIL_INIT_FILE_PATH: &str = "/local/fail_init";
+
+/// If this file exists in the service local dir, on_upgrade fails.
+const FAIL_ON_UPGRADE_FILE_PATH: &str = "/local/fail_on_upgrade";
+
+pub fn main() {}
+
+#[fce]
+pub fn init() {
+    if std::path::Path::new(FAIL_INIT_FILE_PATH).exists() {
+        panic!("init is asked to fail");
+    }
+
+    record_event("init");
+}
+
+#[fce]
+pub fn on_upgrade(old_version: String) {
+    if std::path::Path::new(FAIL_ON_UPGRADE_FILE_PATH).exists() {
+        panic!("on_upgrade is asked to fail");
+    }
+
+    record_event(&format!("on_upgrade {}", old_version));
+}
+
+#[fce]
+pub fn shutdown() {
+    record_event("shutdown");
+}
+
+#[fce]
+pub fn events() -> String {
+    read_events()
+}
@@ -0,0 +1,41 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+mod events;
+
+use crate::events::read_events;
+use crate::events::record_event;
+
+use fluence::fce;
+
+pub fn main() {}
+
+/// Has the well-known name, but isn't a lifecycle function because of its arguments.
+#[fce]
+pub fn init(reason: String) {
+    record_event(&format!("init {}", reason));
+}
+
+/// Has the well-known name, but isn't a lifecycle function because of its arguments.
+#[fce]
+pub fn shutdown(force: bool) {
+    record_event(&format!("shutdown {}", force));
+}
+
+#[fce]
+pub fn events() -> String {
+    read_events()
+}
@@ -0,0 +1,52 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+mod events;
+
+use crate::events::read_events;
+use crate::events::record_event;
+
+use fluence::fce;
+
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+
+/// Lives only in the module memory, so it survives an export and an import of the service
+/// only if the memory snapshot is restored.
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+pub fn main() {}
+
+#[fce]
+pub fn init() {
+    COUNTER.store(100, Ordering::SeqCst);
+    record_event("init");
+}
+
+#[fce]
+pub fn increment() -> u64 {
+    COUNTER.fetch_add(1, Ordering::SeqCst) + 1
+}
+
+#[fce]
+pub fn counter() -> u64 {
+    COUNTER.load(Ordering::SeqCst)
+}
+
+#[fce]
+pub fn events() -> String {
+    read_events()
+}
@@ -0,0 +1,223 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::FaaSError;
+use crate::Result;
+
+use futures_channel::oneshot;
+
+use std::any::Any;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::pin::Pin;
+use std::sync::mpsc::channel;
+use std::sync::mpsc::sync_channel;
+use std::sync::mpsc::SyncSender;
+use std::sync::mpsc::TrySendError;
+use std::task::Context;
+use std::task::Poll;
+use std::thread::JoinHandle;
+
+type Request<S> = Box<dyn FnOnce(&mut S) + Send>;
+
+/// Owns a service on a dedicated thread and executes requests to it one by one,
+/// so a service could be called from async code without blocking the runtime.
+///
+/// The service is created on the executor thread and never leaves it, so it doesn't have
+/// to be Send: FCE and everything built on it are bound to the thread that created them.
+/// The executor itself is Send and Sync and could be shared between threads.
+///
+/// Requests are queued to a bounded queue, a request that doesn't fit into it is rejected
+/// with `FaaSError::ExecutorQueueFull`. A request is cancelled if its future is dropped
+/// before the request is started, so timeouts of async runtimes could be applied to calls.
+/// Already started calls can't be interrupted and run to completion. A request that panics
+/// fails with `FaaSError::CallPanicked`, and the executor keeps serving other requests.
+///
+/// Dropping the executor doesn't block: its queue is closed and the worker thread is detached,
+/// it executes already queued requests and then drops the service. `shutdown` should be used
+/// to wait for them instead.
+pub struct AsyncExecutor<S: 'static> {
+    name: String,
+    queue_capacity: usize,
+    requests: Option<SyncSender<Request<S>>>,
+    worker: Option<JoinHandle<()>>,
+}
+
+/// Result of a request executed by `AsyncExecutor`.
+pub struct ExecutionFuture<R> {
+    executor_name: String,
+    response: oneshot::Receiver<Result<R>>,
+}
+
+impl<S: 'static> AsyncExecutor<S> {
+    /// Starts a thread with the given name and creates the service on it, returns after
+    /// the service is created. With zero capacity a request is accepted only if the executor
+    /// is idle.
+    pub fn new<C, E, N>(create: C, name: N, queue_capacity: usize) -> std::result::Result<Self, E>
+    where
+        C: FnOnce() -> std::result::Result<S, E> + Send + 'static,
+        E: From<FaaSError> + Send + 'static,
+        N: Into<String>,
+    {
+        let name = name.into();
+        let (requests, requests_receiver) = sync_channel::<Request<S>>(queue_capacity);
+        let (created_sender, created) = channel();
+
+        let worker = std::thread::Builder::new()
+            .name(name.clone())
+            .spawn(move || {
+                let mut service = match create() {
+                    Ok(service) => service,
+                    Err(e) => {
+                        let _ = created_sender.send(Err(e));
+                        return;
+                    }
+                };
+                let _ = created_sender.send(Ok(()));
+
+                for request in requests_receiver {
+                    request(&mut service);
+                }
+            })
+            .map_err(FaaSError::from)?;
+
+        match created.recv() {
+            Ok(result) => result?,
+            // the service constructor panicked
+            Err(_) => {
+                return Err(E::from(FaaSError::ExecutorStopped(format!(
+                    "executor {} has stopped while its service was created",
+                    name
+                ))))
+            }
+        }
+
+        Ok(Self {
+            name,
+            queue_capacity,
+            requests: Some(requests),
+            worker: Some(worker),
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn queue_capacity(&self) -> usize {
+        self.queue_capacity
+    }
+
+    /// Queues a request to the service without blocking, the returned future resolves
+    /// to the request result.
+    pub fn execute<R, F>(&self, request: F) -> Result<ExecutionFuture<R>>
+    where
+        R: Send + 'static,
+        F: FnOnce(&mut S) -> R + Send + 'static,
+    {
+        let (response_sender, response) = oneshot::channel();
+        let request: Request<S> = Box::new(move |service: &mut S| {
+            // the future has been dropped while the request was waiting in the queue
+            if response_sender.is_canceled() {
+                return;
+            }
+
+            let result = execute_request(request, service);
+            // the future could be dropped during the call as well
+            let _ = response_sender.send(result);
+        });
+
+        let requests = self.requests.as_ref().ok_or_else(|| self.stopped_error())?;
+        requests.try_send(request).map_err(|e| match e {
+            TrySendError::Full(_) => FaaSError::ExecutorQueueFull(format!(
+                "queue of executor {} is full, its capacity is {}",
+                self.name, self.queue_capacity
+            )),
+            TrySendError::Disconnected(_) => self.stopped_error(),
+        })?;
+
+        Ok(ExecutionFuture {
+            executor_name: self.name.clone(),
+            response,
+        })
+    }
+
+    /// Executes a request and waits for its result, blocks while the queue is full.
+    /// Could be used by callers that aren't async, f.e. by threads serving connections.
+    pub fn execute_blocking<R, F>(&self, request: F) -> Result<R>
+    where
+        R: Send + 'static,
+        F: FnOnce(&mut S) -> R + Send + 'static,
+    {
+        let (response_sender, response) = channel();
+        let request: Request<S> = Box::new(move |service: &mut S| {
+            let _ = response_sender.send(execute_request(request, service));
+        });
+
+        let requests = self.requests.as_ref().ok_or_else(|| self.stopped_error())?;
+        requests.send(request).map_err(|_| self.stopped_error())?;
+
+        response.recv().map_err(|_| self.stopped_error())?
+    }
+
+    /// Waits until all queued requests are executed and the service is dropped.
+    pub fn shutdown(mut self) -> Result<()> {
+        self.requests.take();
+        let worker = self.worker.take().ok_or_else(|| self.stopped_error())?;
+
+        worker.join().map_err(|_| self.stopped_error())
+    }
+
+    fn stopped_error(&self) -> FaaSError {
+        FaaSError::ExecutorStopped(format!("executor {} has stopped", self.name))
+    }
+}
+
+impl<R> Future for ExecutionFuture<R> {
+    type Output = Result<R>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.response).poll(cx).map(|result| {
+            result.map_err(|_| {
+                FaaSError::ExecutorStopped(format!(
+                    "executor {} has stopped before the request was completed",
+                    self.executor_name
+                ))
+            })?
+        })
+    }
+}
+
+/// Executes a request and turns its panic into an error, so that the executor thread survives.
+/// The service is still used after a panic, requests should keep it consistent when they fail.
+fn execute_request<S, R, F>(request: F, service: &mut S) -> Result<R>
+where
+    F: FnOnce(&mut S) -> R,
+{
+    std::panic::catch_unwind(AssertUnwindSafe(|| request(service))).map_err(|panic| {
+        FaaSError::CallPanicked(format!("request panicked: {}", panic_message(&*panic)))
+    })
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown panic"
+    }
+}
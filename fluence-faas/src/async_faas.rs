@@ -0,0 +1,98 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::AsyncExecutor;
+use crate::FluenceFaaS;
+use crate::IValue;
+use crate::Result;
+
+use fluence_sdk_main::CallParameters;
+use serde_json::Value as JValue;
+
+use std::future::Future;
+
+/// FluenceFaaS called from async code, calls are executed one by one by a dedicated thread.
+pub struct AsyncFluenceFaaS {
+    executor: AsyncExecutor<FluenceFaaS>,
+}
+
+impl AsyncFluenceFaaS {
+    /// Creates FaaS by `create` on its executor thread, at most `queue_capacity` calls could
+    /// wait for it.
+    pub fn new<C, N>(create: C, name: N, queue_capacity: usize) -> Result<Self>
+    where
+        C: FnOnce() -> Result<FluenceFaaS> + Send + 'static,
+        N: Into<String>,
+    {
+        let executor = AsyncExecutor::new(create, name, queue_capacity)?;
+
+        Ok(Self { executor })
+    }
+
+    /// Queues a call, fails immediately if the queue is full. The call is cancelled
+    /// if the returned future is dropped before the call is started.
+    pub fn call_with_json<MN, FN>(
+        &self,
+        module_name: MN,
+        func_name: FN,
+        json_args: JValue,
+        call_parameters: CallParameters,
+    ) -> Result<impl Future<Output = Result<JValue>>>
+    where
+        MN: Into<String>,
+        FN: Into<String>,
+    {
+        let module_name = module_name.into();
+        let func_name = func_name.into();
+        let response = self.executor.execute(move |faas| {
+            faas.call_with_json(module_name, func_name, json_args, call_parameters)
+        })?;
+
+        Ok(async move { response.await? })
+    }
+
+    /// Queues a call, fails immediately if the queue is full. The call is cancelled
+    /// if the returned future is dropped before the call is started.
+    pub fn call_with_ivalues<MN, FN>(
+        &self,
+        module_name: MN,
+        func_name: FN,
+        args: Vec<IValue>,
+        call_parameters: CallParameters,
+    ) -> Result<impl Future<Output = Result<Vec<IValue>>>>
+    where
+        MN: Into<String>,
+        FN: Into<String>,
+    {
+        let module_name = module_name.into();
+        let func_name = func_name.into();
+        let response = self.executor.execute(move |faas| {
+            faas.call_with_ivalues(module_name, func_name, &args, call_parameters)
+        })?;
+
+        Ok(async move { response.await? })
+    }
+
+    /// Returns the executor to run arbitrary requests to FaaS.
+    pub fn executor(&self) -> &AsyncExecutor<FluenceFaaS> {
+        &self.executor
+    }
+
+    /// Waits until all queued calls are executed and FaaS is dropped.
+    pub fn shutdown(self) -> Result<()> {
+        self.executor.shutdown()
+    }
+}
@@ -0,0 +1,195 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::FaaSError;
+use crate::Result;
+use crate::TomlFaaSConfig;
+use crate::misc::collect_dir_files;
+use crate::misc::load_toml_value;
+use crate::misc::read_hashed_archive;
+use crate::misc::write_hashed_archive;
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+const BUNDLE_FORMAT_VERSION: u32 = 1;
+const CONFIG_FILE_NAME: &str = "Config.toml";
+const MODULES_DIR_NAME: &str = "modules";
+const DATA_DIR_NAME: &str = "data";
+
+/// Self-contained service: a tar archive with the manifest, Config.toml,
+/// modules in `modules/` and optional seed data of the service local dir in `data/`.
+#[derive(Debug, Clone, Default)]
+pub struct ServiceBundle {
+    /// Content of Config.toml with resolved includes and environment variables.
+    pub config: String,
+
+    /// Modules by names, the name of a module is its file name without extension.
+    pub modules: HashMap<String, Vec<u8>>,
+
+    /// Files of the local dir with paths relative to it.
+    pub data: Vec<(PathBuf, Vec<u8>)>,
+}
+
+impl ServiceBundle {
+    /// Reads a bundle and checks that its content matches the manifest.
+    pub fn load<P: AsRef<Path>>(bundle_path: P) -> Result<Self> {
+        let mut files = read_hashed_archive(bundle_path.as_ref(), BUNDLE_FORMAT_VERSION)?;
+        let config = files
+            .remove(CONFIG_FILE_NAME)
+            .ok_or_else(|| FaaSError::InvalidBundle(format!("{} is missing", CONFIG_FILE_NAME)))?;
+        let config = String::from_utf8(config).map_err(|e| {
+            FaaSError::InvalidBundle(format!("{} isn't a valid UTF-8: {}", CONFIG_FILE_NAME, e))
+        })?;
+
+        let mut modules = HashMap::new();
+        let mut data = Vec::new();
+        for (path, content) in files {
+            let path = PathBuf::from(path);
+            if let Ok(module_path) = path.strip_prefix(MODULES_DIR_NAME) {
+                let module_name = module_path.file_stem().unwrap_or_default();
+                modules.insert(module_name.to_string_lossy().to_string(), content);
+            } else if let Ok(data_path) = path.strip_prefix(DATA_DIR_NAME) {
+                data.push((data_path.to_path_buf(), content));
+            } else {
+                return Err(FaaSError::InvalidBundle(format!(
+                    "unexpected file {:?}",
+                    path
+                )));
+            }
+        }
+
+        Ok(Self {
+            config,
+            modules,
+            data,
+        })
+    }
+
+    /// Packs a service described by the config into a bundle: modules listed in the config
+    /// are taken from its modules_dir and files of data_dir become the local dir seed.
+    pub fn pack(config_path: &Path, data_dir: Option<&Path>, bundle_path: &Path) -> Result<()> {
+        let config = load_toml_value(config_path)?;
+        let faas_config: TomlFaaSConfig = config
+            .clone()
+            .try_into()
+            .map_err(FaaSError::ParseConfigError)?;
+        let config = toml::to_string_pretty(&config)
+            .map_err(|e| FaaSError::ConfigSerializationError(format!("{}", e)))?;
+
+        let mut files = BTreeMap::new();
+        files.insert(PathBuf::from(CONFIG_FILE_NAME), config.into_bytes());
+
+        let modules_dir = faas_config
+            .modules_dir
+            .map(PathBuf::from)
+            .unwrap_or_default();
+        for module in faas_config.module {
+            let file_name = format!("{}.wasm", module.name);
+            let module_path = modules_dir.join(&file_name);
+            let content = std::fs::read(&module_path)
+                .map_err(|e| FaaSError::IOError(format!("{:?}: {}", module_path, e)))?;
+            files.insert(Path::new(MODULES_DIR_NAME).join(file_name), content);
+        }
+
+        if let Some(data_dir) = data_dir {
+            collect_dir_files(data_dir, Path::new(DATA_DIR_NAME), &mut files)?;
+        }
+
+        write_hashed_archive(bundle_path, BUNDLE_FORMAT_VERSION, files)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ServiceBundle;
+    use super::BUNDLE_FORMAT_VERSION;
+    use crate::FaaSError;
+    use crate::misc::write_hashed_archive;
+
+    use std::collections::BTreeMap;
+    use std::path::Path;
+    use std::path::PathBuf;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("fce_bundle_{}_{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_file(path: &Path, content: &[u8]) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn pack_and_load() {
+        let dir = test_dir("pack_and_load");
+        let modules_dir = dir.join("artifacts");
+        let config_path = dir.join("Config.toml");
+        write_file(
+            &config_path,
+            format!(
+                "modules_dir = {:?}\n\n[[module]]\n    name = \"greeting\"\n",
+                modules_dir
+            )
+            .as_bytes(),
+        );
+        write_file(&modules_dir.join("greeting.wasm"), b"greeting module");
+        write_file(&modules_dir.join("unlisted.wasm"), b"unlisted module");
+        write_file(&dir.join("data").join("nested").join("seed.txt"), b"seed");
+
+        let bundle_path = dir.join("service.tar");
+        let packed = ServiceBundle::pack(&config_path, Some(&dir.join("data")), &bundle_path);
+        let bundle = packed.and_then(|_| ServiceBundle::load(&bundle_path));
+        std::fs::remove_dir_all(&dir).unwrap();
+        let bundle = bundle.unwrap_or_else(|e| panic!("bundle should be packed: {:?}", e));
+
+        assert!(bundle.config.contains("greeting"));
+        assert_eq!(bundle.modules.len(), 1);
+        assert_eq!(bundle.modules["greeting"], b"greeting module".to_vec());
+        assert_eq!(
+            bundle.data,
+            vec![(Path::new("nested").join("seed.txt"), b"seed".to_vec())]
+        );
+    }
+
+    #[test]
+    fn unexpected_files_are_rejected() {
+        let dir = test_dir("unexpected_files");
+        let bundle_path = dir.join("service.tar");
+
+        let mut files = BTreeMap::new();
+        files.insert(PathBuf::from("Config.toml"), b"".to_vec());
+        files.insert(PathBuf::from("other").join("file"), b"".to_vec());
+        write_hashed_archive(&bundle_path, BUNDLE_FORMAT_VERSION, files).unwrap();
+        let with_unexpected_file = ServiceBundle::load(&bundle_path);
+
+        let mut files = BTreeMap::new();
+        files.insert(PathBuf::from("modules").join("greeting.wasm"), b"".to_vec());
+        write_hashed_archive(&bundle_path, BUNDLE_FORMAT_VERSION, files).unwrap();
+        let without_config = ServiceBundle::load(&bundle_path);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(matches!(
+            with_unexpected_file,
+            Err(FaaSError::InvalidBundle(_))
+        ));
+        assert!(matches!(without_config, Err(FaaSError::InvalidBundle(_))));
+    }
+}
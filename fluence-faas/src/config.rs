@@ -14,11 +14,19 @@
  * limitations under the License.
  */
 
+use fce::DeterministicConfig;
 use fce::HostImportDescriptor;
+use fce::ImportPolicy;
+use fce::InstanceMode;
+use fce::MappedDirPermissions;
+use fce::WASIFileSystem;
+use fce_module_signature::PublicKey;
+use serde_derive::Serialize;
 
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::path::PathBuf;
+use std::time::Duration;
 
 /// Describes the behaviour of FluenceFaaS.
 #[derive(Default)]
@@ -31,6 +39,12 @@ pub struct FaaSConfig {
 
     /// Settings for a module that name's not been found in modules_config.
     pub default_modules_config: Option<FaaSModuleConfig>,
+
+    /// If Some, only modules signed by one of these keys could be loaded.
+    pub trusted_keys: Option<Vec<PublicKey>>,
+
+    /// If Some, compiled modules are cached in this dir and reused by other FaaS instances.
+    pub compilation_cache_dir: Option<PathBuf>,
 }
 
 /// Various settings that could be used to guide FCE how to load a module in a proper way.
@@ -50,6 +64,39 @@ pub struct FaaSModuleConfig {
 
     /// Mask used to filter logs, for details see `log_utf8_string`
     pub logging_mask: i64,
+
+    /// If Some, the module is executed in the deterministic mode.
+    pub deterministic: Option<DeterministicConfig>,
+
+    /// Imports the module is allowed to use, checked while the module is loaded.
+    pub import_policy: ImportPolicy,
+
+    /// If Some, the module bytes must have this hash in the `blake3:<hex digest>` form.
+    pub hash: Option<String>,
+
+    /// If Some, calls of the module are served by a pool of its instances.
+    pub instance_pool: Option<InstancePoolConfig>,
+
+    /// Whether the module keeps its state between calls or each call gets a pristine instance.
+    pub instance_mode: InstanceMode,
+
+    /// If true, the module is instantiated on its first call instead of FaaS creation.
+    pub lazy_instantiation: bool,
+
+    /// If Some, the module instance is evicted after being idle for this time. A new instance
+    /// reruns `_start` and gets the memory of the evicted one, but not its mutable globals.
+    pub idle_timeout: Option<Duration>,
+}
+
+/// Settings of a pool of pre-instantiated instances of a stateless module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct InstancePoolConfig {
+    /// Count of instances, each of them serves one call at a time.
+    pub size: usize,
+
+    /// If true, the instance memory is restored to its state right after instantiation
+    /// at the end of each call, otherwise instances are reused as is.
+    pub reset_after_call: bool,
 }
 
 impl FaaSModuleConfig {
@@ -58,9 +105,12 @@ impl FaaSModuleConfig {
             Some(FaaSWASIConfig { envs, .. }) => envs.extend(new_envs),
             w @ None => {
                 *w = Some(FaaSWASIConfig {
+                    args: Vec::new(),
                     envs: new_envs,
                     preopened_files: HashSet::new(),
                     mapped_dirs: HashMap::new(),
+                    mapped_dirs_permissions: HashMap::new(),
+                    fs: WASIFileSystem::Host,
                 })
             }
         };
@@ -82,9 +132,12 @@ impl FaaSModuleConfig {
             }
             w @ None => {
                 *w = Some(FaaSWASIConfig {
+                    args: Vec::new(),
                     envs: HashMap::new(),
                     preopened_files: new_preopened_files,
                     mapped_dirs: new_mapped_dirs,
+                    mapped_dirs_permissions: HashMap::new(),
+                    fs: WASIFileSystem::Host,
                 })
             }
         };
@@ -93,6 +146,9 @@ impl FaaSModuleConfig {
 
 #[derive(Debug, Clone, Default)]
 pub struct FaaSWASIConfig {
+    /// A list of arguments (argv) available for this module, the module name is prepended to it.
+    pub args: Vec<String>,
+
     /// A list of environment variables available for this module.
     pub envs: HashMap<Vec<u8>, Vec<u8>>,
 
@@ -102,4 +158,10 @@ pub struct FaaSWASIConfig {
 
     /// Mapping from a usually short to full file name.
     pub mapped_dirs: HashMap<String, PathBuf>,
+
+    /// Permissions of mapped dirs by their aliases, dirs without them are writable and unlimited.
+    pub mapped_dirs_permissions: HashMap<String, MappedDirPermissions>,
+
+    /// Filesystem visible to this module, host one by default.
+    pub fs: WASIFileSystem,
 }
@@ -0,0 +1,348 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::TomlFaaSConfig;
+use crate::TomlFaaSModuleConfig;
+use crate::TomlWASIConfig;
+use crate::misc::module_hash;
+use crate::misc::parse_module_hash;
+use crate::raw_toml_config::check_mapped_dir_restrictions;
+use crate::raw_toml_config::parse_idle_timeout;
+use crate::raw_toml_config::parse_import_policy;
+use crate::raw_toml_config::parse_instance_mode;
+use crate::raw_toml_config::parse_instance_pool;
+use crate::raw_toml_config::parse_mapped_dir;
+use crate::raw_toml_config::parse_wasi_fs;
+
+use fce::ImportPolicy;
+use fce::WASIFileSystem;
+use fce_module_signature::parse_public_key;
+use serde_derive::Serialize;
+
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Maximum number of Wasm pages that a module could have.
+const MAX_MEM_PAGES_COUNT: u32 = 65536;
+
+/// Describes a problem found in a config.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConfigDiagnostic {
+    /// Path to the key with a problem, f.e. `module[1].wasi.mapped_dirs.tmp`.
+    pub key_path: String,
+
+    /// Description of the problem.
+    pub message: String,
+}
+
+impl ConfigDiagnostic {
+    pub fn new<K: Into<String>, M: Into<String>>(key_path: K, message: M) -> Self {
+        Self {
+            key_path: key_path.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.key_path, self.message)
+    }
+}
+
+impl TomlFaaSConfig {
+    /// Check the config against the filesystem and return all found problems at once.
+    pub fn validate(&self) -> Vec<ConfigDiagnostic> {
+        let mut diagnostics = Vec::new();
+
+        let modules_dir = self.modules_dir.as_ref().map(PathBuf::from);
+        let modules_dir = match modules_dir {
+            Some(modules_dir) if !modules_dir.is_dir() => {
+                diagnostics.push(ConfigDiagnostic::new(
+                    "modules_dir",
+                    format!("{:?} isn't an existing directory", modules_dir),
+                ));
+                None
+            }
+            Some(modules_dir) => Some(modules_dir),
+            None if !self.module.is_empty() => {
+                diagnostics.push(ConfigDiagnostic::new(
+                    "modules_dir",
+                    "modules_dir isn't specified, so modules can't be loaded from filesystem",
+                ));
+                None
+            }
+            None => None,
+        };
+
+        for (id, key) in self.trusted_keys.iter().flatten().enumerate() {
+            if let Err(e) = parse_public_key(key) {
+                diagnostics.push(ConfigDiagnostic::new(
+                    format!("trusted_keys[{}]", id),
+                    format!("{}", e),
+                ));
+            }
+        }
+
+        let mut module_names = HashSet::new();
+        for (id, module) in self.module.iter().enumerate() {
+            let key_path = format!("module[{}]", id);
+
+            if !module_names.insert(module.name.as_str()) {
+                diagnostics.push(ConfigDiagnostic::new(
+                    format!("{}.name", key_path),
+                    format!(r#"module with name "{}" is duplicated"#, module.name),
+                ));
+            }
+
+            let hash = match module.hash.as_deref().map(parse_module_hash) {
+                Some(Ok(hash)) => Some(hash),
+                Some(Err(message)) => {
+                    diagnostics.push(ConfigDiagnostic::new(format!("{}.hash", key_path), message));
+                    None
+                }
+                None => None,
+            };
+
+            if let Some(modules_dir) = &modules_dir {
+                let module_path = modules_dir.join(format!("{}.wasm", module.name));
+                if !module_path.is_file() {
+                    diagnostics.push(ConfigDiagnostic::new(
+                        format!("{}.name", key_path),
+                        format!(
+                            r#"module "{}" isn't found in modules_dir: {:?} doesn't exist"#,
+                            module.name, module_path
+                        ),
+                    ));
+                } else if let Some(hash) = hash {
+                    if let Some(message) = check_module_file_hash(&module_path, &hash) {
+                        diagnostics
+                            .push(ConfigDiagnostic::new(format!("{}.hash", key_path), message));
+                    }
+                }
+            }
+
+            validate_module_config(&module.config, &key_path, &mut diagnostics);
+        }
+
+        if let Some(default) = &self.default {
+            validate_module_config(default, "default", &mut diagnostics);
+        }
+
+        diagnostics
+    }
+}
+
+fn check_module_file_hash(module_path: &Path, expected_hash: &str) -> Option<String> {
+    let module_bytes = match std::fs::read(module_path) {
+        Ok(module_bytes) => module_bytes,
+        Err(e) => return Some(format!("{:?} can't be read: {}", module_path, e)),
+    };
+
+    let actual_hash = module_hash(&module_bytes);
+    if actual_hash == expected_hash {
+        None
+    } else {
+        Some(format!("{:?} has hash {}", module_path, actual_hash))
+    }
+}
+
+fn validate_module_config(
+    config: &TomlFaaSModuleConfig,
+    key_path: &str,
+    diagnostics: &mut Vec<ConfigDiagnostic>,
+) {
+    if let Some(mem_pages_count) = config.mem_pages_count {
+        if mem_pages_count > MAX_MEM_PAGES_COUNT {
+            diagnostics.push(ConfigDiagnostic::new(
+                format!("{}.mem_pages_count", key_path),
+                format!(
+                    "{} exceeds the maximum count of Wasm pages {}",
+                    mem_pages_count, MAX_MEM_PAGES_COUNT
+                ),
+            ));
+        }
+    }
+
+    if let Some(mounted_binaries) = &config.mounted_binaries {
+        for (import_name, host_cmd) in mounted_binaries {
+            let key_path = format!("{}.mounted_binaries.{}", key_path, import_name);
+            match host_cmd.as_str() {
+                Some(host_cmd) => {
+                    if let Some(message) = check_host_binary(host_cmd) {
+                        diagnostics.push(ConfigDiagnostic::new(key_path, message));
+                    }
+                }
+                None => diagnostics.push(ConfigDiagnostic::new(
+                    key_path,
+                    format!("expected a string, found {}", host_cmd.type_str()),
+                )),
+            }
+        }
+    }
+
+    let has_mounted_binaries = config
+        .mounted_binaries
+        .as_ref()
+        .map_or(false, |binaries| !binaries.is_empty());
+    if config.deterministic.is_some() && has_mounted_binaries {
+        diagnostics.push(ConfigDiagnostic::new(
+            format!("{}.deterministic", key_path),
+            "mounted binaries can't be used in the deterministic mode",
+        ));
+    }
+
+    if let Some(import_policy) = &config.import_policy {
+        let key_path = format!("{}.import_policy", key_path);
+        match parse_import_policy(import_policy) {
+            Ok(ImportPolicy::Pure) if has_mounted_binaries => diagnostics.push(
+                ConfigDiagnostic::new(key_path, "mounted binaries can't be used by a pure module"),
+            ),
+            Ok(_) => {}
+            Err(message) => diagnostics.push(ConfigDiagnostic::new(key_path, message)),
+        }
+    }
+
+    if let Err(message) = parse_instance_mode(config.instance_mode.as_deref()) {
+        diagnostics.push(ConfigDiagnostic::new(
+            format!("{}.instance_mode", key_path),
+            message,
+        ));
+    }
+
+    let is_memory_fs = config
+        .wasi
+        .as_ref()
+        .map_or(false, |wasi| wasi.fs.as_deref() == Some("memory"));
+    let is_deterministic = config.deterministic.is_some();
+    if let Err(message) =
+        parse_idle_timeout(config.idle_timeout_secs, is_memory_fs, is_deterministic)
+    {
+        diagnostics.push(ConfigDiagnostic::new(
+            format!("{}.idle_timeout_secs", key_path),
+            message,
+        ));
+    }
+
+    if let Some(instance_pool) = &config.instance_pool {
+        let key_path = format!("{}.instance_pool", key_path);
+        match parse_instance_pool(instance_pool) {
+            Ok(_) if has_mounted_binaries => diagnostics.push(ConfigDiagnostic::new(
+                key_path,
+                "mounted binaries can't be used by a module with an instance pool",
+            )),
+            Ok(_) => {}
+            Err(message) => diagnostics.push(ConfigDiagnostic::new(key_path, message)),
+        }
+    }
+
+    if let Some(wasi) = &config.wasi {
+        validate_wasi_config(wasi, &format!("{}.wasi", key_path), diagnostics);
+    }
+}
+
+fn validate_wasi_config(
+    config: &TomlWASIConfig,
+    key_path: &str,
+    diagnostics: &mut Vec<ConfigDiagnostic>,
+) {
+    if let Some(envs) = &config.envs {
+        for (name, value) in envs {
+            if !value.is_str() {
+                diagnostics.push(ConfigDiagnostic::new(
+                    format!("{}.envs.{}", key_path, name),
+                    format!("expected a string, found {}", value.type_str()),
+                ));
+            }
+        }
+    }
+
+    match parse_wasi_fs(config.fs.as_deref(), config.fs_seed.as_deref()) {
+        Ok(WASIFileSystem::Memory { seed: Some(seed) }) if !seed.exists() => {
+            diagnostics.push(ConfigDiagnostic::new(
+                format!("{}.fs_seed", key_path),
+                format!("{:?} doesn't exist", seed),
+            ))
+        }
+        Ok(_) => {}
+        Err(message) => {
+            diagnostics.push(ConfigDiagnostic::new(format!("{}.fs", key_path), message))
+        }
+    }
+
+    let is_memory_fs = config.fs.as_deref() == Some("memory");
+
+    if let Some(preopened_files) = &config.preopened_files {
+        for (id, path) in preopened_files.iter().enumerate() {
+            if !is_memory_fs && !Path::new(path).exists() {
+                diagnostics.push(ConfigDiagnostic::new(
+                    format!("{}.preopened_files[{}]", key_path, id),
+                    format!("{:?} doesn't exist", path),
+                ));
+            }
+        }
+    }
+
+    if let Some(mapped_dirs) = &config.mapped_dirs {
+        for (alias, value) in mapped_dirs {
+            let key_path = format!("{}.mapped_dirs.{}", key_path, alias);
+            match parse_mapped_dir(value) {
+                Ok((path, _)) if !is_memory_fs && !path.is_dir() => {
+                    diagnostics.push(ConfigDiagnostic::new(
+                        key_path,
+                        format!("{:?} isn't an existing directory", path),
+                    ))
+                }
+                Ok((_, Some(permissions))) => {
+                    if let Err(message) = check_mapped_dir_restrictions(&permissions, is_memory_fs)
+                    {
+                        diagnostics.push(ConfigDiagnostic::new(key_path, message))
+                    }
+                }
+                Ok(_) => {}
+                Err(message) => diagnostics.push(ConfigDiagnostic::new(key_path, message)),
+            }
+        }
+    }
+}
+
+/// Returns description of a problem if the binary of the supplied host command can't be found.
+fn check_host_binary(host_cmd: &str) -> Option<String> {
+    let binary = match host_cmd.split_whitespace().next() {
+        Some(binary) => binary,
+        None => return Some(String::from("host command is empty")),
+    };
+
+    let binary_path = Path::new(binary);
+    if binary_path.components().count() > 1 {
+        if binary_path.is_file() {
+            return None;
+        }
+        return Some(format!("binary {:?} isn't found on the host", binary));
+    }
+
+    let found_in_path = std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).any(|dir| dir.join(binary).is_file()))
+        .unwrap_or(false);
+
+    if found_in_path {
+        None
+    } else {
+        Some(format!("binary {:?} isn't found in PATH", binary))
+    }
+}
@@ -0,0 +1,205 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::FaaSError;
+use crate::Result;
+use crate::IType;
+use crate::InstancePoolConfig;
+
+use fce::DeterministicConfig;
+use fce::FCEModuleConfig;
+use fce::ImportPolicy;
+use fce::InstanceMode;
+use fce::MappedDirPermissions;
+use fce::WASIFileSystem;
+use itertools::Itertools;
+use serde_derive::Serialize;
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Fully resolved config of loaded modules: it includes all defaults and everything added
+/// to the config after it was parsed, host imports are represented by their signatures.
+///
+/// Note: the order of fields is matter for TOML serialization, values must go before tables.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct FaaSEffectiveConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modules_dir: Option<PathBuf>,
+    /// Hex-encoded public keys modules must be signed with.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trusted_keys: Option<Vec<String>>,
+    pub module: Vec<FaaSEffectiveModuleConfig>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct FaaSEffectiveModuleConfig {
+    pub name: String,
+    /// Hash of the loaded module bytes in the `blake3:<hex digest>` form.
+    pub hash: String,
+    pub mem_pages_count: u32,
+    pub logger_enabled: bool,
+    pub logging_mask: i64,
+    pub instance_mode: InstanceMode,
+    pub lazy_instantiation: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub idle_timeout_secs: Option<u64>,
+    pub wasi: FaaSEffectiveWASIConfig,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deterministic: Option<DeterministicConfig>,
+    pub import_policy: ImportPolicy,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instance_pool: Option<InstancePoolConfig>,
+    pub host_imports: Vec<HostImportSignature>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct FaaSEffectiveWASIConfig {
+    pub version: String,
+    pub args: Vec<String>,
+    pub fs: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fs_seed: Option<PathBuf>,
+    pub preopened_files: Vec<PathBuf>,
+    pub envs: BTreeMap<String, String>,
+    pub mapped_dirs: BTreeMap<String, PathBuf>,
+    pub mapped_dirs_permissions: BTreeMap<String, MappedDirPermissions>,
+}
+
+/// Name and signature of a function exported from the host side, f.e. `(String) -> String`.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct HostImportSignature {
+    pub name: String,
+    pub signature: String,
+}
+
+impl FaaSEffectiveConfig {
+    pub fn to_toml_string(&self) -> Result<String> {
+        toml::to_string_pretty(self)
+            .map_err(|e| FaaSError::ConfigSerializationError(format!("{}", e)))
+    }
+
+    pub fn to_json_string(&self) -> Result<String> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| FaaSError::ConfigSerializationError(format!("{}", e)))
+    }
+}
+
+impl FaaSEffectiveModuleConfig {
+    pub(crate) fn new(
+        name: String,
+        hash: String,
+        logger_enabled: bool,
+        logging_mask: i64,
+        instance_pool: Option<InstancePoolConfig>,
+        fce_module_config: &FCEModuleConfig,
+    ) -> Self {
+        let envs = fce_module_config
+            .wasi_envs
+            .iter()
+            .map(|(name, value)| {
+                (
+                    String::from_utf8_lossy(name).to_string(),
+                    String::from_utf8_lossy(value).to_string(),
+                )
+            })
+            .collect();
+
+        let args = fce_module_config
+            .wasi_args
+            .iter()
+            .map(|arg| String::from_utf8_lossy(arg).to_string())
+            .collect();
+
+        let preopened_files = fce_module_config
+            .wasi_preopened_files
+            .iter()
+            .cloned()
+            .sorted()
+            .collect();
+
+        let mapped_dirs = fce_module_config
+            .wasi_mapped_dirs
+            .iter()
+            .map(|(alias, path)| (alias.clone(), path.clone()))
+            .collect();
+
+        let mapped_dirs_permissions = fce_module_config
+            .wasi_mapped_dirs_permissions
+            .iter()
+            .map(|(alias, permissions)| (alias.clone(), *permissions))
+            .collect();
+
+        let (fs, fs_seed) = match &fce_module_config.wasi_fs {
+            WASIFileSystem::Host => (String::from("host"), None),
+            WASIFileSystem::Memory { seed } => (String::from("memory"), seed.clone()),
+        };
+
+        let wasi = FaaSEffectiveWASIConfig {
+            version: format!("{:?}", fce_module_config.wasi_version),
+            args,
+            fs,
+            fs_seed,
+            preopened_files,
+            envs,
+            mapped_dirs,
+            mapped_dirs_permissions,
+        };
+
+        let host_imports = fce_module_config
+            .host_imports
+            .iter()
+            .map(|(name, descriptor)| HostImportSignature {
+                name: name.clone(),
+                signature: signature_text_view(
+                    &descriptor.argument_types,
+                    descriptor.output_type.as_ref(),
+                ),
+            })
+            .sorted_by(|lhs, rhs| lhs.name.cmp(&rhs.name))
+            .collect();
+
+        Self {
+            name,
+            hash,
+            mem_pages_count: fce_module_config.mem_pages_count,
+            logger_enabled,
+            logging_mask,
+            instance_mode: fce_module_config.instance_mode,
+            lazy_instantiation: fce_module_config.lazy_instantiation,
+            idle_timeout_secs: fce_module_config
+                .idle_timeout
+                .map(|idle_timeout| idle_timeout.as_secs()),
+            wasi,
+            deterministic: fce_module_config.deterministic,
+            import_policy: fce_module_config.import_policy.clone(),
+            instance_pool,
+            host_imports,
+        }
+    }
+}
+
+fn signature_text_view(argument_types: &[IType], output_type: Option<&IType>) -> String {
+    let arguments = argument_types
+        .iter()
+        .map(|ty| format!("{:?}", ty))
+        .join(", ");
+
+    match output_type {
+        Some(output_type) => format!("({}) -> {:?}", arguments, output_type),
+        None => format!("({})", arguments),
+    }
+}
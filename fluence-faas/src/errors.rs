@@ -48,8 +48,29 @@ pub enum FaaSError {
     /// Errors related to invalid config.
     ParseConfigError(toml::de::Error),
 
+    /// An error occurred while serializing a config.
+    ConfigSerializationError(String),
+
     /// FCE errors.
     EngineError(FCEError),
+
+    /// A module is unsigned or its signature doesn't match any of trusted keys.
+    ModuleSignatureError(String),
+
+    /// A module hash doesn't match the one pinned in the config.
+    ModuleHashMismatch(String),
+
+    /// A service bundle is malformed or its content doesn't match the manifest.
+    InvalidBundle(String),
+
+    /// A request can't be queued since the executor queue is full.
+    ExecutorQueueFull(String),
+
+    /// An executor thread has stopped, so it can't execute requests anymore.
+    ExecutorStopped(String),
+
+    /// A call panicked, the thread that executed it keeps serving other calls.
+    CallPanicked(String),
 }
 
 impl Error for FaaSError {}
@@ -73,6 +94,13 @@ impl std::fmt::Display for FaaSError {
             FaaSError::IOError(err_msg) => write!(f, "{}", err_msg),
             FaaSError::EngineError(err) => write!(f, "{}", err),
             FaaSError::ParseConfigError(err) => write!(f, "{}", err),
+            FaaSError::ConfigSerializationError(err_msg) => write!(f, "{}", err_msg),
+            FaaSError::ModuleSignatureError(err_msg) => write!(f, "{}", err_msg),
+            FaaSError::ModuleHashMismatch(err_msg) => write!(f, "{}", err_msg),
+            FaaSError::InvalidBundle(err_msg) => write!(f, "invalid bundle: {}", err_msg),
+            FaaSError::ExecutorQueueFull(err_msg) => write!(f, "{}", err_msg),
+            FaaSError::ExecutorStopped(err_msg) => write!(f, "{}", err_msg),
+            FaaSError::CallPanicked(err_msg) => write!(f, "{}", err_msg),
         }
     }
 }
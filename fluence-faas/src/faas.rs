@@ -14,46 +14,65 @@
  * limitations under the License.
  */
 
+use crate::bundle::ServiceBundle;
 use crate::config::FaaSConfig;
+use crate::effective_config::FaaSEffectiveConfig;
+use crate::effective_config::FaaSEffectiveModuleConfig;
 use crate::faas_interface::FaaSInterface;
+use crate::instance_pool::InstancePool;
+use crate::FaaSModuleStats;
 use crate::FaaSError;
 use crate::Result;
 use crate::IValue;
 use crate::IType;
 use crate::misc::load_modules_from_fs;
+use crate::misc::module_hash;
 use crate::misc::ModulesLoadStrategy;
 
 use fce::FCE;
 use fce::IFunctionArg;
 use fce_utils::SharedString;
 use fce::RecordTypes;
+use fce_module_signature::PublicKey;
 use fluence_sdk_main::CallParameters;
 
 use serde_json::Value as JValue;
-use std::cell::RefCell;
 use std::convert::TryInto;
 use std::collections::HashSet;
 use std::collections::HashMap;
-use std::rc::Rc;
+use std::path::Path;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::PoisonError;
 
 struct ModuleInterface {
-    function_signatures: HashMap<SharedString, (Rc<Vec<IFunctionArg>>, Rc<Vec<IType>>)>,
-    record_types: Rc<RecordTypes>,
+    function_signatures: HashMap<SharedString, (Arc<Vec<IFunctionArg>>, Arc<Vec<IType>>)>,
+    record_types: Arc<RecordTypes>,
 }
 
-// TODO: remove and use mutex instead
-unsafe impl Send for FluenceFaaS {}
-
+/// FluenceFaaS isn't Send, since FCE is bound to the thread that created it. To call it
+/// from other threads, create it on an `AsyncExecutor` thread (see `AsyncFluenceFaaS`)
+/// and send calls there. Stateless modules could be configured with an instance pool
+/// that is shared between threads by itself.
 pub struct FluenceFaaS {
     /// The Fluence Compute Engine instance.
     fce: FCE,
 
     /// Parameters of call accessible by Wasm modules.
-    call_parameters: Rc<RefCell<CallParameters>>,
+    call_parameters: Arc<Mutex<CallParameters>>,
 
     /// Cached module interfaces by names.
     module_interfaces_cache: HashMap<String, ModuleInterface>,
+
+    /// Pools of instances serving calls of modules configured with them.
+    instance_pools: HashMap<String, Arc<InstancePool>>,
+
+    /// Fully resolved config of loaded modules.
+    effective_config: FaaSEffectiveConfig,
+
+    /// If Some, only modules signed by one of these keys could be loaded.
+    trusted_keys: Option<Vec<PublicKey>>,
 }
 
 impl FluenceFaaS {
@@ -69,29 +88,68 @@ impl FluenceFaaS {
         C: TryInto<FaaSConfig>,
         FaaSError: From<C::Error>,
     {
-        let config = config.try_into()?;
+        let config: FaaSConfig = config.try_into()?;
+        let trusted_keys = config.trusted_keys.as_deref();
         let modules = config
             .modules_dir
             .as_ref()
             .map_or(Ok(HashMap::new()), |dir| {
-                load_modules_from_fs(dir, ModulesLoadStrategy::WasmOnly)
+                load_modules_from_fs(dir, ModulesLoadStrategy::WasmOnly, trusted_keys)
             })?;
 
-        Self::with_modules::<FaaSConfig>(modules, config)
+        Self::with_verified_modules(modules, config)
     }
 
-    /// Creates FaaS with given modules.
-    pub fn with_modules<C>(mut modules: HashMap<String, Vec<u8>>, config: C) -> Result<Self>
+    /// Creates FaaS from a service bundle, seed data of the local dir is ignored
+    /// since FaaS doesn't have one.
+    pub fn with_bundle<P: AsRef<Path>>(bundle_path: P) -> Result<Self> {
+        let bundle = ServiceBundle::load(bundle_path)?;
+        let config: crate::TomlFaaSConfig =
+            toml::from_str(&bundle.config).map_err(FaaSError::ParseConfigError)?;
+
+        Self::with_modules(bundle.modules, config)
+    }
+
+    /// Creates FaaS with given modules, if the config has trusted keys,
+    /// each module must have an embedded signature made by one of them.
+    pub fn with_modules<C>(modules: HashMap<String, Vec<u8>>, config: C) -> Result<Self>
     where
         C: TryInto<FaaSConfig>,
         FaaSError: From<C::Error>,
     {
-        let mut fce = FCE::new();
-        let config = config.try_into()?;
-        let call_parameters = Rc::new(RefCell::new(<_>::default()));
+        let config: FaaSConfig = config.try_into()?;
+        if let Some(trusted_keys) = &config.trusted_keys {
+            for (module_name, module_bytes) in modules.iter() {
+                crate::misc::verify_module(module_name, module_bytes, None, trusted_keys)?;
+            }
+        }
+
+        Self::with_verified_modules(modules, config)
+    }
+
+    fn with_verified_modules(
+        mut modules: HashMap<String, Vec<u8>>,
+        config: FaaSConfig,
+    ) -> Result<Self> {
+        let mut fce = match config.compilation_cache_dir {
+            Some(cache_dir) => FCE::with_compilation_cache(cache_dir),
+            None => FCE::new(),
+        };
+        let call_parameters = Arc::new(Mutex::new(<_>::default()));
 
         let modules_dir = config.modules_dir;
-        for (module_name, module_config) in config.modules_config {
+        let trusted_keys = config
+            .trusted_keys
+            .as_ref()
+            .map(|keys| keys.iter().map(|key| hex::encode(key.as_bytes())).collect());
+        let mut effective_config = FaaSEffectiveConfig {
+            modules_dir: modules_dir.clone(),
+            trusted_keys,
+            module: Vec::with_capacity(config.modules_config.len()),
+        };
+        let mut instance_pools = HashMap::new();
+
+        for (module_name, mut module_config) in config.modules_config {
             let module_bytes =
                 modules.remove(&module_name).ok_or_else(|| {
                     FaaSError::InstantiationError(format!(
@@ -100,11 +158,34 @@ impl FluenceFaaS {
                 ))
                 })?;
 
+            if let Some(expected_hash) = &module_config.hash {
+                crate::misc::check_module_hash(&module_name, &module_bytes, expected_hash)?;
+            }
+            let hash = module_hash(&module_bytes);
+
+            let logger_enabled = module_config.logger_enabled;
+            let logging_mask = module_config.logging_mask;
+            let instance_pool_config = module_config.instance_pool;
+            if let Some(instance_pool) =
+                Self::create_instance_pool(&fce, &module_name, &module_bytes, &mut module_config)?
+            {
+                instance_pools.insert(module_name.clone(), instance_pool);
+            }
+
             let fce_module_config = crate::misc::make_fce_config(
                 module_name.clone(),
                 Some(module_config),
                 call_parameters.clone(),
             )?;
+            effective_config.module.push(FaaSEffectiveModuleConfig::new(
+                module_name.clone(),
+                hash,
+                logger_enabled,
+                logging_mask,
+                instance_pool_config,
+                &fce_module_config,
+            ));
+
             fce.load_module(module_name, &module_bytes, fce_module_config)?;
         }
 
@@ -112,6 +193,9 @@ impl FluenceFaaS {
             fce,
             call_parameters,
             module_interfaces_cache: HashMap::new(),
+            instance_pools,
+            effective_config,
+            trusted_keys: config.trusted_keys,
         })
     }
 
@@ -121,15 +205,16 @@ impl FluenceFaaS {
         C: TryInto<FaaSConfig>,
         FaaSError: From<C::Error>,
     {
-        let config = config.try_into()?;
+        let config: FaaSConfig = config.try_into()?;
+        let trusted_keys = config.trusted_keys.as_deref();
         let modules = config
             .modules_dir
             .as_ref()
             .map_or(Ok(HashMap::new()), |dir| {
-                load_modules_from_fs(dir, ModulesLoadStrategy::Named(names))
+                load_modules_from_fs(dir, ModulesLoadStrategy::Named(names), trusted_keys)
             })?;
 
-        Self::with_modules::<FaaSConfig>(modules, config)
+        Self::with_verified_modules(modules, config)
     }
 
     /// Call a specified function of loaded on a startup module by its name.
@@ -140,7 +225,11 @@ impl FluenceFaaS {
         args: &[IValue],
         call_parameters: fluence_sdk_main::CallParameters,
     ) -> Result<Vec<IValue>> {
-        self.call_parameters.replace(call_parameters);
+        if let Some(instance_pool) = self.instance_pools.get(module_name.as_ref()) {
+            return instance_pool.call(func_name.as_ref(), args, call_parameters, None);
+        }
+
+        self.set_call_parameters(call_parameters);
 
         self.fce
             .call(module_name, func_name, args)
@@ -154,13 +243,67 @@ impl FluenceFaaS {
         func_name: FN,
         json_args: JValue,
         call_parameters: fluence_sdk_main::CallParameters,
+    ) -> Result<JValue> {
+        self.call_with_json_(
+            module_name.as_ref(),
+            func_name.as_ref(),
+            json_args,
+            call_parameters,
+            None,
+        )
+    }
+
+    /// Call a specified function of loaded on a startup module by its name,
+    /// the supplied buffer is used as the module stdin during this call.
+    pub fn call_with_ivalues_and_stdin<MN: AsRef<str>, FN: AsRef<str>>(
+        &mut self,
+        module_name: MN,
+        func_name: FN,
+        args: &[IValue],
+        call_parameters: fluence_sdk_main::CallParameters,
+        stdin: Vec<u8>,
+    ) -> Result<Vec<IValue>> {
+        if let Some(instance_pool) = self.instance_pools.get(module_name.as_ref()) {
+            return instance_pool.call(func_name.as_ref(), args, call_parameters, Some(stdin));
+        }
+
+        self.set_call_parameters(call_parameters);
+
+        self.fce
+            .call_with_stdin(module_name, func_name, args, stdin)
+            .map_err(Into::into)
+    }
+
+    /// Call a specified function of loaded on a startup module by its name,
+    /// the supplied buffer is used as the module stdin during this call.
+    pub fn call_with_json_and_stdin<MN: AsRef<str>, FN: AsRef<str>>(
+        &mut self,
+        module_name: MN,
+        func_name: FN,
+        json_args: JValue,
+        call_parameters: fluence_sdk_main::CallParameters,
+        stdin: Vec<u8>,
+    ) -> Result<JValue> {
+        self.call_with_json_(
+            module_name.as_ref(),
+            func_name.as_ref(),
+            json_args,
+            call_parameters,
+            Some(stdin),
+        )
+    }
+
+    fn call_with_json_(
+        &mut self,
+        module_name: &str,
+        func_name: &str,
+        json_args: JValue,
+        call_parameters: fluence_sdk_main::CallParameters,
+        stdin: Option<Vec<u8>>,
     ) -> Result<JValue> {
         use crate::misc::json_to_ivalues;
         use crate::misc::ivalues_to_json;
 
-        let module_name = module_name.as_ref();
-        let func_name = func_name.as_ref();
-
         let (func_signature, output_types, record_types) =
             self.lookup_module_interface(module_name, func_name)?;
         let iargs = json_to_ivalues(
@@ -169,8 +312,17 @@ impl FluenceFaaS {
             &record_types,
         )?;
 
-        self.call_parameters.replace(call_parameters);
-        let result = self.fce.call(module_name, func_name, &iargs)?;
+        let result = if let Some(instance_pool) = self.instance_pools.get(module_name) {
+            instance_pool.call(func_name, &iargs, call_parameters, stdin)?
+        } else {
+            self.set_call_parameters(call_parameters);
+            match stdin {
+                Some(stdin) => self
+                    .fce
+                    .call_with_stdin(module_name, func_name, &iargs, stdin)?,
+                None => self.fce.call(module_name, func_name, &iargs)?,
+            }
+        };
 
         ivalues_to_json(result, &output_types, &record_types)
     }
@@ -182,13 +334,121 @@ impl FluenceFaaS {
         FaaSInterface { modules }
     }
 
+    /// Return export functions (name and signatures) of the loaded module with given name.
+    pub fn get_module_interface<S: AsRef<str>>(
+        &self,
+        module_name: S,
+    ) -> Option<crate::FaaSModuleInterface<'_>> {
+        self.fce.module_interface(module_name)
+    }
+
+    /// Return the instance pool of a module configured with it, the pool could be shared
+    /// between threads to call the module concurrently.
+    pub fn instance_pool<S: AsRef<str>>(&self, module_name: S) -> Option<Arc<InstancePool>> {
+        self.instance_pools.get(module_name.as_ref()).cloned()
+    }
+
+    /// Return fully resolved config of loaded modules.
+    pub fn effective_config(&self) -> &FaaSEffectiveConfig {
+        &self.effective_config
+    }
+
+    /// Return a copy of the linear memory of a loaded module, the module is instantiated
+    /// if it isn't.
+    pub fn module_memory_snapshot<S: AsRef<str>>(&mut self, module_name: S) -> Result<Vec<u8>> {
+        self.fce
+            .module_memory_snapshot(module_name)
+            .map_err(Into::into)
+    }
+
+    /// Replace the linear memory of a loaded module with the snapshot.
+    pub fn restore_module_memory_snapshot<S: AsRef<str>>(
+        &mut self,
+        module_name: S,
+        snapshot: &[u8],
+    ) -> Result<()> {
+        self.fce
+            .restore_module_memory_snapshot(module_name, snapshot)
+            .map_err(Into::into)
+    }
+
+    /// Evict instances of modules idle longer than their idle timeouts, return their names.
+    /// Modules are checked after each call, so it's needed only if FaaS could stay
+    /// without calls for a long time.
+    pub fn evict_idle_modules(&mut self) -> Vec<String> {
+        self.fce.evict_idle_modules()
+    }
+
+    /// Return paths and sizes of all files of the in-memory filesystem of a loaded module.
+    pub fn module_memory_fs_files<S: AsRef<str>>(
+        &mut self,
+        module_name: S,
+    ) -> Result<Vec<(PathBuf, u64)>> {
+        self.fce
+            .module_memory_fs_files(module_name)
+            .map_err(Into::into)
+    }
+
+    /// Return content of a file of the in-memory filesystem of a loaded module.
+    pub fn read_module_memory_fs_file<S: AsRef<str>, P: AsRef<Path>>(
+        &mut self,
+        module_name: S,
+        path: P,
+    ) -> Result<Vec<u8>> {
+        self.fce
+            .read_module_memory_fs_file(module_name, path)
+            .map_err(Into::into)
+    }
+
+    /// Return runtime statistics of a loaded module, f.e. usage of its restricted mapped dirs.
+    pub fn module_stats<S: AsRef<str>>(&self, module_name: S) -> Result<FaaSModuleStats> {
+        let module_name = module_name.as_ref();
+
+        self.fce
+            .module_stats(module_name)
+            .ok_or_else(|| FaaSError::NoSuchModule(module_name.to_string()))
+    }
+
+    /// Creates a pool for a module configured with it. Calls of such a module are served
+    /// by the pool, so the module is made lazy to be only compiled by the main FCE for its
+    /// interface, it's instantiated there only if it's imported by other modules.
+    fn create_instance_pool(
+        fce: &FCE,
+        module_name: &str,
+        module_bytes: &[u8],
+        module_config: &mut crate::FaaSModuleConfig,
+    ) -> Result<Option<Arc<InstancePool>>> {
+        let instance_pool_config = match module_config.instance_pool {
+            Some(instance_pool_config) => instance_pool_config,
+            None => return Ok(None),
+        };
+
+        let instance_pool = InstancePool::new(
+            module_name,
+            module_bytes,
+            module_config,
+            instance_pool_config,
+            fce.compilation_cache_dir(),
+        )?;
+        module_config.lazy_instantiation = true;
+
+        Ok(Some(Arc::new(instance_pool)))
+    }
+
+    fn set_call_parameters(&self, call_parameters: CallParameters) {
+        *self
+            .call_parameters
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = call_parameters;
+    }
+
     /// At first, tries to find function signature and record types in module_interface_cache,
     /// if there is no them, tries to look
     fn lookup_module_interface<'faas>(
         &'faas mut self,
         module_name: &str,
         func_name: &str,
-    ) -> Result<(Rc<Vec<IFunctionArg>>, Rc<Vec<IType>>, Rc<RecordTypes>)> {
+    ) -> Result<(Arc<Vec<IFunctionArg>>, Arc<Vec<IType>>, Arc<RecordTypes>)> {
         use FaaSError::NoSuchModule;
         use FaaSError::MissingFunctionError;
 
@@ -222,7 +482,7 @@ impl FluenceFaaS {
 
         let arg_types = arg_types.clone();
         let output_types = output_types.clone();
-        let record_types = Rc::new(module_interface.record_types.clone());
+        let record_types = Arc::new(module_interface.record_types.clone());
 
         let module_interface = ModuleInterface {
             function_signatures,
@@ -245,28 +505,66 @@ impl FluenceFaaS {
         C: TryInto<crate::FaaSModuleConfig>,
         FaaSError: From<C::Error>,
     {
-        let config = config.map(|c| c.try_into()).transpose()?;
+        let mut config: Option<crate::FaaSModuleConfig> =
+            config.map(|c| c.try_into()).transpose()?;
         let name = name.into();
 
+        if let Some(trusted_keys) = &self.trusted_keys {
+            crate::misc::verify_module(&name, wasm_bytes, None, trusted_keys)?;
+        }
+
+        if let Some(expected_hash) = config.as_ref().and_then(|c| c.hash.as_ref()) {
+            crate::misc::check_module_hash(&name, wasm_bytes, expected_hash)?;
+        }
+        let hash = module_hash(wasm_bytes);
+
+        let (logger_enabled, logging_mask) =
+            config.as_ref().map_or((false, i64::max_value()), |c| {
+                (c.logger_enabled, c.logging_mask)
+            });
+        let instance_pool_config = config.as_ref().and_then(|c| c.instance_pool);
+        let instance_pool = match &mut config {
+            Some(config) => Self::create_instance_pool(&self.fce, &name, wasm_bytes, config)?,
+            None => None,
+        };
+
         let fce_module_config =
             crate::misc::make_fce_config(name.clone(), config, self.call_parameters.clone())?;
+        let effective_module_config = FaaSEffectiveModuleConfig::new(
+            name.clone(),
+            hash,
+            logger_enabled,
+            logging_mask,
+            instance_pool_config,
+            &fce_module_config,
+        );
+
         self.fce
-            .load_module(name, &wasm_bytes, fce_module_config)
-            .map_err(Into::into)
+            .load_module(name.clone(), &wasm_bytes, fce_module_config)?;
+        self.effective_config.module.push(effective_module_config);
+        if let Some(instance_pool) = instance_pool {
+            self.instance_pools.insert(name, instance_pool);
+        }
+
+        Ok(())
     }
 
     pub fn unload_module<S: AsRef<str>>(&mut self, module_name: S) -> Result<()> {
-        self.fce.unload_module(module_name).map_err(Into::into)
+        let module_name = module_name.as_ref();
+        self.fce.unload_module(module_name)?;
+        self.instance_pools.remove(module_name);
+        self.effective_config
+            .module
+            .retain(|module| module.name != module_name);
+
+        Ok(())
     }
 
+    /// Return WASI state of a module, it fails for a lazy module that hasn't been called yet.
     pub fn module_wasi_state<S: AsRef<str>>(
         &mut self,
         module_name: S,
     ) -> Result<&wasmer_wasi::state::WasiState> {
-        let module_name = module_name.as_ref();
-
-        self.fce
-            .module_wasi_state(module_name)
-            .ok_or_else(|| FaaSError::NoSuchModule(module_name.to_string()))
+        self.fce.module_wasi_state(module_name).map_err(Into::into)
     }
 }
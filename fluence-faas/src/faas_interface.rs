@@ -19,6 +19,7 @@ use super::IRecordType;
 use crate::FaaSModuleInterface;
 use crate::FaaSFunctionSignature;
 
+use fce::ModuleMetadata;
 use fce::RecordTypes;
 use serde::Serialize;
 use serde::Serializer;
@@ -26,7 +27,7 @@ use serde::Serializer;
 use std::fmt;
 use std::collections::HashMap;
 use std::collections::HashSet;
-use std::rc::Rc;
+use std::sync::Arc;
 use itertools::Itertools;
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -77,6 +78,13 @@ impl<'a> fmt::Display for FaaSInterface<'a> {
 
         for (name, module_interface) in self.modules.iter() {
             writeln!(f, "\n{}:", *name)?;
+            if let Some(metadata) = module_interface.metadata {
+                writeln!(
+                    f,
+                    "  // {} {}, source hash {}",
+                    metadata.name, metadata.version, metadata.source_hash
+                )?;
+            }
 
             for function_signature in module_interface.function_signatures.iter() {
                 write!(f, "  fn {}(", function_signature.name)?;
@@ -121,9 +129,9 @@ impl<'a> Serialize for FaaSInterface<'a> {
     {
         #[derive(Serialize)]
         pub struct FunctionSignature<'a> {
-            pub name: &'a Rc<String>,
+            pub name: &'a Arc<String>,
             pub arguments: Vec<(&'a String, &'a IType)>,
-            pub output_types: &'a Rc<Vec<IType>>,
+            pub output_types: &'a Arc<Vec<IType>>,
         }
 
         #[derive(Serialize)]
@@ -138,6 +146,8 @@ impl<'a> Serialize for FaaSInterface<'a> {
             pub name: &'a str,
             pub function_signatures: Vec<FunctionSignature<'a>>,
             pub record_types: Vec<RecordType<'a>>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            pub metadata: Option<&'a ModuleMetadata>,
         }
 
         #[derive(Serialize)]
@@ -161,7 +171,9 @@ impl<'a> Serialize for FaaSInterface<'a> {
             }
         }
 
-        fn serialize_record_type<'a, 'b>(record: (&'a u64, &'b Rc<IRecordType>)) -> RecordType<'b> {
+        fn serialize_record_type<'a, 'b>(
+            record: (&'a u64, &'b Arc<IRecordType>),
+        ) -> RecordType<'b> {
             let fields = record
                 .1
                 .fields
@@ -196,6 +208,7 @@ impl<'a> Serialize for FaaSInterface<'a> {
                     name,
                     function_signatures,
                     record_types,
+                    metadata: interface.metadata,
                 }
             })
             .collect();
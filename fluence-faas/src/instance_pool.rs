@@ -0,0 +1,540 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::FaaSError;
+use crate::FaaSModuleConfig;
+use crate::InstancePoolConfig;
+use crate::IValue;
+use crate::Result;
+
+use fce::FCEFunctionSignature;
+use fce::RecordTypes;
+use fce::FCE;
+use fluence_sdk_main::CallParameters;
+use serde_json::Value as JValue;
+
+use std::collections::HashMap;
+use std::panic::AssertUnwindSafe;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::mpsc::channel;
+use std::sync::mpsc::Receiver;
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::sync::Condvar;
+use std::sync::Mutex;
+use std::sync::PoisonError;
+
+type SlotRequest = Box<dyn FnOnce(&InstanceFactory, &mut Option<PooledInstance>) + Send>;
+
+/// Pre-instantiated instances of a stateless module serving calls concurrently: each call
+/// takes a free instance or waits until one of them is released.
+///
+/// The pool could be shared between threads, see `FluenceFaaS::instance_pool`. FCE isn't
+/// Send, so each instance is owned by a thread of its slot, and calls are sent to it. Instances
+/// are separate from other modules of FaaS, so a pooled module can't have host imports and
+/// can't import functions of other modules. An instance is discarded after a failed call,
+/// since a trap could leave it in an inconsistent state, and it's created again for the next
+/// call served by its slot.
+pub struct InstancePool {
+    module_name: String,
+    config: InstancePoolConfig,
+    function_signatures: HashMap<String, FCEFunctionSignature>,
+    record_types: RecordTypes,
+
+    /// Slots that aren't serving calls at the moment.
+    free_slots: Mutex<Vec<InstanceSlot>>,
+    slot_released: Condvar,
+}
+
+/// Thread that creates, calls and drops an instance of the pool, it stops when its slot
+/// is dropped.
+struct InstanceSlot {
+    requests: Sender<SlotRequest>,
+
+    /// False if the instance has been discarded after a failed call.
+    instantiated: bool,
+}
+
+/// Everything needed to create an instance of the pooled module, each slot has its own copy.
+struct InstanceFactory {
+    module_name: String,
+    module_bytes: Arc<Vec<u8>>,
+    module_config: FaaSModuleConfig,
+    reset_after_call: bool,
+    compilation_cache_dir: Option<PathBuf>,
+}
+
+/// FCE with a single instance of the pooled module.
+struct PooledInstance {
+    fce: FCE,
+    call_parameters: Arc<Mutex<CallParameters>>,
+
+    /// Memory of the instance right after instantiation, Some if it's reset after calls.
+    initial_memory: Option<Vec<u8>>,
+}
+
+/// Returns an acquired slot to the pool when dropped.
+struct SlotGuard<'pool> {
+    pool: &'pool InstancePool,
+    slot: Option<InstanceSlot>,
+}
+
+impl InstancePool {
+    pub(crate) fn new(
+        module_name: &str,
+        module_bytes: &[u8],
+        module_config: &FaaSModuleConfig,
+        config: InstancePoolConfig,
+        compilation_cache_dir: Option<&Path>,
+    ) -> Result<Self> {
+        if !module_config.host_imports.is_empty() {
+            return Err(FaaSError::InstantiationError(format!(
+                "module {} with an instance pool can't have host imports",
+                module_name
+            )));
+        }
+
+        let factory = InstanceFactory {
+            module_name: module_name.to_string(),
+            module_bytes: Arc::new(module_bytes.to_vec()),
+            module_config: copy_module_config(module_config),
+            reset_after_call: config.reset_after_call,
+            compilation_cache_dir: compilation_cache_dir.map(Path::to_path_buf),
+        };
+        let mut slots = (0..config.size)
+            .map(|slot_id| InstanceSlot::spawn(factory.copy(), slot_id))
+            .collect::<Result<Vec<_>>>()?;
+
+        // instances are created eagerly and concurrently by their slots
+        let interfaces = slots
+            .iter()
+            .map(|slot| {
+                slot.request(|factory, instance| {
+                    PooledInstance::get_or_create(instance, factory)
+                        .map(|instance| instance.interface(&factory.module_name))
+                })
+            })
+            .collect::<Vec<_>>();
+        let interfaces = interfaces
+            .into_iter()
+            .map(|interface| interface.recv().map_err(|_| slot_panicked(module_name))?)
+            .collect::<Result<Vec<_>>>()?;
+        for slot in slots.iter_mut() {
+            slot.instantiated = true;
+        }
+
+        // all instances are created from the same module, so they have the same interface
+        let (function_signatures, record_types) = interfaces
+            .into_iter()
+            .next()
+            .flatten()
+            .ok_or_else(|| FaaSError::NoSuchModule(module_name.to_string()))?;
+
+        Ok(Self {
+            module_name: module_name.to_string(),
+            config,
+            function_signatures,
+            record_types,
+            free_slots: Mutex::new(slots),
+            slot_released: Condvar::new(),
+        })
+    }
+
+    pub fn module_name(&self) -> &str {
+        &self.module_name
+    }
+
+    pub fn config(&self) -> InstancePoolConfig {
+        self.config
+    }
+
+    /// Returns count of slots that aren't serving calls at the moment, slots with discarded
+    /// instances are counted too.
+    pub fn free_instances_count(&self) -> usize {
+        self.lock_free_slots().len()
+    }
+
+    /// Calls a function of the module by a free instance, blocks while all instances are busy.
+    pub fn call_with_ivalues<FN: AsRef<str>>(
+        &self,
+        func_name: FN,
+        args: &[IValue],
+        call_parameters: CallParameters,
+    ) -> Result<Vec<IValue>> {
+        self.call(func_name.as_ref(), args, call_parameters, None)
+    }
+
+    /// Calls a function of the module by a free instance, blocks while all instances are busy.
+    pub fn call_with_json<FN: AsRef<str>>(
+        &self,
+        func_name: FN,
+        json_args: JValue,
+        call_parameters: CallParameters,
+    ) -> Result<JValue> {
+        use crate::misc::json_to_ivalues;
+        use crate::misc::ivalues_to_json;
+
+        let func_name = func_name.as_ref();
+        let signature = self
+            .function_signatures
+            .get(func_name)
+            .ok_or_else(|| FaaSError::MissingFunctionError(func_name.to_string()))?;
+
+        let iargs = json_to_ivalues(
+            json_args,
+            signature.arguments.iter().map(|arg| (&arg.name, &arg.ty)),
+            &self.record_types,
+        )?;
+        let result = self.call(func_name, &iargs, call_parameters, None)?;
+
+        ivalues_to_json(result, &signature.outputs, &self.record_types)
+    }
+
+    pub(crate) fn call(
+        &self,
+        func_name: &str,
+        args: &[IValue],
+        call_parameters: CallParameters,
+        stdin: Option<Vec<u8>>,
+    ) -> Result<Vec<IValue>> {
+        let mut guard = self.acquire();
+        let slot = guard
+            .slot
+            .as_mut()
+            .expect("slot is released only when the guard is dropped");
+
+        let request_func_name = func_name.to_string();
+        let args = args.to_vec();
+        let response = slot.request(move |factory, instance| {
+            let module_name = &factory.module_name;
+            let result = PooledInstance::get_or_create(instance, factory).and_then(|pooled| {
+                pooled.call(
+                    module_name,
+                    &request_func_name,
+                    &args,
+                    call_parameters,
+                    stdin,
+                )
+            });
+
+            // the instance is created again by the next call served by this slot,
+            // a failed reset doesn't affect the result of an already finished call
+            match (&result, instance.as_mut()) {
+                (Ok(_), Some(pooled)) => {
+                    if let Err(e) = pooled.reset(module_name) {
+                        log::warn!(
+                            "instance of pooled module {} is discarded, memory reset failed: {}",
+                            module_name,
+                            e
+                        );
+                        *instance = None;
+                    }
+                }
+                _ => *instance = None,
+            }
+
+            (result, instance.is_some())
+        });
+
+        match response.recv() {
+            Ok((result, instantiated)) => {
+                slot.instantiated = instantiated;
+                result
+            }
+            // the slot thread discards the instance of a panicked call
+            Err(_) => {
+                slot.instantiated = false;
+                Err(FaaSError::CallPanicked(format!(
+                    "call of {} of pooled module {} panicked",
+                    func_name, self.module_name
+                )))
+            }
+        }
+    }
+
+    fn acquire(&self) -> SlotGuard<'_> {
+        let mut free_slots = self.lock_free_slots();
+        loop {
+            if let Some(slot) = free_slots.pop() {
+                return SlotGuard {
+                    pool: self,
+                    slot: Some(slot),
+                };
+            }
+
+            free_slots = self
+                .slot_released
+                .wait(free_slots)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    fn release(&self, slot: InstanceSlot) {
+        self.lock_free_slots().push(slot);
+        self.slot_released.notify_one();
+    }
+
+    fn lock_free_slots(&self) -> std::sync::MutexGuard<'_, Vec<InstanceSlot>> {
+        self.free_slots
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl InstanceSlot {
+    fn spawn(factory: InstanceFactory, slot_id: usize) -> Result<Self> {
+        let (requests, requests_receiver) = channel::<SlotRequest>();
+
+        std::thread::Builder::new()
+            .name(format!("{}-pool-{}", factory.module_name, slot_id))
+            .spawn(move || {
+                let mut instance = None;
+                for request in requests_receiver {
+                    let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+                        request(&factory, &mut instance)
+                    }));
+                    // the state of an instance is unknown after a panic, the response sender
+                    // has been dropped with the request, so the caller gets an error
+                    if result.is_err() {
+                        instance = None;
+                    }
+                }
+            })?;
+
+        Ok(Self {
+            requests,
+            instantiated: false,
+        })
+    }
+
+    /// Sends a request to the slot thread, the returned receiver gets its result.
+    fn request<R, F>(&self, request: F) -> Receiver<R>
+    where
+        R: Send + 'static,
+        F: FnOnce(&InstanceFactory, &mut Option<PooledInstance>) -> R + Send + 'static,
+    {
+        let (response_sender, response) = channel();
+        let request: SlotRequest = Box::new(
+            move |factory: &InstanceFactory, instance: &mut Option<PooledInstance>| {
+                let _ = response_sender.send(request(factory, instance));
+            },
+        );
+
+        // a slot thread stops only when its slot is dropped
+        self.requests
+            .send(request)
+            .expect("slot thread should be running while the slot exists");
+
+        response
+    }
+}
+
+impl InstanceFactory {
+    fn copy(&self) -> Self {
+        Self {
+            module_name: self.module_name.clone(),
+            module_bytes: self.module_bytes.clone(),
+            module_config: copy_module_config(&self.module_config),
+            reset_after_call: self.reset_after_call,
+            compilation_cache_dir: self.compilation_cache_dir.clone(),
+        }
+    }
+}
+
+impl PooledInstance {
+    fn new(factory: &InstanceFactory) -> Result<Self> {
+        let mut fce = match &factory.compilation_cache_dir {
+            Some(cache_dir) => FCE::with_compilation_cache(cache_dir),
+            None => FCE::new(),
+        };
+        let call_parameters = Arc::new(Mutex::new(<_>::default()));
+
+        let fce_module_config = crate::misc::make_fce_config(
+            factory.module_name.clone(),
+            Some(copy_module_config(&factory.module_config)),
+            call_parameters.clone(),
+        )?;
+        fce.load_module(
+            &factory.module_name,
+            &factory.module_bytes,
+            fce_module_config,
+        )?;
+
+        let initial_memory = if factory.reset_after_call {
+            Some(fce.module_memory_snapshot(&factory.module_name)?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            fce,
+            call_parameters,
+            initial_memory,
+        })
+    }
+
+    /// Returns the instance of a slot, creates it if the slot is empty.
+    fn get_or_create<'slot>(
+        instance: &'slot mut Option<PooledInstance>,
+        factory: &InstanceFactory,
+    ) -> Result<&'slot mut PooledInstance> {
+        if instance.is_none() {
+            *instance = Some(PooledInstance::new(factory)?);
+        }
+
+        Ok(instance
+            .as_mut()
+            .expect("instance is created if the slot is empty"))
+    }
+
+    fn interface(
+        &self,
+        module_name: &str,
+    ) -> Option<(HashMap<String, FCEFunctionSignature>, RecordTypes)> {
+        let interface = self.fce.module_interface(module_name)?;
+        let function_signatures = interface
+            .function_signatures
+            .into_iter()
+            .map(|signature| (signature.name.to_string(), signature))
+            .collect();
+
+        Some((function_signatures, interface.record_types.clone()))
+    }
+
+    fn call(
+        &mut self,
+        module_name: &str,
+        func_name: &str,
+        args: &[IValue],
+        call_parameters: CallParameters,
+        stdin: Option<Vec<u8>>,
+    ) -> Result<Vec<IValue>> {
+        *self
+            .call_parameters
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = call_parameters;
+
+        let result = match stdin {
+            Some(stdin) => self
+                .fce
+                .call_with_stdin(module_name, func_name, args, stdin),
+            None => self.fce.call(module_name, func_name, args),
+        };
+
+        result.map_err(Into::into)
+    }
+
+    /// Restores the memory of the instance to its state right after instantiation,
+    /// if the pool is configured to do it after calls.
+    fn reset(&mut self, module_name: &str) -> Result<()> {
+        if let Some(initial_memory) = &self.initial_memory {
+            self.fce
+                .restore_module_memory_snapshot(module_name, initial_memory)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for SlotGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(slot) = self.slot.take() {
+            self.pool.release(slot);
+        }
+    }
+}
+
+fn slot_panicked(module_name: &str) -> FaaSError {
+    FaaSError::CallPanicked(format!(
+        "instance of pooled module {} panicked while it was created",
+        module_name
+    ))
+}
+
+/// Copies a config of a module without host imports, pooled modules don't have them.
+/// Instances of a pool are created eagerly and never evicted.
+fn copy_module_config(config: &FaaSModuleConfig) -> FaaSModuleConfig {
+    FaaSModuleConfig {
+        mem_pages_count: config.mem_pages_count,
+        logger_enabled: config.logger_enabled,
+        host_imports: HashMap::new(),
+        wasi: config.wasi.clone(),
+        logging_mask: config.logging_mask,
+        deterministic: config.deterministic,
+        import_policy: config.import_policy.clone(),
+        hash: config.hash.clone(),
+        instance_pool: config.instance_pool,
+        instance_mode: config.instance_mode,
+        lazy_instantiation: false,
+        idle_timeout: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InstancePool;
+    use crate::FaaSModuleConfig;
+    use crate::InstancePoolConfig;
+    use crate::IValue;
+
+    const POOL_SIZE: usize = 2;
+
+    fn call_parameters_pool() -> InstancePool {
+        let module_bytes =
+            std::fs::read("../examples/call_parameters/artifacts/call_parameters.wasm")
+                .expect("call_parameters example should be built");
+        let config = InstancePoolConfig {
+            size: POOL_SIZE,
+            reset_after_call: false,
+        };
+
+        InstancePool::new(
+            "call_parameters",
+            &module_bytes,
+            &FaaSModuleConfig::default(),
+            config,
+            None,
+        )
+        .unwrap_or_else(|e| panic!("instance pool should be created: {:?}", e))
+    }
+
+    fn discarded_instances_count(pool: &InstancePool) -> usize {
+        pool.lock_free_slots()
+            .iter()
+            .filter(|slot| !slot.instantiated)
+            .count()
+    }
+
+    #[test]
+    fn instance_is_recreated_after_failed_call() {
+        let pool = call_parameters_pool();
+
+        let failed_call = pool.call("non_existent", &[], <_>::default(), None);
+        let discarded_after_failure = discarded_instances_count(&pool);
+        let calls = (0..POOL_SIZE)
+            .map(|_| pool.call("call_parameters", &[], <_>::default(), None))
+            .collect::<Vec<_>>();
+
+        assert!(failed_call.is_err());
+        assert_eq!(discarded_after_failure, 1);
+        for call in calls {
+            assert_eq!(call.unwrap(), vec![IValue::String(String::from("\n\n"))]);
+        }
+        assert_eq!(discarded_instances_count(&pool), 0);
+        assert_eq!(pool.free_instances_count(), POOL_SIZE);
+    }
+}
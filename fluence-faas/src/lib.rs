@@ -24,30 +24,62 @@
     unreachable_patterns
 )]
 
+mod async_executor;
+mod async_faas;
+mod bundle;
 mod config;
+mod config_validation;
+mod effective_config;
 mod errors;
 mod faas;
 mod faas_interface;
+mod instance_pool;
 mod misc;
 mod raw_toml_config;
 
 pub(crate) type Result<T> = std::result::Result<T, FaaSError>;
 
 pub use faas::FluenceFaaS;
+pub use async_executor::AsyncExecutor;
+pub use async_executor::ExecutionFuture;
+pub use async_faas::AsyncFluenceFaaS;
+pub use instance_pool::InstancePool;
 pub use faas_interface::FaaSInterface;
+pub use misc::BundleManifest;
+pub use bundle::ServiceBundle;
 
 pub use config::FaaSConfig;
 pub use config::FaaSModuleConfig;
 pub use config::FaaSWASIConfig;
+pub use config::InstancePoolConfig;
+pub use config_validation::ConfigDiagnostic;
+pub use effective_config::FaaSEffectiveConfig;
+pub use effective_config::FaaSEffectiveModuleConfig;
+pub use effective_config::FaaSEffectiveWASIConfig;
+pub use effective_config::HostImportSignature;
 
 pub use raw_toml_config::TomlFaaSConfig;
 pub use raw_toml_config::TomlFaaSModuleConfig;
 pub use raw_toml_config::TomlFaaSNamedModuleConfig;
+pub use raw_toml_config::TomlDeterministicConfig;
+pub use raw_toml_config::TomlImportPolicy;
+pub use raw_toml_config::TomlInstancePoolConfig;
 pub use raw_toml_config::TomlWASIConfig;
 pub use raw_toml_config::from_toml_faas_config;
 pub use raw_toml_config::from_toml_module_config;
 pub use raw_toml_config::from_toml_named_module_config;
 pub use raw_toml_config::from_toml_wasi_config;
+pub use raw_toml_config::from_toml_deterministic_config;
+pub use raw_toml_config::from_toml_import_policy;
+pub use raw_toml_config::from_toml_instance_pool_config;
+pub use raw_toml_config::parse_bytes_value;
+pub use misc::load_toml_config;
+pub use misc::load_toml_value;
+pub use misc::module_hash;
+pub use misc::parse_module_hash;
+pub use misc::collect_dir_files;
+pub use misc::read_hashed_archive;
+pub use misc::write_hashed_archive;
 
 pub use errors::FaaSError;
 
@@ -58,6 +90,15 @@ pub use fce::IFunctionArg;
 pub use fce::IType;
 pub use fce::FCEModuleInterface as FaaSModuleInterface;
 pub use fce::FCEFunctionSignature as FaaSFunctionSignature;
+pub use fce::FCEModuleStats as FaaSModuleStats;
+pub use fce::MappedDirPermissions;
+pub use fce::DeterministicConfig;
+pub use fce::ImportPolicy;
+pub use fce::InstanceMode;
+pub use fce::ModuleMetadata;
+pub use fce_module_signature::PublicKey;
+pub use fce::MappedDirUsage;
+pub use fce::WASIFileSystem;
 pub use fce::HostExportedFunc;
 pub use fce::HostImportDescriptor;
 pub use fce::HostImportError;
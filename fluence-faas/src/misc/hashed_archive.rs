@@ -0,0 +1,359 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::FaaSError;
+use crate::Result;
+use crate::misc::module_hash;
+
+use serde_derive::Deserialize;
+use serde_derive::Serialize;
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+
+const MANIFEST_FILE_NAME: &str = "manifest.json";
+
+/// Describes content of an archive, all files except the manifest itself must be listed.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BundleManifest {
+    pub format_version: u32,
+
+    /// Paths of files in the bundle with their hashes in the `blake3:<hex digest>` form.
+    pub files: BTreeMap<String, String>,
+}
+
+/// Writes files to a tar archive together with the manifest containing their hashes.
+pub fn write_hashed_archive(
+    archive_path: &Path,
+    format_version: u32,
+    files: BTreeMap<PathBuf, Vec<u8>>,
+) -> Result<()> {
+    let manifest = BundleManifest {
+        format_version,
+        files: files
+            .iter()
+            .map(|(path, content)| (to_archive_path(path), module_hash(content)))
+            .collect(),
+    };
+    let manifest = serde_json::to_vec_pretty(&manifest)
+        .map_err(|e| FaaSError::ConfigSerializationError(format!("{}", e)))?;
+
+    let archive_file = std::fs::File::create(archive_path)
+        .map_err(|e| FaaSError::IOError(format!("{:?}: {}", archive_path, e)))?;
+    let mut builder = tar::Builder::new(archive_file);
+    append_file(&mut builder, MANIFEST_FILE_NAME, &manifest)?;
+    for (path, content) in files {
+        append_file(&mut builder, &to_archive_path(&path), &content)?;
+    }
+    builder.finish()?;
+
+    Ok(())
+}
+
+/// Reads an archive written by `write_hashed_archive` and checks that its format version
+/// and content match the manifest, returns files by their `/`-separated paths.
+pub fn read_hashed_archive(
+    archive_path: &Path,
+    format_version: u32,
+) -> Result<HashMap<String, Vec<u8>>> {
+    let archive_file = std::fs::File::open(archive_path)
+        .map_err(|e| FaaSError::IOError(format!("{:?}: {}", archive_path, e)))?;
+
+    let mut files = read_archive(archive_file)?;
+    let manifest = files
+        .remove(MANIFEST_FILE_NAME)
+        .ok_or_else(|| invalid_bundle(format!("{} is missing", MANIFEST_FILE_NAME)))?;
+    let manifest: BundleManifest = serde_json::from_slice(&manifest)
+        .map_err(|e| invalid_bundle(format!("{} is malformed: {}", MANIFEST_FILE_NAME, e)))?;
+
+    check_manifest(&manifest, format_version, &files)?;
+
+    Ok(files)
+}
+
+/// Adds all files of the dir to files with paths relative to the dir prefixed by archive_dir.
+pub fn collect_dir_files(
+    dir: &Path,
+    archive_dir: &Path,
+    files: &mut BTreeMap<PathBuf, Vec<u8>>,
+) -> Result<()> {
+    collect_files(dir, dir, archive_dir, files)
+}
+
+fn read_archive(archive_file: std::fs::File) -> Result<HashMap<String, Vec<u8>>> {
+    let mut archive = tar::Archive::new(archive_file);
+    let mut files = HashMap::new();
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if entry.header().entry_type().is_dir() {
+            continue;
+        }
+        if !entry.header().entry_type().is_file() {
+            return Err(invalid_bundle(format!(
+                "{:?} isn't a regular file",
+                entry.path()?
+            )));
+        }
+
+        let path = entry.path()?.to_path_buf();
+        if path
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_)))
+        {
+            return Err(invalid_bundle(format!("{:?} isn't a relative path", path)));
+        }
+
+        let mut content = Vec::new();
+        entry.read_to_end(&mut content)?;
+        files.insert(to_archive_path(&path), content);
+    }
+
+    Ok(files)
+}
+
+fn check_manifest(
+    manifest: &BundleManifest,
+    format_version: u32,
+    files: &HashMap<String, Vec<u8>>,
+) -> Result<()> {
+    if manifest.format_version != format_version {
+        return Err(invalid_bundle(format!(
+            "unsupported format version {}, expected {}",
+            manifest.format_version, format_version
+        )));
+    }
+
+    for (path, content) in files {
+        match manifest.files.get(path) {
+            Some(hash) if *hash == module_hash(content) => {}
+            Some(_) => return Err(invalid_bundle(format!("{} is corrupted", path))),
+            None => {
+                return Err(invalid_bundle(format!(
+                    "{} isn't listed in the manifest",
+                    path
+                )))
+            }
+        }
+    }
+
+    if let Some(path) = manifest
+        .files
+        .keys()
+        .find(|path| !files.contains_key(*path))
+    {
+        return Err(invalid_bundle(format!("{} is missing", path)));
+    }
+
+    Ok(())
+}
+
+fn collect_files(
+    root_dir: &Path,
+    dir: &Path,
+    archive_dir: &Path,
+    files: &mut BTreeMap<PathBuf, Vec<u8>>,
+) -> Result<()> {
+    let entries =
+        std::fs::read_dir(dir).map_err(|e| FaaSError::IOError(format!("{:?}: {}", dir, e)))?;
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(root_dir, &path, archive_dir, files)?;
+            continue;
+        }
+
+        let content = std::fs::read(&path)?;
+        // unwrap is safe here because path is obtained by walking root_dir
+        let relative_path = path.strip_prefix(root_dir).unwrap();
+        files.insert(archive_dir.join(relative_path), content);
+    }
+
+    Ok(())
+}
+
+fn append_file(
+    builder: &mut tar::Builder<std::fs::File>,
+    path: &str,
+    content: &[u8],
+) -> Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(content.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder.append_data(&mut header, path, content)?;
+
+    Ok(())
+}
+
+/// Paths in archives and the manifest always use `/` as a separator.
+fn to_archive_path(path: &Path) -> String {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn invalid_bundle(message: String) -> FaaSError {
+    FaaSError::InvalidBundle(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::read_hashed_archive;
+    use super::write_hashed_archive;
+    use super::BundleManifest;
+    use super::MANIFEST_FILE_NAME;
+    use crate::FaaSError;
+    use crate::Result;
+    use crate::misc::module_hash;
+
+    use std::collections::BTreeMap;
+    use std::path::PathBuf;
+
+    const FORMAT_VERSION: u32 = 1;
+
+    fn archive_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("fce_archive_{}_{}.tar", name, std::process::id()))
+    }
+
+    /// Writes entries as is, so paths aren't checked and the manifest could be arbitrary.
+    fn write_raw_archive(
+        name: &str,
+        manifest: &BundleManifest,
+        files: &[(&str, &[u8])],
+    ) -> PathBuf {
+        let manifest = serde_json::to_vec(manifest).unwrap();
+        let entries =
+            std::iter::once((MANIFEST_FILE_NAME, manifest.as_slice())).chain(files.iter().copied());
+
+        let path = archive_path(name);
+        let mut builder = tar::Builder::new(std::fs::File::create(&path).unwrap());
+        for (entry_path, content) in entries {
+            let mut header = tar::Header::new_gnu();
+            header.as_gnu_mut().unwrap().name[..entry_path.len()]
+                .copy_from_slice(entry_path.as_bytes());
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append(&header, content).unwrap();
+        }
+        builder.finish().unwrap();
+
+        path
+    }
+
+    fn manifest(files: &[(&str, &[u8])]) -> BundleManifest {
+        BundleManifest {
+            format_version: FORMAT_VERSION,
+            files: files
+                .iter()
+                .map(|(path, content)| (path.to_string(), module_hash(content)))
+                .collect(),
+        }
+    }
+
+    fn read_and_remove(path: PathBuf) -> Result<Vec<(String, Vec<u8>)>> {
+        let files = read_hashed_archive(&path, FORMAT_VERSION);
+        std::fs::remove_file(path).unwrap();
+
+        let mut files = files?.into_iter().collect::<Vec<_>>();
+        files.sort();
+        Ok(files)
+    }
+
+    fn is_invalid_bundle<T>(result: Result<T>) -> bool {
+        matches!(result, Err(FaaSError::InvalidBundle(_)))
+    }
+
+    #[test]
+    fn archive_round_trip() {
+        let mut files = BTreeMap::new();
+        files.insert(
+            PathBuf::from("Config.toml"),
+            b"modules_dir = \"modules\"".to_vec(),
+        );
+        files.insert(
+            PathBuf::from("modules").join("greeting.wasm"),
+            vec![0, 1, 2],
+        );
+
+        let path = archive_path("round_trip");
+        write_hashed_archive(&path, FORMAT_VERSION, files).unwrap();
+
+        assert_eq!(
+            read_and_remove(path).unwrap(),
+            vec![
+                (
+                    String::from("Config.toml"),
+                    b"modules_dir = \"modules\"".to_vec()
+                ),
+                (String::from("modules/greeting.wasm"), vec![0, 1, 2]),
+            ]
+        );
+    }
+
+    #[test]
+    fn tampered_file_is_rejected() {
+        let manifest = manifest(&[("modules/greeting.wasm", b"original")]);
+        let path = write_raw_archive(
+            "tampered",
+            &manifest,
+            &[("modules/greeting.wasm", b"tampered")],
+        );
+
+        assert!(is_invalid_bundle(read_and_remove(path)));
+    }
+
+    #[test]
+    fn unlisted_and_missing_files_are_rejected() {
+        let manifest = manifest(&[("modules/greeting.wasm", b"module")]);
+
+        let path = write_raw_archive(
+            "unlisted",
+            &manifest,
+            &[
+                ("modules/greeting.wasm", b"module"),
+                ("modules/other.wasm", b"other"),
+            ],
+        );
+        assert!(is_invalid_bundle(read_and_remove(path)));
+
+        let path = write_raw_archive("missing", &manifest, &[]);
+        assert!(is_invalid_bundle(read_and_remove(path)));
+    }
+
+    #[test]
+    fn parent_dir_paths_are_rejected() {
+        let files: &[(&str, &[u8])] = &[("data/../../evil", b"evil")];
+        let path = write_raw_archive("parent_dir", &manifest(files), files);
+
+        assert!(is_invalid_bundle(read_and_remove(path)));
+    }
+
+    #[test]
+    fn other_format_version_is_rejected() {
+        let mut manifest = manifest(&[]);
+        manifest.format_version = FORMAT_VERSION + 1;
+        let path = write_raw_archive("format_version", &manifest, &[]);
+
+        assert!(is_invalid_bundle(read_and_remove(path)));
+    }
+}
@@ -14,17 +14,30 @@
  * limitations under the License.
  */
 
+mod hashed_archive;
 mod ivalues_to_json;
 mod json_to_ivalues;
 mod log_utf8_string_impl;
+mod module_hash;
 mod modules_load_strategy;
+mod toml_preprocessor;
 mod utils;
 
+pub use hashed_archive::BundleManifest;
+pub use hashed_archive::collect_dir_files;
+pub use hashed_archive::read_hashed_archive;
+pub use hashed_archive::write_hashed_archive;
 pub(crate) use ivalues_to_json::ivalues_to_json;
 pub(crate) use json_to_ivalues::json_to_ivalues;
 pub(crate) use modules_load_strategy::ModulesLoadStrategy;
+pub use module_hash::module_hash;
+pub(crate) use module_hash::check_module_hash;
+pub use module_hash::parse_module_hash;
+pub use toml_preprocessor::load_toml_config;
+pub use toml_preprocessor::load_toml_value;
 pub(crate) use utils::create_host_import;
 pub(crate) use utils::make_fce_config;
 pub(crate) use utils::load_modules_from_fs;
+pub(crate) use utils::verify_module;
 
 pub(self) use log_utf8_string_impl::log_utf8_string_closure;
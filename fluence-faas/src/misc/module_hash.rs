@@ -0,0 +1,117 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::FaaSError;
+use crate::Result;
+
+/// Prefix of a module hash that names the hash function.
+const MODULE_HASH_PREFIX: &str = "blake3:";
+
+/// Returns the module hash in the form used in configs, f.e. `blake3:af1349b9...`.
+pub fn module_hash(module_bytes: &[u8]) -> String {
+    format!(
+        "{}{}",
+        MODULE_HASH_PREFIX,
+        blake3::hash(module_bytes).to_hex()
+    )
+}
+
+/// Checks that the hash has the `blake3:<hex digest>` form and returns it in lowercase.
+pub fn parse_module_hash(hash: &str) -> std::result::Result<String, String> {
+    let digest = hash.strip_prefix(MODULE_HASH_PREFIX).ok_or_else(|| {
+        format!(
+            r#"unsupported hash "{}", expected "{}<hex digest>""#,
+            hash, MODULE_HASH_PREFIX
+        )
+    })?;
+
+    let is_valid_digest =
+        digest.len() == 2 * blake3::OUT_LEN && digest.chars().all(|c| c.is_ascii_hexdigit());
+    if !is_valid_digest {
+        return Err(format!(
+            r#""{}" isn't a valid blake3 digest, expected {} hex characters"#,
+            digest,
+            2 * blake3::OUT_LEN
+        ));
+    }
+
+    Ok(format!("{}{}", MODULE_HASH_PREFIX, digest.to_lowercase()))
+}
+
+/// Checks that the module bytes have the expected hash.
+pub(crate) fn check_module_hash(
+    module_name: &str,
+    module_bytes: &[u8],
+    expected_hash: &str,
+) -> Result<()> {
+    let actual_hash = module_hash(module_bytes);
+    if actual_hash == expected_hash {
+        return Ok(());
+    }
+
+    Err(FaaSError::ModuleHashMismatch(format!(
+        r#"module "{}" has hash {}, but {} is expected by the config"#,
+        module_name, actual_hash, expected_hash
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check_module_hash;
+    use super::module_hash;
+    use super::parse_module_hash;
+    use crate::FaaSError;
+
+    const DIGEST: &str = "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262";
+
+    #[test]
+    fn hash_is_normalized_to_lowercase() {
+        let hash = format!("blake3:{}", DIGEST);
+        let uppercase_hash = format!("blake3:{}", DIGEST.to_uppercase());
+
+        assert_eq!(parse_module_hash(&hash), Ok(hash.clone()));
+        assert_eq!(parse_module_hash(&uppercase_hash), Ok(hash));
+    }
+
+    #[test]
+    fn malformed_hashes_are_rejected() {
+        let malformed_hashes = vec![
+            format!("sha256:{}", DIGEST),
+            format!("BLAKE3:{}", DIGEST),
+            DIGEST.to_string(),
+            format!("blake3:{}", &DIGEST[1..]),
+            format!("blake3:{}0", DIGEST),
+            format!("blake3:{}g", &DIGEST[1..]),
+        ];
+
+        for hash in malformed_hashes {
+            assert!(parse_module_hash(&hash).is_err(), "{} is accepted", hash);
+        }
+    }
+
+    #[test]
+    fn module_hash_is_checked() {
+        let module_bytes = b"\0asm\x01\0\0\0";
+        let hash = module_hash(module_bytes);
+
+        assert_eq!(parse_module_hash(&hash), Ok(hash.clone()));
+        assert!(check_module_hash("module", module_bytes, &hash).is_ok());
+        assert!(matches!(
+            check_module_hash("module", b"\0asm\x02\0\0\0", &hash),
+            Err(FaaSError::ModuleHashMismatch(_))
+        ));
+    }
+}
@@ -0,0 +1,713 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::FaaSError;
+use crate::Result;
+
+use serde::de::DeserializeOwned;
+use toml::Value;
+use toml::value::Table;
+
+use std::collections::HashMap;
+use std::env::VarError;
+use std::path::Path;
+use std::path::PathBuf;
+
+const INCLUDE_KEY: &str = "include";
+const MODULE_KEY: &str = "module";
+const MODULE_NAME_KEY: &str = "name";
+
+/// Maps key paths of a loaded config (like `module[1].wasi.envs`) to files they come from.
+type KeySources = HashMap<String, PathBuf>;
+
+/// Loads a TOML config from filesystem and preprocesses it by:
+///  1. substituting `${VAR}` and `${VAR:-default}` in all string values
+///     with values of the corresponding environment variables
+///  2. resolving `include = ["base.toml", ...]` relative to the including file
+///     and merging included configs in the order they are listed, the including
+///     config is merged last, so it always overrides its includes.
+///
+/// Merge rules: tables are merged recursively, `[[module]]` entries with the same name
+/// are merged into the first occurrence (preserving the modules order) and new ones are
+/// appended, all other values are simply replaced.
+pub fn load_toml_value<P: AsRef<Path>>(path: P) -> Result<Value> {
+    let mut include_stack = Vec::new();
+    let (config, _) = load_with_includes(path.as_ref(), &mut include_stack)?;
+    Ok(Value::Table(config))
+}
+
+/// Loads a TOML config like `load_toml_value` and deserializes it, errors are reported
+/// with the key that can't be deserialized and the (possibly included) file it comes from.
+pub fn load_toml_config<T: DeserializeOwned, P: AsRef<Path>>(path: P) -> Result<T> {
+    let path = path.as_ref();
+    let mut include_stack = Vec::new();
+    let (config, sources) = load_with_includes(path, &mut include_stack)?;
+
+    deserialize_config(config).map_err(|(e, key)| {
+        let source = key.as_ref().and_then(|key| key_source(&sources, key));
+        let message = match key.as_ref().zip(source) {
+            Some((key, source)) => format!(
+                r#"Error parsing config {:?}: key "{}" from {:?}: {}"#,
+                path, key, source, e
+            ),
+            None => format!("Error parsing config {:?}: {}", path, e),
+        };
+        FaaSError::ConfigParseError(message)
+    })
+}
+
+fn load_with_includes(
+    path: &Path,
+    include_stack: &mut Vec<PathBuf>,
+) -> Result<(Table, KeySources)> {
+    use FaaSError::ConfigParseError;
+    use FaaSError::IOError;
+
+    let canonical_path = path
+        .canonicalize()
+        .map_err(|e| IOError(format!("{:?}: {}", path, e)))?;
+    if include_stack.contains(&canonical_path) {
+        return Err(ConfigParseError(format!(
+            "config {:?} is included cyclically: {:?}",
+            path, include_stack
+        )));
+    }
+
+    let file_content = std::fs::read(path).map_err(|e| IOError(format!("{:?}: {}", path, e)))?;
+    let mut value: Value = toml::from_slice(&file_content)
+        .map_err(|e| ConfigParseError(format!("Error parsing config {:?}: {:?}", path, e)))?;
+
+    interpolate_value(&mut value, &mut String::new(), &env_var)
+        .map_err(|e| ConfigParseError(format!("config {:?}, {}", path, e)))?;
+
+    let config = match value {
+        Value::Table(table) => table,
+        // toml::from_slice always returns a table for a document
+        _ => unreachable!(),
+    };
+    let (includes, config) =
+        take_includes(config).map_err(|e| ConfigParseError(format!("config {:?}, {}", path, e)))?;
+
+    let mut sources = KeySources::new();
+    for key_path in table_key_paths(&config) {
+        sources.insert(key_path, path.to_path_buf());
+    }
+
+    include_stack.push(canonical_path);
+
+    let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let mut merged = Table::new();
+    let mut merged_sources = KeySources::new();
+    for include in includes {
+        let (included, included_sources) =
+            load_with_includes(&base_dir.join(include), include_stack)?;
+        let mut merger = SourcesMerger::new(&mut merged_sources, &included_sources);
+        merge_configs(&mut merged, included, &mut merger);
+    }
+    let mut merger = SourcesMerger::new(&mut merged_sources, &sources);
+    merge_configs(&mut merged, config, &mut merger);
+
+    include_stack.pop();
+
+    Ok((merged, merged_sources))
+}
+
+/// Deserializes a merged config, on error the path of the key that caused it is returned
+/// in the same form as produced by `table_key_paths`.
+fn deserialize_config<T: DeserializeOwned>(
+    config: Table,
+) -> std::result::Result<T, (String, Option<String>)> {
+    let error = match serde_path_to_error::deserialize(Value::Table(config.clone())) {
+        Ok(config) => return Ok(config),
+        Err(e) => e,
+    };
+
+    let key_path = error_key_path(error.path());
+    let error = error.into_inner().to_string();
+    let key_path = key_path.map(|key_path| narrow_key_path::<T>(&config, key_path, &error));
+
+    Err((error, key_path))
+}
+
+fn error_key_path(path: &serde_path_to_error::Path) -> Option<String> {
+    use serde_path_to_error::Segment;
+
+    let mut key_path = String::new();
+    for segment in path.iter() {
+        match segment {
+            Segment::Seq { index } => key_path.push_str(&format!("[{}]", index)),
+            Segment::Map { key } => push_key(&mut key_path, key),
+            Segment::Enum { variant } => push_key(&mut key_path, variant),
+            Segment::Unknown => break,
+        }
+    }
+
+    if key_path.is_empty() {
+        None
+    } else {
+        Some(key_path)
+    }
+}
+
+fn push_key(key_path: &mut String, key: &str) {
+    if !key_path.is_empty() {
+        key_path.push('.');
+    }
+    key_path.push_str(key);
+}
+
+/// Fields of flattened structs (like module configs) are deserialized after their table
+/// is buffered, so the error path ends at that table. In this case the key is found among
+/// the table leaves as the first one whose removal changes the error.
+fn narrow_key_path<T: DeserializeOwned>(config: &Table, key_path: String, error: &str) -> String {
+    let leaves = table_key_paths(config)
+        .into_iter()
+        .filter(|leaf| is_descendant(leaf, &key_path));
+
+    for leaf in leaves {
+        let mut reduced = Value::Table(config.clone());
+        remove_key_path(&mut reduced, &leaf);
+
+        let reduced_error = match reduced.try_into::<T>() {
+            Ok(_) => return leaf,
+            Err(e) => e.to_string(),
+        };
+        // removing a required key leads to a new error that isn't related to the original one
+        let key = leaf.rsplit('.').next().unwrap_or(&leaf);
+        if reduced_error != error && !reduced_error.contains(&format!("missing field `{}`", key)) {
+            return leaf;
+        }
+    }
+
+    key_path
+}
+
+fn is_descendant(key_path: &str, ancestor: &str) -> bool {
+    key_path.starts_with(ancestor)
+        && matches!(
+            key_path[ancestor.len()..].chars().next(),
+            Some('.') | Some('[')
+        )
+}
+
+/// Finds the file a key comes from: the key itself could be an element of a leaf array
+/// or a table with a missing field, so its closest ancestor or first descendant is used.
+fn key_source<'s>(sources: &'s KeySources, key_path: &str) -> Option<&'s PathBuf> {
+    let mut ancestor = key_path;
+    loop {
+        if let Some(source) = sources.get(ancestor) {
+            return Some(source);
+        }
+        match ancestor.rfind(|c| c == '.' || c == '[') {
+            Some(position) => ancestor = &ancestor[..position],
+            None => break,
+        }
+    }
+
+    sources
+        .iter()
+        .filter(|(key, _)| is_descendant(key, key_path))
+        .min_by(|(lhs, _), (rhs, _)| lhs.cmp(rhs))
+        .map(|(_, source)| source)
+}
+
+fn take_includes(mut config: Table) -> std::result::Result<(Vec<String>, Table), String> {
+    let includes = match config.remove(INCLUDE_KEY) {
+        Some(Value::Array(includes)) => includes,
+        Some(Value::String(include)) => vec![Value::String(include)],
+        Some(v) => {
+            return Err(format!(
+                r#"key "{}": expected an array of paths, found {}"#,
+                INCLUDE_KEY,
+                v.type_str()
+            ))
+        }
+        None => return Ok((vec![], config)),
+    };
+
+    let includes = includes
+        .into_iter()
+        .enumerate()
+        .map(|(id, include)| match include {
+            Value::String(include) => Ok(include),
+            v => Err(format!(
+                r#"key "{}[{}]": expected a path, found {}"#,
+                INCLUDE_KEY,
+                id,
+                v.type_str()
+            )),
+        })
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    Ok((includes, config))
+}
+
+/// Merges a top-level config into the base one.
+fn merge_configs(base: &mut Table, config: Table, merger: &mut SourcesMerger<'_>) {
+    for (key, value) in config {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Array(base_modules)), Value::Array(modules)) if key == MODULE_KEY => {
+                merge_modules(base_modules, modules, merger)
+            }
+            (Some(base_value), value) => merge_values(base_value, value, &key, &key, merger),
+            (None, value) => {
+                merger.moved(&value, &key, &key);
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+fn merge_modules(
+    base_modules: &mut Vec<Value>,
+    modules: Vec<Value>,
+    merger: &mut SourcesMerger<'_>,
+) {
+    fn module_name(module: &Value) -> Option<&str> {
+        module.get(MODULE_NAME_KEY).and_then(Value::as_str)
+    }
+
+    for (id, module) in modules.into_iter().enumerate() {
+        let key_path = format!("{}[{}]", MODULE_KEY, id);
+        let base_module = module_name(&module).and_then(|name| {
+            base_modules
+                .iter()
+                .position(|base_module| module_name(base_module) == Some(name))
+        });
+
+        match base_module {
+            Some(base_id) => {
+                let base_key_path = format!("{}[{}]", MODULE_KEY, base_id);
+                merge_values(
+                    &mut base_modules[base_id],
+                    module,
+                    &base_key_path,
+                    &key_path,
+                    merger,
+                )
+            }
+            None => {
+                let base_key_path = format!("{}[{}]", MODULE_KEY, base_modules.len());
+                merger.moved(&module, &key_path, &base_key_path);
+                base_modules.push(module)
+            }
+        }
+    }
+}
+
+fn merge_values(
+    base: &mut Value,
+    value: Value,
+    base_key_path: &str,
+    key_path: &str,
+    merger: &mut SourcesMerger<'_>,
+) {
+    match (base, value) {
+        (Value::Table(base), Value::Table(table)) => {
+            for (key, value) in table {
+                let base_key_path = format!("{}.{}", base_key_path, key);
+                let key_path = format!("{}.{}", key_path, key);
+                match base.get_mut(&key) {
+                    Some(base_value) => {
+                        merge_values(base_value, value, &base_key_path, &key_path, merger)
+                    }
+                    None => {
+                        merger.moved(&value, &key_path, &base_key_path);
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, value) => {
+            merger.moved(&value, key_path, base_key_path);
+            *base = value
+        }
+    }
+}
+
+/// Keeps sources of keys up to date while a config is merged into the base one.
+struct SourcesMerger<'s> {
+    base_sources: &'s mut KeySources,
+    sources: &'s KeySources,
+}
+
+impl<'s> SourcesMerger<'s> {
+    fn new(base_sources: &'s mut KeySources, sources: &'s KeySources) -> Self {
+        Self {
+            base_sources,
+            sources,
+        }
+    }
+
+    /// Records that the value placed at key_path of the merged config now lives at
+    /// base_key_path of the base one.
+    fn moved(&mut self, value: &Value, key_path: &str, base_key_path: &str) {
+        let mut key_paths = Vec::new();
+        value_key_paths(value, key_path.to_string(), &mut key_paths);
+        let mut base_key_paths = Vec::new();
+        value_key_paths(value, base_key_path.to_string(), &mut base_key_paths);
+
+        for (key_path, base_key_path) in key_paths.iter().zip(base_key_paths) {
+            if let Some(source) = self.sources.get(key_path) {
+                self.base_sources.insert(base_key_path, source.clone());
+            }
+        }
+    }
+}
+
+/// Returns key paths of all leaves of a table, arrays of tables (like modules)
+/// are walked into, other arrays are treated as leaves.
+fn table_key_paths(table: &Table) -> Vec<String> {
+    let mut key_paths = Vec::new();
+    for (key, value) in table {
+        value_key_paths(value, key.clone(), &mut key_paths);
+    }
+
+    key_paths
+}
+
+fn value_key_paths(value: &Value, key_path: String, key_paths: &mut Vec<String>) {
+    match value {
+        Value::Table(table) => {
+            for (key, value) in table {
+                value_key_paths(value, format!("{}.{}", key_path, key), key_paths);
+            }
+        }
+        Value::Array(array) if !array.is_empty() && array.iter().all(Value::is_table) => {
+            for (id, value) in array.iter().enumerate() {
+                value_key_paths(value, format!("{}[{}]", key_path, id), key_paths);
+            }
+        }
+        _ => key_paths.push(key_path),
+    }
+}
+
+/// Removes a leaf addressed by a key path produced by `table_key_paths`.
+fn remove_key_path(value: &mut Value, key_path: &str) {
+    let (parent_path, key) = match key_path.rfind('.') {
+        Some(position) => (&key_path[..position], &key_path[position + 1..]),
+        None => ("", key_path),
+    };
+
+    let mut parent = value;
+    for segment in parent_path.split('.').filter(|segment| !segment.is_empty()) {
+        let (name, index) = split_index(segment);
+        parent = match parent.get_mut(name) {
+            Some(value) => value,
+            None => return,
+        };
+        if let Some(index) = index {
+            parent = match parent.get_mut(index) {
+                Some(value) => value,
+                None => return,
+            };
+        }
+    }
+
+    // leaves are never array elements, so the last segment is always a plain key
+    if let Value::Table(table) = parent {
+        table.remove(key);
+    }
+}
+
+fn split_index(segment: &str) -> (&str, Option<usize>) {
+    match segment.find('[') {
+        Some(position) => {
+            let index = segment[position + 1..segment.len() - 1].parse().ok();
+            (&segment[..position], index)
+        }
+        None => (segment, None),
+    }
+}
+
+fn env_var(name: &str) -> std::result::Result<String, VarError> {
+    std::env::var(name)
+}
+
+type VarGetter<'g> = &'g dyn Fn(&str) -> std::result::Result<String, VarError>;
+
+/// Substitutes variables obtained by get_var in all strings of the supplied value,
+/// key_path is used to report the key where an error occurred.
+fn interpolate_value(
+    value: &mut Value,
+    key_path: &mut String,
+    get_var: VarGetter<'_>,
+) -> std::result::Result<(), String> {
+    match value {
+        Value::String(string) => {
+            *string = interpolate_string(string, get_var)
+                .map_err(|e| format!(r#"key "{}": {}"#, key_path, e))?;
+        }
+        Value::Array(array) => {
+            for (id, value) in array.iter_mut().enumerate() {
+                let key_path_len = key_path.len();
+                key_path.push_str(&format!("[{}]", id));
+                interpolate_value(value, key_path, get_var)?;
+                key_path.truncate(key_path_len);
+            }
+        }
+        Value::Table(table) => {
+            for (key, value) in table.iter_mut() {
+                let key_path_len = key_path.len();
+                if !key_path.is_empty() {
+                    key_path.push('.');
+                }
+                key_path.push_str(key);
+                interpolate_value(value, key_path, get_var)?;
+                key_path.truncate(key_path_len);
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// Substitutes `${VAR}` and `${VAR:-default}` with variables, `$$` is an escaped `$`.
+fn interpolate_string(string: &str, get_var: VarGetter<'_>) -> std::result::Result<String, String> {
+    let mut result = String::with_capacity(string.len());
+    let mut rest = string;
+
+    while let Some(position) = rest.find('$') {
+        result.push_str(&rest[..position]);
+        rest = &rest[position + 1..];
+
+        if rest.starts_with('$') {
+            result.push('$');
+            rest = &rest[1..];
+            continue;
+        }
+
+        if !rest.starts_with('{') {
+            result.push('$');
+            continue;
+        }
+
+        let end = rest
+            .find('}')
+            .ok_or_else(|| format!(r#"unterminated variable substitution in "{}""#, string))?;
+        let expression = &rest[1..end];
+        rest = &rest[end + 1..];
+
+        let (var_name, default) = match expression.find(":-") {
+            Some(position) => (&expression[..position], Some(&expression[position + 2..])),
+            None => (expression, None),
+        };
+
+        if var_name.is_empty() {
+            return Err(format!(r#"empty variable name in "{}""#, string));
+        }
+
+        match (get_var(var_name), default) {
+            // like in shell, the default is also used for a set but empty variable
+            (Ok(var_value), Some(default)) if var_value.is_empty() => result.push_str(default),
+            (Ok(var_value), _) => result.push_str(&var_value),
+            (Err(_), Some(default)) => result.push_str(default),
+            (Err(e), None) => {
+                return Err(format!(
+                    r#"environment variable "{}" can't be substituted: {}"#,
+                    var_name, e
+                ))
+            }
+        }
+    }
+    result.push_str(rest);
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::interpolate_string;
+    use super::load_toml_config;
+    use super::merge_configs;
+    use super::KeySources;
+    use super::SourcesMerger;
+    use crate::FaaSError;
+    use crate::TomlFaaSConfig;
+
+    use toml::Value;
+
+    use std::env::VarError;
+
+    #[test]
+    fn interpolate() {
+        let get_var = |name: &str| match name {
+            "IPFS_PATH" => Ok(String::from("/usr/local/bin/ipfs")),
+            "IPFS_PATH_EMPTY" => Ok(String::new()),
+            _ => Err(VarError::NotPresent),
+        };
+
+        let result = interpolate_string("${IPFS_PATH}", &get_var);
+        assert_eq!(result, Ok(String::from("/usr/local/bin/ipfs")));
+
+        let result = interpolate_string("${IPFS_PATH_UNSET:-/usr/bin/ipfs}", &get_var);
+        assert_eq!(result, Ok(String::from("/usr/bin/ipfs")));
+
+        let result = interpolate_string("${IPFS_PATH_EMPTY:-/usr/bin/ipfs}", &get_var);
+        assert_eq!(result, Ok(String::from("/usr/bin/ipfs")));
+
+        let result = interpolate_string("${IPFS_PATH_EMPTY}", &get_var);
+        assert_eq!(result, Ok(String::new()));
+
+        let result = interpolate_string("$$HOME and $HOME", &get_var);
+        assert_eq!(result, Ok(String::from("$HOME and $HOME")));
+
+        assert!(interpolate_string("${IPFS_PATH_UNSET}", &get_var).is_err());
+        assert!(interpolate_string("${IPFS_PATH", &get_var).is_err());
+    }
+
+    #[test]
+    fn error_is_reported_with_included_file() {
+        let config_dir = std::env::temp_dir().join(format!(
+            "faas_toml_preprocessor_test_{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&config_dir).unwrap();
+
+        let base_path = config_dir.join("base.toml");
+        std::fs::write(
+            &base_path,
+            r#"
+            [[module]]
+            name = "facade"
+
+            [[module]]
+            name = "curl"
+            mem_pages_count = "many"
+            "#,
+        )
+        .unwrap();
+
+        let config_path = config_dir.join("config.toml");
+        std::fs::write(
+            &config_path,
+            r#"
+            include = ["base.toml"]
+            modules_dir = "artifacts"
+
+            [[module]]
+            name = "curl"
+            logger_enabled = true
+            "#,
+        )
+        .unwrap();
+
+        let result = load_toml_config::<TomlFaaSConfig, _>(&config_path);
+        std::fs::remove_dir_all(&config_dir).unwrap();
+
+        let message = match result {
+            Err(FaaSError::ConfigParseError(message)) => message,
+            result => panic!("expected a config parse error, got {:?}", result),
+        };
+        assert!(message.contains(r#"key "module[1].mem_pages_count""#));
+        assert!(message.contains(&format!("{:?}", base_path)));
+    }
+
+    #[test]
+    fn error_is_reported_for_one_of_several_bad_keys() {
+        let config_dir = std::env::temp_dir().join(format!(
+            "faas_toml_preprocessor_several_{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&config_dir).unwrap();
+
+        let config_path = config_dir.join("config.toml");
+        std::fs::write(
+            &config_path,
+            r#"
+            modules_dir = "artifacts"
+
+            [[module]]
+            name = "curl"
+            logger_enabled = "yes"
+            mem_pages_count = "many"
+            "#,
+        )
+        .unwrap();
+
+        let result = load_toml_config::<TomlFaaSConfig, _>(&config_path);
+        std::fs::remove_dir_all(&config_dir).unwrap();
+
+        let message = match result {
+            Err(FaaSError::ConfigParseError(message)) => message,
+            result => panic!("expected a config parse error, got {:?}", result),
+        };
+        // the reported key must be the one the reported error is about
+        let logger_error =
+            message.contains(r#"key "module[0].logger_enabled""#) && message.contains(r#""yes""#);
+        let mem_pages_error =
+            message.contains(r#"key "module[0].mem_pages_count""#) && message.contains(r#""many""#);
+        assert!(logger_error || mem_pages_error, "{}", message);
+    }
+
+    #[test]
+    fn merge_modules() {
+        let base: Value = toml::from_str(
+            r#"
+            modules_dir = "base"
+
+            [[module]]
+            name = "curl"
+            logger_enabled = true
+
+            [[module]]
+            name = "facade"
+            "#,
+        )
+        .unwrap();
+
+        let config: Value = toml::from_str(
+            r#"
+            modules_dir = "artifacts"
+
+            [[module]]
+            name = "curl"
+            mem_pages_count = 10
+
+            [[module]]
+            name = "local_storage"
+            "#,
+        )
+        .unwrap();
+
+        let expected: Value = toml::from_str(
+            r#"
+            modules_dir = "artifacts"
+
+            [[module]]
+            name = "curl"
+            logger_enabled = true
+            mem_pages_count = 10
+
+            [[module]]
+            name = "facade"
+
+            [[module]]
+            name = "local_storage"
+            "#,
+        )
+        .unwrap();
+
+        let mut merged = base.as_table().unwrap().clone();
+        let mut merged_sources = KeySources::new();
+        let sources = KeySources::new();
+        let mut merger = SourcesMerger::new(&mut merged_sources, &sources);
+        merge_configs(&mut merged, config.as_table().unwrap().clone(), &mut merger);
+
+        assert_eq!(Value::Table(merged), expected);
+    }
+}
@@ -21,6 +21,11 @@ use crate::errors::FaaSError;
 
 use fce::FCEModuleConfig;
 use fce::HostImportDescriptor;
+use fce::WASIFileSystem;
+use fce_module_signature::detached_signature_path;
+use fce_module_signature::parse_signature;
+use fce_module_signature::PublicKey;
+use fce_module_signature::Signature;
 use wasmer_core::import::ImportObject;
 use wasmer_core::import::Namespace;
 use wasmer_core::vm::Ctx;
@@ -29,10 +34,11 @@ use wasmer_wit::values::InterfaceValue as IValue;
 use wasmer_wit::types::InterfaceType as IType;
 
 use std::collections::HashMap;
-use std::cell::RefCell;
 use std::path::PathBuf;
-use std::rc::Rc;
 use std::ops::Deref;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::PoisonError;
 
 pub(crate) fn create_host_import(host_cmd: String) -> HostImportDescriptor {
     let host_cmd_closure = move |_ctx: &mut Ctx, args: Vec<IValue>| {
@@ -63,10 +69,13 @@ pub(crate) fn create_host_import(host_cmd: String) -> HostImportDescriptor {
 }
 
 fn create_call_parameters_import(
-    call_parameters: Rc<RefCell<fluence_sdk_main::CallParameters>>,
+    call_parameters: Arc<Mutex<fluence_sdk_main::CallParameters>>,
 ) -> HostImportDescriptor {
     let call_parameters_closure = move |_ctx: &mut Ctx, _args: Vec<IValue>| {
-        let result = crate::to_interface_value(call_parameters.borrow().deref()).unwrap();
+        let call_parameters = call_parameters
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let result = crate::to_interface_value(call_parameters.deref()).unwrap();
         Some(result)
     };
 
@@ -82,9 +91,11 @@ fn create_call_parameters_import(
 pub(crate) fn make_fce_config(
     module_name: String,
     faas_module_config: Option<FaaSModuleConfig>,
-    call_parameters: Rc<RefCell<fluence_sdk_main::CallParameters>>,
+    call_parameters: Arc<Mutex<fluence_sdk_main::CallParameters>>,
 ) -> Result<FCEModuleConfig> {
     let mut fce_module_config = FCEModuleConfig::default();
+    // the module name is used as a program name (argv[0])
+    fce_module_config.wasi_args = vec![module_name.as_bytes().to_vec()];
 
     let faas_module_config = match faas_module_config {
         Some(faas_module_config) => faas_module_config,
@@ -95,20 +106,35 @@ pub(crate) fn make_fce_config(
         fce_module_config.mem_pages_count = mem_pages_count;
     }
 
+    fce_module_config.deterministic = faas_module_config.deterministic;
+    fce_module_config.import_policy = faas_module_config.import_policy;
+    fce_module_config.instance_mode = faas_module_config.instance_mode;
+    fce_module_config.lazy_instantiation = faas_module_config.lazy_instantiation;
+    fce_module_config.idle_timeout = faas_module_config.idle_timeout;
+
     if let Some(wasi) = faas_module_config.wasi {
+        let args = wasi.args.into_iter().map(String::into_bytes);
+        fce_module_config.wasi_args.extend(args);
         fce_module_config.wasi_envs = wasi.envs;
         fce_module_config.wasi_preopened_files = wasi.preopened_files;
         fce_module_config.wasi_mapped_dirs = wasi.mapped_dirs;
+        fce_module_config.wasi_mapped_dirs_permissions = wasi.mapped_dirs_permissions;
+        fce_module_config.wasi_fs = wasi.fs;
 
-        // create environment variables for all mapped directories
+        // create environment variables for all mapped directories,
+        // in the in-memory filesystem they are located in the root by their aliases
+        let is_memory_fs = fce_module_config.wasi_fs != WASIFileSystem::Host;
         let mapped_dirs = fce_module_config
             .wasi_mapped_dirs
             .iter()
             .map(|(from, to)| {
-                (
-                    from.as_bytes().to_vec(),
-                    to.to_string_lossy().as_bytes().to_vec(),
-                )
+                let to = if is_memory_fs {
+                    format!("/{}", from)
+                } else {
+                    to.to_string_lossy().to_string()
+                };
+
+                (from.as_bytes().to_vec(), to.into_bytes())
             })
             .collect::<HashMap<_, _>>();
 
@@ -142,9 +168,12 @@ pub(crate) fn make_fce_config(
 use crate::misc::ModulesLoadStrategy;
 
 /// Loads modules from a directory at a given path. Non-recursive, ignores subdirectories.
+/// If trusted keys are provided, each module must have an embedded signature or
+/// a detached one in `<module file name>.sig` made by one of them.
 pub(crate) fn load_modules_from_fs(
     modules_dir: &PathBuf,
     modules: ModulesLoadStrategy<'_>,
+    trusted_keys: Option<&[PublicKey]>,
 ) -> Result<HashMap<String, Vec<u8>>> {
     use FaaSError::IOError;
 
@@ -167,11 +196,30 @@ pub(crate) fn load_modules_from_fs(
             .map_err(|name| IOError(format!("invalid file name: {:?}", name)))?;
 
         if modules.should_load(&module_name.as_ref()) {
-            let module_bytes = std::fs::read(path)?;
+            let module_bytes = std::fs::read(&path)?;
             let module_name = modules.extract_module_name(module_name);
-            if hash_map.insert(module_name, module_bytes).is_some() {
-                return Err(FaaSError::ConfigParseError(String::from(
-                    "module {} is duplicated in config",
+
+            if let Some(trusted_keys) = trusted_keys {
+                let signature_path = detached_signature_path(&path);
+                let detached_signature = match std::fs::read_to_string(&signature_path) {
+                    Ok(signature) => Some(parse_signature(&signature).map_err(|e| {
+                        FaaSError::ModuleSignatureError(format!("{:?}: {}", signature_path, e))
+                    })?),
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+                    Err(e) => return Err(IOError(format!("{:?}: {}", signature_path, e))),
+                };
+
+                verify_module(
+                    &module_name,
+                    &module_bytes,
+                    detached_signature.as_ref(),
+                    trusted_keys,
+                )?;
+            }
+            if hash_map.insert(module_name.clone(), module_bytes).is_some() {
+                return Err(FaaSError::ConfigParseError(format!(
+                    "module {} is duplicated in modules dir {:?}",
+                    module_name, modules_dir
                 )));
             }
         }
@@ -190,3 +238,54 @@ pub(crate) fn load_modules_from_fs(
 
     Ok(loaded)
 }
+
+/// Checks that the module is signed by one of trusted keys.
+pub(crate) fn verify_module(
+    module_name: &str,
+    module_bytes: &[u8],
+    detached_signature: Option<&Signature>,
+    trusted_keys: &[PublicKey],
+) -> Result<()> {
+    fce_module_signature::verify_module(module_bytes, detached_signature, trusted_keys).map_err(
+        |e| {
+            FaaSError::ModuleSignatureError(format!(
+                r#"module "{}" can't be loaded: {}"#,
+                module_name, e
+            ))
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::make_fce_config;
+    use crate::FaaSModuleConfig;
+    use crate::FaaSWASIConfig;
+
+    use std::sync::Arc;
+    use std::sync::Mutex;
+
+    #[test]
+    fn module_name_is_argv0() {
+        let call_parameters = Arc::new(Mutex::new(<_>::default()));
+
+        let fce_config = make_fce_config(String::from("curl"), None, call_parameters.clone())
+            .expect("config without module settings should be accepted");
+        assert_eq!(fce_config.wasi_args, vec![b"curl".to_vec()]);
+
+        let module_config = FaaSModuleConfig {
+            wasi: Some(FaaSWASIConfig {
+                args: vec![String::from("--verbose"), String::from("-o")],
+                ..<_>::default()
+            }),
+            ..<_>::default()
+        };
+        let fce_config =
+            make_fce_config(String::from("curl"), Some(module_config), call_parameters)
+                .expect("config with WASI args should be accepted");
+        assert_eq!(
+            fce_config.wasi_args,
+            vec![b"curl".to_vec(), b"--verbose".to_vec(), b"-o".to_vec()]
+        );
+    }
+}
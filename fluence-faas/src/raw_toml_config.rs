@@ -17,6 +17,14 @@
 use crate::FaaSError;
 use crate::Result;
 use crate::config::*;
+use crate::misc::parse_module_hash;
+
+use fce::DeterministicConfig;
+use fce::ImportPolicy;
+use fce::InstanceMode;
+use fce::MappedDirPermissions;
+use fce::WASIFileSystem;
+use fce_module_signature::parse_public_key;
 
 use serde_derive::Serialize;
 use serde_derive::Deserialize;
@@ -25,14 +33,17 @@ use std::convert::TryInto;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::path::PathBuf;
+use std::time::Duration;
 
 /*
 An example of the config:
 
 modules_dir = "wasm/artifacts/wasm_modules"
+trusted_keys = ["d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a"]
 
 [[module]]
     name = "ipfs_node.wasm"
+    hash = "blake3:af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262"
     mem_pages_count = 100
     logger_enabled = true
 
@@ -41,9 +52,28 @@ modules_dir = "wasm/artifacts/wasm_modules"
     ipfs = "/usr/local/bin/ipfs"
 
     [module.wasi]
+    args = ["--verbose"]
     envs = { "IPFS_ADDR" = "/dns4/relay02.fluence.dev/tcp/15001" }
     preopened_files = ["/Users/user/tmp"]
-    mapped_dirs = {"tmp" = "/Users/user/tmp"}
+    mapped_dirs = {"tmp" = "/Users/user/tmp", "data" = { path = "/srv/data", readonly = true }}
+
+[[module]]
+    name = "consensus"
+    instance_mode = "per_call"
+    lazy_instantiation = true
+    idle_timeout_secs = 600
+
+    [module.instance_pool]
+    size = 4
+    reset_after_call = true
+
+    [module.import_policy]
+    kind = "pure"
+
+    [module.deterministic]
+    rng_seed = 42
+    clock_start_ns = 1600000000000000000
+    clock_step_ns = 1000
 
 [default]
     mem_pages_count = 100
@@ -54,6 +84,8 @@ modules_dir = "wasm/artifacts/wasm_modules"
     ipfs = "/usr/local/bin/ipfs"
 
     [default.wasi]
+    fs = "memory"
+    fs_seed = "/Users/user/fixtures.tar"
     envs = []
     preopened_files = ["/Users/user/tmp"]
     mapped_dirs = {"tmp" = "/Users/user/tmp"}
@@ -62,19 +94,19 @@ modules_dir = "wasm/artifacts/wasm_modules"
 #[derive(Deserialize, Serialize, Debug, Clone, Default)]
 pub struct TomlFaaSConfig {
     pub modules_dir: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trusted_keys: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub module: Vec<TomlFaaSNamedModuleConfig>,
     pub default: Option<TomlFaaSModuleConfig>,
 }
 
 impl TomlFaaSConfig {
-    /// Load config from filesystem.
+    /// Load config from filesystem, environment variables substitution
+    /// and includes are supported (see `load_toml_value` for details).
     pub fn load<P: Into<PathBuf>>(path: P) -> Result<Self> {
-        let path = path.into();
-        let file_content = std::fs::read(&path)?;
-        toml::from_slice(&file_content).map_err(|e| {
-            FaaSError::ConfigParseError(format!("Error parsing config {:?}: {:?}", path, e))
-        })
+        let path: PathBuf = path.into();
+        crate::misc::load_toml_config(&path)
     }
 }
 
@@ -89,6 +121,8 @@ impl TryInto<FaaSConfig> for TomlFaaSConfig {
 #[derive(Deserialize, Serialize, Debug, Clone, Default)]
 pub struct TomlFaaSNamedModuleConfig {
     pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
     #[serde(flatten)]
     pub config: TomlFaaSModuleConfig,
 }
@@ -116,6 +150,12 @@ pub struct TomlFaaSModuleConfig {
     pub wasi: Option<TomlWASIConfig>,
     pub mounted_binaries: Option<toml::value::Table>,
     pub logging_mask: Option<i64>,
+    pub instance_mode: Option<String>,
+    pub lazy_instantiation: Option<bool>,
+    pub idle_timeout_secs: Option<u64>,
+    pub deterministic: Option<TomlDeterministicConfig>,
+    pub import_policy: Option<TomlImportPolicy>,
+    pub instance_pool: Option<TomlInstancePoolConfig>,
 }
 
 impl TomlFaaSNamedModuleConfig {
@@ -125,16 +165,40 @@ impl TomlFaaSNamedModuleConfig {
     {
         Self {
             name: name.into(),
+            hash: None,
             config: <_>::default(),
         }
     }
 }
 
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct TomlDeterministicConfig {
+    pub rng_seed: Option<u64>,
+    pub clock_start_ns: Option<u64>,
+    pub clock_step_ns: Option<u64>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct TomlImportPolicy {
+    pub kind: Option<String>,
+    pub wasi_functions: Option<Vec<String>>,
+    pub host_namespaces: Option<Vec<String>>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct TomlInstancePoolConfig {
+    pub size: Option<usize>,
+    pub reset_after_call: Option<bool>,
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone, Default)]
 pub struct TomlWASIConfig {
+    pub args: Option<Vec<String>>,
     pub preopened_files: Option<Vec<String>>,
     pub envs: Option<toml::value::Table>,
     pub mapped_dirs: Option<toml::value::Table>,
+    pub fs: Option<String>,
+    pub fs_seed: Option<String>,
 }
 
 /// Prepare config after parsing it from TOML.
@@ -147,17 +211,35 @@ pub fn from_toml_faas_config(config: TomlFaaSConfig) -> Result<FaaSConfig> {
 
     let default_modules_config = config.default.map(from_toml_module_config).transpose()?;
 
+    let trusted_keys = config
+        .trusted_keys
+        .map(|keys| {
+            keys.iter()
+                .map(|key| parse_public_key(key))
+                .collect::<std::result::Result<Vec<_>, _>>()
+        })
+        .transpose()
+        .map_err(|e| FaaSError::ConfigParseError(format!("trusted_keys: {}", e)))?;
+
     Ok(FaaSConfig {
         modules_dir: config.modules_dir.map(PathBuf::from),
         modules_config,
         default_modules_config,
+        trusted_keys,
+        compilation_cache_dir: None,
     })
 }
 
 pub fn from_toml_named_module_config(
     config: TomlFaaSNamedModuleConfig,
 ) -> Result<(String, FaaSModuleConfig)> {
-    let module_config = from_toml_module_config(config.config)?;
+    let mut module_config = from_toml_module_config(config.config)?;
+    module_config.hash = config
+        .hash
+        .map(|hash| parse_module_hash(&hash))
+        .transpose()
+        .map_err(|e| FaaSError::ConfigParseError(format!("module {}: {}", config.name, e)))?;
+
     Ok((config.name, module_config))
 }
 
@@ -176,16 +258,78 @@ pub fn from_toml_module_config(config: TomlFaaSModuleConfig) -> Result<FaaSModul
         host_cli_imports.insert(import_name, crate::misc::create_host_import(host_cmd));
     }
 
+    let deterministic = config.deterministic.map(from_toml_deterministic_config);
+    if deterministic.is_some() && !host_cli_imports.is_empty() {
+        return Err(FaaSError::ConfigParseError(String::from(
+            "mounted binaries can't be used in the deterministic mode",
+        )));
+    }
+
+    let import_policy = match config.import_policy {
+        Some(import_policy) => from_toml_import_policy(import_policy)?,
+        None => ImportPolicy::Any,
+    };
+
+    let instance_pool = config
+        .instance_pool
+        .map(from_toml_instance_pool_config)
+        .transpose()?;
+    if instance_pool.is_some() && !host_cli_imports.is_empty() {
+        return Err(FaaSError::ConfigParseError(String::from(
+            "mounted binaries can't be used by a module with an instance pool",
+        )));
+    }
+
+    let instance_mode = parse_instance_mode(config.instance_mode.as_deref())
+        .map_err(FaaSError::ConfigParseError)?;
+
     let wasi = config.wasi.map(from_toml_wasi_config).transpose()?;
+    let is_memory_fs = wasi
+        .as_ref()
+        .map_or(false, |wasi| wasi.fs != WASIFileSystem::Host);
+    let idle_timeout = parse_idle_timeout(
+        config.idle_timeout_secs,
+        is_memory_fs,
+        deterministic.is_some(),
+    )
+    .map_err(FaaSError::ConfigParseError)?;
+
     Ok(FaaSModuleConfig {
         mem_pages_count: config.mem_pages_count,
         logger_enabled: config.logger_enabled.unwrap_or(true),
         host_imports: host_cli_imports,
         wasi,
         logging_mask: config.logging_mask.unwrap_or(i64::max_value()),
+        deterministic,
+        import_policy,
+        hash: None,
+        instance_pool,
+        instance_mode,
+        lazy_instantiation: config.lazy_instantiation.unwrap_or(false),
+        idle_timeout,
     })
 }
 
+pub fn from_toml_deterministic_config(config: TomlDeterministicConfig) -> DeterministicConfig {
+    let default = DeterministicConfig::default();
+
+    DeterministicConfig {
+        rng_seed: config.rng_seed.unwrap_or(default.rng_seed),
+        clock_start_ns: config.clock_start_ns.unwrap_or(default.clock_start_ns),
+        clock_step_ns: config.clock_step_ns.unwrap_or(default.clock_step_ns),
+    }
+}
+
+pub fn from_toml_import_policy(config: TomlImportPolicy) -> Result<ImportPolicy> {
+    parse_import_policy(&config).map_err(FaaSError::ConfigParseError)
+}
+
+pub fn from_toml_instance_pool_config(
+    config: TomlInstancePoolConfig,
+) -> Result<InstancePoolConfig> {
+    parse_instance_pool(&config).map_err(FaaSError::ConfigParseError)
+}
+
 pub fn from_toml_wasi_config(wasi: TomlWASIConfig) -> Result<FaaSWASIConfig> {
     let to_vec = |elem: (String, toml::Value)| -> Result<(Vec<u8>, Vec<u8>)> {
         let to = elem
@@ -195,13 +339,7 @@ pub fn from_toml_wasi_config(wasi: TomlWASIConfig) -> Result<FaaSWASIConfig> {
         Ok((elem.0.into_bytes(), to.into_bytes()))
     };
 
-    let to_path = |elem: (String, toml::Value)| -> Result<(String, PathBuf)> {
-        let to = elem
-            .1
-            .try_into::<String>()
-            .map_err(FaaSError::ParseConfigError)?;
-        Ok((elem.0, PathBuf::from(to)))
-    };
+    let args = wasi.args.unwrap_or_default();
 
     let envs = wasi.envs.unwrap_or_default();
     let envs = envs
@@ -215,40 +353,312 @@ pub fn from_toml_wasi_config(wasi: TomlWASIConfig) -> Result<FaaSWASIConfig> {
         .map(PathBuf::from)
         .collect::<HashSet<_>>();
 
-    let mapped_dirs = wasi.mapped_dirs.unwrap_or_default();
-    let mapped_dirs = mapped_dirs
-        .into_iter()
-        .map(to_path)
-        .collect::<Result<HashMap<_, _>>>()?;
+    let fs = parse_wasi_fs(wasi.fs.as_deref(), wasi.fs_seed.as_deref())
+        .map_err(FaaSError::ConfigParseError)?;
+    let is_memory_fs = fs != WASIFileSystem::Host;
+
+    let mut mapped_dirs = HashMap::new();
+    let mut mapped_dirs_permissions = HashMap::new();
+    for (alias, value) in wasi.mapped_dirs.unwrap_or_default() {
+        let to_error = |e| FaaSError::ConfigParseError(format!("mapped dir {}: {}", alias, e));
+        let (path, permissions) = parse_mapped_dir(&value).map_err(to_error)?;
+        if let Some(permissions) = permissions {
+            check_mapped_dir_restrictions(&permissions, is_memory_fs).map_err(to_error)?;
+            mapped_dirs_permissions.insert(alias.clone(), permissions);
+        }
+        mapped_dirs.insert(alias, path);
+    }
 
     Ok(FaaSWASIConfig {
+        args,
         envs,
         preopened_files,
         mapped_dirs,
+        mapped_dirs_permissions,
+        fs,
     })
 }
 
+/// Parses `fs = "host" | "memory"` with optional `fs_seed` allowed only for the in-memory one.
+pub(crate) fn parse_wasi_fs(
+    fs: Option<&str>,
+    fs_seed: Option<&str>,
+) -> std::result::Result<WASIFileSystem, String> {
+    match (fs, fs_seed) {
+        (None, None) | (Some("host"), None) => Ok(WASIFileSystem::Host),
+        (Some("memory"), seed) => Ok(WASIFileSystem::Memory {
+            seed: seed.map(PathBuf::from),
+        }),
+        (None, Some(_)) | (Some("host"), Some(_)) => Err(String::from(
+            r#"fs_seed could be used only with fs = "memory""#,
+        )),
+        (Some(fs), _) => Err(format!(
+            r#"unknown filesystem "{}", expected "host" or "memory""#,
+            fs
+        )),
+    }
+}
+
+/// Restrictions of mapped dirs are enforced only for the host filesystem,
+/// so they are rejected for the in-memory one instead of being silently ignored.
+pub(crate) fn check_mapped_dir_restrictions(
+    permissions: &MappedDirPermissions,
+    is_memory_fs: bool,
+) -> std::result::Result<(), String> {
+    if is_memory_fs && (permissions.readonly || permissions.max_bytes.is_some()) {
+        return Err(String::from(
+            "readonly and max_bytes can't be used with the in-memory filesystem",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Parses `instance_mode = "shared" | "per_call"`, modules are shared by default.
+pub(crate) fn parse_instance_mode(
+    instance_mode: Option<&str>,
+) -> std::result::Result<InstanceMode, String> {
+    match instance_mode {
+        None | Some("shared") => Ok(InstanceMode::Shared),
+        Some("per_call") => Ok(InstanceMode::PerCall),
+        Some(instance_mode) => Err(format!(
+            r#"unknown instance mode "{}", expected "shared" or "per_call""#,
+            instance_mode
+        )),
+    }
+}
+
+/// Parses idle_timeout_secs, eviction drops the WASI state of a module,
+/// so it can't be used with the in-memory filesystem and in the deterministic mode.
+pub(crate) fn parse_idle_timeout(
+    idle_timeout_secs: Option<u64>,
+    is_memory_fs: bool,
+    is_deterministic: bool,
+) -> std::result::Result<Option<Duration>, String> {
+    match idle_timeout_secs {
+        None => Ok(None),
+        Some(0) => Err(String::from("idle_timeout_secs should be greater than zero")),
+        Some(_) if is_memory_fs => Err(String::from(
+            "idle_timeout_secs can't be used with the in-memory filesystem, its files would be lost on eviction",
+        )),
+        Some(_) if is_deterministic => Err(String::from(
+            "idle_timeout_secs can't be used in the deterministic mode, its clock and RNG would be reset on eviction",
+        )),
+        Some(idle_timeout_secs) => Ok(Some(Duration::from_secs(idle_timeout_secs))),
+    }
+}
+
+/// Parses an instance pool config, its size is required and must be positive.
+pub(crate) fn parse_instance_pool(
+    config: &TomlInstancePoolConfig,
+) -> std::result::Result<InstancePoolConfig, String> {
+    match config.size {
+        Some(size) if size > 0 => Ok(InstancePoolConfig {
+            size,
+            reset_after_call: config.reset_after_call.unwrap_or(false),
+        }),
+        Some(_) => Err(String::from("size of an instance pool must be at least 1")),
+        None => Err(String::from("size of an instance pool must be specified")),
+    }
+}
+
+/// Parses `kind = "any" | "pure" | "allowlist"`, lists of allowed WASI functions and
+/// host namespaces make sense only for the allowlist that is the default kind for them.
+pub(crate) fn parse_import_policy(
+    config: &TomlImportPolicy,
+) -> std::result::Result<ImportPolicy, String> {
+    let has_lists = config.wasi_functions.is_some() || config.host_namespaces.is_some();
+    let to_set = |list: &Option<Vec<String>>| list.iter().flatten().cloned().collect();
+
+    match (config.kind.as_deref(), has_lists) {
+        (None, false) | (Some("any"), false) => Ok(ImportPolicy::Any),
+        (Some("pure"), false) => Ok(ImportPolicy::Pure),
+        (None, true) | (Some("allowlist"), _) => Ok(ImportPolicy::Allowlist {
+            wasi_functions: to_set(&config.wasi_functions),
+            host_namespaces: to_set(&config.host_namespaces),
+        }),
+        (Some(kind), true) if kind == "any" || kind == "pure" => Err(format!(
+            r#"wasi_functions and host_namespaces could be used only with kind = "allowlist", found "{}""#,
+            kind
+        )),
+        (Some(kind), _) => Err(format!(
+            r#"unknown import policy "{}", expected "any", "pure" or "allowlist""#,
+            kind
+        )),
+    }
+}
+
+/// Parses a mapped dir that is either a path or a table like
+/// `{ path = "/srv/data", readonly = true, max_bytes = "100MB" }`.
+pub(crate) fn parse_mapped_dir(
+    value: &toml::Value,
+) -> std::result::Result<(PathBuf, Option<MappedDirPermissions>), String> {
+    let table = match value {
+        toml::Value::String(path) => return Ok((PathBuf::from(path), None)),
+        toml::Value::Table(table) => table,
+        v => {
+            return Err(format!(
+                "expected a path or a table, found {}",
+                v.type_str()
+            ))
+        }
+    };
+
+    for key in table.keys() {
+        if !["path", "readonly", "max_bytes"].contains(&key.as_str()) {
+            return Err(format!("unknown key {}", key));
+        }
+    }
+
+    let path = match table.get("path") {
+        Some(toml::Value::String(path)) => PathBuf::from(path),
+        Some(v) => return Err(format!("path: expected a string, found {}", v.type_str())),
+        None => return Err(String::from("path isn't specified")),
+    };
+
+    let readonly = match table.get("readonly") {
+        Some(toml::Value::Boolean(readonly)) => *readonly,
+        Some(v) => return Err(format!("readonly: expected a bool, found {}", v.type_str())),
+        None => false,
+    };
+
+    let max_bytes = table
+        .get("max_bytes")
+        .map(parse_bytes_value)
+        .transpose()
+        .map_err(|e| format!("max_bytes: {}", e))?;
+
+    let permissions = MappedDirPermissions {
+        readonly,
+        max_bytes,
+    };
+    Ok((path, Some(permissions)))
+}
+
+/// Parses a size in bytes set by a non-negative integer or a string like "100MB"
+/// (see `parse_bytes_size` for supported units).
+pub fn parse_bytes_value(value: &toml::Value) -> std::result::Result<u64, String> {
+    match value {
+        toml::Value::Integer(size) if *size >= 0 => Ok(*size as u64),
+        toml::Value::String(size) => {
+            parse_bytes_size(size).ok_or_else(|| format!(r#""{}" isn't a valid size"#, size))
+        }
+        v => Err(format!(
+            "expected a non-negative integer or a string like \"100MB\", found {}",
+            v
+        )),
+    }
+}
+
+/// Parses sizes like "1024", "512B", "100KB", "100MB", "1GB", "64KiB", "10MiB", "1GiB".
+fn parse_bytes_size(size: &str) -> Option<u64> {
+    const UNITS: [(&str, u64); 7] = [
+        ("KiB", 1 << 10),
+        ("MiB", 1 << 20),
+        ("GiB", 1 << 30),
+        ("KB", 1_000),
+        ("MB", 1_000_000),
+        ("GB", 1_000_000_000),
+        ("B", 1),
+    ];
+
+    let size = size.trim();
+    let (number, multiplier) = UNITS
+        .iter()
+        .find(|(suffix, _)| size.ends_with(suffix))
+        .map(|(suffix, multiplier)| (&size[..size.len() - suffix.len()], *multiplier))
+        .unwrap_or((size, 1));
+
+    number.trim().parse::<u64>().ok()?.checked_mul(multiplier)
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{TomlFaaSNamedModuleConfig, TomlFaaSModuleConfig, TomlWASIConfig};
+    use crate::{ImportPolicy, InstanceMode, TomlImportPolicy};
+    use super::parse_bytes_size;
+    use super::parse_import_policy;
+    use super::parse_instance_mode;
 
     #[test]
     fn serialize_named() {
         let config = TomlFaaSNamedModuleConfig {
             name: "name".to_string(),
+            hash: None,
             config: TomlFaaSModuleConfig {
                 mem_pages_count: Some(100),
                 logger_enabled: Some(false),
                 wasi: Some(TomlWASIConfig {
+                    args: None,
                     preopened_files: Some(vec!["a".to_string()]),
                     envs: None,
                     mapped_dirs: None,
+                    fs: None,
+                    fs_seed: None,
                 }),
                 mounted_binaries: None,
                 logging_mask: None,
+                instance_mode: None,
+                lazy_instantiation: None,
+                idle_timeout_secs: None,
+                deterministic: None,
+                import_policy: None,
+                instance_pool: None,
             },
         };
 
         assert!(toml::to_string(&config).is_ok())
     }
+
+    #[test]
+    fn bytes_size() {
+        assert_eq!(parse_bytes_size("1024"), Some(1024));
+        assert_eq!(parse_bytes_size("512B"), Some(512));
+        assert_eq!(parse_bytes_size("100MB"), Some(100_000_000));
+        assert_eq!(parse_bytes_size("64 KiB"), Some(65536));
+        assert_eq!(parse_bytes_size("MB"), None);
+        assert_eq!(parse_bytes_size("-1MB"), None);
+    }
+
+    #[test]
+    fn import_policy() {
+        let policy = |kind: Option<&str>, wasi_functions: Option<Vec<&str>>| TomlImportPolicy {
+            kind: kind.map(String::from),
+            wasi_functions: wasi_functions
+                .map(|functions| functions.into_iter().map(String::from).collect()),
+            host_namespaces: None,
+        };
+
+        assert_eq!(
+            parse_import_policy(&policy(None, None)),
+            Ok(ImportPolicy::Any)
+        );
+        assert_eq!(
+            parse_import_policy(&policy(Some("pure"), None)),
+            Ok(ImportPolicy::Pure)
+        );
+        assert_eq!(
+            parse_import_policy(&policy(None, Some(vec!["fd_write"]))),
+            Ok(ImportPolicy::Allowlist {
+                wasi_functions: vec![String::from("fd_write")].into_iter().collect(),
+                host_namespaces: <_>::default(),
+            })
+        );
+        assert!(parse_import_policy(&policy(Some("pure"), Some(vec!["fd_write"]))).is_err());
+        assert!(parse_import_policy(&policy(Some("impure"), None)).is_err());
+    }
+
+    #[test]
+    fn instance_mode() {
+        assert_eq!(parse_instance_mode(None), Ok(InstanceMode::Shared));
+        assert_eq!(
+            parse_instance_mode(Some("shared")),
+            Ok(InstanceMode::Shared)
+        );
+        assert_eq!(
+            parse_instance_mode(Some("per_call")),
+            Ok(InstanceMode::PerCall)
+        );
+        assert!(parse_instance_mode(Some("per-call")).is_err());
+    }
 }
@@ -23,7 +23,7 @@ use pretty_assertions::assert_eq;
 use once_cell::sync::Lazy;
 use serde_json::json;
 
-use std::rc::Rc;
+use std::sync::Arc;
 
 static ARG_CONFIG: Lazy<fluence_faas::TomlFaaSConfig> = Lazy::new(|| {
     let mut arguments_passing_config =
@@ -53,9 +53,9 @@ pub fn get_interfaces() {
     let string_type_outputs = vec![IType::String];
 
     let string_type_sign = fluence_faas::FaaSFunctionSignature {
-        name: Rc::new(String::from("string_type")),
-        arguments: Rc::new(string_type_arguments),
-        outputs: Rc::new(string_type_outputs),
+        name: Arc::new(String::from("string_type")),
+        arguments: Arc::new(string_type_arguments),
+        outputs: Arc::new(string_type_outputs),
     };
 
     let bytearray_type_arguments = vec![fluence_faas::IFunctionArg {
@@ -65,9 +65,9 @@ pub fn get_interfaces() {
     let bytearray_type_outputs = vec![IType::Array(Box::new(IType::U8))];
 
     let bytearray_type_sign = fluence_faas::FaaSFunctionSignature {
-        name: Rc::new(String::from("bytearray_type")),
-        arguments: Rc::new(bytearray_type_arguments),
-        outputs: Rc::new(bytearray_type_outputs),
+        name: Arc::new(String::from("bytearray_type")),
+        arguments: Arc::new(bytearray_type_arguments),
+        outputs: Arc::new(bytearray_type_outputs),
     };
 
     let i32_type_arguments = vec![fluence_faas::IFunctionArg {
@@ -77,9 +77,9 @@ pub fn get_interfaces() {
     let i32_type_outputs = vec![IType::S32];
 
     let i32_type_sign = fluence_faas::FaaSFunctionSignature {
-        name: Rc::new(String::from("i32_type")),
-        arguments: Rc::new(i32_type_arguments),
-        outputs: Rc::new(i32_type_outputs),
+        name: Arc::new(String::from("i32_type")),
+        arguments: Arc::new(i32_type_arguments),
+        outputs: Arc::new(i32_type_outputs),
     };
 
     let i64_type_arguments = vec![fluence_faas::IFunctionArg {
@@ -90,9 +90,9 @@ pub fn get_interfaces() {
     let i64_type_outputs = vec![IType::S64];
 
     let i64_type_sign = fluence_faas::FaaSFunctionSignature {
-        name: Rc::new(String::from("i64_type")),
-        arguments: Rc::new(i64_type_arguments),
-        outputs: Rc::new(i64_type_outputs),
+        name: Arc::new(String::from("i64_type")),
+        arguments: Arc::new(i64_type_arguments),
+        outputs: Arc::new(i64_type_outputs),
     };
 
     let u32_type_arguments = vec![fluence_faas::IFunctionArg {
@@ -102,9 +102,9 @@ pub fn get_interfaces() {
     let u32_type_outputs = vec![IType::U32];
 
     let u32_type_sign = fluence_faas::FaaSFunctionSignature {
-        name: Rc::new(String::from("u32_type")),
-        arguments: Rc::new(u32_type_arguments),
-        outputs: Rc::new(u32_type_outputs),
+        name: Arc::new(String::from("u32_type")),
+        arguments: Arc::new(u32_type_arguments),
+        outputs: Arc::new(u32_type_outputs),
     };
 
     let u64_type_arguments = vec![fluence_faas::IFunctionArg {
@@ -114,9 +114,9 @@ pub fn get_interfaces() {
     let u64_type_outputs = vec![IType::U64];
 
     let u64_type_sign = fluence_faas::FaaSFunctionSignature {
-        name: Rc::new(String::from("u64_type")),
-        arguments: Rc::new(u64_type_arguments),
-        outputs: Rc::new(u64_type_outputs),
+        name: Arc::new(String::from("u64_type")),
+        arguments: Arc::new(u64_type_arguments),
+        outputs: Arc::new(u64_type_outputs),
     };
 
     let f32_type_arguments = vec![fluence_faas::IFunctionArg {
@@ -126,9 +126,9 @@ pub fn get_interfaces() {
     let f32_type_outputs = vec![IType::F32];
 
     let f32_type_sign = fluence_faas::FaaSFunctionSignature {
-        name: Rc::new(String::from("f32_type")),
-        arguments: Rc::new(f32_type_arguments),
-        outputs: Rc::new(f32_type_outputs),
+        name: Arc::new(String::from("f32_type")),
+        arguments: Arc::new(f32_type_arguments),
+        outputs: Arc::new(f32_type_outputs),
     };
 
     let f64_type_arguments = vec![fluence_faas::IFunctionArg {
@@ -138,18 +138,18 @@ pub fn get_interfaces() {
     let f64_type_outputs = vec![IType::F64];
 
     let f64_type_sign = fluence_faas::FaaSFunctionSignature {
-        name: Rc::new(String::from("f64_type")),
-        arguments: Rc::new(f64_type_arguments),
-        outputs: Rc::new(f64_type_outputs),
+        name: Arc::new(String::from("f64_type")),
+        arguments: Arc::new(f64_type_arguments),
+        outputs: Arc::new(f64_type_outputs),
     };
 
     let empty_type_arguments = vec![];
     let empty_type_outputs = vec![IType::String];
 
     let empty_type_sign = fluence_faas::FaaSFunctionSignature {
-        name: Rc::new(String::from("empty_type")),
-        arguments: Rc::new(empty_type_arguments),
-        outputs: Rc::new(empty_type_outputs),
+        name: Arc::new(String::from("empty_type")),
+        arguments: Arc::new(empty_type_arguments),
+        outputs: Arc::new(empty_type_outputs),
     };
 
     let bool_type_arguments = vec![fluence_faas::IFunctionArg {
@@ -159,9 +159,9 @@ pub fn get_interfaces() {
     let bool_type_outputs = vec![IType::I32];
 
     let bool_type_sign = fluence_faas::FaaSFunctionSignature {
-        name: Rc::new(String::from("bool_type")),
-        arguments: Rc::new(bool_type_arguments),
-        outputs: Rc::new(bool_type_outputs),
+        name: Arc::new(String::from("bool_type")),
+        arguments: Arc::new(bool_type_arguments),
+        outputs: Arc::new(bool_type_outputs),
     };
 
     let all_types_arguments = vec![
@@ -217,9 +217,9 @@ pub fn get_interfaces() {
     let all_types_outputs = vec![IType::Array(Box::new(IType::U8))];
 
     let all_types_sign = fluence_faas::FaaSFunctionSignature {
-        name: Rc::new(String::from("all_types")),
-        arguments: Rc::new(all_types_arguments),
-        outputs: Rc::new(all_types_outputs),
+        name: Arc::new(String::from("all_types")),
+        arguments: Arc::new(all_types_arguments),
+        outputs: Arc::new(all_types_outputs),
     };
 
     let functions = vec![
@@ -0,0 +1,197 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use fluence_faas::AsyncFluenceFaaS;
+use fluence_faas::FaaSError;
+use fluence_faas::FluenceFaaS;
+
+use pretty_assertions::assert_eq;
+
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc::channel;
+use std::sync::Arc;
+use std::time::Duration;
+
+fn create_async_faas(queue_capacity: usize) -> AsyncFluenceFaaS {
+    let call_parameters_config_path = "../examples/call_parameters/Config.toml";
+
+    let call_parameters_config_raw = std::fs::read(call_parameters_config_path)
+        .expect("../examples/call_parameters/Config.toml should presence");
+
+    let mut call_parameters_config: fluence_faas::TomlFaaSConfig =
+        toml::from_slice(&call_parameters_config_raw)
+            .expect("call_parameters config should be well-formed");
+    call_parameters_config.modules_dir =
+        Some(String::from("../examples/call_parameters/artifacts"));
+
+    AsyncFluenceFaaS::new(
+        move || FluenceFaaS::with_raw_config(call_parameters_config),
+        "call_parameters",
+        queue_capacity,
+    )
+    .unwrap_or_else(|e| panic!("can't create async FaaS: {:?}", e))
+}
+
+fn call_parameters(call_id: &str) -> fluence_sdk_main::CallParameters {
+    fluence_sdk_main::CallParameters {
+        call_id: call_id.to_string(),
+        user_name: String::from("root"),
+        application_id: String::from("0x31337"),
+    }
+}
+
+#[tokio::test]
+async fn async_calls() {
+    let faas = create_async_faas(4);
+
+    let calls = (0..4)
+        .map(|id| {
+            faas.call_with_json(
+                "call_parameters",
+                "call_parameters",
+                serde_json::json!([]),
+                call_parameters(&id.to_string()),
+            )
+            .unwrap_or_else(|e| panic!("can't queue call_parameters: {:?}", e))
+        })
+        .collect::<Vec<_>>();
+
+    for (id, call) in calls.into_iter().enumerate() {
+        let result = call
+            .await
+            .unwrap_or_else(|e| panic!("can't invoke call_parameters: {:?}", e));
+        assert_eq!(result, serde_json::json!(format!("{}\nroot\n0x31337", id)));
+    }
+}
+
+#[tokio::test]
+async fn full_queue_rejects_calls() {
+    let faas = create_async_faas(1);
+
+    // occupy the executor until the test releases it
+    let (release, released) = channel::<()>();
+    let blocker = faas
+        .executor()
+        .execute(move |_| released.recv().is_ok())
+        .expect("executor should accept the first request");
+    // the only place of the queue is taken as soon as the blocker is started
+    while faas.executor().execute(|_| ()).is_err() {
+        tokio::time::delay_for(Duration::from_millis(10)).await;
+    }
+
+    let rejected = faas.call_with_json(
+        "call_parameters",
+        "call_parameters",
+        serde_json::json!([]),
+        call_parameters("rejected"),
+    );
+    assert!(matches!(rejected, Err(FaaSError::ExecutorQueueFull(_))));
+
+    release
+        .send(())
+        .expect("blocker should wait for the release");
+    assert!(blocker.await.expect("blocker should be executed"));
+}
+
+#[tokio::test]
+async fn timed_out_call_is_cancelled() {
+    let faas = create_async_faas(4);
+
+    let (release, released) = channel::<()>();
+    let blocker = faas
+        .executor()
+        .execute(move |_| released.recv().is_ok())
+        .expect("executor should accept the blocker");
+
+    let executed = Arc::new(AtomicBool::new(false));
+    let executed_flag = executed.clone();
+    let call = faas
+        .executor()
+        .execute(move |_| executed_flag.store(true, Ordering::SeqCst))
+        .expect("executor should accept the call");
+
+    let timed_out = tokio::time::timeout(Duration::from_millis(50), call).await;
+    assert!(timed_out.is_err());
+
+    release
+        .send(())
+        .expect("blocker should wait for the release");
+    assert!(blocker.await.expect("blocker should be executed"));
+
+    // requests are executed in order, so the cancelled one has been already skipped
+    faas.executor()
+        .execute(|_| ())
+        .expect("executor should accept a request")
+        .await
+        .expect("request should be executed");
+    assert!(!executed.load(Ordering::SeqCst));
+}
+
+#[tokio::test]
+async fn drop_does_not_wait_for_queued_calls() {
+    let faas = create_async_faas(4);
+
+    let (release, released) = channel::<()>();
+    let blocker = faas
+        .executor()
+        .execute(move |_| released.recv().is_ok())
+        .expect("executor should accept the blocker");
+    let call = faas
+        .call_with_json(
+            "call_parameters",
+            "call_parameters",
+            serde_json::json!([]),
+            call_parameters("queued"),
+        )
+        .unwrap_or_else(|e| panic!("can't queue call_parameters: {:?}", e));
+
+    // the blocker is still running, so a blocking drop would never return
+    drop(faas);
+
+    release
+        .send(())
+        .expect("blocker should wait for the release");
+    assert!(blocker.await.expect("blocker should be executed"));
+    let result = call
+        .await
+        .unwrap_or_else(|e| panic!("queued call should be executed: {:?}", e));
+    assert_eq!(result, serde_json::json!("queued\nroot\n0x31337"));
+}
+
+#[tokio::test]
+async fn panicked_request_does_not_stop_executor() {
+    let faas = create_async_faas(4);
+
+    let panicked = faas
+        .executor()
+        .execute(|_| panic!("request failed"))
+        .expect("executor should accept the request")
+        .await;
+    let result = faas
+        .call_with_json(
+            "call_parameters",
+            "call_parameters",
+            serde_json::json!([]),
+            call_parameters("after_panic"),
+        )
+        .unwrap_or_else(|e| panic!("can't queue call_parameters: {:?}", e))
+        .await
+        .unwrap_or_else(|e| panic!("executor should survive the panic: {:?}", e));
+
+    assert!(matches!(panicked, Err(FaaSError::CallPanicked(_))));
+    assert_eq!(result, serde_json::json!("after_panic\nroot\n0x31337"));
+}
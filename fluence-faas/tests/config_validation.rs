@@ -0,0 +1,84 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use fluence_faas::ConfigDiagnostic;
+use fluence_faas::TomlFaaSConfig;
+
+use pretty_assertions::assert_eq;
+
+#[test]
+pub fn valid_config() {
+    let mut config = TomlFaaSConfig::load("../examples/greeting/Config.toml")
+        .unwrap_or_else(|e| panic!("greeting config should be well-formed: {:?}", e));
+    config.modules_dir = Some(String::from("../examples/greeting/artifacts"));
+
+    assert_eq!(config.validate(), vec![]);
+}
+
+#[test]
+pub fn all_problems_reported() {
+    let config: TomlFaaSConfig = toml::from_str(
+        r#"
+        modules_dir = "../examples/greeting/artifacts"
+
+        [[module]]
+            name = "greeting"
+
+            [module.mounted_binaries]
+            ipfs = "/non/existent/ipfs"
+
+        [[module]]
+            name = "greeting"
+
+            [module.wasi]
+            preopened_files = ["/non/existent/dir"]
+            mapped_dirs = { "tmp" = "/non/existent/dir", "data" = { path = ".", max_bytes = "lots" } }
+
+        [[module]]
+            name = "non_existent"
+        "#,
+    )
+    .expect("config should be well-formed");
+
+    let expected = vec![
+        ConfigDiagnostic::new(
+            "module[0].mounted_binaries.ipfs",
+            r#"binary "/non/existent/ipfs" isn't found on the host"#,
+        ),
+        ConfigDiagnostic::new(
+            "module[1].name",
+            r#"module with name "greeting" is duplicated"#,
+        ),
+        ConfigDiagnostic::new(
+            "module[1].wasi.preopened_files[0]",
+            r#""/non/existent/dir" doesn't exist"#,
+        ),
+        ConfigDiagnostic::new(
+            "module[1].wasi.mapped_dirs.data",
+            r#"max_bytes: "lots" isn't a valid size"#,
+        ),
+        ConfigDiagnostic::new(
+            "module[1].wasi.mapped_dirs.tmp",
+            r#""/non/existent/dir" isn't an existing directory"#,
+        ),
+        ConfigDiagnostic::new(
+            "module[2].name",
+            r#"module "non_existent" isn't found in modules_dir: "../examples/greeting/artifacts/non_existent.wasm" doesn't exist"#,
+        ),
+    ];
+
+    assert_eq!(config.validate(), expected);
+}
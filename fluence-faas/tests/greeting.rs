@@ -20,7 +20,7 @@ use fluence_faas::IValue;
 
 use pretty_assertions::assert_eq;
 
-use std::rc::Rc;
+use std::sync::Arc;
 
 #[test]
 pub fn greeting() {
@@ -81,15 +81,16 @@ pub fn get_interfaces() {
     let output_types = vec![fluence_faas::IType::String];
 
     let greeting_sign = fluence_faas::FaaSFunctionSignature {
-        name: Rc::new(String::from("greeting")),
-        arguments: Rc::new(arguments),
-        outputs: Rc::new(output_types),
+        name: Arc::new(String::from("greeting")),
+        arguments: Arc::new(arguments),
+        outputs: Arc::new(output_types),
     };
 
     let record_types = std::collections::HashMap::new();
     let module_interface = FaaSModuleInterface {
         record_types: &record_types,
         function_signatures: vec![greeting_sign],
+        metadata: None,
     };
 
     let mut modules = std::collections::HashMap::new();
@@ -97,3 +98,36 @@ pub fn get_interfaces() {
 
     assert_eq!(interface, fluence_faas::FaaSInterface { modules });
 }
+
+#[test]
+pub fn effective_config() {
+    let greeting_config_path = "../examples/greeting/Config.toml";
+
+    let greeting_config_raw = std::fs::read(greeting_config_path)
+        .expect("../examples/greeting/Config.toml should presence");
+
+    let mut greeting_config: fluence_faas::TomlFaaSConfig =
+        toml::from_slice(&greeting_config_raw).expect("greeting config should be well-formed");
+    greeting_config.modules_dir = Some(String::from("../examples/greeting/artifacts"));
+
+    let faas = FluenceFaaS::with_raw_config(greeting_config)
+        .unwrap_or_else(|e| panic!("can't create Fluence FaaS instance: {:?}", e));
+
+    let effective_config = faas.effective_config();
+    assert_eq!(effective_config.module.len(), 1);
+
+    let module_config = &effective_config.module[0];
+    assert_eq!(module_config.name, "greeting");
+    assert_eq!(module_config.mem_pages_count, 1);
+    assert!(!module_config.logger_enabled);
+    assert_eq!(
+        module_config.host_imports,
+        vec![fluence_faas::HostImportSignature {
+            name: String::from("get_call_parameters"),
+            signature: String::from("() -> Record(0)"),
+        }]
+    );
+
+    assert!(effective_config.to_toml_string().is_ok());
+    assert!(effective_config.to_json_string().is_ok());
+}
@@ -0,0 +1,123 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use fluence_faas::FluenceFaaS;
+use fluence_faas::IValue;
+use fluence_faas::TomlInstancePoolConfig;
+
+use pretty_assertions::assert_eq;
+
+const POOL_SIZE: usize = 4;
+const THREADS_COUNT: usize = 8;
+const CALLS_PER_THREAD: usize = 20;
+
+fn create_faas(reset_after_call: bool) -> FluenceFaaS {
+    let call_parameters_config_path = "../examples/call_parameters/Config.toml";
+
+    let call_parameters_config_raw = std::fs::read(call_parameters_config_path)
+        .expect("../examples/call_parameters/Config.toml should presence");
+
+    let mut call_parameters_config: fluence_faas::TomlFaaSConfig =
+        toml::from_slice(&call_parameters_config_raw)
+            .expect("call_parameters config should be well-formed");
+    call_parameters_config.modules_dir =
+        Some(String::from("../examples/call_parameters/artifacts"));
+    call_parameters_config.module[0].config.instance_pool = Some(TomlInstancePoolConfig {
+        size: Some(POOL_SIZE),
+        reset_after_call: Some(reset_after_call),
+    });
+
+    FluenceFaaS::with_raw_config(call_parameters_config)
+        .unwrap_or_else(|e| panic!("can't create Fluence FaaS instance: {:?}", e))
+}
+
+fn call_parameters(call_id: String) -> fluence_sdk_main::CallParameters {
+    fluence_sdk_main::CallParameters {
+        call_id,
+        user_name: String::from("root"),
+        application_id: String::from("0x31337"),
+    }
+}
+
+#[test]
+pub fn instance_pool_serves_concurrent_calls() {
+    let faas = create_faas(false);
+    let pool = faas
+        .instance_pool("call_parameters")
+        .expect("call_parameters should have an instance pool");
+    assert_eq!(pool.free_instances_count(), POOL_SIZE);
+
+    let handles = (0..THREADS_COUNT)
+        .map(|thread_id| {
+            let pool = pool.clone();
+            std::thread::spawn(move || {
+                for call_id in 0..CALLS_PER_THREAD {
+                    let call_id = format!("{}.{}", thread_id, call_id);
+                    let result = pool
+                        .call_with_json(
+                            "call_parameters",
+                            serde_json::json!([]),
+                            call_parameters(call_id.clone()),
+                        )
+                        .unwrap_or_else(|e| panic!("can't invoke call_parameters: {:?}", e));
+
+                    assert_eq!(
+                        result,
+                        serde_json::json!(format!("{}\nroot\n0x31337", call_id))
+                    );
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for handle in handles {
+        handle.join().expect("thread calling the pool panicked");
+    }
+
+    assert_eq!(pool.free_instances_count(), POOL_SIZE);
+}
+
+#[test]
+pub fn faas_dispatches_calls_to_instance_pool() {
+    let mut faas = create_faas(true);
+
+    for call_id in 0..CALLS_PER_THREAD {
+        let call_id = call_id.to_string();
+        let result = faas
+            .call_with_ivalues(
+                "call_parameters",
+                "call_parameters",
+                &[],
+                call_parameters(call_id.clone()),
+            )
+            .unwrap_or_else(|e| panic!("can't invoke call_parameters: {:?}", e));
+
+        assert_eq!(
+            result,
+            vec![IValue::String(format!("{}\nroot\n0x31337", call_id))]
+        );
+    }
+
+    let effective_config = faas.effective_config();
+    let instance_pool = effective_config.module[0]
+        .instance_pool
+        .expect("effective config should contain the instance pool");
+    assert_eq!(instance_pool.size, POOL_SIZE);
+    assert!(instance_pool.reset_after_call);
+    // calls are served by the pool, so the module isn't instantiated by FaaS itself
+    assert!(effective_config.module[0].lazy_instantiation);
+    assert!(faas.get_module_interface("call_parameters").is_some());
+}
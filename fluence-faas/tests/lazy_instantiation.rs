@@ -0,0 +1,151 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use fluence_faas::FluenceFaaS;
+use fluence_faas::IValue;
+
+use pretty_assertions::assert_eq;
+
+use std::time::Duration;
+
+const IDLE_TIMEOUT_SECS: u64 = 1;
+
+fn create_faas() -> FluenceFaaS {
+    let greeting_config_path = "../examples/greeting/Config.toml";
+
+    let greeting_config_raw = std::fs::read(greeting_config_path)
+        .expect("../examples/greeting/Config.toml should presence");
+
+    let mut greeting_config: fluence_faas::TomlFaaSConfig =
+        toml::from_slice(&greeting_config_raw).expect("greeting config should be well-formed");
+    greeting_config.modules_dir = Some(String::from("../examples/greeting/artifacts"));
+    greeting_config.module[0].config.lazy_instantiation = Some(true);
+    greeting_config.module[0].config.idle_timeout_secs = Some(IDLE_TIMEOUT_SECS);
+
+    FluenceFaaS::with_raw_config(greeting_config)
+        .unwrap_or_else(|e| panic!("can't create Fluence FaaS instance: {:?}", e))
+}
+
+fn is_instantiated(faas: &FluenceFaaS) -> bool {
+    faas.module_stats("greeting")
+        .unwrap_or_else(|e| panic!("can't get module stats: {:?}", e))
+        .instantiated
+}
+
+fn greet(faas: &mut FluenceFaaS, name: &str) -> Vec<IValue> {
+    faas.call_with_ivalues(
+        "greeting",
+        "greeting",
+        &[IValue::String(String::from(name))],
+        <_>::default(),
+    )
+    .unwrap_or_else(|e| panic!("can't invoke greeting: {:?}", e))
+}
+
+#[test]
+pub fn module_is_instantiated_on_first_call() {
+    let mut faas = create_faas();
+    assert!(!is_instantiated(&faas));
+
+    let interface = faas.get_interface();
+    let greeting_interface = interface
+        .modules
+        .get("greeting")
+        .expect("interface of a lazy module should be available");
+    assert_eq!(greeting_interface.function_signatures.len(), 1);
+    assert!(!is_instantiated(&faas));
+
+    let result = greet(&mut faas, "Fluence");
+    assert_eq!(result, vec![IValue::String(String::from("Hi, Fluence"))]);
+    assert!(is_instantiated(&faas));
+
+    let effective_config = faas.effective_config();
+    assert!(effective_config.module[0].lazy_instantiation);
+    assert_eq!(
+        effective_config.module[0].idle_timeout_secs,
+        Some(IDLE_TIMEOUT_SECS)
+    );
+}
+
+#[test]
+pub fn wasi_state_does_not_instantiate_module() {
+    let mut faas = create_faas();
+
+    let wasi_state_before_call = faas.module_wasi_state("greeting").is_ok();
+    let instantiated_by_wasi_state = is_instantiated(&faas);
+    greet(&mut faas, "Fluence");
+    let wasi_state_after_call = faas.module_wasi_state("greeting").is_ok();
+
+    assert!(!wasi_state_before_call);
+    assert!(!instantiated_by_wasi_state);
+    assert!(wasi_state_after_call);
+}
+
+#[test]
+pub fn idle_module_is_evicted_with_its_memory() {
+    let mut faas = create_faas();
+    greet(&mut faas, "Fluence");
+
+    assert!(faas.evict_idle_modules().is_empty());
+    let memory_before_eviction = faas
+        .module_memory_snapshot("greeting")
+        .unwrap_or_else(|e| panic!("can't take a memory snapshot: {:?}", e));
+
+    std::thread::sleep(Duration::from_secs(IDLE_TIMEOUT_SECS) + Duration::from_millis(100));
+    assert_eq!(faas.evict_idle_modules(), vec![String::from("greeting")]);
+    assert!(!is_instantiated(&faas));
+
+    let memory_after_eviction = faas
+        .module_memory_snapshot("greeting")
+        .unwrap_or_else(|e| panic!("can't take a memory snapshot: {:?}", e));
+    assert!(memory_before_eviction == memory_after_eviction);
+
+    let result = greet(&mut faas, "Wasm");
+    assert_eq!(result, vec![IValue::String(String::from("Hi, Wasm"))]);
+}
+
+#[test]
+pub fn idle_timeout_with_memory_fs_is_rejected() {
+    let mut module = fluence_faas::TomlFaaSNamedModuleConfig::new("greeting");
+    module.config.idle_timeout_secs = Some(IDLE_TIMEOUT_SECS);
+    module.config.wasi = Some(fluence_faas::TomlWASIConfig {
+        fs: Some(String::from("memory")),
+        ..<_>::default()
+    });
+    let config = fluence_faas::TomlFaaSConfig {
+        module: vec![module],
+        ..<_>::default()
+    };
+
+    assert!(fluence_faas::from_toml_faas_config(config).is_err());
+}
+
+#[test]
+pub fn idle_timeout_in_deterministic_mode_is_rejected() {
+    let mut module = fluence_faas::TomlFaaSNamedModuleConfig::new("greeting");
+    module.config.idle_timeout_secs = Some(IDLE_TIMEOUT_SECS);
+    module.config.deterministic = Some(<_>::default());
+    let config = fluence_faas::TomlFaaSConfig {
+        module: vec![module],
+        ..<_>::default()
+    };
+
+    assert!(fluence_faas::from_toml_faas_config(config.clone()).is_err());
+    let diagnostics = config.validate();
+    assert!(diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.key_path == "module[0].idle_timeout_secs"));
+}
@@ -0,0 +1,96 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use fluence_faas::FaaSError;
+use fluence_faas::FluenceFaaS;
+use fluence_faas::TomlFaaSConfig;
+use fluence_faas::module_hash;
+
+use std::collections::HashMap;
+
+const GREETING_WASM_PATH: &str = "../examples/greeting/artifacts/greeting.wasm";
+
+fn greeting_hash() -> String {
+    let greeting_bytes =
+        std::fs::read(GREETING_WASM_PATH).expect("../examples/greeting/artifacts should presence");
+    module_hash(&greeting_bytes)
+}
+
+fn load_greeting_with_hash(hash: String) -> Result<FluenceFaaS, FaaSError> {
+    let mut greeting_config = TomlFaaSConfig::load("../examples/greeting/Config.toml")
+        .unwrap_or_else(|e| panic!("greeting config should be well-formed: {:?}", e));
+    greeting_config.modules_dir = Some(String::from("../examples/greeting/artifacts"));
+    greeting_config.module[0].hash = Some(hash);
+
+    FluenceFaaS::with_raw_config(greeting_config)
+}
+
+#[test]
+pub fn matching_hash() {
+    assert!(load_greeting_with_hash(greeting_hash()).is_ok());
+
+    let uppercase_hash = greeting_hash().replace("blake3:", "").to_uppercase();
+    assert!(load_greeting_with_hash(format!("blake3:{}", uppercase_hash)).is_ok());
+}
+
+#[test]
+pub fn mismatched_hash() {
+    let other_hash = module_hash(b"not a greeting module");
+
+    assert!(matches!(
+        load_greeting_with_hash(other_hash),
+        Err(FaaSError::ModuleHashMismatch(_))
+    ));
+}
+
+#[test]
+pub fn malformed_hash() {
+    let digest = greeting_hash().replace("blake3:", "");
+
+    assert!(matches!(
+        load_greeting_with_hash(format!("sha256:{}", digest)),
+        Err(FaaSError::ConfigParseError(_))
+    ));
+    assert!(matches!(
+        load_greeting_with_hash(format!("blake3:{}", &digest[2..])),
+        Err(FaaSError::ConfigParseError(_))
+    ));
+}
+
+#[test]
+pub fn load_module_checks_hash() {
+    let greeting_bytes =
+        std::fs::read(GREETING_WASM_PATH).expect("../examples/greeting/artifacts should presence");
+    let mut faas =
+        FluenceFaaS::with_modules::<fluence_faas::FaaSConfig>(HashMap::new(), <_>::default())
+            .unwrap_or_else(|e| panic!("can't create Fluence FaaS instance: {:?}", e));
+
+    let mismatched_config = fluence_faas::FaaSModuleConfig {
+        hash: Some(module_hash(b"not a greeting module")),
+        ..<_>::default()
+    };
+    let result = faas.load_module("greeting", &greeting_bytes, Some(mismatched_config));
+    assert!(matches!(result, Err(FaaSError::ModuleHashMismatch(_))));
+    assert!(faas.get_interface().modules.is_empty());
+
+    let config = fluence_faas::FaaSModuleConfig {
+        hash: Some(greeting_hash()),
+        ..<_>::default()
+    };
+    faas.load_module("greeting", &greeting_bytes, Some(config))
+        .unwrap_or_else(|e| panic!("can't load greeting: {:?}", e));
+    assert!(faas.get_interface().modules.contains_key("greeting"));
+}
@@ -0,0 +1,99 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use fluence_faas::FluenceFaaS;
+use fluence_faas::IValue;
+use fluence_faas::InstanceMode;
+
+use pretty_assertions::assert_eq;
+
+fn create_faas(instance_mode: &str) -> FluenceFaaS {
+    let greeting_config_path = "../examples/greeting/Config.toml";
+
+    let greeting_config_raw = std::fs::read(greeting_config_path)
+        .expect("../examples/greeting/Config.toml should presence");
+
+    let mut greeting_config: fluence_faas::TomlFaaSConfig =
+        toml::from_slice(&greeting_config_raw).expect("greeting config should be well-formed");
+    greeting_config.modules_dir = Some(String::from("../examples/greeting/artifacts"));
+    greeting_config.module[0].config.instance_mode = Some(String::from(instance_mode));
+
+    FluenceFaaS::with_raw_config(greeting_config)
+        .unwrap_or_else(|e| panic!("can't create Fluence FaaS instance: {:?}", e))
+}
+
+fn greet(faas: &mut FluenceFaaS, name: &str) -> Vec<IValue> {
+    faas.call_with_ivalues(
+        "greeting",
+        "greeting",
+        &[IValue::String(String::from(name))],
+        <_>::default(),
+    )
+    .unwrap_or_else(|e| panic!("can't invoke greeting: {:?}", e))
+}
+
+#[test]
+pub fn per_call_instance_is_discarded_after_call() {
+    let mut faas = create_faas("per_call");
+    let pristine_memory = faas
+        .module_memory_snapshot("greeting")
+        .unwrap_or_else(|e| panic!("can't take a memory snapshot: {:?}", e));
+
+    for name in &["Fluence", "", "Wasm"] {
+        let result = greet(&mut faas, name);
+        assert_eq!(result, vec![IValue::String(format!("Hi, {}", name))]);
+
+        let memory = faas
+            .module_memory_snapshot("greeting")
+            .unwrap_or_else(|e| panic!("can't take a memory snapshot: {:?}", e));
+        assert!(memory == pristine_memory);
+    }
+
+    let effective_config = faas.effective_config();
+    assert_eq!(
+        effective_config.module[0].instance_mode,
+        InstanceMode::PerCall
+    );
+}
+
+#[test]
+pub fn shared_instance_keeps_state() {
+    let mut faas = create_faas("shared");
+    let initial_memory = faas
+        .module_memory_snapshot("greeting")
+        .unwrap_or_else(|e| panic!("can't take a memory snapshot: {:?}", e));
+
+    greet(&mut faas, "Fluence");
+
+    let memory = faas
+        .module_memory_snapshot("greeting")
+        .unwrap_or_else(|e| panic!("can't take a memory snapshot: {:?}", e));
+    assert!(memory != initial_memory);
+}
+
+#[test]
+pub fn unknown_instance_mode_is_rejected() {
+    let greeting_config = fluence_faas::TomlFaaSConfig {
+        module: vec![{
+            let mut module = fluence_faas::TomlFaaSNamedModuleConfig::new("greeting");
+            module.config.instance_mode = Some(String::from("per_request"));
+            module
+        }],
+        ..<_>::default()
+    };
+
+    assert!(fluence_faas::from_toml_faas_config(greeting_config).is_err());
+}
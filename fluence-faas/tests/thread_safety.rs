@@ -0,0 +1,114 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use fluence_faas::AsyncFluenceFaaS;
+use fluence_faas::FaaSError;
+use fluence_faas::FluenceFaaS;
+use fluence_faas::InstancePool;
+use fluence_faas::IValue;
+
+use pretty_assertions::assert_eq;
+
+use std::sync::Arc;
+
+const THREADS_COUNT: usize = 8;
+const CALLS_PER_THREAD: usize = 50;
+
+fn create_faas() -> Result<FluenceFaaS, FaaSError> {
+    let call_parameters_config_path = "../examples/call_parameters/Config.toml";
+
+    let call_parameters_config_raw = std::fs::read(call_parameters_config_path)
+        .expect("../examples/call_parameters/Config.toml should presence");
+
+    let mut call_parameters_config: fluence_faas::TomlFaaSConfig =
+        toml::from_slice(&call_parameters_config_raw)
+            .expect("call_parameters config should be well-formed");
+    call_parameters_config.modules_dir =
+        Some(String::from("../examples/call_parameters/artifacts"));
+
+    FluenceFaaS::with_raw_config(call_parameters_config)
+}
+
+fn call(faas: &mut FluenceFaaS, thread_id: usize, call_id: usize) {
+    let call_id = format!("{}.{}", thread_id, call_id);
+    let user_name = format!("user_{}", thread_id);
+    let application_id = "0x31337";
+
+    let result = faas
+        .call_with_ivalues(
+            "call_parameters",
+            "call_parameters",
+            &[],
+            fluence_sdk_main::CallParameters {
+                call_id: call_id.clone(),
+                user_name: user_name.clone(),
+                application_id: application_id.to_string(),
+            },
+        )
+        .unwrap_or_else(|e| panic!("can't invoke call_parameters: {:?}", e));
+
+    assert_eq!(
+        result,
+        vec![IValue::String(format!(
+            "{}\n{}\n{}",
+            call_id, user_name, application_id
+        ))]
+    );
+}
+
+#[test]
+pub fn handles_are_send_and_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    // FluenceFaaS itself isn't Send, these handles only send requests to threads owning it
+    assert_send_sync::<AsyncFluenceFaaS>();
+    assert_send_sync::<InstancePool>();
+}
+
+#[test]
+pub fn async_faas_called_from_threads() {
+    let faas = AsyncFluenceFaaS::new(create_faas, "thread_safety", THREADS_COUNT)
+        .unwrap_or_else(|e| panic!("can't create async FaaS: {:?}", e));
+    let faas = Arc::new(faas);
+
+    let handles = (0..THREADS_COUNT)
+        .map(|thread_id| {
+            let faas = faas.clone();
+            std::thread::spawn(move || {
+                for call_id in 0..CALLS_PER_THREAD {
+                    faas.executor()
+                        .execute_blocking(move |faas| call(faas, thread_id, call_id))
+                        .unwrap_or_else(|e| panic!("executor should run the call: {:?}", e));
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for handle in handles {
+        handle.join().expect("thread calling FaaS panicked");
+    }
+}
+
+#[test]
+pub fn creation_error_is_returned() {
+    let faas = AsyncFluenceFaaS::new(
+        || Err(FaaSError::NoSuchModule(String::from("missing"))),
+        "creation_error",
+        0,
+    );
+
+    assert!(matches!(faas, Err(FaaSError::NoSuchModule(_))));
+}
@@ -23,6 +23,14 @@ pub const DESCRIPTION: &str = env!("CARGO_PKG_DESCRIPTION");
 pub const IN_WASM_PATH: &str = "in-wasm-path";
 pub const WIT_PATH: &str = "wit-path";
 pub const OUT_WASM_PATH: &str = "out-wasm-path";
+pub const CONFIG_PATH: &str = "config-path";
+pub const SECRET_KEY_PATH: &str = "secret-key-path";
+pub const PUBLIC_KEY: &str = "public-key";
+pub const SIGNATURE_PATH: &str = "signature-path";
+pub const DETACHED: &str = "detached";
+pub const WASM_PATHS: &str = "wasm-paths";
+pub const DATA_DIR: &str = "data-dir";
+pub const OUT_BUNDLE_PATH: &str = "out-bundle-path";
 
 pub fn build<'a, 'b>() -> App<'a, 'b> {
     SubCommand::with_name("build")
@@ -64,6 +72,110 @@ pub fn show_wit<'a, 'b>() -> App<'a, 'b> {
             .help("path to the Wasm file")])
 }
 
+pub fn info<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("info")
+        .about("Show build metadata embedded into provided Wasm file")
+        .setting(clap::AppSettings::ArgRequiredElseHelp)
+        .args(&[Arg::with_name(IN_WASM_PATH)
+            .required(true)
+            .takes_value(true)
+            .short("i")
+            .help("path to the Wasm file")])
+}
+
+pub fn sign<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("sign")
+        .about("Sign provided Wasm file with an Ed25519 key")
+        .setting(clap::AppSettings::ArgRequiredElseHelp)
+        .args(&[
+            Arg::with_name(IN_WASM_PATH)
+                .required(true)
+                .takes_value(true)
+                .short("i")
+                .help("path to the Wasm file"),
+            Arg::with_name(SECRET_KEY_PATH)
+                .required(true)
+                .takes_value(true)
+                .short("k")
+                .help("path to a file with a hex-encoded Ed25519 secret key"),
+            Arg::with_name(OUT_WASM_PATH)
+                .takes_value(true)
+                .short("o")
+                .help("path to result file with embedded signature"),
+            Arg::with_name(DETACHED)
+                .long("detached")
+                .help("write the signature to <wasm file>.sig instead of embedding it"),
+        ])
+}
+
+pub fn verify<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("verify")
+        .about("Verify a signature of provided Wasm file")
+        .setting(clap::AppSettings::ArgRequiredElseHelp)
+        .args(&[
+            Arg::with_name(IN_WASM_PATH)
+                .required(true)
+                .takes_value(true)
+                .short("i")
+                .help("path to the Wasm file"),
+            Arg::with_name(PUBLIC_KEY)
+                .required(true)
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .short("p")
+                .help("hex-encoded Ed25519 public key, could be specified several times"),
+            Arg::with_name(SIGNATURE_PATH)
+                .takes_value(true)
+                .short("s")
+                .help("path to a detached signature, <wasm file>.sig is used if it exists"),
+        ])
+}
+
+pub fn hash<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("hash")
+        .about("Print hashes of provided Wasm files in the service config syntax")
+        .setting(clap::AppSettings::ArgRequiredElseHelp)
+        .args(&[Arg::with_name(WASM_PATHS)
+            .required(true)
+            .takes_value(true)
+            .multiple(true)
+            .help("paths to Wasm files or directories with them")])
+}
+
+pub fn package<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("package")
+        .about("Pack a service config, its modules and seed data into a bundle")
+        .setting(clap::AppSettings::ArgRequiredElseHelp)
+        .args(&[
+            Arg::with_name(CONFIG_PATH)
+                .required(true)
+                .takes_value(true)
+                .short("c")
+                .help("path to the service config"),
+            Arg::with_name(DATA_DIR)
+                .takes_value(true)
+                .short("d")
+                .help("path to a directory with seed data of the service local dir"),
+            Arg::with_name(OUT_BUNDLE_PATH)
+                .required(true)
+                .takes_value(true)
+                .short("o")
+                .help("path to result bundle"),
+        ])
+}
+
+pub fn check_config<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("check-config")
+        .about("Check provided service config and print all found problems")
+        .setting(clap::AppSettings::ArgRequiredElseHelp)
+        .args(&[Arg::with_name(CONFIG_PATH)
+            .required(true)
+            .takes_value(true)
+            .short("c")
+            .help("path to the service config")])
+}
+
 pub fn repl<'a, 'b>() -> App<'a, 'b> {
     SubCommand::with_name("repl")
         .about("Start Fluence application service REPL")
@@ -16,6 +16,7 @@
 
 use crate::Result;
 use crate::errors::CLIError;
+use crate::metadata::PackagesMetadata;
 
 use std::process::Command;
 
@@ -24,7 +25,10 @@ use std::process::Command;
 enum DiagnosticMessage {
     BuildScriptExecuted,
     BuildFinished,
-    CompilerArtifact { filenames: Vec<String> },
+    CompilerArtifact {
+        package_id: String,
+        filenames: Vec<String>,
+    },
     RunWithArgs,
 }
 
@@ -53,13 +57,18 @@ pub(crate) fn build(trailing_args: Vec<&str>) -> Result<()> {
         )));
     }
 
-    let mut wasms: Vec<String> = Vec::new();
+    let mut wasms: Vec<(String, String)> = Vec::new();
     for line in output.lines() {
-        if let Ok(DiagnosticMessage::CompilerArtifact { filenames }) = serde_json::from_str(line) {
+        if let Ok(DiagnosticMessage::CompilerArtifact {
+            package_id,
+            filenames,
+        }) = serde_json::from_str(line)
+        {
             wasms.extend(
                 filenames
                     .into_iter()
                     .filter(|name| name.ends_with(".wasm"))
+                    .map(|name| (package_id.clone(), name))
                     .collect::<Vec<_>>(),
             )
         }
@@ -70,9 +79,13 @@ pub(crate) fn build(trailing_args: Vec<&str>) -> Result<()> {
         return Ok(());
     }
 
-    for wasm in wasms {
+    let packages_metadata = PackagesMetadata::load()?;
+    for (package_id, wasm) in wasms {
         let wasm_path = std::path::PathBuf::from(wasm);
-        fce_wit_generator::embed_wit(wasm_path)?;
+        fce_wit_generator::embed_wit(wasm_path.clone())?;
+
+        let metadata = packages_metadata.module_metadata(&package_id)?;
+        fce_wit_parser::embed_metadata(wasm_path, &metadata)?;
     }
 
     Ok(())
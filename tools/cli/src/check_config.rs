@@ -0,0 +1,43 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::Result;
+use crate::errors::CLIError;
+
+use fluence_app_service::TomlAppServiceConfig;
+
+use std::path::PathBuf;
+
+/// Loads a service config, validates it and prints all found problems.
+pub(crate) fn check_config(config_path: PathBuf) -> Result<()> {
+    let config = TomlAppServiceConfig::load(config_path.clone())?;
+    let diagnostics = config.validate();
+
+    if diagnostics.is_empty() {
+        println!("{:?}: config is valid", config_path);
+        return Ok(());
+    }
+
+    for diagnostic in diagnostics.iter() {
+        println!("{}", diagnostic);
+    }
+
+    Err(CLIError::InvalidConfig(format!(
+        "{:?}: {} problem(s) found",
+        config_path,
+        diagnostics.len()
+    )))
+}
@@ -16,6 +16,8 @@
 
 use fce_wit_generator::WITGeneratorError;
 use fce_wit_parser::WITParserError;
+use fce_module_signature::ModuleSignatureError;
+use fluence_app_service::AppServiceError;
 
 use std::io::Error as StdIOError;
 use std::error::Error;
@@ -36,6 +38,21 @@ pub enum CLIError {
 
     /// Various errors related to I/O operations.
     IOError(StdIOError),
+
+    /// An error occurred while loading a service config.
+    ConfigLoadError(AppServiceError),
+
+    /// A service config has problems found by validation.
+    InvalidConfig(String),
+
+    /// An error occurred while collecting metadata of a built module.
+    MetadataError(String),
+
+    /// An error occurred while packing a service bundle.
+    PackageError(AppServiceError),
+
+    /// A module can't be signed or its signature is invalid.
+    ModuleSignatureError(ModuleSignatureError),
 }
 
 impl Error for CLIError {}
@@ -48,6 +65,13 @@ impl std::fmt::Display for CLIError {
             CLIError::WITParserError(err) => write!(f, "{}", err),
             CLIError::WasmCompilationError(err) => write!(f, "{}", err),
             CLIError::IOError(err) => write!(f, "{:?}", err),
+            CLIError::ConfigLoadError(err) => write!(f, "{}", err),
+            CLIError::InvalidConfig(err_msg) => write!(f, "{}", err_msg),
+            CLIError::ModuleSignatureError(err) => write!(f, "{}", err),
+            CLIError::PackageError(err) => write!(f, "failed to pack the bundle: {}", err),
+            CLIError::MetadataError(err_msg) => {
+                write!(f, "failed to collect module metadata: {}", err_msg)
+            }
         }
     }
 }
@@ -69,3 +93,15 @@ impl From<StdIOError> for CLIError {
         CLIError::IOError(err)
     }
 }
+
+impl From<AppServiceError> for CLIError {
+    fn from(err: AppServiceError) -> Self {
+        CLIError::ConfigLoadError(err)
+    }
+}
+
+impl From<ModuleSignatureError> for CLIError {
+    fn from(err: ModuleSignatureError) -> Self {
+        CLIError::ModuleSignatureError(err)
+    }
+}
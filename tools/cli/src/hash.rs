@@ -0,0 +1,52 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::Result;
+
+use std::path::PathBuf;
+
+/// Prints `[[module]]` entries with names and hashes of provided modules, directories are
+/// expanded to all Wasm files in them.
+pub(crate) fn print_hashes(wasm_paths: Vec<PathBuf>) -> Result<()> {
+    let mut modules = Vec::new();
+    for path in wasm_paths {
+        if path.is_dir() {
+            for entry in std::fs::read_dir(path)? {
+                let path = entry?.path();
+                if path.extension().map_or(false, |e| e == "wasm") {
+                    modules.push(path);
+                }
+            }
+        } else {
+            modules.push(path);
+        }
+    }
+    modules.sort();
+
+    for module in modules {
+        let module_bytes = std::fs::read(&module)?;
+        let name = module.file_stem().unwrap_or_default().to_string_lossy();
+
+        println!("[[module]]");
+        println!("    name = \"{}\"", name);
+        println!(
+            "    hash = \"{}\"\n",
+            fluence_app_service::module_hash(&module_bytes)
+        );
+    }
+
+    Ok(())
+}
@@ -27,7 +27,11 @@
 
 mod args;
 mod build;
+mod check_config;
 mod errors;
+mod hash;
+mod metadata;
+mod signature;
 
 pub(crate) type Result<T> = std::result::Result<T, crate::errors::CLIError>;
 
@@ -39,6 +43,12 @@ pub fn main() -> std::result::Result<(), anyhow::Error> {
         .subcommand(args::build())
         .subcommand(args::embed_wit())
         .subcommand(args::show_wit())
+        .subcommand(args::info())
+        .subcommand(args::sign())
+        .subcommand(args::verify())
+        .subcommand(args::hash())
+        .subcommand(args::package())
+        .subcommand(args::check_config())
         .subcommand(args::repl());
     let arg_matches = app.get_matches();
 
@@ -72,11 +82,84 @@ pub fn main() -> std::result::Result<(), anyhow::Error> {
             let wasm_path = arg.value_of(args::IN_WASM_PATH).unwrap();
             let wasm_path = std::path::PathBuf::from(wasm_path);
 
+            // metadata is printed as WAT comments to keep the output a valid WIT
+            if let Some(metadata) = fce_wit_parser::extract_metadata_from_file(wasm_path.clone())? {
+                for line in metadata.to_string().lines() {
+                    println!(";; {}", line);
+                }
+            }
+
             let result = fce_wit_parser::extract_text_wit(wasm_path)?;
             println!("{}", result);
 
             Ok(())
         }
+        ("info", Some(arg)) => {
+            let wasm_path = arg.value_of(args::IN_WASM_PATH).unwrap();
+            let wasm_path = std::path::PathBuf::from(wasm_path);
+
+            match fce_wit_parser::extract_metadata_from_file(wasm_path)? {
+                Some(metadata) => println!("{}", metadata),
+                None => println!("the module doesn't contain metadata, build it with `fce build`"),
+            }
+
+            Ok(())
+        }
+        ("sign", Some(arg)) => {
+            let wasm_path = std::path::PathBuf::from(arg.value_of(args::IN_WASM_PATH).unwrap());
+            let key_path = std::path::PathBuf::from(arg.value_of(args::SECRET_KEY_PATH).unwrap());
+            let out_wasm_path = arg
+                .value_of(args::OUT_WASM_PATH)
+                .map(std::path::PathBuf::from);
+            let detached = arg.is_present(args::DETACHED);
+
+            crate::signature::sign(wasm_path, key_path, out_wasm_path, detached)?;
+
+            Ok(())
+        }
+        ("verify", Some(arg)) => {
+            let wasm_path = std::path::PathBuf::from(arg.value_of(args::IN_WASM_PATH).unwrap());
+            let public_keys = arg.values_of(args::PUBLIC_KEY).unwrap().collect::<Vec<_>>();
+            let signature_path = arg
+                .value_of(args::SIGNATURE_PATH)
+                .map(std::path::PathBuf::from);
+
+            crate::signature::verify(wasm_path, &public_keys, signature_path)?;
+
+            Ok(())
+        }
+        ("hash", Some(arg)) => {
+            let wasm_paths = arg.values_of(args::WASM_PATHS).unwrap();
+            let wasm_paths = wasm_paths.map(std::path::PathBuf::from).collect();
+
+            crate::hash::print_hashes(wasm_paths)?;
+
+            Ok(())
+        }
+        ("package", Some(arg)) => {
+            let config_path = std::path::PathBuf::from(arg.value_of(args::CONFIG_PATH).unwrap());
+            let data_dir = arg.value_of(args::DATA_DIR).map(std::path::PathBuf::from);
+            let bundle_path =
+                std::path::PathBuf::from(arg.value_of(args::OUT_BUNDLE_PATH).unwrap());
+
+            fluence_app_service::ServiceBundle::pack(
+                &config_path,
+                data_dir.as_deref(),
+                &bundle_path,
+            )
+            .map_err(fluence_app_service::AppServiceError::from)
+            .map_err(crate::errors::CLIError::PackageError)?;
+
+            Ok(())
+        }
+        ("check-config", Some(arg)) => {
+            let config_path = arg.value_of(args::CONFIG_PATH).unwrap();
+            let config_path = std::path::PathBuf::from(config_path);
+
+            crate::check_config::check_config(config_path)?;
+
+            Ok(())
+        }
         ("repl", Some(args)) => {
             use std::process::Command;
             // use UNIX-specific API for replacing process image
@@ -0,0 +1,157 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::Result;
+use crate::errors::CLIError;
+
+use fce_wit_parser::ModuleMetadata;
+
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Name of the Fluence Rust SDK crate.
+const SDK_CRATE_NAME: &str = "fluence";
+
+#[derive(serde::Deserialize)]
+struct CargoMetadata {
+    packages: Vec<CargoPackage>,
+    resolve: Option<CargoResolve>,
+}
+
+#[derive(serde::Deserialize)]
+struct CargoPackage {
+    id: String,
+    name: String,
+    version: String,
+    authors: Vec<String>,
+    repository: Option<String>,
+    manifest_path: PathBuf,
+}
+
+#[derive(serde::Deserialize)]
+struct CargoResolve {
+    nodes: Vec<CargoResolveNode>,
+}
+
+#[derive(serde::Deserialize)]
+struct CargoResolveNode {
+    id: String,
+    dependencies: Vec<String>,
+}
+
+/// Metadata of all packages of the current workspace and their dependencies.
+pub(crate) struct PackagesMetadata(CargoMetadata);
+
+impl PackagesMetadata {
+    pub(crate) fn load() -> Result<Self> {
+        let output = Command::new("cargo")
+            .arg("metadata")
+            .arg("--format-version")
+            .arg("1")
+            .output()?;
+
+        if !output.status.success() {
+            return Err(CLIError::MetadataError(format!(
+                "cargo metadata failed with status {}",
+                output.status
+            )));
+        }
+
+        let metadata = serde_json::from_slice(&output.stdout)
+            .map_err(|e| CLIError::MetadataError(format!("{}", e)))?;
+
+        Ok(Self(metadata))
+    }
+
+    /// Collects metadata of a module built from the package with provided id.
+    pub(crate) fn module_metadata(&self, package_id: &str) -> Result<ModuleMetadata> {
+        let package = self.package(package_id).ok_or_else(|| {
+            CLIError::MetadataError(format!("package {} isn't found", package_id))
+        })?;
+
+        let crate_dir = package.manifest_path.parent().ok_or_else(|| {
+            CLIError::MetadataError(format!(
+                "{:?} is an invalid manifest path",
+                package.manifest_path
+            ))
+        })?;
+
+        Ok(ModuleMetadata {
+            name: package.name.clone(),
+            version: package.version.clone(),
+            authors: package.authors.clone(),
+            repository: package.repository.clone(),
+            sdk_version: self.sdk_version(package_id),
+            source_hash: source_hash(crate_dir)?,
+        })
+    }
+
+    fn package(&self, package_id: &str) -> Option<&CargoPackage> {
+        self.0
+            .packages
+            .iter()
+            .find(|package| package.id == package_id)
+    }
+
+    fn sdk_version(&self, package_id: &str) -> Option<String> {
+        let resolve = self.0.resolve.as_ref()?;
+        let node = resolve.nodes.iter().find(|node| node.id == package_id)?;
+
+        node.dependencies
+            .iter()
+            .filter_map(|id| self.package(id))
+            .find(|package| package.name == SDK_CRATE_NAME)
+            .map(|package| package.version.clone())
+    }
+}
+
+/// Hashes relative paths and contents of all files of a crate except build artifacts.
+fn source_hash(crate_dir: &Path) -> Result<String> {
+    let mut files = Vec::new();
+    collect_source_files(crate_dir, &mut files)?;
+    files.sort();
+
+    let mut hasher = blake3::Hasher::new();
+    for file in files {
+        let content = std::fs::read(&file)?;
+        let relative_path = file.strip_prefix(crate_dir).unwrap_or(&file);
+
+        hasher.update(relative_path.to_string_lossy().as_bytes());
+        hasher.update(&(content.len() as u64).to_le_bytes());
+        hasher.update(&content);
+    }
+
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+fn collect_source_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        if file_name == "target" || file_name.starts_with('.') {
+            continue;
+        }
+
+        if path.is_dir() {
+            collect_source_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,81 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::Result;
+
+use fce_module_signature::detached_signature_path;
+use fce_module_signature::embed_signature;
+use fce_module_signature::parse_public_key;
+use fce_module_signature::parse_secret_key;
+use fce_module_signature::parse_signature;
+use fce_module_signature::sign_module;
+use fce_module_signature::verify_module;
+
+use std::path::PathBuf;
+
+pub(crate) fn sign(
+    wasm_path: PathBuf,
+    key_path: PathBuf,
+    out_wasm_path: Option<PathBuf>,
+    detached: bool,
+) -> Result<()> {
+    let wasm = std::fs::read(&wasm_path)?;
+    let secret_key = parse_secret_key(&std::fs::read_to_string(key_path)?)?;
+    let signature = sign_module(&wasm, &secret_key)?;
+
+    let out_wasm_path = out_wasm_path.unwrap_or(wasm_path);
+    if detached {
+        let signature_path = detached_signature_path(&out_wasm_path);
+        std::fs::write(&signature_path, hex::encode(signature.to_bytes()))?;
+        println!("signature is written to {:?}", signature_path);
+    } else {
+        let signed_wasm = embed_signature(&wasm, &signature)?;
+        std::fs::write(&out_wasm_path, signed_wasm)?;
+        println!("signature is embedded to {:?}", out_wasm_path);
+    }
+
+    Ok(())
+}
+
+pub(crate) fn verify(
+    wasm_path: PathBuf,
+    public_keys: &[&str],
+    signature_path: Option<PathBuf>,
+) -> Result<()> {
+    let wasm = std::fs::read(&wasm_path)?;
+    let public_keys = public_keys
+        .iter()
+        .map(|key| parse_public_key(key))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    let signature_path = signature_path.or_else(|| {
+        let path = detached_signature_path(&wasm_path);
+        if path.is_file() {
+            Some(path)
+        } else {
+            None
+        }
+    });
+    let detached_signature = match signature_path {
+        Some(path) => Some(parse_signature(&std::fs::read_to_string(path)?)?),
+        None => None,
+    };
+
+    verify_module(&wasm, detached_signature.as_ref(), &public_keys)?;
+    println!("signature is valid");
+
+    Ok(())
+}
@@ -138,15 +138,52 @@ impl REPL {
             }
             Some("fs") => {
                 next_argument!(module_name, args, "Module name should be specified");
-                match self.app_service.get_wasi_state(module_name) {
-                    Ok(wasi_state) => Self::print_fs_state(wasi_state),
-                    Err(e) => println!("{}", e),
-                };
+                match args.next() {
+                    Some("ls") => match self.app_service.module_memory_fs_files(module_name) {
+                        Ok(files) => {
+                            for (path, size) in files {
+                                println!("{:>10} {}", size, path.display());
+                            }
+                        }
+                        Err(e) => println!("{}", e),
+                    },
+                    Some("cat") => {
+                        next_argument!(file_path, args, "File path should be specified");
+                        match self
+                            .app_service
+                            .read_module_memory_fs_file(module_name, file_path)
+                        {
+                            Ok(content) => println!("{}", String::from_utf8_lossy(&content)),
+                            Err(e) => println!("{}", e),
+                        }
+                    }
+                    Some(subcommand) => println!("unsupported fs subcommand {}", subcommand),
+                    None => match self.app_service.get_wasi_state(module_name) {
+                        Ok(wasi_state) => Self::print_fs_state(wasi_state),
+                        Err(e) => println!("{}", e),
+                    },
+                }
             }
             Some("interface") => {
                 let interface = self.app_service.get_interface();
                 print!("Application service interface:\n{}", interface);
             }
+            Some("config") => {
+                let effective_config = self.app_service.effective_config();
+                let result = match args.next() {
+                    Some("json") => effective_config.to_json_string(),
+                    Some("toml") | None => effective_config.to_toml_string(),
+                    Some(format) => {
+                        println!("unsupported config format {}, use toml or json", format);
+                        return;
+                    }
+                };
+
+                match result {
+                    Ok(config) => println!("{}", config),
+                    Err(e) => println!("config serialization failed with {}", e),
+                }
+            }
             Some("h") | Some("help") | None => {
                 println!(
                     "Enter:\n\
@@ -155,8 +192,11 @@ impl REPL {
                                 unload <module_name>                    - to unload Wasm module from AppService\n\
                                 call <module_name> <func_name> [args]   - to call function with func_name of module with module_name\n\
                                 interface                               - to print public interface of current AppService\n\
+                                config [toml|json]                      - to print effective config of current AppService\n\
                                 envs <module_name>                      - to print environment variables of module with module_name\n\
                                 fs <module_name>                        - to print filesystem state of module with module_name\n\
+                                fs <module_name> ls                     - to list files of in-memory filesystem of module with module_name\n\
+                                fs <module_name> cat <path>             - to print a file of in-memory filesystem of module with module_name\n\
                                 h/help                                  - to print this message\n\
                                 Ctrl-C                                  - to exit"
                 );
@@ -0,0 +1,189 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::io::BufRead;
+use std::io::Read;
+use std::io::Write;
+
+/// Requests with bigger bodies are rejected.
+const MAX_BODY_SIZE: usize = 16 * 1024 * 1024;
+
+/// Minimal HTTP/1.1 request, the connection is always closed after the response.
+pub(crate) struct Request {
+    pub(crate) method: String,
+    pub(crate) path: String,
+    pub(crate) body: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub(crate) enum HttpError {
+    /// The request can't be parsed.
+    BadRequest(String),
+
+    /// The request body exceeds MAX_BODY_SIZE.
+    PayloadTooLarge,
+
+    /// An error occurred while reading the request.
+    IOError(std::io::Error),
+}
+
+impl From<std::io::Error> for HttpError {
+    fn from(err: std::io::Error) -> Self {
+        HttpError::IOError(err)
+    }
+}
+
+pub(crate) fn read_request<R: BufRead>(reader: &mut R) -> Result<Request, HttpError> {
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    let mut request_line = request_line.split_whitespace();
+    let (method, path) = match (request_line.next(), request_line.next()) {
+        (Some(method), Some(path)) => (method.to_string(), path.to_string()),
+        _ => {
+            return Err(HttpError::BadRequest(String::from(
+                "malformed request line",
+            )))
+        }
+    };
+
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Err(HttpError::BadRequest(String::from(
+                "unexpected end of headers",
+            )));
+        }
+
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = split_header(header) {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.parse().map_err(|_| {
+                    HttpError::BadRequest(format!("invalid content length {}", value))
+                })?;
+            }
+        }
+    }
+
+    if content_length > MAX_BODY_SIZE {
+        return Err(HttpError::PayloadTooLarge);
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+
+    Ok(Request { method, path, body })
+}
+
+pub(crate) fn write_response<W: Write>(
+    writer: &mut W,
+    status: u16,
+    body: &[u8],
+) -> std::io::Result<()> {
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        503 => "Service Unavailable",
+        _ => "Internal Server Error",
+    };
+
+    write!(
+        writer,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        reason,
+        body.len()
+    )?;
+    writer.write_all(body)?;
+    writer.flush()
+}
+
+fn split_header(header: &str) -> Option<(&str, &str)> {
+    let colon_pos = header.find(':')?;
+    Some((header[..colon_pos].trim(), header[colon_pos + 1..].trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::read_request;
+    use super::write_response;
+    use super::HttpError;
+    use super::MAX_BODY_SIZE;
+
+    use std::io::Cursor;
+
+    fn read(request: &str) -> Result<super::Request, HttpError> {
+        read_request(&mut Cursor::new(request.as_bytes()))
+    }
+
+    #[test]
+    fn request_with_body() {
+        let request = read(
+            "POST / HTTP/1.1\r\nHost: localhost\r\ncontent-length: 4\r\n\r\nbody and the rest",
+        )
+        .unwrap();
+
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/");
+        assert_eq!(request.body, b"body");
+    }
+
+    #[test]
+    fn request_without_body() {
+        let request = read("GET /health HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+
+        assert_eq!(request.method, "GET");
+        assert_eq!(request.path, "/health");
+        assert!(request.body.is_empty());
+    }
+
+    #[test]
+    fn malformed_requests() {
+        let malformed_line = read("GET\r\n\r\n");
+        let unfinished_headers = read("GET / HTTP/1.1\r\nHost: localhost\r\n");
+        let invalid_length = read("POST / HTTP/1.1\r\nContent-Length: -1\r\n\r\n");
+        let too_large = read(&format!(
+            "POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+            MAX_BODY_SIZE + 1
+        ));
+        let truncated_body = read("POST / HTTP/1.1\r\nContent-Length: 10\r\n\r\nbody");
+
+        assert!(matches!(malformed_line, Err(HttpError::BadRequest(_))));
+        assert!(matches!(unfinished_headers, Err(HttpError::BadRequest(_))));
+        assert!(matches!(invalid_length, Err(HttpError::BadRequest(_))));
+        assert!(matches!(too_large, Err(HttpError::PayloadTooLarge)));
+        assert!(matches!(truncated_body, Err(HttpError::IOError(_))));
+    }
+
+    #[test]
+    fn response() {
+        let mut response = Vec::new();
+        write_response(&mut response, 404, b"{}").unwrap();
+
+        assert_eq!(
+            String::from_utf8(response).unwrap(),
+            "HTTP/1.1 404 Not Found\r\nContent-Type: application/json\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{}"
+        );
+    }
+}
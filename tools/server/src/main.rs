@@ -0,0 +1,235 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Local server hosting application services and exposing them over JSON-RPC 2.0 via HTTP.
+
+#![warn(rust_2018_idioms)]
+#![deny(
+    dead_code,
+    nonstandard_style,
+    unused_imports,
+    unused_mut,
+    unused_variables,
+    unused_unsafe,
+    unreachable_patterns
+)]
+
+mod http;
+mod rpc;
+
+use http::HttpError;
+use rpc::Services;
+
+use anyhow::Context;
+use fluence_app_service::AppService;
+use fluence_app_service::AsyncAppService;
+use fluence_app_service::TomlAppServiceConfig;
+use serde_json::json;
+
+use std::collections::HashMap;
+use std::io::BufReader;
+use std::io::Read;
+use std::io::Write;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+
+const DEFAULT_LISTEN_ADDRESS: &str = "127.0.0.1:9090";
+
+/// Each connection is handled by its own thread, connections above this count are rejected.
+const MAX_CONNECTIONS: usize = 64;
+
+/// Count of requests that could wait for a service, each connection has at most one of them.
+const SERVICE_QUEUE_CAPACITY: usize = MAX_CONNECTIONS;
+
+/// Timeout of each read and write of a connection, so that stalled clients release threads.
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(30);
+
+static ACTIVE_CONNECTIONS: AtomicUsize = AtomicUsize::new(0);
+
+pub(crate) type Result<T> = std::result::Result<T, anyhow::Error>;
+
+fn main() -> Result<()> {
+    env_logger::init();
+
+    let arg_matches = clap::App::new("fce-server")
+        .version(env!("CARGO_PKG_VERSION"))
+        .about("Hosts application services and exposes them over JSON-RPC 2.0 via HTTP")
+        .arg(
+            clap::Arg::with_name("listen")
+                .long("listen")
+                .short("l")
+                .takes_value(true)
+                .value_name("ADDRESS")
+                .help("TCP address to listen on, 127.0.0.1:9090 by default"),
+        )
+        .arg(
+            clap::Arg::with_name("unix")
+                .long("unix")
+                .short("u")
+                .takes_value(true)
+                .value_name("SOCKET_PATH")
+                .conflicts_with("listen")
+                .help("Unix socket to listen on instead of a TCP address"),
+        )
+        .arg(
+            clap::Arg::with_name("service")
+                .long("service")
+                .short("s")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .required(true)
+                .value_name("SERVICE_ID=CONFIG_PATH")
+                .help("Service to host, could be specified several times"),
+        )
+        .get_matches();
+
+    let mut services = HashMap::new();
+    for service in arg_matches.values_of("service").unwrap_or_default() {
+        let (service_id, config_path) = parse_service_arg(service)?;
+        let config = TomlAppServiceConfig::load(config_path)?;
+        let id = service_id.to_string();
+        let create = move || AppService::new(config, id, HashMap::new());
+        let service = AsyncAppService::new(create, service_id, SERVICE_QUEUE_CAPACITY)
+            .with_context(|| format!("service {} can't be created", service_id))?;
+        services.insert(service_id.to_string(), service);
+    }
+    let services = Arc::new(Services::new(services));
+
+    if let Some(socket_path) = arg_matches.value_of("unix") {
+        return serve_unix(socket_path, services);
+    }
+
+    let address = arg_matches
+        .value_of("listen")
+        .unwrap_or(DEFAULT_LISTEN_ADDRESS);
+    let listener = std::net::TcpListener::bind(address)
+        .with_context(|| format!("{} can't be listened", address))?;
+    log::info!("listening on {}", address);
+
+    for stream in listener.incoming() {
+        spawn_handler(stream?, services.clone());
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn serve_unix(socket_path: &str, services: Arc<Services>) -> Result<()> {
+    use std::os::unix::fs::FileTypeExt;
+
+    // a socket file left by a previous run prevents binding, other files are kept intact
+    match std::fs::symlink_metadata(socket_path) {
+        Ok(metadata) if metadata.file_type().is_socket() => std::fs::remove_file(socket_path)?,
+        Ok(_) => anyhow::bail!("{} already exists and isn't a socket", socket_path),
+        Err(_) => {}
+    }
+
+    let listener = std::os::unix::net::UnixListener::bind(socket_path)
+        .with_context(|| format!("{} can't be listened", socket_path))?;
+    log::info!("listening on {}", socket_path);
+
+    for stream in listener.incoming() {
+        spawn_handler(stream?, services.clone());
+    }
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn serve_unix(_socket_path: &str, _services: Arc<Services>) -> Result<()> {
+    anyhow::bail!("unix sockets aren't supported on this platform")
+}
+
+/// Stream of an accepted connection.
+trait Connection: Read + Write + Send + 'static {
+    fn set_timeouts(&self, timeout: Duration) -> std::io::Result<()>;
+}
+
+impl Connection for std::net::TcpStream {
+    fn set_timeouts(&self, timeout: Duration) -> std::io::Result<()> {
+        self.set_read_timeout(Some(timeout))?;
+        self.set_write_timeout(Some(timeout))
+    }
+}
+
+#[cfg(unix)]
+impl Connection for std::os::unix::net::UnixStream {
+    fn set_timeouts(&self, timeout: Duration) -> std::io::Result<()> {
+        self.set_read_timeout(Some(timeout))?;
+        self.set_write_timeout(Some(timeout))
+    }
+}
+
+/// Decrements the count of active connections when a connection is handled,
+/// even if its handler panicked.
+struct ConnectionSlot;
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        ACTIVE_CONNECTIONS.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+fn spawn_handler<S: Connection>(mut stream: S, services: Arc<Services>) {
+    if let Err(e) = stream.set_timeouts(CONNECTION_TIMEOUT) {
+        log::warn!("connection timeouts can't be set: {}", e);
+        return;
+    }
+
+    let active_connections = ACTIVE_CONNECTIONS.fetch_add(1, Ordering::SeqCst);
+    let slot = ConnectionSlot;
+    if active_connections >= MAX_CONNECTIONS {
+        drop(slot);
+        let body = json!({ "error": "too many connections" }).to_string();
+        if let Err(e) = http::write_response(&mut stream, 503, body.as_bytes()) {
+            log::warn!("response can't be written: {}", e);
+        }
+        return;
+    }
+
+    std::thread::spawn(move || {
+        let _slot = slot;
+        handle_connection(stream, &services)
+    });
+}
+
+fn handle_connection<S: Read + Write>(stream: S, services: &Services) {
+    let mut reader = BufReader::new(stream);
+    let (status, body) = match http::read_request(&mut reader) {
+        Ok(request) => services.handle(&request),
+        Err(HttpError::BadRequest(message)) => (400, json!({ "error": message })),
+        Err(HttpError::PayloadTooLarge) => (413, json!({ "error": "request body is too large" })),
+        Err(HttpError::IOError(e)) => {
+            log::warn!("request can't be read: {}", e);
+            return;
+        }
+    };
+
+    if let Err(e) = http::write_response(reader.get_mut(), status, body.to_string().as_bytes()) {
+        log::warn!("response can't be written: {}", e);
+    }
+}
+
+fn parse_service_arg(service: &str) -> Result<(&str, &str)> {
+    let eq_pos = service
+        .find('=')
+        .with_context(|| format!("{} should be in the SERVICE_ID=CONFIG_PATH form", service))?;
+
+    Ok((&service[..eq_pos], &service[eq_pos + 1..]))
+}
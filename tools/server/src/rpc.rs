@@ -0,0 +1,358 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::http::Request;
+
+use fluence_app_service::AsyncAppService;
+use fluence_app_service::CallParameters;
+use serde::de::DeserializeOwned;
+use serde_derive::Deserialize;
+use serde_json::json;
+use serde_json::Value as JValue;
+
+use std::collections::HashMap;
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const SERVICE_ERROR: i64 = -32000;
+
+/// Services hosted by the server. A service isn't Send, so each of them lives on its executor
+/// thread and handles requests one by one: calls of one service are serialized,
+/// while different services are called concurrently.
+pub(crate) struct Services {
+    services: HashMap<String, AsyncAppService>,
+}
+
+#[derive(Deserialize)]
+struct RpcRequest {
+    jsonrpc: String,
+    #[serde(default)]
+    id: JValue,
+    method: String,
+    #[serde(default)]
+    params: JValue,
+}
+
+#[derive(Deserialize)]
+struct CallRequest {
+    service_id: String,
+    function_name: String,
+    #[serde(default = "empty_arguments")]
+    arguments: JValue,
+    #[serde(default)]
+    call_parameters: RpcCallParameters,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct RpcCallParameters {
+    call_id: String,
+    user_name: String,
+    application_id: String,
+}
+
+#[derive(Deserialize)]
+struct InterfaceRequest {
+    service_id: String,
+}
+
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl Services {
+    pub(crate) fn new(services: HashMap<String, AsyncAppService>) -> Self {
+        Self { services }
+    }
+
+    /// Handles an HTTP request, returns the HTTP status and the response body.
+    pub(crate) fn handle(&self, request: &Request) -> (u16, JValue) {
+        match (request.method.as_str(), request.path.as_str()) {
+            ("GET", "/health") => (200, self.health()),
+            ("POST", "/") => (200, self.handle_rpc(&request.body)),
+            (_, "/health") | (_, "/") => (405, json!({ "error": "method not allowed" })),
+            _ => (404, json!({ "error": "not found" })),
+        }
+    }
+
+    fn handle_rpc(&self, body: &[u8]) -> JValue {
+        let request: RpcRequest = match serde_json::from_slice(body) {
+            Ok(request) => request,
+            Err(e) => return error_response(JValue::Null, RpcError::new(PARSE_ERROR, e)),
+        };
+
+        if request.jsonrpc != "2.0" {
+            let error = RpcError::new(INVALID_REQUEST, "only JSON-RPC 2.0 is supported");
+            return error_response(request.id, error);
+        }
+
+        let result = match request.method.as_str() {
+            "call" => self.call(request.params),
+            "interface" => self.interface(request.params),
+            "health" => Ok(self.health()),
+            method => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("method {} not found", method),
+            )),
+        };
+
+        match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": request.id, "result": result }),
+            Err(error) => error_response(request.id, error),
+        }
+    }
+
+    fn call(&self, params: JValue) -> Result<JValue, RpcError> {
+        let request: CallRequest = parse_params(params)?;
+        let call_parameters = CallParameters {
+            call_id: request.call_parameters.call_id,
+            user_name: request.call_parameters.user_name,
+            application_id: request.call_parameters.application_id,
+        };
+
+        let function_name = request.function_name;
+        let arguments = request.arguments;
+
+        self.service(&request.service_id)?
+            .executor()
+            .execute_blocking(move |service| {
+                service.call(function_name, arguments, call_parameters)
+            })
+            .map_err(|e| RpcError::new(SERVICE_ERROR, e))?
+            .map_err(|e| RpcError::new(SERVICE_ERROR, e))
+    }
+
+    fn interface(&self, params: JValue) -> Result<JValue, RpcError> {
+        let request: InterfaceRequest = parse_params(params)?;
+        // the interface borrows the service, so it's serialized on the service thread
+        self.service(&request.service_id)?
+            .executor()
+            .execute_blocking(|service| serde_json::to_value(service.get_facade_interface()))
+            .map_err(|e| RpcError::new(SERVICE_ERROR, e))?
+            .map_err(|e| RpcError::new(SERVICE_ERROR, e))
+    }
+
+    fn health(&self) -> JValue {
+        let mut service_ids = self.services.keys().collect::<Vec<_>>();
+        service_ids.sort();
+
+        json!({ "status": "ok", "services": service_ids })
+    }
+
+    fn service(&self, service_id: &str) -> Result<&AsyncAppService, RpcError> {
+        self.services.get(service_id).ok_or_else(|| {
+            RpcError::new(INVALID_PARAMS, format!("service {} not found", service_id))
+        })
+    }
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl ToString) -> Self {
+        Self {
+            code,
+            message: message.to_string(),
+        }
+    }
+}
+
+fn parse_params<T: DeserializeOwned>(params: JValue) -> Result<T, RpcError> {
+    serde_json::from_value(params).map_err(|e| RpcError::new(INVALID_PARAMS, e))
+}
+
+fn error_response(id: JValue, error: RpcError) -> JValue {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": error.code, "message": error.message },
+    })
+}
+
+fn empty_arguments() -> JValue {
+    JValue::Array(Vec::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Services;
+    use super::INVALID_PARAMS;
+    use super::INVALID_REQUEST;
+    use super::METHOD_NOT_FOUND;
+    use super::PARSE_ERROR;
+    use super::SERVICE_ERROR;
+    use crate::http::Request;
+
+    use fluence_app_service::AppService;
+    use fluence_app_service::AsyncAppService;
+    use fluence_app_service::TomlAppServiceConfig;
+    use serde_json::json;
+    use serde_json::Value as JValue;
+
+    use std::collections::HashMap;
+
+    fn request(method: &str, path: &str, body: JValue) -> Request {
+        Request {
+            method: method.to_string(),
+            path: path.to_string(),
+            body: body.to_string().into_bytes(),
+        }
+    }
+
+    fn rpc(services: &Services, body: JValue) -> JValue {
+        let (status, response) = services.handle(&request("POST", "/", body));
+        assert_eq!(status, 200);
+        response
+    }
+
+    fn error_code(response: &JValue) -> &JValue {
+        &response["error"]["code"]
+    }
+
+    #[test]
+    fn routing() {
+        let services = Services::new(HashMap::new());
+
+        let health = services.handle(&request("GET", "/health", JValue::Null));
+        let wrong_method = services.handle(&request("GET", "/", JValue::Null));
+        let wrong_path = services.handle(&request("POST", "/call", JValue::Null));
+
+        assert_eq!(health, (200, json!({ "status": "ok", "services": [] })));
+        assert_eq!(wrong_method.0, 405);
+        assert_eq!(wrong_path.0, 404);
+    }
+
+    #[test]
+    fn malformed_requests() {
+        let services = Services::new(HashMap::new());
+
+        let (_, not_json) = services.handle(&Request {
+            method: String::from("POST"),
+            path: String::from("/"),
+            body: b"{".to_vec(),
+        });
+        let old_version = rpc(
+            &services,
+            json!({ "jsonrpc": "1.0", "id": 1, "method": "health" }),
+        );
+        let unknown_method = rpc(
+            &services,
+            json!({ "jsonrpc": "2.0", "id": 2, "method": "upgrade" }),
+        );
+        let unknown_service = rpc(
+            &services,
+            json!({
+                "jsonrpc": "2.0",
+                "id": 3,
+                "method": "call",
+                "params": { "service_id": "greeting", "function_name": "greeting" },
+            }),
+        );
+        let invalid_params = rpc(
+            &services,
+            json!({ "jsonrpc": "2.0", "id": 4, "method": "call", "params": [] }),
+        );
+
+        assert_eq!(error_code(&not_json), PARSE_ERROR);
+        assert_eq!(not_json["id"], JValue::Null);
+        assert_eq!(error_code(&old_version), INVALID_REQUEST);
+        assert_eq!(old_version["id"], 1);
+        assert_eq!(error_code(&unknown_method), METHOD_NOT_FOUND);
+        assert_eq!(error_code(&unknown_service), INVALID_PARAMS);
+        assert_eq!(error_code(&invalid_params), INVALID_PARAMS);
+    }
+
+    #[test]
+    fn service_calls() {
+        let base_dir = std::env::temp_dir().join(format!("fce_server_rpc_{}", std::process::id()));
+        let config = format!(
+            r#"
+            modules_dir = "../../examples/greeting/artifacts/"
+            service_base_dir = "{}"
+
+            [[module]]
+                name = "greeting"
+
+            [acl.greeting]
+            users = ["owner"]
+            "#,
+            base_dir.display()
+        );
+        let config: TomlAppServiceConfig = toml::from_str(&config).unwrap();
+        std::fs::create_dir_all(&base_dir).unwrap();
+        let service = AsyncAppService::new(
+            move || AppService::new(config, "greeting_service", HashMap::new()),
+            "greeting_service",
+            0,
+        )
+        .unwrap_or_else(|e| panic!("service should be created: {:?}", e));
+        let mut services = HashMap::new();
+        services.insert(String::from("greeting_service"), service);
+        let services = Services::new(services);
+
+        let call = |user_name: &str| {
+            rpc(
+                &services,
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": "call",
+                    "method": "call",
+                    "params": {
+                        "service_id": "greeting_service",
+                        "function_name": "greeting",
+                        "arguments": ["Fluence"],
+                        "call_parameters": { "user_name": user_name },
+                    },
+                }),
+            )
+        };
+        let allowed_call = call("owner");
+        let denied_call = call("guest");
+        let interface = rpc(
+            &services,
+            json!({
+                "jsonrpc": "2.0",
+                "id": "interface",
+                "method": "interface",
+                "params": { "service_id": "greeting_service" },
+            }),
+        );
+        let health = rpc(
+            &services,
+            json!({ "jsonrpc": "2.0", "id": "health", "method": "health" }),
+        );
+        // dropping a service doesn't wait for its thread, so it's shut down explicitly
+        for (_, service) in services.services {
+            service.shutdown().unwrap();
+        }
+        std::fs::remove_dir_all(&base_dir).unwrap();
+
+        assert_eq!(
+            allowed_call,
+            json!({ "jsonrpc": "2.0", "id": "call", "result": "Hi, Fluence" })
+        );
+        assert_eq!(error_code(&denied_call), SERVICE_ERROR);
+        assert_eq!(
+            interface["result"]["modules"][0]["function_signatures"][0]["name"],
+            "greeting"
+        );
+        assert_eq!(
+            health["result"],
+            json!({ "status": "ok", "services": ["greeting_service"] })
+        );
+    }
+}